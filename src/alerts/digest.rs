@@ -0,0 +1,268 @@
+use crate::alerts::MonitoringResult;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+
+/// Digest period for summarized price-drop notifications
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Digest {
+    Daily,
+    Weekly,
+}
+
+impl Digest {
+    /// Length of the window covered by the digest
+    pub fn duration(&self) -> Duration {
+        match self {
+            Digest::Daily => Duration::days(1),
+            Digest::Weekly => Duration::days(7),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Digest::Daily => "Daily",
+            Digest::Weekly => "Weekly",
+        }
+    }
+}
+
+/// One product's triggered alerts, merged into a single digest line
+#[derive(Debug, Clone)]
+pub struct DigestEntry {
+    pub product_id: String,
+    /// Display name, falling back to the product id when unknown
+    pub product_name: String,
+    pub alert_count: usize,
+    pub lowest_price: f64,
+    pub target_price: f64,
+    /// Largest amount below the target price seen in the period
+    pub biggest_drop: f64,
+    pub biggest_drop_percent: f64,
+}
+
+/// Rendered digest for the email channel
+#[derive(Debug, Clone)]
+pub struct DigestReport {
+    pub period: Digest,
+    pub generated_at: DateTime<Utc>,
+    pub entries: Vec<DigestEntry>,
+    pub text: String,
+    pub html: String,
+}
+
+impl DigestReport {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entry with the largest drop across all products
+    pub fn biggest_drop(&self) -> Option<&DigestEntry> {
+        self.entries.first()
+    }
+}
+
+/// Build a digest of triggered alerts within `period`, grouped by product;
+/// prices are labelled with `format_price` and products with `product_name`
+pub fn build_digest(
+    results: &[MonitoringResult],
+    period: Digest,
+    format_price: impl Fn(f64) -> String,
+    product_name: impl Fn(&str) -> Option<String>,
+) -> DigestReport {
+    let generated_at = Utc::now();
+    let cutoff = generated_at - period.duration();
+
+    let mut grouped: HashMap<&str, DigestEntry> = HashMap::new();
+    for result in results
+        .iter()
+        // 临期提醒（带有效期）不属于降价摘要
+        .filter(|r| r.triggered && r.error.is_none() && r.expiry.is_none() && r.timestamp >= cutoff)
+    {
+        let Some(price) = result.current_price else {
+            continue;
+        };
+        let drop = (result.target_price - price).max(0.0);
+        let drop_percent = if result.target_price > 0.0 {
+            drop / result.target_price * 100.0
+        } else {
+            0.0
+        };
+
+        let entry = grouped
+            .entry(result.product_id.as_str())
+            .or_insert_with(|| DigestEntry {
+                product_id: result.product_id.clone(),
+                product_name: product_name(&result.product_id)
+                    .unwrap_or_else(|| result.product_id.clone()),
+                alert_count: 0,
+                lowest_price: price,
+                target_price: result.target_price,
+                biggest_drop: drop,
+                biggest_drop_percent: drop_percent,
+            });
+        entry.alert_count += 1;
+        entry.lowest_price = entry.lowest_price.min(price);
+        if drop > entry.biggest_drop {
+            entry.biggest_drop = drop;
+            entry.biggest_drop_percent = drop_percent;
+            entry.target_price = result.target_price;
+        }
+    }
+
+    let mut entries: Vec<DigestEntry> = grouped.into_values().collect();
+    entries.sort_by(|a, b| {
+        b.biggest_drop
+            .partial_cmp(&a.biggest_drop)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.product_id.cmp(&b.product_id))
+    });
+
+    let text = render_text(period, &entries, &format_price);
+    let html = render_html(period, &entries, &format_price);
+
+    DigestReport {
+        period,
+        generated_at,
+        entries,
+        text,
+        html,
+    }
+}
+
+fn render_text(
+    period: Digest,
+    entries: &[DigestEntry],
+    format_price: impl Fn(f64) -> String,
+) -> String {
+    let mut text = format!("{} price digest\n", period.label());
+    if entries.is_empty() {
+        text.push_str("No price changes for your alerts in this period.\n");
+        return text;
+    }
+
+    for entry in entries {
+        text.push_str(&format!(
+            "- {}: now {} (target {}, down {} / {:.1}%), {} alert(s)\n",
+            entry.product_name,
+            format_price(entry.lowest_price),
+            format_price(entry.target_price),
+            format_price(entry.biggest_drop),
+            entry.biggest_drop_percent,
+            entry.alert_count
+        ));
+    }
+    text
+}
+
+fn render_html(
+    period: Digest,
+    entries: &[DigestEntry],
+    format_price: impl Fn(f64) -> String,
+) -> String {
+    let mut html = format!("<h2>{} price digest</h2>\n", period.label());
+    if entries.is_empty() {
+        html.push_str("<p>No price changes for your alerts in this period.</p>\n");
+        return html;
+    }
+
+    html.push_str("<table>\n<tr><th>Product</th><th>Price</th><th>Target</th><th>Drop</th><th>Alerts</th></tr>\n");
+    for entry in entries {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{} ({:.1}%)</td><td>{}</td></tr>\n",
+            escape_html(&entry.product_name),
+            escape_html(&format_price(entry.lowest_price)),
+            escape_html(&format_price(entry.target_price)),
+            escape_html(&format_price(entry.biggest_drop)),
+            entry.biggest_drop_percent,
+            entry.alert_count
+        ));
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{Currency, PriceFormatter};
+
+    fn triggered(product_id: &str, current: f64, target: f64) -> MonitoringResult {
+        MonitoringResult {
+            alert_id: uuid::Uuid::new_v4().to_string(),
+            product_id: product_id.to_string(),
+            triggered: true,
+            current_price: Some(current),
+            target_price: target,
+            expiry: None,
+            timestamp: Utc::now(),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_groups_drops_per_product() {
+        let mut not_triggered = triggered("milk", 5.0, 3.0);
+        not_triggered.triggered = false;
+
+        let results = vec![
+            triggered("cola", 3.0, 3.5),
+            triggered("cola", 2.5, 3.5),
+            triggered("bread", 1.8, 2.0),
+            not_triggered,
+        ];
+
+        let report = build_digest(&results, Digest::Daily, |p| format!("¥{p:.2}"), |_| None);
+        assert_eq!(report.entries.len(), 2);
+
+        let cola = report.biggest_drop().unwrap();
+        assert_eq!(cola.product_id, "cola");
+        assert_eq!(cola.alert_count, 2);
+        assert_eq!(cola.lowest_price, 2.5);
+        assert!((cola.biggest_drop - 1.0).abs() < 1e-9);
+
+        assert_eq!(report.text.matches("cola").count(), 1);
+        assert!(report.html.contains("<td>cola</td>"));
+        assert!(!report.text.contains("milk"));
+    }
+
+    #[test]
+    fn test_empty_digest() {
+        let report = build_digest(&[], Digest::Weekly, |p| format!("¥{p:.2}"), |_| None);
+        assert!(report.is_empty());
+        assert!(report.text.contains("No price changes"));
+        assert!(report.html.contains("No price changes"));
+    }
+
+    #[test]
+    fn test_uses_formatter_and_product_names() {
+        let results = vec![triggered("p-1", 2.5, 3.5), triggered("p-2", 1.0, 2.0)];
+        let formatter = PriceFormatter::new(Currency::USD);
+        let report = build_digest(
+            &results,
+            Digest::Daily,
+            |p| formatter.format(p),
+            |id| (id == "p-1").then(|| "Cola <330ml>".to_string()),
+        );
+
+        assert!(
+            report
+                .text
+                .contains("- Cola <330ml>: now $2.50 (target $3.50")
+        );
+        assert!(report.text.contains("- p-2: now $1.00"));
+        assert!(
+            report
+                .html
+                .contains("<td>Cola &lt;330ml&gt;</td><td>$2.50</td>")
+        );
+        assert!(!report.text.contains('¥'));
+    }
+}
@@ -1,9 +1,13 @@
+pub mod digest;
 pub mod monitor;
 pub mod notification;
+pub mod source;
 pub mod ui;
 
-pub use monitor::{MonitoringResult, PriceMonitor};
+pub use digest::{Digest, DigestEntry, DigestReport, build_digest};
+pub use monitor::{DEFAULT_MAX_ALERTS_PER_USER, MonitoringResult, PriceMonitor};
 pub use notification::{Notification, NotificationService, NotificationType};
+pub use source::{CurrentPriceProvider, MockCurrentPriceProvider};
 pub use ui::AlertUI;
 
 use anyhow::Result;
@@ -21,6 +25,10 @@ pub enum AlertError {
     InvalidThreshold(f64),
     #[error("Database error: {0}")]
     DatabaseError(String),
+    #[error("Duplicate alert: {0}")]
+    DuplicateAlert(String),
+    #[error("Alert limit reached: at most {0} alerts per user")]
+    AlertLimitExceeded(usize),
 }
 
 pub type AlertResult<T> = Result<T, AlertError>;
@@ -1,7 +1,8 @@
+use crate::alerts::source::CurrentPriceProvider;
 use crate::alerts::{AlertError, AlertResult};
-use crate::models::{PriceAlert, PriceRecord};
+use crate::models::{AlertCondition, AlertStatus, PriceAlert, PriceRecord};
 use anyhow::Result;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::thread;
@@ -20,8 +21,17 @@ pub struct PriceMonitor {
     check_interval: Duration,
     /// Product price cache
     price_cache: Arc<Mutex<HashMap<String, Vec<PriceRecord>>>>,
+    /// Maximum number of alerts a single user may keep
+    max_alerts_per_user: usize,
+    /// Live price feed; falls back to simulated prices when absent
+    price_provider: Option<Arc<dyn CurrentPriceProvider>>,
+    /// Results of the most recent background check
+    latest_results: Arc<Mutex<Vec<MonitoringResult>>>,
 }
 
+/// Default per-user alert limit
+pub const DEFAULT_MAX_ALERTS_PER_USER: usize = 50;
+
 impl PriceMonitor {
     pub fn new() -> Self {
         Self {
@@ -30,6 +40,50 @@ impl PriceMonitor {
             last_check: Arc::new(Mutex::new(HashMap::new())),
             check_interval: Duration::from_secs(300), // Check every 5 minutes
             price_cache: Arc::new(Mutex::new(HashMap::new())),
+            max_alerts_per_user: DEFAULT_MAX_ALERTS_PER_USER,
+            price_provider: None,
+            latest_results: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Create a monitor that polls the given price provider
+    pub fn with_price_provider(provider: Arc<dyn CurrentPriceProvider>) -> Self {
+        let mut monitor = Self::new();
+        monitor.price_provider = Some(provider);
+        monitor
+    }
+
+    /// Replace the price provider used for checks
+    pub fn set_price_provider(&mut self, provider: Arc<dyn CurrentPriceProvider>) {
+        self.price_provider = Some(provider);
+    }
+
+    /// Apply the interval from monitoring settings
+    pub fn apply_settings(&mut self, settings: &crate::settings::config::MonitoringSettings) {
+        self.set_check_interval(Duration::from_secs(
+            settings.monitoring_interval_minutes as u64 * 60,
+        ));
+    }
+
+    /// Results produced by the most recent background check
+    pub fn latest_results(&self) -> Vec<MonitoringResult> {
+        self.latest_results
+            .lock()
+            .map(|r| r.clone())
+            .unwrap_or_default()
+    }
+
+    /// Handle sharing all state with this monitor, used by the polling thread
+    fn shared(&self) -> Self {
+        Self {
+            alerts: Arc::clone(&self.alerts),
+            is_running: Arc::clone(&self.is_running),
+            last_check: Arc::clone(&self.last_check),
+            check_interval: self.check_interval,
+            price_cache: Arc::clone(&self.price_cache),
+            max_alerts_per_user: self.max_alerts_per_user,
+            price_provider: self.price_provider.clone(),
+            latest_results: Arc::clone(&self.latest_results),
         }
     }
 
@@ -90,10 +144,30 @@ impl PriceMonitor {
         })?;
 
         // Validate alert
-        if alert.target_price <= 0.0 {
+        if alert.condition == AlertCondition::PriceBelow && alert.target_price <= 0.0 {
             return Err(AlertError::InvalidThreshold(alert.target_price));
         }
 
+        // 同一用户、同一商品、同一条件只允许一个提醒（不看目标价和天数）
+        let user_alerts: Vec<&PriceAlert> = alerts
+            .values()
+            .filter(|existing| existing.user_id == alert.user_id)
+            .collect();
+        if user_alerts.iter().any(|existing| {
+            existing.product_id == alert.product_id
+                && std::mem::discriminant(&existing.condition)
+                    == std::mem::discriminant(&alert.condition)
+        }) {
+            return Err(AlertError::DuplicateAlert(format!(
+                "user {} already has a {:?} alert for product {}",
+                alert.user_id, alert.condition, alert.product_id
+            )));
+        }
+
+        if user_alerts.len() >= self.max_alerts_per_user {
+            return Err(AlertError::AlertLimitExceeded(self.max_alerts_per_user));
+        }
+
         log::info!(
             "Adding price alert for product {} with target price {}",
             alert.product_id,
@@ -162,6 +236,39 @@ impl PriceMonitor {
         Ok(user_alerts)
     }
 
+    /// Mute an alert until the given time without deleting it
+    pub fn snooze_alert(&mut self, alert_id: &str, until: DateTime<Utc>) -> AlertResult<()> {
+        let mut alerts = self.alerts.lock().map_err(|e| {
+            AlertError::MonitoringFailed(format!("Failed to acquire alerts lock: {}", e))
+        })?;
+
+        let alert = alerts
+            .get_mut(alert_id)
+            .ok_or_else(|| AlertError::AlertNotFound(alert_id.to_string()))?;
+        alert.snoozed_until = Some(until);
+        log::info!("Snoozed price alert {} until {}", alert_id, until);
+        Ok(())
+    }
+
+    /// Get a user's alerts with their current state, optionally only those in `status`
+    pub fn get_user_alerts_with_status(
+        &self,
+        user_id: &str,
+        status: Option<AlertStatus>,
+    ) -> AlertResult<Vec<(PriceAlert, AlertStatus)>> {
+        let alerts = self.alerts.lock().map_err(|e| {
+            AlertError::MonitoringFailed(format!("Failed to acquire alerts lock: {}", e))
+        })?;
+
+        let now = Utc::now();
+        Ok(alerts
+            .values()
+            .filter(|alert| alert.user_id == user_id)
+            .map(|alert| (alert.clone(), alert.status_at(now)))
+            .filter(|(_, s)| status.is_none_or(|wanted| wanted == *s))
+            .collect())
+    }
+
     /// Get all alerts
     pub fn get_all_alerts(&self) -> AlertResult<Vec<PriceAlert>> {
         let alerts = self.alerts.lock().map_err(|e| {
@@ -171,16 +278,39 @@ impl PriceMonitor {
         Ok(alerts.values().cloned().collect())
     }
 
+    /// Replace every alert, e.g. when restoring a backup
+    pub fn replace_alerts(&self, new_alerts: Vec<PriceAlert>) -> AlertResult<()> {
+        let mut alerts = self.alerts.lock().map_err(|e| {
+            AlertError::MonitoringFailed(format!("Failed to acquire alerts lock: {}", e))
+        })?;
+
+        *alerts = new_alerts
+            .into_iter()
+            .map(|alert| (alert.id.clone(), alert))
+            .collect();
+        Ok(())
+    }
+
     /// Check all alerts for price triggers
     pub fn check_all_alerts(&self) -> AlertResult<Vec<MonitoringResult>> {
-        let alerts = self.alerts.lock().map_err(|e| {
+        let mut alerts = self.alerts.lock().map_err(|e| {
             AlertError::MonitoringFailed(format!("Failed to acquire alerts lock: {}", e))
         })?;
 
+        // 移除已过期的提醒
+        let now = Utc::now();
+        alerts.retain(|id, alert| {
+            let expired = alert.is_expired(now);
+            if expired {
+                log::info!("Removing expired price alert {}", id);
+            }
+            !expired
+        });
+
         let mut results = Vec::new();
 
         for alert in alerts.values() {
-            if !alert.is_active {
+            if !alert.is_active || alert.is_snoozed(now) {
                 continue;
             }
 
@@ -204,6 +334,7 @@ impl PriceMonitor {
                         triggered: false,
                         current_price: None,
                         target_price: alert.target_price,
+                        expiry: None,
                         timestamp: Utc::now(),
                         error: Some(e.to_string()),
                     });
@@ -214,14 +345,32 @@ impl PriceMonitor {
         Ok(results)
     }
 
-    /// Check a single alert
+    /// Check a single alert. Prices are compared after discounts unless the
+    /// alert opts out via `use_effective_price`.
     fn check_single_alert(&self, alert: &PriceAlert) -> Result<MonitoringResult, AlertError> {
-        // Get current price for the product
-        let current_price = self.get_current_price(&alert.product_id)?;
+        let latest = self.get_latest_record(&alert.product_id)?;
+        let current_price = latest.as_ref().map(|record| {
+            if alert.use_effective_price {
+                record.effective_price()
+            } else {
+                record.price
+            }
+        });
 
-        let triggered = match current_price {
-            Some(price) => alert.should_trigger(price),
-            None => false,
+        let (triggered, expiry) = match alert.condition {
+            AlertCondition::PriceBelow => (
+                current_price.is_some_and(|price| alert.should_trigger(price)),
+                None,
+            ),
+            AlertCondition::ExpiringSoon { within_days } => {
+                let today = Utc::now().date_naive();
+                let expiry = latest.as_ref().and_then(|record| record.expiry);
+                let triggered = alert.is_active
+                    && latest
+                        .as_ref()
+                        .is_some_and(|record| record.expires_within(today, within_days));
+                (triggered, expiry)
+            }
         };
 
         Ok(MonitoringResult {
@@ -230,26 +379,25 @@ impl PriceMonitor {
             triggered,
             current_price,
             target_price: alert.target_price,
+            expiry,
             timestamp: Utc::now(),
             error: None,
         })
     }
 
-    /// Get current price for a product (mock implementation)
-    fn get_current_price(&self, product_id: &str) -> Result<Option<f64>, AlertError> {
-        // In a real implementation, this would query the database or external API
-        // For now, we'll simulate price data
+    /// Latest price record for a product from the price provider, or simulated data
+    fn get_latest_record(&self, product_id: &str) -> Result<Option<PriceRecord>, AlertError> {
+        if let Some(provider) = &self.price_provider {
+            return provider.fetch_latest(product_id).map(Some);
+        }
 
         let mock_prices = self.generate_mock_prices(product_id)?;
 
         // Get the most recent price
-        let current_price = mock_prices
-            .iter()
+        Ok(mock_prices
+            .into_iter()
             .filter(|p| p.verification_status == "verified")
-            .max_by_key(|p| p.timestamp)
-            .map(|p| p.price);
-
-        Ok(current_price)
+            .max_by_key(|p| p.timestamp))
     }
 
     /// Generate mock prices for testing (simulates database query)
@@ -287,6 +435,9 @@ impl PriceMonitor {
                 is_on_sale: price < base_price,
                 receipt_image: None,
                 verification_status: "verified".to_string(),
+                discount: None,
+                expiry: None,
+                source: crate::models::PriceSource::Api,
             });
         }
 
@@ -299,32 +450,31 @@ impl PriceMonitor {
     }
 
     /// Start the background monitoring thread
+    #[cfg(not(target_arch = "wasm32"))]
     fn start_monitoring_thread(&self) -> AlertResult<()> {
-        let is_running = Arc::clone(&self.is_running);
-        let alerts = Arc::clone(&self.alerts);
-        let check_interval = self.check_interval;
+        let worker = self.shared();
 
         thread::spawn(move || {
             log::info!("Price monitoring thread started");
 
-            while Self::should_continue_monitoring(&is_running) {
-                // Perform price checks
-                if let Ok(alert_map) = alerts.lock() {
-                    for alert in alert_map.values() {
-                        if !alert.is_active {
-                            continue;
+            while Self::should_continue_monitoring(&worker.is_running) {
+                match worker.check_all_alerts() {
+                    Ok(results) => {
+                        if let Ok(mut latest) = worker.latest_results.lock() {
+                            *latest = results;
                         }
-
-                        // In a real implementation, this would check prices and trigger notifications
-                        log::debug!(
-                            "Checking alert {} for product {}",
-                            alert.id,
-                            alert.product_id
-                        );
                     }
+                    Err(e) => log::error!("Price check failed: {}", e),
                 }
 
-                thread::sleep(check_interval);
+                // 分段休眠，使 stop() 能及时生效
+                let started = Instant::now();
+                while started.elapsed() < worker.check_interval
+                    && Self::should_continue_monitoring(&worker.is_running)
+                {
+                    let remaining = worker.check_interval.saturating_sub(started.elapsed());
+                    thread::sleep(remaining.min(Duration::from_millis(100)));
+                }
             }
 
             log::info!("Price monitoring thread stopped");
@@ -333,6 +483,13 @@ impl PriceMonitor {
         Ok(())
     }
 
+    /// On wasm there are no threads; the UI drives checks via `check_all_alerts`
+    #[cfg(target_arch = "wasm32")]
+    fn start_monitoring_thread(&self) -> AlertResult<()> {
+        log::info!("Background polling unavailable on wasm; checks run on demand");
+        Ok(())
+    }
+
     /// Check if monitoring should continue
     fn should_continue_monitoring(is_running: &Arc<Mutex<bool>>) -> bool {
         match is_running.lock() {
@@ -347,6 +504,16 @@ impl PriceMonitor {
         log::info!("Updated check interval to {:?}", interval);
     }
 
+    /// Time between background checks
+    pub fn check_interval(&self) -> Duration {
+        self.check_interval
+    }
+
+    /// Update the per-user alert limit
+    pub fn set_max_alerts_per_user(&mut self, max_alerts: usize) {
+        self.max_alerts_per_user = max_alerts;
+    }
+
     /// Clear price cache
     pub fn clear_cache(&self) -> AlertResult<()> {
         let mut cache = self.price_cache.lock().map_err(|e| {
@@ -373,6 +540,245 @@ pub struct MonitoringResult {
     pub triggered: bool,
     pub current_price: Option<f64>,
     pub target_price: f64,
+    /// Expiry date seen by an `ExpiringSoon` alert
+    pub expiry: Option<NaiveDate>,
     pub timestamp: DateTime<Utc>,
     pub error: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Discount;
+
+    fn alert(user_id: &str, product_id: &str, target_price: f64) -> PriceAlert {
+        PriceAlert::new(user_id.to_string(), product_id.to_string(), target_price)
+    }
+
+    #[test]
+    fn test_snoozed_alert_skipped_until_snooze_passes() {
+        let mut monitor = PriceMonitor::new();
+        let a = alert("u1", "cola", 1000.0);
+        let id = a.id.clone();
+        monitor.add_alert(a).unwrap();
+
+        monitor
+            .snooze_alert(&id, Utc::now() + chrono::Duration::hours(1))
+            .unwrap();
+        let results = monitor.check_all_alerts().unwrap();
+        assert!(results.iter().all(|r| r.alert_id != id));
+
+        let snoozed = monitor
+            .get_user_alerts_with_status("u1", Some(AlertStatus::Snoozed))
+            .unwrap();
+        assert_eq!(snoozed.len(), 1);
+
+        // 静音时间已过
+        monitor
+            .snooze_alert(&id, Utc::now() - chrono::Duration::seconds(1))
+            .unwrap();
+        let results = monitor.check_all_alerts().unwrap();
+        let result = results.iter().find(|r| r.alert_id == id).unwrap();
+        assert!(result.triggered);
+    }
+
+    #[test]
+    fn test_polling_with_mock_source_triggers_alert() {
+        use crate::alerts::source::MockCurrentPriceProvider;
+
+        let source = MockCurrentPriceProvider::new();
+        source.set_price("cola", 150.0);
+
+        let mut monitor = PriceMonitor::with_price_provider(Arc::new(source.clone()));
+        monitor.set_check_interval(Duration::from_millis(20));
+        let a = alert("u1", "cola", 120.0);
+        let id = a.id.clone();
+        monitor.add_alert(a).unwrap();
+
+        monitor.start().unwrap();
+        source.set_price("cola", 99.0);
+
+        let deadline = Instant::now() + Duration::from_secs(2);
+        let mut triggered = false;
+        while Instant::now() < deadline {
+            if monitor
+                .latest_results()
+                .iter()
+                .any(|r| r.alert_id == id && r.triggered)
+            {
+                triggered = true;
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        monitor.stop().unwrap();
+
+        assert!(triggered);
+    }
+
+    #[test]
+    fn test_replace_alerts_drops_previous_alerts() {
+        let monitor = PriceMonitor::new();
+        monitor.add_alert(alert("u1", "cola", 100.0)).unwrap();
+        let restored = alert("u2", "tea", 80.0);
+        let restored_id = restored.id.clone();
+
+        monitor.replace_alerts(vec![restored]).unwrap();
+
+        let alerts = monitor.get_all_alerts().unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].id, restored_id);
+    }
+
+    #[test]
+    fn test_duplicate_alert_rejected() {
+        let monitor = PriceMonitor::new();
+        monitor.add_alert(alert("u1", "cola", 100.0)).unwrap();
+
+        let err = monitor.add_alert(alert("u1", "cola", 100.0)).unwrap_err();
+        assert!(matches!(err, AlertError::DuplicateAlert(_)));
+        // 只改目标价也算重复
+        let err = monitor.add_alert(alert("u1", "cola", 90.0)).unwrap_err();
+        assert!(matches!(err, AlertError::DuplicateAlert(_)));
+
+        // 不同条件或不同用户不算重复
+        let mut expiring = alert("u1", "cola", 100.0);
+        expiring.condition = AlertCondition::ExpiringSoon { within_days: 3 };
+        monitor.add_alert(expiring).unwrap();
+        monitor.add_alert(alert("u2", "cola", 100.0)).unwrap();
+    }
+
+    #[test]
+    fn test_alert_limit_per_user() {
+        let mut monitor = PriceMonitor::new();
+        monitor.set_max_alerts_per_user(3);
+
+        let mut ids = Vec::new();
+        for i in 0..3 {
+            let a = alert("u1", &format!("product-{}", i), 100.0);
+            ids.push(a.id.clone());
+            monitor.add_alert(a).unwrap();
+        }
+
+        let err = monitor
+            .add_alert(alert("u1", "product-3", 100.0))
+            .unwrap_err();
+        assert!(matches!(err, AlertError::AlertLimitExceeded(3)));
+        monitor.add_alert(alert("u2", "product-3", 100.0)).unwrap();
+
+        monitor.remove_alert(&ids[0]).unwrap();
+        monitor.add_alert(alert("u1", "product-3", 100.0)).unwrap();
+    }
+
+    #[test]
+    fn test_expired_alert_pruned() {
+        let monitor = PriceMonitor::new();
+        let mut expired = alert("u1", "cola", 1000.0);
+        expired.expires_at = Some(Utc::now() - chrono::Duration::minutes(1));
+        let expired_id = expired.id.clone();
+        monitor.add_alert(expired).unwrap();
+        monitor.add_alert(alert("u1", "water", 1000.0)).unwrap();
+
+        let results = monitor.check_all_alerts().unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(
+            monitor
+                .get_all_alerts()
+                .unwrap()
+                .iter()
+                .all(|a| a.id != expired_id)
+        );
+    }
+
+    /// Source returning records with a fixed expiry per product
+    struct ExpiryProvider(HashMap<String, Option<NaiveDate>>);
+
+    impl CurrentPriceProvider for ExpiryProvider {
+        fn fetch_latest(&self, product_id: &str) -> AlertResult<PriceRecord> {
+            let mut record = PriceRecord::new(
+                Some(product_id.to_string()),
+                "store".to_string(),
+                None,
+                10.0,
+                false,
+                None,
+            );
+            record.expiry = self.0.get(product_id).copied().flatten();
+            Ok(record)
+        }
+    }
+
+    /// Source returning a ¥350 record with a 20% coupon (effective ¥280)
+    struct CouponProvider;
+
+    impl CurrentPriceProvider for CouponProvider {
+        fn fetch_latest(&self, product_id: &str) -> AlertResult<PriceRecord> {
+            let record = PriceRecord::new(
+                Some(product_id.to_string()),
+                "store".to_string(),
+                None,
+                350.0,
+                true,
+                None,
+            )
+            .with_discount(Discount::percentage(20.0).with_coupon("SAVE20".to_string()));
+            Ok(record)
+        }
+    }
+
+    #[test]
+    fn test_alert_uses_effective_price_when_enabled() {
+        let monitor = PriceMonitor::with_price_provider(Arc::new(CouponProvider));
+        let effective = alert("u1", "tea", 300.0);
+        let effective_id = effective.id.clone();
+        let mut sticker = alert("u2", "tea", 300.0);
+        sticker.use_effective_price = false;
+        let sticker_id = sticker.id.clone();
+        monitor.add_alert(effective).unwrap();
+        monitor.add_alert(sticker).unwrap();
+
+        let results = monitor.check_all_alerts().unwrap();
+        let effective = results.iter().find(|r| r.alert_id == effective_id).unwrap();
+        assert!(effective.triggered);
+        assert_eq!(effective.current_price, Some(280.0));
+
+        // 标价 ¥350 高于目标价，关闭开关时不触发
+        let sticker = results.iter().find(|r| r.alert_id == sticker_id).unwrap();
+        assert!(!sticker.triggered);
+        assert_eq!(sticker.current_price, Some(350.0));
+    }
+
+    #[test]
+    fn test_expiring_soon_condition() {
+        let today = Utc::now().date_naive();
+        let source = ExpiryProvider(HashMap::from([
+            ("milk".to_string(), Some(today + chrono::Duration::days(2))),
+            ("rice".to_string(), Some(today + chrono::Duration::days(40))),
+            ("salt".to_string(), None),
+        ]));
+        let monitor = PriceMonitor::with_price_provider(Arc::new(source));
+
+        for product in ["milk", "rice", "salt"] {
+            monitor
+                .add_alert(PriceAlert::expiring_soon(
+                    "u1".to_string(),
+                    product.to_string(),
+                    7,
+                ))
+                .unwrap();
+        }
+
+        let results = monitor.check_all_alerts().unwrap();
+        let triggered: Vec<&str> = results
+            .iter()
+            .filter(|r| r.triggered)
+            .map(|r| r.product_id.as_str())
+            .collect();
+        assert_eq!(triggered, vec!["milk"]);
+
+        let milk = results.iter().find(|r| r.product_id == "milk").unwrap();
+        assert_eq!(milk.expiry, Some(today + chrono::Duration::days(2)));
+        let salt = results.iter().find(|r| r.product_id == "salt").unwrap();
+        assert_eq!(salt.expiry, None);
+    }
+}
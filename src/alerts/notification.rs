@@ -1,5 +1,5 @@
 use crate::alerts::{AlertError, AlertResult};
-use crate::models::{PriceAlert, User};
+use crate::models::{DeepLink, PriceAlert, User};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use std::collections::VecDeque;
@@ -34,7 +34,12 @@ impl NotificationService {
         let notification = Notification {
             id: uuid::Uuid::new_v4().to_string(),
             user_id: user.id.clone(),
-            notification_type: NotificationType::PriceAlert,
+            notification_type: NotificationType::PriceAlert {
+                product_id: alert.product_id.clone(),
+                store_id: None,
+                current_price,
+                target_price: alert.target_price,
+            },
             title: "Price Alert: Target Reached!".to_string(),
             message: format!(
                 "Your price alert for product {} has been triggered! Current price: ¥{:.2}, Target: ¥{:.2}",
@@ -88,6 +93,8 @@ impl NotificationService {
 
         log::info!("Queueing notification: {}", notification.title);
         queue.push_back(notification);
+        // 释放队列锁后再处理，process_queue 会重新获取
+        drop(queue);
 
         // Process the queue
         self.process_queue()?;
@@ -130,10 +137,12 @@ impl NotificationService {
         notification: &mut Notification,
     ) -> Result<(), AlertError> {
         match notification.notification_type {
-            NotificationType::PriceAlert => self.send_price_alert_internal(notification),
-            NotificationType::SystemAlert => self.send_system_alert_internal(notification),
-            NotificationType::ProductUpdate => self.send_product_update_internal(notification),
-            NotificationType::UserMessage => self.send_user_message_internal(notification),
+            NotificationType::PriceAlert { .. } => self.send_price_alert_internal(notification),
+            NotificationType::SystemAlert { .. } => self.send_system_alert_internal(notification),
+            NotificationType::ProductUpdate { .. } => {
+                self.send_product_update_internal(notification)
+            }
+            NotificationType::UserMessage { .. } => self.send_user_message_internal(notification),
         }
     }
 
@@ -346,13 +355,66 @@ pub struct Notification {
     pub status: NotificationStatus,
 }
 
-/// Types of notifications
+impl Notification {
+    /// Navigation target for this notification, if it refers to a product or store
+    pub fn deep_link(&self) -> Option<DeepLink> {
+        let (product_id, store_id) = match &self.notification_type {
+            NotificationType::PriceAlert {
+                product_id,
+                store_id,
+                ..
+            }
+            | NotificationType::ProductUpdate {
+                product_id,
+                store_id,
+                ..
+            } => (Some(product_id), store_id.as_ref()),
+            NotificationType::SystemAlert {
+                product_id,
+                store_id,
+            }
+            | NotificationType::UserMessage {
+                product_id,
+                store_id,
+            } => (product_id.as_ref(), store_id.as_ref()),
+        };
+
+        match (product_id, store_id) {
+            (Some(product_id), store_id) => Some(DeepLink::Product {
+                product_id: product_id.clone(),
+                store_id: store_id.cloned(),
+            }),
+            (None, Some(store_id)) => Some(DeepLink::Store {
+                store_id: store_id.clone(),
+            }),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Types of notifications with the ids and prices they refer to
 #[derive(Debug, Clone, PartialEq)]
 pub enum NotificationType {
-    PriceAlert,
-    SystemAlert,
-    ProductUpdate,
-    UserMessage,
+    PriceAlert {
+        product_id: String,
+        store_id: Option<String>,
+        current_price: f64,
+        target_price: f64,
+    },
+    SystemAlert {
+        product_id: Option<String>,
+        store_id: Option<String>,
+    },
+    ProductUpdate {
+        product_id: String,
+        store_id: Option<String>,
+        old_price: Option<f64>,
+        new_price: Option<f64>,
+    },
+    UserMessage {
+        product_id: Option<String>,
+        store_id: Option<String>,
+    },
 }
 
 /// Notification status
@@ -363,3 +425,72 @@ pub enum NotificationStatus {
     Failed,
     Read,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_price_alert_notification_deep_link() {
+        let service = NotificationService::new();
+        let user = User::new(
+            "alice".to_string(),
+            "alice@example.com".to_string(),
+            "hash".to_string(),
+        );
+        let alert = PriceAlert::new(user.id.clone(), "product-42".to_string(), 3.0);
+
+        service.send_price_alert(&user, &alert, 2.5).unwrap();
+
+        let notifications = service.get_user_notifications(&user.id).unwrap();
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(
+            notifications[0].deep_link(),
+            Some(DeepLink::Product {
+                product_id: "product-42".to_string(),
+                store_id: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_deep_link_with_store() {
+        let service = NotificationService::new();
+        service
+            .send_notification(
+                "u1",
+                NotificationType::ProductUpdate {
+                    product_id: "p1".to_string(),
+                    store_id: Some("s1".to_string()),
+                    old_price: Some(4.0),
+                    new_price: Some(3.0),
+                },
+                "Price changed".to_string(),
+                "p1 is now cheaper".to_string(),
+                None,
+            )
+            .unwrap();
+        service
+            .send_notification(
+                "u1",
+                NotificationType::SystemAlert {
+                    product_id: None,
+                    store_id: None,
+                },
+                "Maintenance".to_string(),
+                "Back soon".to_string(),
+                None,
+            )
+            .unwrap();
+
+        let notifications = service.get_user_notifications("u1").unwrap();
+        assert_eq!(
+            notifications[0].deep_link(),
+            Some(DeepLink::Product {
+                product_id: "p1".to_string(),
+                store_id: Some("s1".to_string()),
+            })
+        );
+        assert_eq!(notifications[1].deep_link(), None);
+    }
+}
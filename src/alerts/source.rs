@@ -0,0 +1,52 @@
+use crate::alerts::{AlertError, AlertResult};
+use crate::models::PriceRecord;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Live price feed consulted by the price monitor
+pub trait CurrentPriceProvider: Send + Sync {
+    /// Fetch the latest known price record for a product
+    fn fetch_latest(&self, product_id: &str) -> AlertResult<PriceRecord>;
+}
+
+/// In-memory price provider whose prices can be changed by tests
+#[derive(Clone, Default)]
+pub struct MockCurrentPriceProvider {
+    prices: Arc<Mutex<HashMap<String, f64>>>,
+}
+
+impl MockCurrentPriceProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set (or replace) the current price of a product
+    pub fn set_price(&self, product_id: &str, price: f64) {
+        if let Ok(mut prices) = self.prices.lock() {
+            prices.insert(product_id.to_string(), price);
+        }
+    }
+}
+
+impl CurrentPriceProvider for MockCurrentPriceProvider {
+    fn fetch_latest(&self, product_id: &str) -> AlertResult<PriceRecord> {
+        let prices = self.prices.lock().map_err(|e| {
+            AlertError::MonitoringFailed(format!("Failed to acquire price lock: {}", e))
+        })?;
+
+        let price = prices.get(product_id).copied().ok_or_else(|| {
+            AlertError::MonitoringFailed(format!("No price for product {}", product_id))
+        })?;
+
+        let mut record = PriceRecord::new(
+            Some(product_id.to_string()),
+            "mock".to_string(),
+            Some("system".to_string()),
+            price,
+            false,
+            None,
+        );
+        record.verify();
+        Ok(record)
+    }
+}
@@ -1,5 +1,6 @@
 use crate::alerts::{AlertService, Notification, NotificationType};
-use crate::models::PriceAlert;
+use crate::models::{AlertCondition, AlertStatus, DeepLink, PriceAlert};
+use crate::utils::PriceFormatter;
 use eframe::egui;
 
 /// Alert management UI component
@@ -15,6 +16,8 @@ pub struct AlertUI {
     error_message: Option<String>,
     unread_count: usize,
     check_interval_secs: u64,
+    pending_deep_link: Option<DeepLink>,
+    price_formatter: PriceFormatter,
 }
 
 impl AlertUI {
@@ -31,9 +34,16 @@ impl AlertUI {
             error_message: None,
             unread_count: 0,
             check_interval_secs: 300,
+            pending_deep_link: None,
+            price_formatter: PriceFormatter::default(),
         }
     }
 
+    /// Format prices with `formatter`, usually built from the app settings
+    pub fn set_price_formatter(&mut self, formatter: PriceFormatter) {
+        self.price_formatter = formatter;
+    }
+
     /// Render the alerts UI tab
     pub fn show(&mut self, ui: &mut egui::Ui, user_id: &str) {
         ui.heading("价格提醒管理");
@@ -169,11 +179,30 @@ impl AlertUI {
             ui.horizontal(|ui| {
                 ui.vertical(|ui| {
                     ui.label(format!("商品ID: {}", alert.product_id));
-                    ui.label(format!("目标价格: ¥{:.2}", alert.target_price));
-                    ui.label(format!(
-                        "状态: {}",
-                        if alert.is_active { "激活" } else { "暂停" }
-                    ));
+                    match alert.condition {
+                        AlertCondition::PriceBelow => {
+                            ui.label(format!(
+                                "目标价格: {}",
+                                self.price_formatter.format(alert.target_price)
+                            ));
+                        }
+                        AlertCondition::ExpiringSoon { within_days } => {
+                            ui.label(format!("临期提醒: {} 天内到期", within_days));
+                        }
+                    }
+                    let status = match alert.status_at(chrono::Utc::now()) {
+                        AlertStatus::Active => "激活".to_string(),
+                        AlertStatus::Paused => "暂停".to_string(),
+                        AlertStatus::Expired => "已过期".to_string(),
+                        AlertStatus::Snoozed => format!(
+                            "已静音至 {}",
+                            alert
+                                .snoozed_until
+                                .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                                .unwrap_or_default()
+                        ),
+                    };
+                    ui.label(format!("状态: {}", status));
                 });
 
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
@@ -194,7 +223,20 @@ impl AlertUI {
                         }
                     }
 
-                    if ui.button("编辑").clicked() {
+                    if ui.button("静音1天").clicked() {
+                        let until = chrono::Utc::now() + chrono::Duration::days(1);
+                        if let Err(e) = self
+                            .alert_service
+                            .monitor_mut()
+                            .snooze_alert(&alert.id, until)
+                        {
+                            self.error_message = Some(format!("静音提醒失败: {}", e));
+                        }
+                    }
+
+                    // 编辑对话框仅支持目标价格
+                    let editable = alert.condition == AlertCondition::PriceBelow;
+                    if editable && ui.button("编辑").clicked() {
                         self.selected_alert_id = Some(alert.id.clone());
                         self.new_alert_product_id = alert.product_id.clone();
                         self.new_alert_target_price = alert.target_price.to_string();
@@ -251,10 +293,20 @@ impl AlertUI {
     }
 
     /// Display a single notification item
-    fn show_notification_item(&self, ui: &mut egui::Ui, notification: &Notification) {
+    fn show_notification_item(&mut self, ui: &mut egui::Ui, notification: &Notification) {
         ui.group(|ui| {
             ui.vertical(|ui| {
-                ui.label(&notification.title);
+                match notification.deep_link() {
+                    Some(link) => {
+                        if ui.link(&notification.title).clicked() {
+                            self.pending_deep_link = Some(link);
+                            self.show_notification_panel = false;
+                        }
+                    }
+                    None => {
+                        ui.label(&notification.title);
+                    }
+                }
                 ui.label(&notification.message);
                 ui.label(format!(
                     "时间: {}",
@@ -262,10 +314,10 @@ impl AlertUI {
                 ));
 
                 let type_text = match notification.notification_type {
-                    NotificationType::PriceAlert => "价格提醒",
-                    NotificationType::SystemAlert => "系统通知",
-                    NotificationType::ProductUpdate => "商品更新",
-                    NotificationType::UserMessage => "用户消息",
+                    NotificationType::PriceAlert { .. } => "价格提醒",
+                    NotificationType::SystemAlert { .. } => "系统通知",
+                    NotificationType::ProductUpdate { .. } => "商品更新",
+                    NotificationType::UserMessage { .. } => "用户消息",
                 };
                 ui.label(format!("类型: {}", type_text));
             });
@@ -296,6 +348,10 @@ impl AlertUI {
                 target_price,
                 is_active: true,
                 created_at: chrono::Utc::now(),
+                expires_at: None,
+                snoozed_until: None,
+                condition: AlertCondition::PriceBelow,
+                use_effective_price: true,
             };
 
             let res = if self.selected_alert_id.is_some() {
@@ -358,6 +414,11 @@ impl AlertUI {
             .unwrap_or(0);
     }
 
+    /// Take the navigation target chosen by clicking a notification
+    pub fn take_deep_link(&mut self) -> Option<DeepLink> {
+        self.pending_deep_link.take()
+    }
+
     /// Get alert service reference
     pub fn alert_service(&self) -> &AlertService {
         &self.alert_service
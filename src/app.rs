@@ -1,21 +1,45 @@
+mod commands;
+pub mod hours_editor;
+pub mod price_form;
+pub mod recent;
+pub mod undo;
+
 use crate::alerts::AlertUI;
 use crate::auth::{AuthState, AuthUI};
+use crate::demo;
+use crate::headless::HeadlessApp;
+use crate::models::{DeepLink, OpeningHours, PriceRecord, PriceSource, Product, Store};
 #[cfg(not(target_arch = "wasm32"))]
-use crate::database::DatabaseManager;
-use crate::models::{PriceRecord, Product, Store};
+use crate::scanner::{ScannerPriceEntry, ScannerUI};
+use crate::services::{
+    BackupExtras, Calendar, Geocoder, PriceService, ServiceError, ServiceResult,
+};
 #[cfg(not(target_arch = "wasm32"))]
-use crate::scanner::ScannerUI;
-use crate::services::AppServices;
+use crate::services::{CachingGeocoder, NominatimGeocoder};
+use crate::settings::{AppConfig, DataAction, SettingsUI};
+use crate::tr;
+use crate::utils::chart::{CHART_HEIGHT_PX, CHART_WIDTH_PX};
+use crate::utils::{
+    DEFAULT_PHONE_REGION, ImageStore, PriceChartData, SystemClock, Tz, format_relative,
+    normalize_phone, render_price_chart_png,
+};
 use chrono::Utc;
+use commands::{Command, CommandPalette};
 use eframe::egui;
-#[cfg(not(target_arch = "wasm32"))]
-use std::sync::Arc;
+use hours_editor::OpeningHoursEditor;
+use price_form::{PriceSubmission, PriceSubmissionForm};
+use recent::RecentlyViewedByUser;
+use std::sync::{Arc, Mutex};
+use undo::{AppAction, Reversible, UndoContext, UndoStack};
 use walkers::{
     HttpTiles, Map, MapMemory, Position, Tiles,
     extras::{LabeledSymbol, LabeledSymbolStyle, Places, Symbol},
     sources::OpenStreetMap,
 };
 
+/// Resolved place name for a (latitude, longitude)
+type LocationLabel = ((f64, f64), String);
+
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
@@ -30,8 +54,25 @@ pub struct TemplateApp {
     #[serde(skip)]
     map_memory: MapMemory,
     products: Vec<Product>,
-    current_location: (f64, f64),      // 当前位置 (纬度, 经度)
+    current_location: (f64, f64), // 当前位置 (纬度, 经度)
+    search_radius_km: f64,        // 门店搜索半径
+    open_now_only: bool,          // 仅显示营业中的门店
+    #[serde(skip)]
+    hours_editor: Option<(String, OpeningHoursEditor)>, // 选中门店的营业时间编辑器
+    #[serde(skip)]
+    location_geocoder: Option<Arc<dyn Geocoder>>, // 当前位置反向地理编码
+    #[serde(skip)]
+    location_label: Arc<Mutex<Option<LocationLabel>>>, // 已解析的位置名称
+    #[serde(skip)]
+    location_lookup: Option<(f64, f64)>, // 已发起查询的坐标
+    #[serde(skip)]
+    holiday_calendar: Option<Calendar>, // 价格走势图上标注的节假日
     selected_product: Option<Product>, // 选中的商品
+    recently_viewed: RecentlyViewedByUser, // 各用户最近浏览的商品
+    #[serde(skip)]
+    price_form: Option<PriceSubmissionForm>, // 选中商品的价格提交表单
+    #[serde(skip)]
+    image_store: Option<ImageStore>, // 小票图片存储
     product_search_text: String,
     selected_category: Option<String>,
     #[serde(skip)]
@@ -42,13 +83,20 @@ pub struct TemplateApp {
     #[serde(skip)]
     scanner_ui: ScannerUI, // Scanner UI component
     #[serde(skip)]
-    app_services: AppServices, // Business logic services
-    #[cfg(not(target_arch = "wasm32"))]
+    settings_ui: SettingsUI, // Settings UI component
+    #[serde(skip)]
+    core: HeadlessApp, // Business logic services and database
+    #[serde(skip)]
+    undo_stack: UndoStack<AppAction>, // 可撤销的删除/清空操作
     #[serde(skip)]
-    database_manager: Option<Arc<DatabaseManager>>, // Database connection
+    undo_message: Option<String>, // 最近一次撤销/重做的结果
+    #[serde(skip)]
+    command_palette: CommandPalette, // Ctrl+K 命令面板
+    #[serde(skip)]
+    focus_product_search: bool, // 下一帧聚焦商品搜索框
 }
 
-#[derive(serde::Deserialize, serde::Serialize, PartialEq)]
+#[derive(serde::Deserialize, serde::Serialize, PartialEq, Clone, Copy, Debug)]
 enum Tab {
     Stores,    // 门店管理
     Products,  // 商品比价
@@ -68,161 +116,160 @@ impl Default for Tab {
 impl Default for TemplateApp {
     fn default() -> Self {
         Self {
-            stores: Self::create_sample_stores(),
+            stores: demo::sample_stores(&SystemClock),
             search_text: String::new(),
             current_tab: Tab::default(),
             selected_store: None,
             previous_store_id: None,
             tiles: None,
             map_memory: MapMemory::default(),
-            products: Self::create_sample_products(),
+            products: demo::sample_products(&SystemClock),
             current_location: (35.6812, 139.7671), // 当前位置 (纬度, 经度)
-            selected_product: None,                // 选中的商品
+            search_radius_km: 20.0,
+            open_now_only: false,
+            hours_editor: None,
+            location_geocoder: Self::default_location_geocoder(),
+            location_label: Arc::new(Mutex::new(None)),
+            location_lookup: None,
+            holiday_calendar: None,
+            selected_product: None, // 选中的商品
+            recently_viewed: RecentlyViewedByUser::default(),
+            price_form: None,
+            image_store: ImageStore::open_default().ok(),
             product_search_text: String::new(),
             selected_category: None,
             auth_ui: AuthUI::new(),
             alert_ui: AlertUI::new(),
             #[cfg(not(target_arch = "wasm32"))]
             scanner_ui: ScannerUI::new(),
-            app_services: AppServices::new(),
-            #[cfg(not(target_arch = "wasm32"))]
-            database_manager: None,
+            settings_ui: SettingsUI::default(),
+            core: HeadlessApp::new(),
+            undo_stack: UndoStack::default(),
+            undo_message: None,
+            command_palette: CommandPalette::default(),
+            focus_product_search: false,
         }
     }
 }
 
 impl TemplateApp {
-    fn create_sample_stores() -> Vec<Store> {
-        vec![
-            Store {
-                id: "1".to_string(),
-                name: "全家便利店 - 东京站店".to_string(),
-                address: "东京都千代田区丸の内1-9-1".to_string(),
-                latitude: 35.6812,
-                longitude: 139.7671,
-                rating: 4.5,
-                opening_hours: "24小时营业".to_string(),
-                phone: "03-1234-5678".to_string(),
-                tags: vec!["便利店".to_string(), "24小时".to_string()],
-                symbol: '🏪',
-                created_at: Utc::now(),
-            },
-            Store {
-                id: "2".to_string(),
-                name: "松本清 - 新宿店".to_string(),
-                address: "东京都新宿区新宿3-1-1".to_string(),
-                latitude: 35.6895,
-                longitude: 139.6917,
-                rating: 4.2,
-                opening_hours: "10:00-22:00".to_string(),
-                phone: "03-2345-6789".to_string(),
-                tags: vec![
-                    "药妆店".to_string(),
-                    "化妆品".to_string(),
-                    "免税".to_string(),
-                ],
-                symbol: '🏪',
-                created_at: Utc::now(),
-            },
-            Store {
-                id: "3".to_string(),
-                name: "唐吉诃德 - 涩谷店".to_string(),
-                address: "东京都涩谷区道玄坂2-25-5".to_string(),
-                latitude: 35.6580,
-                longitude: 139.6994,
-                rating: 4.0,
-                opening_hours: "24小时营业".to_string(),
-                phone: "03-3456-7890".to_string(),
-                tags: vec![
-                    "综合商店".to_string(),
-                    "免税".to_string(),
-                    "24小时".to_string(),
-                ],
-                symbol: '🏪',
-                created_at: Utc::now(),
-            },
-            Store {
-                id: "4".to_string(),
-                name: "无印良品 - 银座店".to_string(),
-                address: "东京都中央区银座3-3-5".to_string(),
-                latitude: 35.6721,
-                longitude: 139.7636,
-                rating: 4.3,
-                opening_hours: "11:00-20:00".to_string(),
-                phone: "03-4567-8901".to_string(),
-                tags: vec![
-                    "生活用品".to_string(),
-                    "服装".to_string(),
-                    "家居".to_string(),
-                ],
-                symbol: '🏪',
-                created_at: Utc::now(),
-            },
-            Store {
-                id: "5".to_string(),
-                name: "优衣库 - 原宿店".to_string(),
-                address: "东京都涩谷区神宫前1-14-30".to_string(),
-                latitude: 35.6716,
-                longitude: 139.7031,
-                rating: 4.4,
-                opening_hours: "10:00-21:00".to_string(),
-                phone: "03-5678-9012".to_string(),
-                tags: vec!["服装".to_string(), "时尚".to_string()],
-                symbol: '🏪',
-                created_at: Utc::now(),
-            },
-        ]
+    /// Built-in holidays for the configured region plus the optional calendar file
+    fn load_holiday_calendar(config: &AppConfig) -> Calendar {
+        let settings = &config.monitoring_settings;
+        let mut calendar = Calendar::builtin(&settings.holiday_region);
+        if let Some(path) = &settings.holiday_calendar_file {
+            match Calendar::load(&settings.holiday_region, std::path::Path::new(path)) {
+                Ok(extra) => calendar.extend(extra.dates().to_vec()),
+                Err(e) => log::error!("Failed to load holiday calendar: {}", e),
+            }
+        }
+        calendar
     }
 
-    fn create_sample_products() -> Vec<Product> {
-        vec![
-            Product {
-                id: "1".to_string(),
-                name: "可口可乐".to_string(),
-                category: "饮料".to_string(),
-                description: "碳酸饮料，330ml".to_string(),
-                barcode: Some("1234567890123".to_string()),
-                images: vec!["cola.jpg".to_string()],
-                prices: vec![PriceRecord {
-                    id: Some("price1".to_string()),
-                    product_id: Some("1".to_string()),
-                    store_id: "1".to_string(),
-                    user_id: None,
-                    price: 3.5,
-                    timestamp: Utc::now(),
-                    is_on_sale: false,
-                    receipt_image: None,
-                    verification_status: "verified".to_string(),
-                }],
-                tags: vec!["饮料".to_string(), "碳酸".to_string()],
-                created_at: Utc::now(),
-            },
-            Product {
-                id: "2".to_string(),
-                name: "百事可乐".to_string(),
-                category: "饮料".to_string(),
-                description: "碳酸饮料，330ml".to_string(),
-                barcode: Some("1234567890124".to_string()),
-                images: vec!["pepsi.jpg".to_string()],
-                prices: vec![PriceRecord {
-                    id: Some("price2".to_string()),
-                    product_id: Some("2".to_string()),
-                    store_id: "2".to_string(),
-                    user_id: None,
-                    price: 3.0,
-                    timestamp: Utc::now(),
-                    is_on_sale: true,
-                    receipt_image: None,
-                    verification_status: "verified".to_string(),
-                }],
-                tags: vec!["饮料".to_string(), "碳酸".to_string()],
-                created_at: Utc::now(),
-            },
-        ]
+    /// Reverse geocoder for the current-location label (Nominatim, cached);
+    /// only queried when reverse geocoding is enabled in settings
+    fn default_location_geocoder() -> Option<Arc<dyn Geocoder>> {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let client = Arc::new(crate::services::http::ReqwestClient);
+            let nominatim = Arc::new(NominatimGeocoder::new(client));
+            Some(Arc::new(CachingGeocoder::new(nominatim)))
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            None
+        }
+    }
+
+    /// Place name of the current location; starts a background lookup on first use
+    fn current_location_label(&mut self, ctx: &egui::Context) -> Option<String> {
+        let location = self.current_location;
+        if let Ok(label) = self.location_label.lock() {
+            if let Some((at, text)) = label.as_ref() {
+                if *at == location {
+                    return Some(text.clone());
+                }
+            }
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.settings_ui.get_config().reverse_geocoding && self.location_lookup != Some(location)
+        {
+            if let Some(geocoder) = self.location_geocoder.clone() {
+                self.location_lookup = Some(location);
+                let slot = self.location_label.clone();
+                let ctx = ctx.clone();
+                // 网络查询放到后台线程，避免阻塞界面
+                std::thread::spawn(move || match geocoder.reverse(location.0, location.1) {
+                    Ok(text) => {
+                        if let Ok(mut slot) = slot.lock() {
+                            *slot = Some((location, text));
+                        }
+                        ctx.request_repaint();
+                    }
+                    Err(e) => log::warn!("Reverse geocoding failed: {}", e),
+                });
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = ctx;
+        None
+    }
+
+    /// `dt` in the timezone chosen in settings
+    fn local_time(&self, dt: &chrono::DateTime<Utc>) -> chrono::DateTime<Tz> {
+        dt.with_timezone(&self.settings_ui.get_config().timezone())
+    }
+
+    /// "3 hours ago" style label, with the local time on hover
+    fn relative_time_label(&self, ui: &mut egui::Ui, dt: &chrono::DateTime<Utc>) {
+        ui.label(format_relative(
+            *dt,
+            Utc::now(),
+            crate::i18n::current_locale(),
+        ))
+        .on_hover_text(self.local_time(dt).format("%Y-%m-%d %H:%M").to_string());
+    }
+
+    /// Origin for store distances: the saved location picked in the stores tab, else the live location
+    fn reference_location(&self) -> (f64, f64) {
+        self.settings_ui
+            .get_config()
+            .saved_locations
+            .reference_point(self.current_location)
+    }
+
+    /// Dropdown choosing which location drives distance calculations
+    fn render_location_picker(&mut self, ui: &mut egui::Ui, live_label: &str) {
+        let locations = &self.settings_ui.get_config().saved_locations;
+        let mut selected = locations.active.clone();
+        let names: Vec<String> = locations.locations.iter().map(|l| l.name.clone()).collect();
+        let live_text = format!("📍 {}", live_label);
+        egui::ComboBox::from_id_salt("distance_origin")
+            .selected_text(match &selected {
+                Some(name) => format!("🏠 {}", name),
+                None => live_text.clone(),
+            })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut selected, None, live_text);
+                for name in names {
+                    let text = format!("🏠 {}", name);
+                    ui.selectable_value(&mut selected, Some(name), text);
+                }
+            });
+        if selected != self.settings_ui.get_config().saved_locations.active {
+            self.settings_ui.select_location(selected.as_deref());
+        }
     }
 
     /// Called once before the first frame.
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        // 先迁移数据目录，之后的配置读取才能找到迁移后的文件
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Err(e) = crate::utils::file_utils::run_data_migration() {
+            log::error!("Failed to migrate data directory: {}", e);
+        }
+
         // 配置字体
         let mut fonts = egui::FontDefinitions::default();
         // 添加中文字体
@@ -240,20 +287,24 @@ impl TemplateApp {
 
         cc.egui_ctx.set_fonts(fonts);
 
+        // 按配置设置界面语言
+        let config = AppConfig::load().unwrap_or_default();
+        crate::i18n::set_locale(config.locale());
+
         // 使用带默认值的结构体更新，避免后续字段再赋值
         let mut app = Self {
             tiles: Some(Box::new(HttpTiles::new(OpenStreetMap, cc.egui_ctx.clone()))),
+            holiday_calendar: Some(Self::load_holiday_calendar(&config)),
             ..Self::default()
         };
 
         // Initialize database connection on native builds
         #[cfg(not(target_arch = "wasm32"))]
-        {
-            app.initialize_database();
-        }
+        app.initialize_database();
 
         // Initialize services with sample data
         app.initialize_services();
+        app.apply_settings();
 
         app
     }
@@ -262,17 +313,12 @@ impl TemplateApp {
     #[cfg(not(target_arch = "wasm32"))]
     fn initialize_database(&mut self) {
         // Try to initialize database connection
-        let rt = tokio::runtime::Runtime::new().unwrap();
-        match rt.block_on(DatabaseManager::new_default()) {
+        match self.core.open_default_database() {
             Ok(database_manager) => {
-                let database_manager = Arc::new(database_manager);
-                self.database_manager = Some(database_manager.clone());
-
                 // Initialize AuthUI with database
                 match AuthUI::with_database_sync(database_manager) {
                     Ok(auth_ui) => {
                         self.auth_ui = auth_ui;
-                        log::info!("Database connection initialized successfully");
                     }
                     Err(e) => {
                         log::error!("Failed to initialize AuthUI with database: {}", e);
@@ -289,29 +335,156 @@ impl TemplateApp {
 
     /// Initialize services with sample data
     fn initialize_services(&mut self) {
-        // Add sample stores
-        for store in &self.stores {
-            let _ = self.app_services.store_service.create_store(
-                store.name.clone(),
-                store.address.clone(),
-                store.latitude,
-                store.longitude,
-                store.opening_hours.clone(),
-                store.phone.clone(),
-                store.tags.clone(),
-                store.symbol,
-            );
+        // Add sample stores and products, keeping their ids so UI actions
+        // (delete / undo) address the same records in the services
+        self.core.seed(&self.stores, &self.products);
+        self.refresh_store_ratings();
+
+        let metrics = self.core.services.metrics.clone();
+        self.settings_ui.set_metrics(metrics.clone());
+        #[cfg(not(target_arch = "wasm32"))]
+        self.scanner_ui.set_metrics(metrics);
+    }
+
+    /// Recompute store ratings from the reviews and show them in the store list
+    fn refresh_store_ratings(&mut self) {
+        if let Err(e) = self.core.services.refresh_store_ratings() {
+            log::warn!("Cannot refresh store ratings: {}", e);
+            return;
         }
+        let store_service = &self.core.services.store_service;
+        for store in self.stores.iter_mut().chain(self.selected_store.as_mut()) {
+            // 仅在列表中的门店保持原评分
+            if let Ok(updated) = store_service.get_store(&store.id) {
+                store.rating = updated.rating;
+            }
+        }
+    }
 
-        // Add sample products
-        for product in &self.products {
-            let _ = self.app_services.product_service.create_product(
-                product.name.clone(),
-                product.category.clone(),
-                product.description.clone(),
-                product.barcode.clone(),
-                product.tags.clone(),
-            );
+    /// Hand the saved settings to the services that keep their own copy;
+    /// called at startup and whenever the settings are saved or restored
+    fn apply_settings(&mut self) {
+        let config = self.settings_ui.get_config();
+        self.alert_ui
+            .alert_service_mut()
+            .monitor_mut()
+            .apply_settings(&config.monitoring_settings);
+        self.core
+            .services
+            .price_service
+            .set_rounding_mode(config.rounding_mode);
+    }
+
+    /// Whether a price is older than the configured freshness cutoff
+    fn is_price_stale(&self, record: &PriceRecord) -> bool {
+        let max_age_days = self
+            .settings_ui
+            .get_config()
+            .monitoring_settings
+            .price_freshness_days;
+        !PriceService::is_price_fresh(record, max_age_days)
+    }
+
+    /// Format an amount in the configured default currency
+    pub fn format_money(&self, amount: f64) -> String {
+        self.settings_ui
+            .get_config()
+            .price_formatter()
+            .format(amount)
+    }
+
+    /// Delete a product through the services; undoable with Ctrl+Z
+    pub fn delete_product(&mut self, product_id: &str) -> ServiceResult<()> {
+        let product = self.core.services.product_service.get_product(product_id)?;
+        self.core
+            .services
+            .product_service
+            .delete_product(product_id)?;
+        let action = AppAction::DeleteProduct(product);
+        self.sync_lists(&action, false);
+        self.undo_message = Some(format!("{}（Ctrl+Z 撤销）", action.description()));
+        self.undo_stack.record(action);
+        Ok(())
+    }
+
+    /// Delete a store through the services; undoable with Ctrl+Z
+    pub fn delete_store(&mut self, store_id: &str) -> ServiceResult<()> {
+        let store = self.core.services.store_service.get_store(store_id)?;
+        self.core.services.store_service.delete_store(store_id)?;
+        let action = AppAction::DeleteStore(store);
+        self.sync_lists(&action, false);
+        self.undo_message = Some(format!("{}（Ctrl+Z 撤销）", action.description()));
+        self.undo_stack.record(action);
+        Ok(())
+    }
+
+    /// Undo (`redo == false`) or redo the latest action
+    fn undo_or_redo(&mut self, redo: bool) {
+        let mut context = UndoContext {
+            services: &mut self.core.services,
+            #[cfg(not(target_arch = "wasm32"))]
+            scan_history: self.scanner_ui.scan_history_mut(),
+        };
+        let result = if redo {
+            self.undo_stack.redo(&mut context)
+        } else {
+            self.undo_stack.undo(&mut context)
+        };
+
+        match result {
+            Ok(Some(action)) => {
+                let action = action.clone();
+                // 撤销删除 → 列表中恢复；重做删除 → 列表中移除
+                self.sync_lists(&action, !redo);
+                let verb = if redo { "已重做" } else { "已撤销" };
+                self.undo_message = Some(format!("{}：{}", verb, action.description()));
+            }
+            Ok(None) => {}
+            Err(e) => {
+                log::warn!("Undo/redo failed: {}", e);
+                self.undo_message = Some(format!("操作失败：{}", e));
+            }
+        }
+    }
+
+    /// Run a keyboard shortcut or command palette action
+    fn execute_command(&mut self, command: Command) {
+        match command {
+            Command::SwitchTab(tab) => self.current_tab = tab,
+            Command::NewScan => {
+                self.current_tab = Tab::Scanner;
+                #[cfg(not(target_arch = "wasm32"))]
+                self.scanner_ui.start_auto_scan();
+            }
+            Command::Search => {
+                self.current_tab = Tab::Products;
+                self.focus_product_search = true;
+            }
+            Command::OpenSettings => self.current_tab = Tab::Settings,
+        }
+    }
+
+    /// Mirror a delete (`restored == false`) or its undo in the displayed lists
+    fn sync_lists(&mut self, action: &AppAction, restored: bool) {
+        match action {
+            AppAction::DeleteProduct(product) => {
+                self.products.retain(|p| p.id != product.id);
+                if restored {
+                    self.products.push(product.clone());
+                } else if self.selected_product.as_ref().map(|p| &p.id) == Some(&product.id) {
+                    self.selected_product = None;
+                }
+            }
+            AppAction::DeleteStore(store) => {
+                self.stores.retain(|s| s.id != store.id);
+                if restored {
+                    self.stores.push(store.clone());
+                } else if self.selected_store.as_ref().map(|s| &s.id) == Some(&store.id) {
+                    self.selected_store = None;
+                }
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            AppAction::ClearScanHistory(_) => {}
         }
     }
 
@@ -322,29 +495,33 @@ impl TemplateApp {
             ui.horizontal(|ui| {
                 ui.label("搜索：");
                 ui.add(egui::TextEdit::singleline(&mut self.search_text));
+                ui.label("范围：");
+                ui.add(egui::Slider::new(&mut self.search_radius_km, 1.0..=100.0).suffix("km"));
+                ui.checkbox(&mut self.open_now_only, "仅营业中");
+                ui.separator();
+                let live = self.current_location_label(ui.ctx()).unwrap_or_else(|| {
+                    format!(
+                        "{:.4}, {:.4}",
+                        self.current_location.0, self.current_location.1
+                    )
+                });
+                self.render_location_picker(ui, &live);
             });
         });
 
         ui.separator();
-        let filtered_stores: Vec<_> = self
-            .stores
-            .iter()
-            .filter(|store| {
-                self.search_text.is_empty()
-                    || store
-                        .name
-                        .to_lowercase()
-                        .contains(&self.search_text.to_lowercase())
-                    || store
-                        .address
-                        .to_lowercase()
-                        .contains(&self.search_text.to_lowercase())
-                    || store.tags.iter().any(|tag| {
-                        tag.to_lowercase()
-                            .contains(&self.search_text.to_lowercase())
-                    })
-            })
-            .collect();
+        let (latitude, longitude) = self.reference_location();
+        let mut filtered_stores = self.core.services.store_service.search_nearby(
+            &self.search_text,
+            latitude,
+            longitude,
+            self.search_radius_km,
+            &self.stores,
+        );
+        if self.open_now_only {
+            let now = chrono::Local::now().naive_local();
+            filtered_stores.retain(|(store, _)| store.open_status(now).is_open());
+        }
         ui.with_layout(
             egui::Layout::left_to_right(egui::Align::TOP).with_cross_justify(true),
             |ui| {
@@ -404,20 +581,16 @@ impl TemplateApp {
                                     });
                                 })
                                 .body(|mut body| {
-                                    for store in filtered_stores.iter() {
+                                    for (store, distance) in filtered_stores.iter() {
                                         let is_selected =
                                             self.selected_store.as_ref() == Some(store);
-                                        let distance = store.distance_to(
-                                            self.current_location.0,
-                                            self.current_location.1,
-                                        );
                                         body.row(20.0, |mut row| {
                                             row.col(|ui| {
                                                 if ui
                                                     .selectable_label(is_selected, &store.name)
                                                     .clicked()
                                                 {
-                                                    self.selected_store = Some((*store).clone());
+                                                    self.selected_store = Some(store.clone());
                                                 }
                                             });
                                             row.col(|ui| {
@@ -442,15 +615,48 @@ impl TemplateApp {
         );
 
         // 地图区域
+        let mut store_to_delete = None;
+        let mut hours_to_save = None;
         if let Some(selected_store) = &self.selected_store {
+            if self.hours_editor.as_ref().map(|(id, _)| id) != Some(&selected_store.id) {
+                self.hours_editor = Some((
+                    selected_store.id.clone(),
+                    OpeningHoursEditor::from_text(&selected_store.opening_hours),
+                ));
+            }
             if let Some(tiles) = &mut self.tiles {
                 egui::Window::new("地图").show(ui.ctx(), |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(&selected_store.address);
+                        // 可解析的号码显示为可点击的 tel: 链接
+                        match normalize_phone(&selected_store.phone, DEFAULT_PHONE_REGION) {
+                            Some(e164) => {
+                                ui.hyperlink_to(
+                                    format!("📞 {}", selected_store.phone),
+                                    format!("tel:{}", e164),
+                                );
+                            }
+                            None => {
+                                ui.label(format!("📞 {}", selected_store.phone));
+                            }
+                        }
+                        if ui.button("🗑 删除门店").clicked() {
+                            store_to_delete = Some(selected_store.id.clone());
+                        }
+                    });
+                    if let Some((_, editor)) = &mut self.hours_editor {
+                        egui::CollapsingHeader::new("🕒 编辑营业时间").show(ui, |ui| {
+                            if let Some(hours) = editor.show(ui) {
+                                hours_to_save = Some((selected_store.id.clone(), hours));
+                            }
+                        });
+                    }
                     let store_pos =
                         Position::new(selected_store.longitude, selected_store.latitude);
                     let places = Places::new(
                         filtered_stores
                             .iter()
-                            .map(|store| LabeledSymbol {
+                            .map(|(store, _)| LabeledSymbol {
                                 position: Position::new(store.longitude, store.latitude),
                                 label: store.name.clone(),
                                 symbol: Some(Symbol::Circle("🏪".to_string())),
@@ -502,12 +708,105 @@ impl TemplateApp {
                 });
             }
         }
+        if let Some((store_id, hours)) = hours_to_save {
+            self.save_store_hours(&store_id, &hours);
+        }
+        if let Some(store_id) = store_to_delete {
+            if let Err(e) = self.delete_store(&store_id) {
+                log::warn!("Cannot delete store {}: {}", store_id, e);
+            }
+        }
+    }
+
+    /// Save edited opening hours in canonical form to the services and the displayed list
+    fn save_store_hours(&mut self, store_id: &str, hours: &OpeningHours) {
+        let canonical = hours.to_canonical_string();
+        let result = self.core.services.store_service.update_store(
+            store_id,
+            None,
+            None,
+            None,
+            None,
+            Some(canonical.clone()),
+            None,
+            None,
+        );
+        match result {
+            // 仅在列表中的门店（如示例数据）不在服务中
+            Ok(_) | Err(ServiceError::NotFound(_)) => {}
+            Err(e) => {
+                log::warn!("Cannot update hours of store {}: {}", store_id, e);
+                return;
+            }
+        }
+        for store in self
+            .stores
+            .iter_mut()
+            .chain(self.selected_store.as_mut())
+            .filter(|s| s.id == store_id)
+        {
+            store.opening_hours = canonical.clone();
+        }
+    }
+
+    /// Show a product's details and remember it as recently viewed by the current user
+    fn open_product(&mut self, product: Product) {
+        let user_id = self.auth_ui.get_current_user().map(|u| u.id.clone());
+        self.recently_viewed.record(user_id.as_deref(), &product.id);
+        self.selected_product = Some(product);
+    }
+
+    /// Quick-access strip of the current user's recently viewed products
+    fn render_recently_viewed(&mut self, ui: &mut egui::Ui) {
+        let user_id = self.auth_ui.get_current_user().map(|u| u.id.clone());
+        let recent: Vec<Product> = self
+            .recently_viewed
+            .for_user(user_id.as_deref())
+            .into_iter()
+            .flat_map(|list| list.ids())
+            .filter_map(|id| self.products.iter().find(|p| p.id == id).cloned())
+            .collect();
+        if recent.is_empty() {
+            return;
+        }
+        let mut opened = None;
+        ui.horizontal_wrapped(|ui| {
+            ui.label("最近浏览：");
+            for product in recent {
+                if ui.small_button(&product.name).clicked() {
+                    opened = Some(product);
+                }
+            }
+        });
+        if let Some(product) = opened {
+            self.open_product(product);
+        }
+    }
+
+    /// 跳转到通知等处给出的商品或商店
+    fn open_deep_link(&mut self, link: DeepLink) {
+        match link {
+            DeepLink::Product { product_id, .. } => {
+                self.current_tab = Tab::Products;
+                match self.products.iter().find(|p| p.id == product_id).cloned() {
+                    Some(product) => self.open_product(product),
+                    None => self.selected_product = None,
+                }
+            }
+            DeepLink::Store { store_id } => {
+                self.current_tab = Tab::Stores;
+                self.selected_store = self.stores.iter().find(|s| s.id == store_id).cloned();
+            }
+        }
     }
 
     fn render_products_tab(&mut self, ui: &mut egui::Ui) {
         ui.horizontal(|ui| {
             ui.label("搜索商品：");
-            ui.text_edit_singleline(&mut self.product_search_text);
+            let search = ui.text_edit_singleline(&mut self.product_search_text);
+            if std::mem::take(&mut self.focus_product_search) {
+                search.request_focus();
+            }
 
             // 分类过滤
             egui::ComboBox::from_label("分类")
@@ -540,9 +839,11 @@ impl TemplateApp {
                 });
         });
 
+        self.render_recently_viewed(ui);
         ui.separator();
 
         // 商品列表
+        let mut opened = None;
         egui::ScrollArea::vertical().show(ui, |ui| {
             // 过滤商品
             let filtered_products: Vec<_> = self
@@ -577,13 +878,20 @@ impl TemplateApp {
                 ui.label("分类");
                 ui.label("最低价格");
                 ui.label("价格范围");
+                ui.label("评分");
                 ui.label("标签");
             });
             ui.separator();
 
             for product in filtered_products {
-                let lowest_price = product.current_lowest_price();
+                let lowest_price =
+                    self.format_money(self.lowest_price_today(product).unwrap_or(0.0));
                 let price_range = self.get_price_range(product);
+                let price_range = format!(
+                    "{} - {}",
+                    self.format_money(price_range.0),
+                    self.format_money(price_range.1)
+                );
 
                 ui.horizontal(|ui| {
                     let selected_product_id = self.selected_product.as_ref().map(|p| p.id.clone());
@@ -594,24 +902,210 @@ impl TemplateApp {
                         )
                         .clicked()
                     {
-                        self.selected_product = Some(product.clone());
+                        opened = Some(product.clone());
                     }
                     ui.label(&product.category);
-                    ui.label(format!("¥{:.2}", lowest_price.map_or(0.0, |p| p.price)));
-                    ui.label(format!("¥{:.2} - ¥{:.2}", price_range.0, price_range.1));
+                    ui.label(lowest_price);
+                    ui.label(price_range);
+                    match self
+                        .core
+                        .services
+                        .review_service
+                        .product_rating(&product.id)
+                    {
+                        Some(rating) => ui.label(format!(
+                            "{} {:.1} ({})",
+                            rating.stars(),
+                            rating.average,
+                            rating.count
+                        )),
+                        None => ui.label("无评价"),
+                    };
                     ui.label(product.tags.join("、"));
                 });
             }
         });
 
+        if let Some(product) = opened {
+            self.open_product(product);
+        }
+
         // 如果选中了商品，显示详情
         if let Some(selected_product) = &self.selected_product {
-            self.show_product_detail(ui, selected_product);
+            if self.show_product_detail(ui, selected_product) {
+                let product_id = selected_product.id.clone();
+                if let Err(e) = self.delete_product(&product_id) {
+                    log::warn!("Cannot delete product {}: {}", product_id, e);
+                }
+            }
+        }
+
+        // 登录用户可为选中商品提交门店价格
+        let (Some(product_id), Some(user_id)) = (
+            self.selected_product.as_ref().map(|p| p.id.clone()),
+            self.auth_ui.get_current_user().map(|u| u.id.clone()),
+        ) else {
+            return;
+        };
+        if self.price_form.as_ref().map(|f| f.product_id()) != Some(product_id.as_str()) {
+            self.price_form = Some(PriceSubmissionForm::new(&product_id));
+        }
+        let mut submission = None;
+        egui::Window::new("提交价格").show(ui.ctx(), |ui| {
+            if let Some(form) = &mut self.price_form {
+                submission = form.show(ui, &self.stores);
+            }
+        });
+        if let Some(submission) = submission {
+            match self.submit_user_price(&product_id, &user_id, submission) {
+                Ok(()) => self.price_form = Some(PriceSubmissionForm::new(&product_id)),
+                Err(e) => {
+                    if let Some(form) = &mut self.price_form {
+                        form.set_error(e.to_string());
+                    }
+                }
+            }
         }
     }
 
+    /// Record a user's price through the price service and show it in the product's history
+    fn submit_user_price(
+        &mut self,
+        product_id: &str,
+        user_id: &str,
+        submission: PriceSubmission,
+    ) -> ServiceResult<()> {
+        // 小票图片先存入数据目录，记录中保存相对路径
+        let receipt_image = match &submission.receipt_image {
+            Some(path) => {
+                let store = self.image_store.as_ref().ok_or_else(|| {
+                    ServiceError::ExternalServiceError("Receipt storage unavailable".to_string())
+                })?;
+                Some(
+                    store
+                        .save_file(std::path::Path::new(path))
+                        .map_err(|e| ServiceError::ValidationError(e.to_string()))?,
+                )
+            }
+            None => None,
+        };
+        let record = match submission.source {
+            PriceSource::Manual => self.core.services.price_service.add_price_record(
+                product_id,
+                &submission.store_id,
+                user_id,
+                submission.price,
+                submission.is_on_sale,
+                receipt_image,
+            )?,
+            source => {
+                let record = PriceRecord::new(
+                    Some(product_id.to_string()),
+                    submission.store_id,
+                    Some(user_id.to_string()),
+                    submission.price,
+                    submission.is_on_sale,
+                    receipt_image,
+                )
+                .with_source(source);
+                self.core.services.price_service.submit_record(record)?
+            }
+        };
+        self.store_new_price(record);
+        Ok(())
+    }
+
+    /// Queue a newly saved price for the database and show it in its product's history
+    fn store_new_price(&mut self, record: PriceRecord) {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Err(e) = self.core.sync_price(&record) {
+            log::warn!("Failed to queue price {:?} for sync: {}", record.id, e);
+        }
+        self.show_new_price(record);
+    }
+
+    /// Add a price saved through the price service to its product's history
+    fn show_new_price(&mut self, record: PriceRecord) {
+        let Some(product_id) = record.product_id.clone() else {
+            return;
+        };
+        for product in self
+            .products
+            .iter_mut()
+            .chain(self.selected_product.as_mut())
+            .filter(|p| p.id == product_id)
+        {
+            product.prices.push(record.clone());
+        }
+    }
+
+    /// Save a price entered on the scanner tab through the price service
+    #[cfg(not(target_arch = "wasm32"))]
+    fn submit_scanner_price(&mut self, entry: ScannerPriceEntry) {
+        let result = match entry {
+            ScannerPriceEntry::Scanned {
+                barcode,
+                store_id,
+                price,
+            } => {
+                let user_id = self.auth_ui.get_current_user().map(|u| u.id.clone());
+                self.core
+                    .services
+                    .price_service
+                    .submit_scanned_price(&barcode, store_id, user_id, price)
+                    .map(|record| {
+                        self.store_new_price(record);
+                        "Price saved".to_string()
+                    })
+                    .map_err(|e| e.to_string())
+            }
+            ScannerPriceEntry::CsvFile { path, store_id } => std::fs::read_to_string(&path)
+                .map_err(|e| format!("Cannot read {}: {}", path.display(), e))
+                .and_then(|csv| {
+                    self.core
+                        .services
+                        .price_service
+                        .import_csv(&csv, &store_id)
+                        .map_err(|e| e.to_string())
+                })
+                .map(|report| {
+                    for id in &report.imported_ids {
+                        if let Ok(record) = self.core.services.price_service.get_price_record(id) {
+                            self.store_new_price(record);
+                        }
+                    }
+                    format!(
+                        "Imported {} prices ({} unknown barcodes, {} errors)",
+                        report.imported_count(),
+                        report.skipped.len(),
+                        report.errors.len()
+                    )
+                }),
+        };
+        self.scanner_ui.report_price_result(result);
+    }
+
+    /// Lowest of today's prices that count under the price service's verification policy
+    fn lowest_price_today(&self, product: &Product) -> Option<f64> {
+        let today = Utc::now().date_naive();
+        product
+            .prices
+            .iter()
+            .filter(|p| {
+                self.core.services.price_service.counts_in_stats(p)
+                    && p.timestamp.date_naive() == today
+            })
+            .map(|p| p.price)
+            .min_by(|a, b| a.total_cmp(b))
+    }
+
     fn get_price_range(&self, product: &Product) -> (f64, f64) {
-        let prices: Vec<_> = product.prices.iter().map(|p| p.price).collect();
+        let prices: Vec<_> = product
+            .prices
+            .iter()
+            .filter(|p| self.core.services.price_service.counts_in_stats(p))
+            .map(|p| p.price)
+            .collect();
         match (
             prices.iter().min_by(|a, b| a.partial_cmp(b).unwrap()),
             prices.iter().max_by(|a, b| a.partial_cmp(b).unwrap()),
@@ -621,17 +1115,58 @@ impl TemplateApp {
         }
     }
 
-    fn show_product_detail(&self, ui: &mut egui::Ui, product: &Product) {
+    /// Returns true when the user asked to delete the product
+    fn show_product_detail(&self, ui: &mut egui::Ui, product: &Product) -> bool {
+        let mut delete_requested = false;
         egui::Window::new("商品详情").show(ui.ctx(), |ui| {
-            ui.heading(&product.name);
+            ui.horizontal(|ui| {
+                ui.heading(&product.name);
+                if ui.button("🗑 删除商品").clicked() {
+                    delete_requested = true;
+                }
+            });
             ui.label(&product.description);
 
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(barcode) = &product.barcode {
+                ui.horizontal(|ui| {
+                    ui.label(format!("条码: {}", barcode));
+                    if ui.button("💾 保存条码图片").clicked() {
+                        match crate::utils::render_barcode(
+                            barcode,
+                            crate::models::BarcodeType::for_code(barcode),
+                        ) {
+                            Ok(png) => Self::save_png(&format!("{}.png", barcode), &png),
+                            Err(e) => log::warn!("Cannot render barcode {}: {}", barcode, e),
+                        }
+                    }
+                });
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            if ui.button("📤 保存分享二维码").clicked() {
+                let link = DeepLink::Product {
+                    product_id: product.id.clone(),
+                    store_id: None,
+                };
+                match crate::utils::make_share_qr(&link) {
+                    Ok(png) => Self::save_png(&format!("{}-qr.png", product.id), &png),
+                    Err(e) => log::warn!("Cannot render share QR for {}: {}", product.id, e),
+                }
+            }
+
             ui.separator();
 
             // 价格历史
             ui.heading("价格历史");
             let mut prices: Vec<_> = product.prices.iter().collect();
             prices.sort_by_key(|p| p.timestamp);
+            // 各门店最新的一条价格，过旧时加标记
+            let mut latest_by_store: std::collections::HashMap<&str, &PriceRecord> =
+                std::collections::HashMap::new();
+            for price in &prices {
+                latest_by_store.insert(price.store_id.as_str(), price);
+            }
 
             for price in prices {
                 let store = self
@@ -642,16 +1177,164 @@ impl TemplateApp {
                     .unwrap_or("未知商店");
 
                 ui.horizontal(|ui| {
+                    ui.label(price.source.icon())
+                        .on_hover_text(format!("来源: {:?}", price.source));
                     ui.label(format!(
-                        "{} - ¥{:.2} {}",
-                        price.timestamp.format("%Y-%m-%d"),
-                        price.price,
+                        "{} - {} {}",
+                        self.local_time(&price.timestamp).format("%Y-%m-%d"),
+                        self.format_money(price.price),
                         if price.is_on_sale { "[特价]" } else { "" }
                     ));
                     ui.label(store);
+                    if latest_by_store
+                        .get(price.store_id.as_str())
+                        .is_some_and(|latest| std::ptr::eq(*latest, price))
+                        && self.is_price_stale(price)
+                    {
+                        ui.colored_label(egui::Color32::GRAY, "数据较旧");
+                    }
+                    if let Some(texture) = price
+                        .receipt_image
+                        .as_deref()
+                        .and_then(|path| self.receipt_thumbnail(ui.ctx(), path))
+                    {
+                        ui.add(egui::Image::new(&texture).max_height(48.0))
+                            .on_hover_text("小票");
+                    }
                 });
             }
         });
+        delete_requested
+    }
+
+    /// Texture of a stored receipt's thumbnail, cached in egui memory.
+    /// Failed loads are cached too, so a missing file is not re-read every frame
+    fn receipt_thumbnail(&self, ctx: &egui::Context, path: &str) -> Option<egui::TextureHandle> {
+        let id = egui::Id::new(("receipt_thumbnail", path));
+        if let Some(cached) = ctx.data(|d| d.get_temp::<Option<egui::TextureHandle>>(id)) {
+            return cached;
+        }
+        let store = self.image_store.as_ref()?;
+        let texture = match store.load_thumbnail(path) {
+            Ok(thumbnail) => {
+                let image = egui::ColorImage::from_rgba_unmultiplied(
+                    [thumbnail.width() as usize, thumbnail.height() as usize],
+                    thumbnail.as_raw(),
+                );
+                Some(ctx.load_texture(path, image, egui::TextureOptions::LINEAR))
+            }
+            Err(e) => {
+                log::warn!("Failed to load receipt thumbnail {}: {}", path, e);
+                None
+            }
+        };
+        ctx.data_mut(|d| d.insert_temp(id, texture.clone()));
+        texture
+    }
+
+    /// Save PNG bytes where the user chooses
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_png(file_name: &str, png: &[u8]) {
+        let Some(path) = rfd::FileDialog::new()
+            .set_file_name(file_name)
+            .add_filter("PNG", &["png"])
+            .save_file()
+        else {
+            return;
+        };
+        if let Err(e) = std::fs::write(&path, png) {
+            log::error!("Failed to save image {}: {}", path.display(), e);
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    /// Alerts and settings included in a backup, which live outside the services
+    fn backup_extras(&self) -> BackupExtras {
+        BackupExtras {
+            alerts: self
+                .alert_ui
+                .alert_service()
+                .monitor()
+                .get_all_alerts()
+                .unwrap_or_default(),
+            config: self.settings_ui.get_config().clone(),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    /// Put restored data back where the UI reads it
+    fn apply_backup_extras(&mut self, extras: BackupExtras) -> Result<(), String> {
+        self.alert_ui
+            .alert_service()
+            .monitor()
+            .replace_alerts(extras.alerts)
+            .map_err(|e| e.to_string())?;
+        extras.config.save().map_err(|e| e.to_string())?;
+        self.settings_ui.apply_config(extras.config);
+        self.apply_settings();
+
+        let services = &mut self.core.services;
+        self.products = services
+            .product_service
+            .get_all_products()
+            .map_err(|e| e.to_string())?;
+        for product in &self.products {
+            services.price_service.register_product(product);
+        }
+        self.stores = services
+            .store_service
+            .list_stores(0, usize::MAX)
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// Export or import a backup file chosen by the user
+    #[cfg(not(target_arch = "wasm32"))]
+    fn run_data_action(&mut self, action: DataAction) {
+        let result = match action {
+            DataAction::Export => {
+                let Some(path) = rfd::FileDialog::new()
+                    .set_file_name("eprice-backup.zip")
+                    .add_filter("ZIP", &["zip"])
+                    .save_file()
+                else {
+                    return;
+                };
+                self.core
+                    .services
+                    .export_backup(&self.backup_extras())
+                    .map_err(|e| e.to_string())
+                    .and_then(|bytes| {
+                        std::fs::write(&path, bytes)
+                            .map_err(|e| format!("无法写入 {}: {}", path.display(), e))
+                    })
+            }
+            DataAction::Import => {
+                let Some(path) = rfd::FileDialog::new()
+                    .add_filter("ZIP", &["zip"])
+                    .pick_file()
+                else {
+                    return;
+                };
+                let mut extras = self.backup_extras();
+                std::fs::read(&path)
+                    .map_err(|e| format!("无法读取 {}: {}", path.display(), e))
+                    .and_then(|bytes| {
+                        self.core
+                            .services
+                            .import_backup(&bytes, &mut extras)
+                            .map_err(|e| e.to_string())
+                    })
+                    .and_then(|_| self.apply_backup_extras(extras))
+            }
+        };
+        self.settings_ui.report_data_result(result);
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn run_data_action(&mut self, _action: DataAction) {
+        self.settings_ui
+            .report_data_result(Err("备份仅支持桌面版".to_string()));
     }
 
     fn render_community_tab(&mut self, ui: &mut egui::Ui) {
@@ -666,7 +1349,7 @@ impl TemplateApp {
             ui.vertical(|ui| {
                 ui.heading("最新评价");
 
-                match self.app_services.review_service.get_recent_reviews(0, 5) {
+                match self.core.services.review_service.get_recent_reviews(0, 5) {
                     Ok(reviews) => {
                         if reviews.is_empty() {
                             ui.label("暂无评价");
@@ -675,7 +1358,7 @@ impl TemplateApp {
                                 ui.group(|ui| {
                                     ui.horizontal(|ui| {
                                         ui.label(format!("⭐ {}/5", review.rating));
-                                        ui.label(review.created_at.format("%m-%d").to_string());
+                                        self.relative_time_label(ui, &review.created_at);
                                     });
                                     ui.label(&review.comment);
 
@@ -710,7 +1393,7 @@ impl TemplateApp {
             ui.vertical(|ui| {
                 ui.heading("系统统计");
 
-                match self.app_services.review_service.get_review_stats() {
+                match self.core.services.review_service.get_review_stats() {
                     Ok(stats) => {
                         ui.label(format!("总评价数: {}", stats.total_reviews));
                         ui.label(format!("店铺评价: {}", stats.store_reviews));
@@ -741,13 +1424,16 @@ impl TemplateApp {
             if let Some(current_user) = self.auth_ui.get_current_user() {
                 if !self.stores.is_empty() {
                     let store = &self.stores[0];
-                    let _ = self.app_services.review_service.submit_review(
+                    let submitted = self.core.services.review_service.submit_review(
                         current_user.id.clone(),
                         Some(store.id.clone()),
                         None,
                         4,
                         "这是一个测试评价，服务不错！".to_string(),
                     );
+                    if submitted.is_ok() {
+                        self.refresh_store_ratings();
+                    }
                 }
             }
         }
@@ -762,6 +1448,42 @@ impl eframe::App for TemplateApp {
 
     /// Called each time the UI needs repainting, which may be many times per second.
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // 撤销 / 重做；文本框获得焦点时留给它自己的撤销
+        let editing_text = ctx.wants_keyboard_input();
+        if !editing_text {
+            if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::Z)) {
+                self.undo_or_redo(false);
+            } else if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::Y)) {
+                self.undo_or_redo(true);
+            }
+        }
+
+        // Ctrl+1..Ctrl+7 切换标签页，Ctrl+K 打开命令面板（输入文字时不拦截）
+        const TAB_KEYS: [egui::Key; 7] = [
+            egui::Key::Num1,
+            egui::Key::Num2,
+            egui::Key::Num3,
+            egui::Key::Num4,
+            egui::Key::Num5,
+            egui::Key::Num6,
+            egui::Key::Num7,
+        ];
+        if !editing_text {
+            for (index, key) in TAB_KEYS.into_iter().enumerate() {
+                if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, key)) {
+                    if let Some(tab) = commands::tab_for_shortcut(index + 1) {
+                        self.execute_command(Command::SwitchTab(tab));
+                    }
+                }
+            }
+            if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::K)) {
+                self.command_palette.toggle();
+            }
+        }
+        if let Some(command) = self.command_palette.show(ctx) {
+            self.execute_command(command);
+        }
+
         // 顶部导航栏
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             egui::MenuBar::new().ui(ui, |ui| {
@@ -807,99 +1529,135 @@ impl eframe::App for TemplateApp {
                     ui.add_space(8.0);
 
                     // Show service statistics
-                    if let Ok(user_stats) = self.app_services.user_service.get_user_stats() {
+                    if let Ok(user_stats) = self.core.services.user_service.get_user_stats() {
                         ui.label(format!("用户: {}", user_stats.total_users));
                     }
 
-                    if let Ok(product_stats) = self.app_services.product_service.get_product_stats()
+                    if let Ok(product_stats) =
+                        self.core.services.product_service.get_product_stats()
                     {
                         ui.label(format!("商品: {}", product_stats.total_products));
                     }
 
-                    if let Ok(store_stats) = self.app_services.store_service.get_store_stats() {
+                    if let Ok(store_stats) = self.core.services.store_service.get_store_stats() {
                         ui.label(format!("店铺: {}", store_stats.total_stores));
                     }
                 }
+
+                if let Some(message) = &self.undo_message {
+                    ui.add_space(16.0);
+                    ui.weak(message);
+                }
             });
         });
 
         // 侧边栏
         egui::SidePanel::left("side_panel").show(ctx, |ui| {
-            ui.heading("功能导航");
+            ui.heading(tr!("nav.title"));
             ui.separator();
 
             if ui
-                .selectable_label(self.current_tab == Tab::Stores, "门店管理")
+                .selectable_label(self.current_tab == Tab::Stores, tr!("nav.stores"))
                 .clicked()
             {
                 self.current_tab = Tab::Stores;
             }
             if ui
-                .selectable_label(self.current_tab == Tab::Products, "商品比价")
+                .selectable_label(self.current_tab == Tab::Products, tr!("nav.products"))
                 .clicked()
             {
                 self.current_tab = Tab::Products;
             }
             #[cfg(not(target_arch = "wasm32"))]
             if ui
-                .selectable_label(self.current_tab == Tab::Scanner, "条码扫描")
+                .selectable_label(self.current_tab == Tab::Scanner, tr!("nav.scanner"))
                 .clicked()
             {
                 self.current_tab = Tab::Scanner;
             }
             if ui
-                .selectable_label(self.current_tab == Tab::Alerts, "价格提醒")
+                .selectable_label(self.current_tab == Tab::Alerts, tr!("nav.alerts"))
                 .clicked()
             {
                 self.current_tab = Tab::Alerts;
             }
             if ui
-                .selectable_label(self.current_tab == Tab::Trends, "价格趋势")
+                .selectable_label(self.current_tab == Tab::Trends, tr!("nav.trends"))
                 .clicked()
             {
                 self.current_tab = Tab::Trends;
             }
             if ui
-                .selectable_label(self.current_tab == Tab::Community, "用户互动")
+                .selectable_label(self.current_tab == Tab::Community, tr!("nav.community"))
                 .clicked()
             {
                 self.current_tab = Tab::Community;
             }
+            if ui
+                .selectable_label(self.current_tab == Tab::Settings, tr!("nav.settings"))
+                .clicked()
+            {
+                self.current_tab = Tab::Settings;
+            }
         });
 
         // 主内容区
-        egui::CentralPanel::default().show(ctx, |ui| {
-            match self.current_tab {
-                Tab::Stores => self.render_stores_tab(ui),
-                Tab::Products => self.render_products_tab(ui),
-                #[cfg(not(target_arch = "wasm32"))]
-                Tab::Scanner => {
-                    self.scanner_ui.show(ctx, ui);
+        egui::CentralPanel::default().show(ctx, |ui| match self.current_tab {
+            Tab::Stores => self.render_stores_tab(ui),
+            Tab::Products => self.render_products_tab(ui),
+            #[cfg(not(target_arch = "wasm32"))]
+            Tab::Scanner => {
+                self.scanner_ui.set_price_stores(
+                    self.stores
+                        .iter()
+                        .map(|s| (s.id.clone(), s.name.clone()))
+                        .collect(),
+                );
+                self.scanner_ui.show(ctx, ui);
+                if let Some(entry) = self.scanner_ui.take_price_entry() {
+                    self.submit_scanner_price(entry);
                 }
-                #[cfg(target_arch = "wasm32")]
-                Tab::Scanner => {
-                    ui.heading("Scanner");
-                    ui.label("Scanner functionality is only available on desktop platforms.");
+                if let Some(link) = self.scanner_ui.take_deep_link() {
+                    self.open_deep_link(link);
                 }
-                Tab::Alerts => {
-                    if let Some(current_user) = self.auth_ui.get_current_user() {
-                        self.alert_ui.show(ui, &current_user.id);
-                    } else {
-                        ui.heading("价格提醒");
-                        ui.colored_label(egui::Color32::YELLOW, "请先登录以使用价格提醒功能");
-                        ui.label("登录后您可以设置价格提醒，当商品价格达到您设定的目标价格时会收到通知。");
-                    }
+                if let Some(items) = self.scanner_ui.take_cleared_history() {
+                    self.undo_stack.record(AppAction::ClearScanHistory(items));
                 }
-                Tab::Trends => {
-                    self.render_trends_tab(ui);
+            }
+            #[cfg(target_arch = "wasm32")]
+            Tab::Scanner => {
+                ui.heading("Scanner");
+                ui.label("Scanner functionality is only available on desktop platforms.");
+            }
+            Tab::Alerts => {
+                if let Some(current_user) = self.auth_ui.get_current_user() {
+                    self.alert_ui
+                        .set_price_formatter(self.settings_ui.get_config().price_formatter());
+                    self.alert_ui.show(ui, &current_user.id);
+                    if let Some(link) = self.alert_ui.take_deep_link() {
+                        self.open_deep_link(link);
+                    }
+                } else {
+                    ui.heading("价格提醒");
+                    ui.colored_label(egui::Color32::YELLOW, "请先登录以使用价格提醒功能");
+                    ui.label(
+                        "登录后您可以设置价格提醒，当商品价格达到您设定的目标价格时会收到通知。",
+                    );
                 }
-                Tab::Community => {
-                    self.render_community_tab(ui);
+            }
+            Tab::Trends => {
+                self.render_trends_tab(ui);
+            }
+            Tab::Community => {
+                self.render_community_tab(ui);
+            }
+            Tab::Settings => {
+                self.settings_ui.show(ui);
+                if self.settings_ui.take_config_saved() {
+                    self.apply_settings();
                 }
-                Tab::Settings => {
-                    ui.heading("设置");
-                    ui.label("在这里可以设置应用的配置");
-                    // TODO: 添加设置功能
+                if let Some(action) = self.settings_ui.take_data_action() {
+                    self.run_data_action(action);
                 }
             }
         });
@@ -914,6 +1672,16 @@ impl TemplateApp {
     fn render_trends_tab(&mut self, ui: &mut egui::Ui) {
         ui.heading("价格趋势分析");
         ui.label("商品价格历史走势分析");
+        let mut include_unverified = self.core.services.price_service.include_unverified();
+        if ui
+            .checkbox(&mut include_unverified, "统计包含未验证价格")
+            .changed()
+        {
+            self.core
+                .services
+                .price_service
+                .set_include_unverified(include_unverified);
+        }
         ui.separator();
 
         // Product selection for trend analysis
@@ -960,14 +1728,15 @@ impl TemplateApp {
                 ui.label("价格统计");
 
                 if let Ok(stats) = self
-                    .app_services
+                    .core
+                    .services
                     .price_service
                     .get_price_statistics(&product.id)
                 {
-                    ui.label(format!("最低价: ¥{:.2}", stats.min_price));
-                    ui.label(format!("最高价: ¥{:.2}", stats.max_price));
-                    ui.label(format!("平均价: ¥{:.2}", stats.avg_price));
-                    ui.label(format!("中位数: ¥{:.2}", stats.median_price));
+                    ui.label(format!("最低价: {}", self.format_money(stats.min_price)));
+                    ui.label(format!("最高价: {}", self.format_money(stats.max_price)));
+                    ui.label(format!("平均价: {}", self.format_money(stats.avg_price)));
+                    ui.label(format!("中位数: {}", self.format_money(stats.median_price)));
                     ui.label(format!("价格记录数: {}", stats.total_records));
                     ui.label(format!("覆盖店铺数: {}", stats.stores_count));
                     ui.label(format!("促销比例: {:.1}%", stats.sale_percentage));
@@ -995,8 +1764,9 @@ impl TemplateApp {
                     let price_change_percent = (price_change / first_price) * 100.0;
 
                     ui.label(format!(
-                        "价格变化: ¥{:.2} ({:+.1}%)",
-                        price_change, price_change_percent
+                        "价格变化: {} ({:+.1}%)",
+                        self.format_money(price_change),
+                        price_change_percent
                     ));
 
                     let trend_color = if price_change > 0.0 {
@@ -1033,15 +1803,24 @@ impl TemplateApp {
 
     /// Render a simple price chart using egui
     fn render_price_chart(&self, ui: &mut egui::Ui, product: &Product) {
-        ui.label("价格走势图");
-
-        let mut prices: Vec<_> = product.prices.iter().collect();
-        prices.sort_by_key(|p| p.timestamp);
-
-        if prices.is_empty() {
+        let Some(chart) = PriceChartData::from_prices(&product.prices) else {
+            ui.label("价格走势图");
             ui.label("暂无数据可显示");
             return;
-        }
+        };
+
+        ui.horizontal(|ui| {
+            ui.label("价格走势图");
+            #[cfg(not(target_arch = "wasm32"))]
+            if ui.button("导出图表").clicked() {
+                match render_price_chart_png(&chart, CHART_WIDTH_PX, CHART_HEIGHT_PX, |price| {
+                    self.format_money(price)
+                }) {
+                    Ok(png) => Self::save_png(&format!("{}-chart.png", product.name), &png),
+                    Err(e) => log::error!("Failed to render price chart: {}", e),
+                }
+            }
+        });
 
         // Create a simple line chart
         let chart_rect = ui.available_rect_before_wrap();
@@ -1062,25 +1841,18 @@ impl TemplateApp {
             egui::Color32::from_rgb(240, 240, 240),
         );
 
-        if prices.len() > 1 {
-            let min_price = prices.iter().map(|p| p.price).fold(f64::INFINITY, f64::min);
-            let max_price = prices
-                .iter()
-                .map(|p| p.price)
-                .fold(f64::NEG_INFINITY, f64::max);
-            let price_range = (max_price - min_price).max(0.01); // Avoid division by zero
+        if chart.points.len() > 1 {
+            let (min_price, max_price) = (chart.min_price, chart.max_price);
 
             // Draw price line
-            let points: Vec<egui::Pos2> = prices
-                .iter()
-                .enumerate()
-                .map(|(i, price_record)| {
-                    let x = chart_rect.min.x
-                        + (i as f32 / (prices.len() - 1) as f32) * chart_rect.width();
-                    let y = chart_rect.max.y
-                        - ((price_record.price - min_price) / price_range) as f32
-                            * chart_rect.height();
-                    egui::pos2(x, y)
+            let points: Vec<egui::Pos2> = chart
+                .normalized()
+                .into_iter()
+                .map(|(x, y)| {
+                    egui::pos2(
+                        chart_rect.min.x + x * chart_rect.width(),
+                        chart_rect.max.y - y * chart_rect.height(),
+                    )
                 })
                 .collect();
 
@@ -1094,20 +1866,41 @@ impl TemplateApp {
 
             // Draw price points
             for (i, point) in points.iter().enumerate() {
-                let color = if prices[i].is_on_sale {
+                let color = if chart.points[i].on_sale {
                     egui::Color32::RED // Red for sale prices
                 } else {
                     egui::Color32::BLUE // Blue for regular prices
                 };
 
                 painter.circle_filled(*point, 3.0, color);
+
+                // 节假日附近的价格点加橙色圆圈并标注名称
+                let date = chart.points[i]
+                    .timestamp
+                    .with_timezone(&chrono::Local)
+                    .date_naive();
+                if let Some(notable) = self
+                    .holiday_calendar
+                    .as_ref()
+                    .and_then(|calendar| calendar.annotation_for(date))
+                {
+                    let orange = egui::Color32::from_rgb(255, 140, 0);
+                    painter.circle_stroke(*point, 6.0, egui::Stroke::new(1.5, orange));
+                    painter.text(
+                        *point - egui::vec2(0.0, 8.0),
+                        egui::Align2::CENTER_BOTTOM,
+                        &notable.label,
+                        egui::FontId::proportional(11.0),
+                        orange,
+                    );
+                }
             }
 
             // Draw price labels
             painter.text(
                 egui::pos2(chart_rect.min.x + 5.0, chart_rect.min.y + 5.0),
                 egui::Align2::LEFT_TOP,
-                format!("最高: ¥{:.2}", max_price),
+                format!("最高: {}", self.format_money(max_price)),
                 egui::FontId::default(),
                 egui::Color32::BLACK,
             );
@@ -1115,7 +1908,7 @@ impl TemplateApp {
             painter.text(
                 egui::pos2(chart_rect.min.x + 5.0, chart_rect.max.y - 20.0),
                 egui::Align2::LEFT_BOTTOM,
-                format!("最低: ¥{:.2}", min_price),
+                format!("最低: {}", self.format_money(min_price)),
                 egui::FontId::default(),
                 egui::Color32::BLACK,
             );
@@ -1143,6 +1936,15 @@ impl TemplateApp {
             return;
         }
 
+        // 按包装规格换算单价，例如 ¥/100ml；规格与件数沿用价格服务登记商品时的解析结果
+        let price_service = &self.core.services.price_service;
+        let quantity = price_service
+            .product_quantity(&product.id)
+            .map(|q| q.to_base())
+            .filter(|q| q.value > 0.0);
+        // 多件装按件换算，规格为单件规格
+        let pack_count = price_service.product_pack_count(&product.id) as f64;
+
         egui::ScrollArea::vertical().show(ui, |ui| {
             for (store_id, prices) in store_prices {
                 let store_name = self
@@ -1159,14 +1961,47 @@ impl TemplateApp {
                     ui.horizontal(|ui| {
                         ui.vertical(|ui| {
                             ui.label(&store_name);
-                            ui.label(format!("当前价格: ¥{:.2}", latest_price.price));
+                            ui.label(format!(
+                                "当前价格: {}",
+                                self.format_money(latest_price.price)
+                            ));
+                            let item_price = latest_price.price / pack_count;
+                            if pack_count > 1.0 {
+                                ui.label(format!(
+                                    "每件: {} ({} 件装)",
+                                    self.format_money(item_price),
+                                    pack_count
+                                ));
+                            }
+                            if let Some(q) = quantity {
+                                ui.label(format!(
+                                    "单价: {}/100{}",
+                                    self.format_money(item_price / q.value * 100.0),
+                                    q.unit.symbol()
+                                ));
+                            }
                             if latest_price.is_on_sale {
                                 ui.colored_label(egui::Color32::RED, "[促销中]");
                             }
-                            ui.label(format!(
-                                "更新时间: {}",
-                                latest_price.timestamp.format("%Y-%m-%d %H:%M")
-                            ));
+                            if latest_price.discount.is_some() {
+                                ui.colored_label(
+                                    egui::Color32::RED,
+                                    format!(
+                                        "折后价: {}",
+                                        self.format_money(latest_price.effective_price())
+                                    ),
+                                );
+                            }
+                            ui.horizontal(|ui| {
+                                ui.label(format!(
+                                    "更新时间: {}",
+                                    self.local_time(&latest_price.timestamp)
+                                        .format("%Y-%m-%d %H:%M")
+                                ));
+                                if self.is_price_stale(latest_price) {
+                                    ui.colored_label(egui::Color32::GRAY, "数据较旧");
+                                }
+                            });
                         });
 
                         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
@@ -1188,7 +2023,7 @@ impl TemplateApp {
                 ui.label("热门商品");
 
                 // Show trending products
-                if let Ok(trending) = self.app_services.price_service.get_trending_prices(5) {
+                if let Ok(trending) = self.core.services.price_service.get_trending_prices(5) {
                     for trend in trending {
                         ui.group(|ui| {
                             if let Some(product) =
@@ -1199,7 +2034,7 @@ impl TemplateApp {
                                     ui.with_layout(
                                         egui::Layout::right_to_left(egui::Align::Center),
                                         |ui| {
-                                            ui.label(format!("¥{:.2}", trend.latest_price));
+                                            ui.label(self.format_money(trend.latest_price));
                                             ui.label(format!("({} 次更新)", trend.activity_count));
                                         },
                                     );
@@ -1246,8 +2081,8 @@ impl TemplateApp {
                                 ui.with_layout(
                                     egui::Layout::right_to_left(egui::Align::Center),
                                     |ui| {
-                                        ui.label(format!("¥{:.2}", price.price));
-                                        ui.label(price.timestamp.format("%m-%d %H:%M").to_string());
+                                        ui.label(self.format_money(price.price));
+                                        self.relative_time_label(ui, &price.timestamp);
                                     },
                                 );
                             });
@@ -1258,3 +2093,129 @@ impl TemplateApp {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::Currency;
+
+    #[test]
+    fn test_format_money_follows_configured_currency() {
+        let mut app = TemplateApp::default();
+        let mut config = app.settings_ui.get_config().clone();
+
+        config.default_currency = Currency::CNY;
+        app.settings_ui.apply_config(config.clone());
+        assert_eq!(app.format_money(1280.0), "¥1,280.00");
+
+        config.default_currency = Currency::JPY;
+        app.settings_ui.apply_config(config.clone());
+        assert_eq!(app.format_money(1280.0), "¥1,280");
+
+        config.default_currency = Currency::USD;
+        app.settings_ui.apply_config(config.clone());
+        assert_eq!(app.format_money(1280.0), "$1,280.00");
+
+        config.default_currency = Currency::EUR;
+        app.settings_ui.apply_config(config);
+        assert_eq!(app.format_money(3.5), "€3.50");
+    }
+
+    #[test]
+    fn test_apply_settings_updates_monitor_interval() {
+        let mut app = TemplateApp::default();
+        let mut config = app.settings_ui.get_config().clone();
+        config.monitoring_settings.monitoring_interval_minutes = 45;
+        app.settings_ui.apply_config(config);
+
+        app.apply_settings();
+        assert_eq!(
+            app.alert_ui.alert_service().monitor().check_interval(),
+            std::time::Duration::from_secs(45 * 60)
+        );
+    }
+
+    #[test]
+    fn test_apply_settings_updates_rounding_mode() {
+        let mut app = TemplateApp::default();
+        let mut config = app.settings_ui.get_config().clone();
+        config.rounding_mode = crate::utils::RoundingMode::Floor;
+        app.settings_ui.apply_config(config);
+
+        app.apply_settings();
+        assert_eq!(
+            app.core.services.price_service.rounding_mode(),
+            crate::utils::RoundingMode::Floor
+        );
+    }
+
+    #[test]
+    fn test_reverse_geocoding_is_off_by_default() {
+        let mut app = TemplateApp::default();
+        let ctx = egui::Context::default();
+
+        assert!(!app.settings_ui.get_config().reverse_geocoding);
+        assert_eq!(app.current_location_label(&ctx), None);
+        assert_eq!(app.location_lookup, None);
+    }
+
+    #[test]
+    fn test_missing_receipt_thumbnail_is_cached() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = TemplateApp {
+            image_store: Some(ImageStore::new(dir.path())),
+            ..Default::default()
+        };
+        let ctx = egui::Context::default();
+
+        assert!(app.receipt_thumbnail(&ctx, "missing.png").is_none());
+        let id = egui::Id::new(("receipt_thumbnail", "missing.png"));
+        let cached = ctx.data(|d| d.get_temp::<Option<egui::TextureHandle>>(id));
+        assert!(matches!(cached, Some(None)));
+    }
+
+    #[test]
+    fn test_saved_location_changes_distance_origin() {
+        let mut app = TemplateApp::default();
+        assert_eq!(app.reference_location(), app.current_location);
+
+        let mut config = app.settings_ui.get_config().clone();
+        config
+            .saved_locations
+            .add(crate::settings::SavedLocation::new(
+                "home", 34.7025, 135.4959,
+            ))
+            .unwrap();
+        config.saved_locations.select(Some("home"));
+        app.settings_ui.apply_config(config.clone());
+        assert_eq!(app.reference_location(), (34.7025, 135.4959));
+
+        config.saved_locations.select(None);
+        app.settings_ui.apply_config(config);
+        assert_eq!(app.reference_location(), app.current_location);
+    }
+
+    #[test]
+    fn test_command_palette_filter_and_execute() {
+        let entries = commands::palette_entries();
+        let scan: Vec<Command> = commands::filter_entries(&entries, "scan")
+            .into_iter()
+            .map(|entry| entry.command)
+            .collect();
+        assert!(scan.contains(&Command::NewScan));
+        assert!(!scan.contains(&Command::OpenSettings));
+        assert_eq!(commands::filter_entries(&entries, "").len(), entries.len());
+
+        let mut app = TemplateApp::default();
+        assert_eq!(app.current_tab, Tab::Stores);
+        app.execute_command(Command::SwitchTab(Tab::Trends));
+        assert_eq!(app.current_tab, Tab::Trends);
+        app.execute_command(Command::OpenSettings);
+        assert_eq!(app.current_tab, Tab::Settings);
+
+        assert_eq!(commands::tab_for_shortcut(2), Some(Tab::Products));
+        assert_eq!(commands::tab_for_shortcut(7), Some(Tab::Settings));
+        assert_eq!(commands::tab_for_shortcut(0), None);
+        assert_eq!(commands::tab_for_shortcut(8), None);
+    }
+}
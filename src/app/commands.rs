@@ -0,0 +1,171 @@
+//! Keyboard shortcuts and the Ctrl+K command palette.
+
+use super::Tab;
+use crate::tr;
+use eframe::egui;
+
+/// Sidebar tabs in Ctrl+1..Ctrl+7 order
+pub(super) const TAB_ORDER: [Tab; 7] = [
+    Tab::Stores,
+    Tab::Products,
+    Tab::Scanner,
+    Tab::Alerts,
+    Tab::Trends,
+    Tab::Community,
+    Tab::Settings,
+];
+
+/// Action the palette can run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Command {
+    SwitchTab(Tab),
+    NewScan,
+    Search,
+    OpenSettings,
+}
+
+/// Palette row: a command with its display label and extra search words
+#[derive(Debug, Clone)]
+pub(super) struct PaletteEntry {
+    pub command: Command,
+    pub label: String,
+    pub keywords: &'static str,
+}
+
+/// Tab bound to Ctrl+`number` (1-based)
+pub(super) fn tab_for_shortcut(number: usize) -> Option<Tab> {
+    number
+        .checked_sub(1)
+        .and_then(|index| TAB_ORDER.get(index))
+        .copied()
+}
+
+/// Every command offered by the palette
+pub(super) fn palette_entries() -> Vec<PaletteEntry> {
+    let tab_keywords = |tab: Tab| match tab {
+        Tab::Stores => ("nav.stores", "tab stores map"),
+        Tab::Products => ("nav.products", "tab products prices"),
+        Tab::Scanner => ("nav.scanner", "tab scanner barcode"),
+        Tab::Alerts => ("nav.alerts", "tab alerts"),
+        Tab::Trends => ("nav.trends", "tab trends chart"),
+        Tab::Community => ("nav.community", "tab community reviews"),
+        Tab::Settings => ("nav.settings", "tab settings"),
+    };
+
+    let mut entries: Vec<PaletteEntry> = TAB_ORDER
+        .iter()
+        .enumerate()
+        .map(|(index, tab)| {
+            let (key, keywords) = tab_keywords(*tab);
+            PaletteEntry {
+                command: Command::SwitchTab(*tab),
+                label: format!("→ {}（Ctrl+{}）", tr!(key), index + 1),
+                keywords,
+            }
+        })
+        .collect();
+    entries.extend([
+        PaletteEntry {
+            command: Command::NewScan,
+            label: "📷 新建扫描".to_string(),
+            keywords: "new scan barcode camera",
+        },
+        PaletteEntry {
+            command: Command::Search,
+            label: "🔍 搜索商品".to_string(),
+            keywords: "search find products",
+        },
+        PaletteEntry {
+            command: Command::OpenSettings,
+            label: "⚙ 打开设置".to_string(),
+            keywords: "open settings preferences",
+        },
+    ]);
+    entries
+}
+
+/// Entries whose label or keywords contain every whitespace-separated word of `query`
+/// (case-insensitive), in palette order
+pub(super) fn filter_entries<'a>(
+    entries: &'a [PaletteEntry],
+    query: &str,
+) -> Vec<&'a PaletteEntry> {
+    let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    entries
+        .iter()
+        .filter(|entry| {
+            let haystack = format!("{} {}", entry.label, entry.keywords).to_lowercase();
+            words.iter().all(|word| haystack.contains(word))
+        })
+        .collect()
+}
+
+/// Ctrl+K palette window state
+#[derive(Debug, Default)]
+pub(super) struct CommandPalette {
+    open: bool,
+    query: String,
+    selected: usize,
+}
+
+impl CommandPalette {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.query.clear();
+        self.selected = 0;
+    }
+
+    /// Render the palette if open; returns the command chosen with Enter or a click
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<Command> {
+        if !self.open {
+            return None;
+        }
+
+        let entries = palette_entries();
+        let matches = filter_entries(&entries, &self.query);
+        self.selected = self.selected.min(matches.len().saturating_sub(1));
+
+        let (up, down, enter, escape) = ctx.input_mut(|i| {
+            (
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+            )
+        });
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        if down && self.selected + 1 < matches.len() {
+            self.selected += 1;
+        }
+
+        let mut chosen = enter
+            .then(|| matches.get(self.selected).map(|entry| entry.command))
+            .flatten();
+        egui::Window::new("命令面板")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 60.0))
+            .show(ctx, |ui| {
+                ui.text_edit_singleline(&mut self.query).request_focus();
+                ui.separator();
+                if matches.is_empty() {
+                    ui.weak("没有匹配的命令");
+                }
+                for (index, entry) in matches.iter().enumerate() {
+                    if ui
+                        .selectable_label(index == self.selected, &entry.label)
+                        .clicked()
+                    {
+                        chosen = Some(entry.command);
+                    }
+                }
+            });
+
+        if chosen.is_some() || escape {
+            self.open = false;
+        }
+        chosen
+    }
+}
@@ -0,0 +1,119 @@
+//! Structured editor for store opening hours.
+
+use crate::models::{DayHours, OpeningHours};
+use eframe::egui;
+
+/// Day labels shown in the editor, Monday first
+const DAY_LABELS: [&str; 7] = ["周一", "周二", "周三", "周四", "周五", "周六", "周日"];
+
+/// How a day is edited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DayMode {
+    Hours,
+    AllDay,
+    Closed,
+}
+
+/// Editable state of one day
+#[derive(Debug, Clone)]
+struct DayEdit {
+    mode: DayMode,
+    open: String,
+    close: String,
+}
+
+impl DayEdit {
+    fn new(hours: DayHours) -> Self {
+        let hm = |minutes: u32| format!("{:02}:{:02}", minutes / 60, minutes % 60);
+        match hours {
+            DayHours::Range { start, end } => Self {
+                mode: DayMode::Hours,
+                open: hm(start),
+                close: hm(end),
+            },
+            DayHours::AllDay => Self {
+                mode: DayMode::AllDay,
+                ..Self::new(DEFAULT_RANGE)
+            },
+            DayHours::Closed => Self {
+                mode: DayMode::Closed,
+                ..Self::new(DEFAULT_RANGE)
+            },
+        }
+    }
+
+    fn hours(&self) -> Option<DayHours> {
+        match self.mode {
+            DayMode::AllDay => Some(DayHours::AllDay),
+            DayMode::Closed => Some(DayHours::Closed),
+            DayMode::Hours => DayHours::parse(&format!("{}-{}", self.open, self.close)),
+        }
+    }
+}
+
+/// Range pre-filled for days without explicit hours
+const DEFAULT_RANGE: DayHours = DayHours::Range {
+    start: 9 * 60,
+    end: 21 * 60,
+};
+
+/// Per-day open/close editor producing canonical `opening_hours` text
+#[derive(Debug, Clone)]
+pub struct OpeningHoursEditor {
+    days: Vec<DayEdit>,
+}
+
+impl OpeningHoursEditor {
+    /// Editor pre-filled from a store's `opening_hours`; unparseable text starts from 09:00-21:00
+    pub fn from_text(text: &str) -> Self {
+        let hours = OpeningHours::from_string(text).unwrap_or(OpeningHours::daily(DEFAULT_RANGE));
+        Self {
+            days: hours.days.iter().map(|day| DayEdit::new(*day)).collect(),
+        }
+    }
+
+    /// Edited hours, or None while any time field is invalid
+    pub fn hours(&self) -> Option<OpeningHours> {
+        let mut days = [DayHours::Closed; 7];
+        for (day, edit) in days.iter_mut().zip(&self.days) {
+            *day = edit.hours()?;
+        }
+        Some(OpeningHours { days })
+    }
+
+    /// Render the editor; returns hours to save when the save button is clicked
+    pub fn show(&mut self, ui: &mut egui::Ui) -> Option<OpeningHours> {
+        egui::Grid::new("opening_hours_editor")
+            .num_columns(4)
+            .show(ui, |ui| {
+                for (label, day) in DAY_LABELS.iter().zip(self.days.iter_mut()) {
+                    ui.label(*label);
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut day.mode, DayMode::Hours, "时段");
+                        ui.radio_value(&mut day.mode, DayMode::AllDay, "24小时");
+                        ui.radio_value(&mut day.mode, DayMode::Closed, "休息");
+                    });
+                    let enabled = day.mode == DayMode::Hours;
+                    ui.add_enabled(
+                        enabled,
+                        egui::TextEdit::singleline(&mut day.open).desired_width(48.0),
+                    );
+                    ui.add_enabled(
+                        enabled,
+                        egui::TextEdit::singleline(&mut day.close).desired_width(48.0),
+                    );
+                    ui.end_row();
+                }
+            });
+
+        let hours = self.hours();
+        match &hours {
+            Some(hours) => ui.label(format!("保存为：{}", hours.to_canonical_string())),
+            None => ui.colored_label(egui::Color32::RED, "时间格式应为 HH:MM"),
+        };
+        let save = ui
+            .add_enabled(hours.is_some(), egui::Button::new("保存营业时间"))
+            .clicked();
+        hours.filter(|_| save)
+    }
+}
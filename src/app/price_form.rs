@@ -0,0 +1,281 @@
+//! Form for submitting a store price of the selected product.
+
+use crate::models::{PriceSource, Store};
+use crate::ocr::{
+    OcrConfig, OcrError, OcrResult, ReceiptAutofill, ReceiptParser, TextExtractor, TextRecognizer,
+    autofill_from_receipt,
+};
+use crate::utils::{normalize_width, parse_price};
+use eframe::egui;
+use std::sync::{Arc, Mutex};
+
+/// Validated form input, ready for `PriceService::add_price_record`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceSubmission {
+    pub store_id: String,
+    pub price: f64,
+    pub is_on_sale: bool,
+    pub receipt_image: Option<String>,
+    pub source: PriceSource, // 价格沿用小票识别结果时为 Ocr
+}
+
+/// Parse a price typed in major units ("3", "3.5", "1,280.00")
+pub fn parse_amount(text: &str) -> Result<f64, String> {
+    let text = normalize_width(text.trim());
+    // parse_price 把不带小数点的输入视为最小单位，这里补上小数点按元解析
+    let minor = if text.contains('.') {
+        parse_price(&text)
+    } else {
+        parse_price(&format!("{}.", text))
+    }?;
+    Ok(minor as f64 / 100.0)
+}
+
+/// Result slot filled by the background receipt recognition
+type AutofillSlot = Arc<Mutex<Option<OcrResult<ReceiptAutofill>>>>;
+
+/// Store whose name matches the receipt header, ignoring case
+fn match_store<'a>(stores: &'a [Store], header: &str) -> Option<&'a Store> {
+    let header = header.to_lowercase();
+    stores.iter().find(|store| {
+        let name = store.name.to_lowercase();
+        header.contains(&name) || name.contains(&header)
+    })
+}
+
+/// Price submission form state for one product
+pub struct PriceSubmissionForm {
+    product_id: String,
+    store_id: Option<String>,
+    price: String,
+    is_on_sale: bool,
+    receipt_image: String,
+    error: Option<String>,
+    recognizer: Arc<dyn TextRecognizer>,
+    autofill: Option<AutofillSlot>, // 正在进行的小票识别
+    autofill_note: Option<String>,
+    ocr_price: Option<String>, // 小票识别填入的价格文本
+}
+
+impl PriceSubmissionForm {
+    pub fn new(product_id: &str) -> Self {
+        Self {
+            product_id: product_id.to_string(),
+            store_id: None,
+            price: String::new(),
+            is_on_sale: false,
+            receipt_image: String::new(),
+            error: None,
+            recognizer: Arc::new(TextExtractor::new()),
+            autofill: None,
+            autofill_note: None,
+            ocr_price: None,
+        }
+    }
+
+    pub fn product_id(&self) -> &str {
+        &self.product_id
+    }
+
+    /// Show an error returned when saving the submission
+    pub fn set_error(&mut self, error: String) {
+        self.error = Some(error);
+    }
+
+    /// Recognize the attached receipt on a background thread; the result is
+    /// applied by `show` once ready
+    fn start_autofill(&mut self, ctx: &egui::Context) {
+        let path = std::path::PathBuf::from(self.receipt_image.trim());
+        let format = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("png")
+            .to_lowercase();
+        let slot = AutofillSlot::default();
+        self.autofill = Some(slot.clone());
+        self.autofill_note = None;
+
+        let recognizer = self.recognizer.clone();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let result = std::fs::read(&path)
+                .map_err(|e| OcrError::FileNotFound(e.to_string()))
+                .and_then(|bytes| {
+                    autofill_from_receipt(
+                        recognizer.as_ref(),
+                        &ReceiptParser::new(),
+                        &bytes,
+                        &format,
+                        &OcrConfig::default(),
+                    )
+                });
+            if let Ok(mut slot) = slot.lock() {
+                *slot = Some(result);
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    /// Fill blank fields from a recognized receipt for the user to confirm
+    fn apply_autofill(&mut self, autofill: ReceiptAutofill, stores: &[Store]) {
+        let Some(price) = autofill.price else {
+            self.autofill_note = Some("小票识别置信度过低，请手动填写".to_string());
+            return;
+        };
+        // 不覆盖用户已填写的价格
+        if self.price.trim().is_empty() {
+            self.price = format!("{:.2}", price);
+            self.ocr_price = Some(self.price.clone());
+        }
+        if self.store_id.is_none() {
+            if let Some(store) = autofill
+                .store_name
+                .as_deref()
+                .and_then(|header| match_store(stores, header))
+            {
+                self.store_id = Some(store.id.clone());
+            }
+        }
+        self.autofill_note = Some("已根据小票填写，请确认".to_string());
+    }
+
+    /// Check the inputs, returning the first problem as a user-facing message
+    pub fn validate(&self) -> Result<PriceSubmission, String> {
+        let store_id = self.store_id.clone().ok_or("请选择门店")?;
+        let price = parse_amount(&self.price).map_err(|e| format!("价格无效: {}", e))?;
+        if price <= 0.0 {
+            return Err("价格必须大于 0".to_string());
+        }
+        let receipt_image = self.receipt_image.trim();
+        let source = if self.ocr_price.as_ref() == Some(&self.price) {
+            PriceSource::Ocr
+        } else {
+            PriceSource::Manual
+        };
+        Ok(PriceSubmission {
+            store_id,
+            price,
+            is_on_sale: self.is_on_sale,
+            receipt_image: (!receipt_image.is_empty()).then(|| receipt_image.to_string()),
+            source,
+        })
+    }
+
+    /// Render the form; returns the submission when the submit button is clicked with valid input
+    pub fn show(&mut self, ui: &mut egui::Ui, stores: &[Store]) -> Option<PriceSubmission> {
+        let selected = self
+            .store_id
+            .as_ref()
+            .and_then(|id| stores.iter().find(|s| &s.id == id))
+            .map(|s| s.name.as_str())
+            .unwrap_or("选择门店");
+        egui::ComboBox::from_label("门店")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                for store in stores {
+                    ui.selectable_value(&mut self.store_id, Some(store.id.clone()), &store.name);
+                }
+            });
+        ui.horizontal(|ui| {
+            ui.label("价格:");
+            ui.text_edit_singleline(&mut self.price);
+        });
+        ui.checkbox(&mut self.is_on_sale, "特价");
+        let mut recognize = false;
+        ui.horizontal(|ui| {
+            ui.label("小票图片:");
+            ui.text_edit_singleline(&mut self.receipt_image);
+            #[cfg(not(target_arch = "wasm32"))]
+            if ui.button("📁").clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("图片", &["png", "jpg", "jpeg"])
+                    .pick_file()
+                {
+                    self.receipt_image = path.display().to_string();
+                    recognize = true;
+                }
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                let idle = self.autofill.is_none() && !self.receipt_image.trim().is_empty();
+                recognize |= ui
+                    .add_enabled(idle, egui::Button::new("🔍 识别小票"))
+                    .clicked();
+            }
+        });
+        if recognize {
+            self.start_autofill(ui.ctx());
+        }
+
+        let finished = self
+            .autofill
+            .as_ref()
+            .and_then(|slot| slot.lock().ok().and_then(|mut slot| slot.take()));
+        if let Some(result) = finished {
+            self.autofill = None;
+            match result {
+                Ok(autofill) => self.apply_autofill(autofill, stores),
+                Err(e) => self.autofill_note = Some(format!("小票识别失败: {}", e)),
+            }
+        }
+        if self.autofill.is_some() {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("正在识别小票…");
+            });
+        } else if let Some(note) = &self.autofill_note {
+            ui.weak(note);
+        }
+
+        if let Some(error) = &self.error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+        if !ui.button("提交价格").clicked() {
+            return None;
+        }
+        match self.validate() {
+            Ok(submission) => {
+                self.error = None;
+                Some(submission)
+            }
+            Err(e) => {
+                self.error = Some(e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn autofill(price: f64) -> ReceiptAutofill {
+        ReceiptAutofill {
+            price: Some(price),
+            store_name: None,
+            confidence: 0.9,
+        }
+    }
+
+    #[test]
+    fn test_autofill_fills_empty_price_as_ocr() {
+        let mut form = PriceSubmissionForm::new("p1");
+        form.store_id = Some("s1".to_string());
+        form.apply_autofill(autofill(198.0), &[]);
+
+        assert_eq!(form.price, "198.00");
+        assert_eq!(form.validate().unwrap().source, PriceSource::Ocr);
+    }
+
+    #[test]
+    fn test_autofill_keeps_typed_price() {
+        let mut form = PriceSubmissionForm::new("p1");
+        form.store_id = Some("s1".to_string());
+        form.price = "158".to_string();
+        form.apply_autofill(autofill(198.0), &[]);
+
+        assert_eq!(form.price, "158");
+        assert_eq!(form.validate().unwrap().source, PriceSource::Manual);
+    }
+}
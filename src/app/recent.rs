@@ -0,0 +1,115 @@
+//! Recently viewed products, most recent first.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// Bounded, de-duplicated list of product ids, most recently viewed first
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecentlyViewed {
+    ids: VecDeque<String>,
+    capacity: usize,
+}
+
+impl Default for RecentlyViewed {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+impl RecentlyViewed {
+    pub const DEFAULT_CAPACITY: usize = 10;
+
+    /// List keeping at most `capacity` products (at least one)
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            ids: VecDeque::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Move `product_id` to the front, evicting the oldest entry when full
+    pub fn record(&mut self, product_id: &str) {
+        if self.ids.front().map(String::as_str) == Some(product_id) {
+            return;
+        }
+        self.ids.retain(|id| id != product_id);
+        self.ids.push_front(product_id.to_string());
+        self.ids.truncate(self.capacity);
+    }
+
+    /// Product ids, most recent first
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.ids.iter().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+}
+
+/// Recently viewed lists keyed by user id; signed-out browsing uses the empty key
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecentlyViewedByUser {
+    lists: HashMap<String, RecentlyViewed>,
+}
+
+impl RecentlyViewedByUser {
+    fn key(user_id: Option<&str>) -> String {
+        user_id.unwrap_or_default().to_string()
+    }
+
+    /// List of `user_id` (None for signed-out browsing)
+    pub fn for_user(&self, user_id: Option<&str>) -> Option<&RecentlyViewed> {
+        self.lists.get(&Self::key(user_id))
+    }
+
+    /// Record a product view by `user_id`
+    pub fn record(&mut self, user_id: Option<&str>, product_id: &str) {
+        self.lists
+            .entry(Self::key(user_id))
+            .or_default()
+            .record(product_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(list: &RecentlyViewed) -> Vec<&str> {
+        list.ids().collect()
+    }
+
+    #[test]
+    fn test_reviewing_moves_to_front_without_duplicates() {
+        let mut list = RecentlyViewed::default();
+        list.record("milk");
+        list.record("bread");
+        list.record("eggs");
+        list.record("milk");
+
+        assert_eq!(ids(&list), vec!["milk", "eggs", "bread"]);
+    }
+
+    #[test]
+    fn test_full_list_evicts_oldest() {
+        let mut list = RecentlyViewed::new(3);
+        for id in ["a", "b", "c", "d"] {
+            list.record(id);
+        }
+        assert_eq!(ids(&list), vec!["d", "c", "b"]);
+
+        // 每个用户各自保存
+        let mut by_user = RecentlyViewedByUser::default();
+        by_user.record(Some("u1"), "a");
+        by_user.record(None, "b");
+        assert_eq!(ids(by_user.for_user(Some("u1")).unwrap()), vec!["a"]);
+        assert_eq!(ids(by_user.for_user(None).unwrap()), vec!["b"]);
+    }
+}
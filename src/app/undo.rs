@@ -0,0 +1,274 @@
+//! Undo/redo of destructive UI actions.
+
+use crate::models::{Product, Store};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::scanner::ScanHistoryItem;
+use crate::services::{AppServices, ServiceResult};
+use std::collections::VecDeque;
+
+/// Operation that can be reverted and re-applied against a target
+pub trait Reversible<T: ?Sized> {
+    fn undo(&self, target: &mut T) -> ServiceResult<()>;
+    fn redo(&self, target: &mut T) -> ServiceResult<()>;
+    /// Short label for status messages
+    fn description(&self) -> String;
+}
+
+/// Bounded history of applied actions with redo support
+#[derive(Debug)]
+pub struct UndoStack<A> {
+    done: VecDeque<A>,
+    undone: Vec<A>,
+    capacity: usize,
+}
+
+impl<A> UndoStack<A> {
+    pub const DEFAULT_CAPACITY: usize = 50;
+
+    /// Stack keeping at most `capacity` actions (at least one)
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            done: VecDeque::new(),
+            undone: Vec::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Record an action that has just been applied; clears the redo history
+    pub fn record(&mut self, action: A) {
+        self.undone.clear();
+        if self.done.len() == self.capacity {
+            // 超出容量时丢弃最早的操作
+            self.done.pop_front();
+        }
+        self.done.push_back(action);
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Number of actions that can be undone
+    pub fn len(&self) -> usize {
+        self.done.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.done.is_empty()
+    }
+
+    /// Revert the latest action. Returns the action, or None when there is
+    /// nothing to undo; on error the action stays on the stack
+    pub fn undo<T: ?Sized>(&mut self, target: &mut T) -> ServiceResult<Option<&A>>
+    where
+        A: Reversible<T>,
+    {
+        let Some(action) = self.done.pop_back() else {
+            return Ok(None);
+        };
+        if let Err(e) = action.undo(target) {
+            self.done.push_back(action);
+            return Err(e);
+        }
+        self.undone.push(action);
+        Ok(self.undone.last())
+    }
+
+    /// Re-apply the latest undone action
+    pub fn redo<T: ?Sized>(&mut self, target: &mut T) -> ServiceResult<Option<&A>>
+    where
+        A: Reversible<T>,
+    {
+        let Some(action) = self.undone.pop() else {
+            return Ok(None);
+        };
+        if let Err(e) = action.redo(target) {
+            self.undone.push(action);
+            return Err(e);
+        }
+        self.done.push_back(action);
+        Ok(self.done.back())
+    }
+}
+
+impl<A> Default for UndoStack<A> {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+/// State an [`AppAction`] is undone/redone against
+pub struct UndoContext<'a> {
+    pub services: &'a mut AppServices,
+    #[cfg(not(target_arch = "wasm32"))]
+    pub scan_history: &'a mut Vec<ScanHistoryItem>,
+}
+
+/// Destructive app action, holding what is needed to reverse it
+#[derive(Debug, Clone)]
+pub enum AppAction {
+    DeleteProduct(Product),
+    DeleteStore(Store),
+    #[cfg(not(target_arch = "wasm32"))]
+    ClearScanHistory(Vec<ScanHistoryItem>),
+}
+
+impl Reversible<UndoContext<'_>> for AppAction {
+    fn undo(&self, target: &mut UndoContext<'_>) -> ServiceResult<()> {
+        match self {
+            AppAction::DeleteProduct(product) => target
+                .services
+                .product_service
+                .restore_product(product.clone()),
+            AppAction::DeleteStore(store) => {
+                target.services.store_service.restore_store(store.clone())
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            AppAction::ClearScanHistory(items) => {
+                // 清空后新增的记录保留在恢复的记录之后
+                let newer = std::mem::replace(target.scan_history, items.clone());
+                target.scan_history.extend(newer);
+                Ok(())
+            }
+        }
+    }
+
+    fn redo(&self, target: &mut UndoContext<'_>) -> ServiceResult<()> {
+        match self {
+            AppAction::DeleteProduct(product) => {
+                target.services.product_service.delete_product(&product.id)
+            }
+            AppAction::DeleteStore(store) => target.services.store_service.delete_store(&store.id),
+            #[cfg(not(target_arch = "wasm32"))]
+            AppAction::ClearScanHistory(_) => {
+                target.scan_history.clear();
+                Ok(())
+            }
+        }
+    }
+
+    fn description(&self) -> String {
+        match self {
+            AppAction::DeleteProduct(product) => format!("删除商品 {}", product.name),
+            AppAction::DeleteStore(store) => format!("删除门店 {}", store.name),
+            #[cfg(not(target_arch = "wasm32"))]
+            AppAction::ClearScanHistory(items) => format!("清空 {} 条扫描记录", items.len()),
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::services::ServiceError;
+
+    fn delete_sample_product(services: &mut AppServices) -> Product {
+        let product = services
+            .product_service
+            .create_product(
+                "Barley Tea".to_string(),
+                "Beverages".to_string(),
+                "Roasted barley tea".to_string(),
+                None,
+                vec![],
+            )
+            .unwrap();
+        services
+            .product_service
+            .delete_product(&product.id)
+            .unwrap();
+        product
+    }
+
+    #[test]
+    fn test_undo_and_redo_product_delete() {
+        let mut services = AppServices::new();
+        let mut scan_history = Vec::new();
+        let mut stack = UndoStack::default();
+
+        let product = delete_sample_product(&mut services);
+        stack.record(AppAction::DeleteProduct(product.clone()));
+
+        let mut context = UndoContext {
+            services: &mut services,
+            scan_history: &mut scan_history,
+        };
+        let undone = stack.undo(&mut context).unwrap().cloned();
+        assert!(matches!(undone, Some(AppAction::DeleteProduct(_))));
+        assert_eq!(
+            context
+                .services
+                .product_service
+                .get_product(&product.id)
+                .unwrap(),
+            product
+        );
+        assert!(stack.can_redo());
+
+        stack.redo(&mut context).unwrap();
+        assert!(matches!(
+            context.services.product_service.get_product(&product.id),
+            Err(ServiceError::NotFound(_))
+        ));
+        assert!(stack.can_undo());
+        assert!(!stack.can_redo());
+
+        // 栈为空时无操作
+        stack.undo(&mut context).unwrap();
+        assert!(stack.undo(&mut context).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_failed_undo_keeps_action() {
+        let mut services = AppServices::new();
+        let mut scan_history = Vec::new();
+        let mut stack = UndoStack::default();
+
+        let product = delete_sample_product(&mut services);
+        stack.record(AppAction::DeleteProduct(product.clone()));
+        // 商品已被其他途径恢复，撤销会冲突
+        services
+            .product_service
+            .restore_product(product.clone())
+            .unwrap();
+
+        let mut context = UndoContext {
+            services: &mut services,
+            scan_history: &mut scan_history,
+        };
+        assert!(matches!(
+            stack.undo(&mut context),
+            Err(ServiceError::Conflict(_))
+        ));
+        assert_eq!(stack.len(), 1);
+        assert!(!stack.can_redo());
+    }
+
+    #[test]
+    fn test_stack_is_bounded_and_record_clears_redo() {
+        let mut services = AppServices::new();
+        let mut scan_history = Vec::new();
+        let mut stack = UndoStack::new(2);
+
+        for _ in 0..3 {
+            let product = delete_sample_product(&mut services);
+            stack.record(AppAction::DeleteProduct(product));
+        }
+        assert_eq!(stack.len(), 2);
+
+        let mut context = UndoContext {
+            services: &mut services,
+            scan_history: &mut scan_history,
+        };
+        stack.undo(&mut context).unwrap();
+        assert!(stack.can_redo());
+
+        let product = delete_sample_product(context.services);
+        stack.record(AppAction::DeleteProduct(product));
+        assert!(!stack.can_redo());
+    }
+}
@@ -366,13 +366,12 @@ impl AsyncManager {
 
         while running.len() < max_concurrent && !queue.is_empty() {
             if let Some(operation_id) = queue.pop_front() {
-                let operations = self.operations.lock().unwrap();
-                if let Some(_operation) = operations.get(&operation_id) {
+                let exists = self.operations.lock().unwrap().contains_key(&operation_id);
+                if exists {
                     if self.are_dependencies_satisfied(&operation_id) {
                         running.insert(operation_id.clone());
                         drop(running);
                         drop(queue);
-                        drop(operations);
 
                         // Start the operation (in a real implementation, this would spawn a task)
                         self.start_operation(&operation_id);
@@ -426,25 +425,31 @@ impl AsyncManager {
                     .push(operation_id.to_string());
                 self.notify_status_change(operation_id, OperationStatus::Completed, None);
             }
-            OperationResult::Failure(_) => {
+            OperationResult::Failure(error) => {
                 self.failed_operations
                     .lock()
                     .unwrap()
                     .push(operation_id.to_string());
 
-                // Handle retry if enabled
-                if self.auto_retry_enabled {
-                    if let Some(operation) = self.operations.lock().unwrap().get_mut(operation_id) {
-                        if operation.can_retry() {
+                // Handle retry if enabled; validation/permission failures are never retried
+                if self.auto_retry_enabled && error.is_retryable() {
+                    // 释放 operations 锁后再处理队列，避免 process_queue 重入死锁
+                    let should_retry = match self.operations.lock().unwrap().get_mut(operation_id) {
+                        Some(operation) if operation.can_retry() => {
                             operation.increment_retry();
-                            self.operation_queue
-                                .lock()
-                                .unwrap()
-                                .push_back(operation_id.to_string());
-                            self.notify_status_change(operation_id, OperationStatus::Pending, None);
-                            self.process_queue();
-                            return;
+                            true
                         }
+                        _ => false,
+                    };
+
+                    if should_retry {
+                        self.operation_queue
+                            .lock()
+                            .unwrap()
+                            .push_back(operation_id.to_string());
+                        self.notify_status_change(operation_id, OperationStatus::Pending, None);
+                        self.process_queue();
+                        return;
                     }
                 }
 
@@ -477,23 +482,29 @@ impl AsyncManager {
     }
 
     fn check_dependent_operations(&self, completed_operation_id: &str) {
-        let operations = self.operations.lock().unwrap();
-        let mut queue = self.operation_queue.lock().unwrap();
-
-        // Find operations that depend on the completed operation
-        for (id, operation) in operations.iter() {
-            if operation
-                .dependencies
-                .contains(&completed_operation_id.to_string())
-                && self.are_dependencies_satisfied(id)
-                && !queue.contains(id)
-            {
-                queue.push_back(id.clone());
+        // Collect candidates first; are_dependencies_satisfied takes the operations lock itself
+        let dependents: Vec<String> = self
+            .operations
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, operation)| {
+                operation
+                    .dependencies
+                    .contains(&completed_operation_id.to_string())
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for id in dependents {
+            if self.are_dependencies_satisfied(&id) {
+                let mut queue = self.operation_queue.lock().unwrap();
+                if !queue.contains(&id) {
+                    queue.push_back(id);
+                }
             }
         }
 
-        drop(queue);
-        drop(operations);
         self.process_queue();
     }
 
@@ -632,3 +643,78 @@ impl Default for AsyncManager {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::async_ops::operations::OperationError;
+
+    fn insert_operation(manager: &AsyncManager) -> String {
+        let operation = AsyncOperation::data_sync("sync".to_string());
+        let id = operation.id.clone();
+        manager
+            .operations
+            .lock()
+            .unwrap()
+            .insert(id.clone(), operation);
+        manager
+            .running_operations
+            .lock()
+            .unwrap()
+            .insert(id.clone());
+        id
+    }
+
+    #[test]
+    fn test_non_retryable_failure_not_requeued() {
+        let manager = AsyncManager::new();
+        let id = insert_operation(&manager);
+
+        manager.complete_operation(
+            &id,
+            OperationResult::Failure(OperationError::InvalidInput("bad price".to_string())),
+        );
+
+        assert!(manager.operation_queue.lock().unwrap().is_empty());
+        assert_eq!(manager.operations.lock().unwrap()[&id].retry_count, 0);
+        assert_eq!(
+            manager.get_operation_status(&id),
+            Some(OperationStatus::Failed)
+        );
+    }
+
+    #[test]
+    fn test_retryable_failure_requeued() {
+        let manager = AsyncManager::new();
+        let id = insert_operation(&manager);
+
+        manager.complete_operation(
+            &id,
+            OperationResult::Failure(OperationError::NetworkError("timeout".to_string())),
+        );
+
+        assert_eq!(manager.operations.lock().unwrap()[&id].retry_count, 1);
+    }
+
+    #[test]
+    fn test_service_failure_retry_follows_source() {
+        use crate::services::ServiceError;
+
+        let manager = AsyncManager::new();
+        let retried = insert_operation(&manager);
+        let rejected = insert_operation(&manager);
+
+        manager.complete_operation(
+            &retried,
+            OperationResult::Failure(ServiceError::DatabaseError("locked".to_string()).into()),
+        );
+        manager.complete_operation(
+            &rejected,
+            OperationResult::Failure(ServiceError::NotFound("p1".to_string()).into()),
+        );
+
+        let operations = manager.operations.lock().unwrap();
+        assert_eq!(operations[&retried].retry_count, 1);
+        assert_eq!(operations[&rejected].retry_count, 0);
+    }
+}
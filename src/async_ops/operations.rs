@@ -1,4 +1,5 @@
 use crate::async_ops::progress::ProgressTracker;
+use crate::error::AppError;
 use crate::models::{PriceRecord, Product, Store, User};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -86,6 +87,9 @@ pub enum OperationError {
     CancellationError(String),
     ResourceUnavailable(String),
     InternalError(String),
+    /// Failure raised by the service layer, kept for retry decisions
+    #[serde(skip)]
+    Source(AppError),
 }
 
 impl fmt::Display for OperationError {
@@ -100,12 +104,33 @@ impl fmt::Display for OperationError {
             OperationError::CancellationError(msg) => write!(f, "Cancellation error: {}", msg),
             OperationError::ResourceUnavailable(msg) => write!(f, "Resource unavailable: {}", msg),
             OperationError::InternalError(msg) => write!(f, "Internal error: {}", msg),
+            OperationError::Source(error) => write!(f, "{}", error),
         }
     }
 }
 
 impl std::error::Error for OperationError {}
 
+impl OperationError {
+    /// Whether the failed operation is worth re-enqueueing, decided by
+    /// [`AppError::is_retryable`]
+    pub fn is_retryable(&self) -> bool {
+        AppError::from(self.clone()).is_retryable()
+    }
+}
+
+impl From<AppError> for OperationError {
+    fn from(error: AppError) -> Self {
+        OperationError::Source(error)
+    }
+}
+
+impl From<crate::services::ServiceError> for OperationError {
+    fn from(error: crate::services::ServiceError) -> Self {
+        OperationError::Source(AppError::from(error))
+    }
+}
+
 /// Represents an async operation with metadata and progress tracking
 #[derive(Clone)]
 pub struct AsyncOperation {
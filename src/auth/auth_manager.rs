@@ -2,7 +2,9 @@ use crate::auth::models::{LoginRequest, RegisterRequest, User};
 use crate::auth::{AuthError, AuthResult};
 #[cfg(not(target_arch = "wasm32"))]
 use crate::database::{UserRepository, repository::Repository};
-use crate::utils::crypto::{hash_password, verify_password};
+use crate::utils::crypto::{
+    PasswordHashParams, hash_password_with_params, needs_rehash, verify_password,
+};
 use crate::utils::{validate_email, validate_password, validate_username};
 #[cfg(not(target_arch = "wasm32"))]
 use sqlx::Pool;
@@ -13,6 +15,7 @@ use sqlx::Sqlite;
 #[cfg(not(target_arch = "wasm32"))]
 pub struct AuthManager {
     user_repository: UserRepository,
+    hash_params: PasswordHashParams,
 }
 
 impl AuthManager {
@@ -21,9 +24,16 @@ impl AuthManager {
     pub fn new(pool: Pool<Sqlite>) -> Self {
         Self {
             user_repository: UserRepository::new(pool),
+            hash_params: PasswordHashParams::default(),
         }
     }
 
+    /// Hash new passwords with `params`; older hashes are upgraded at login
+    pub fn with_hash_params(mut self, params: PasswordHashParams) -> Self {
+        self.hash_params = params;
+        self
+    }
+
     /// Register a new user
     pub async fn register(&self, request: RegisterRequest) -> AuthResult<User> {
         // Validate the registration request
@@ -62,9 +72,10 @@ impl AuthManager {
         }
 
         // Hash the password
-        let password_hash = hash_password(&request.password).map_err(|e| {
-            AuthError::PasswordValidation(format!("Password hashing failed: {}", e))
-        })?;
+        let password_hash = hash_password_with_params(&request.password, &self.hash_params)
+            .map_err(|e| {
+                AuthError::PasswordValidation(format!("Password hashing failed: {}", e))
+            })?;
 
         // Create new user
         let user = User::new(request.username, request.email, password_hash);
@@ -93,6 +104,18 @@ impl AuthManager {
             return Err(AuthError::InvalidCredentials);
         }
 
+        // 哈希参数已调整时，用刚验证过的明文按当前参数重新哈希
+        if needs_rehash(&user.password_hash, &self.hash_params) {
+            match hash_password_with_params(&request.password, &self.hash_params) {
+                Ok(new_hash) => {
+                    user.password_hash = new_hash;
+                    self.user_repository.update(&user).await?;
+                    log::info!("Password hash upgraded for user: {}", user.id);
+                }
+                Err(e) => log::warn!("Password rehash failed for user {}: {}", user.id, e),
+            }
+        }
+
         // Update last login
         user.update_last_login();
         self.user_repository.update_last_login(&user.id).await?;
@@ -131,9 +154,10 @@ impl AuthManager {
         }
 
         // Hash new password
-        let new_password_hash = hash_password(new_password).map_err(|e| {
-            AuthError::PasswordValidation(format!("Password hashing failed: {}", e))
-        })?;
+        let new_password_hash = hash_password_with_params(new_password, &self.hash_params)
+            .map_err(|e| {
+                AuthError::PasswordValidation(format!("Password hashing failed: {}", e))
+            })?;
 
         // Update user
         let mut updated_user = user;
@@ -216,3 +240,74 @@ impl AuthManager {
         Ok(user.is_some())
     }
 }
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::database::{DatabaseManager, migrations};
+    use crate::utils::crypto::hash_password_with_cost;
+
+    const CURRENT: PasswordHashParams = PasswordHashParams { cost: 5 };
+
+    async fn manager_with_user(dir: &std::path::Path, stored_hash: String) -> AuthManager {
+        let url = format!("sqlite://{}?mode=rwc", dir.join("eprice.db").display());
+        let database = DatabaseManager::new(&url).await.unwrap();
+        migrations::run_migrations(database.pool()).await.unwrap();
+        let pool = database.pool().clone();
+        UserRepository::new(pool.clone())
+            .create(&User::new(
+                "alice".to_string(),
+                "alice@example.com".to_string(),
+                stored_hash,
+            ))
+            .await
+            .unwrap();
+        AuthManager::new(pool).with_hash_params(CURRENT)
+    }
+
+    async fn login(manager: &AuthManager) -> User {
+        manager
+            .login(LoginRequest {
+                email: "alice@example.com".to_string(),
+                password: "Secret123!".to_string(),
+                remember_me: false,
+            })
+            .await
+            .unwrap()
+    }
+
+    async fn stored_hash(manager: &AuthManager) -> String {
+        manager
+            .user_repository
+            .find_by_email("alice@example.com")
+            .await
+            .unwrap()
+            .unwrap()
+            .password_hash
+    }
+
+    #[tokio::test]
+    async fn test_outdated_hash_is_upgraded_on_login() {
+        let dir = tempfile::tempdir().unwrap();
+        let old_hash = hash_password_with_cost("Secret123!", 4).unwrap();
+        let manager = manager_with_user(dir.path(), old_hash.clone()).await;
+
+        login(&manager).await;
+
+        let new_hash = stored_hash(&manager).await;
+        assert_ne!(new_hash, old_hash);
+        assert!(!needs_rehash(&new_hash, &CURRENT));
+        assert!(verify_password("Secret123!", &new_hash).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_current_hash_is_kept_on_login() {
+        let dir = tempfile::tempdir().unwrap();
+        let hash = hash_password_with_params("Secret123!", &CURRENT).unwrap();
+        let manager = manager_with_user(dir.path(), hash.clone()).await;
+
+        login(&manager).await;
+
+        assert_eq!(stored_hash(&manager).await, hash);
+    }
+}
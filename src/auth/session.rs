@@ -1,7 +1,6 @@
 use crate::auth::models::User;
-use crate::utils::file_utils::{
-    ensure_directory_exists, get_data_directory, load_from_file, save_to_file,
-};
+use crate::utils::crypto::constant_time_eq;
+use crate::utils::file_utils::{ensure_directory_exists, get_data_directory, load_from_file};
 use chrono::{DateTime, Duration, Utc};
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
@@ -98,17 +97,18 @@ impl SessionManager {
 
     /// Validate session and return user if valid
     pub fn validate_session(&mut self, session_id: &str) -> Option<&User> {
-        let is_expired = if let Some(session) = self.sessions.get(session_id) {
-            session.is_expired()
-        } else {
-            return None;
-        };
+        // 按常量时间比较会话 ID，避免通过响应时间猜测
+        let (session_id, is_expired) = self
+            .sessions
+            .iter()
+            .find(|(id, _)| constant_time_eq(id.as_bytes(), session_id.as_bytes()))
+            .map(|(id, session)| (id.clone(), session.is_expired()))?;
 
         if is_expired {
-            self.sessions.remove(session_id);
+            self.sessions.remove(&session_id);
             log::info!("Session expired and removed: {}", session_id);
             None
-        } else if let Some(session) = self.sessions.get_mut(session_id) {
+        } else if let Some(session) = self.sessions.get_mut(&session_id) {
             session.update_activity();
             Some(&session.user)
         } else {
@@ -197,7 +197,33 @@ pub fn get_remembered_session() -> Option<String> {
 
 fn remembered_session_file() -> std::io::Result<PathBuf> {
     let dir = get_data_directory().map_err(|e| std::io::Error::other(e.to_string()))?;
-    Ok(dir.join("remembered_session.json"))
+    Ok(dir.join("auth").join("remembered_session.json"))
+}
+
+/// Environment variable overriding the generated app secret
+#[cfg(not(target_arch = "wasm32"))]
+const APP_SECRET_ENV: &str = "EPRICE_APP_SECRET";
+
+/// Secret the remembered-session key is derived from: `EPRICE_APP_SECRET`
+/// if set, otherwise a random secret generated once under the data directory
+#[cfg(not(target_arch = "wasm32"))]
+fn app_secret() -> std::io::Result<Vec<u8>> {
+    if let Ok(secret) = std::env::var(APP_SECRET_ENV) {
+        if !secret.is_empty() {
+            return Ok(secret.into_bytes());
+        }
+    }
+    let dir = get_data_directory().map_err(|e| std::io::Error::other(e.to_string()))?;
+    let path = dir.join("auth").join("app.key");
+    if let Ok(secret) = std::fs::read(&path) {
+        if !secret.is_empty() {
+            return Ok(secret);
+        }
+    }
+    let mut secret = vec![0u8; 32];
+    getrandom::fill(&mut secret).map_err(|e| std::io::Error::other(e.to_string()))?;
+    crate::utils::file_utils::atomic_write(&path, &secret)?;
+    Ok(secret)
 }
 
 fn persist_remembered_session() -> std::io::Result<()> {
@@ -210,13 +236,32 @@ fn persist_remembered_session() -> std::io::Result<()> {
         .and_then(|g| g.clone())
         .unwrap_or_default();
     let json = serde_json::to_vec(&value).unwrap_or_else(|_| b"null".to_vec());
-    save_to_file(path, &json).map_err(|e| std::io::Error::other(e.to_string()))
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        crate::utils::file_utils::write_encrypted(path, &json, &app_secret()?)
+            .map_err(|e| std::io::Error::other(e.to_string()))
+    }
+    #[cfg(target_arch = "wasm32")]
+    crate::utils::file_utils::save_to_file(path, &json)
+        .map_err(|e| std::io::Error::other(e.to_string()))
+}
+
+/// Read the remembered session file, accepting plaintext files written by older versions
+fn read_remembered_session(path: &std::path::Path) -> Option<Vec<u8>> {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Ok(bytes) = app_secret()
+        .map_err(anyhow::Error::from)
+        .and_then(|secret| crate::utils::file_utils::read_encrypted(path, &secret))
+    {
+        return Some(bytes);
+    }
+    load_from_file(path).ok()
 }
 
 pub fn load_remembered_session_from_disk() {
     if let Ok(path) = remembered_session_file() {
         if crate::utils::file_utils::file_exists(&path) {
-            if let Ok(bytes) = load_from_file(&path) {
+            if let Some(bytes) = read_remembered_session(&path) {
                 if let Ok(value) = serde_json::from_slice::<Option<String>>(&bytes) {
                     if let Ok(mut guard) = REMEMBERED_SESSION_ID.lock() {
                         *guard = value;
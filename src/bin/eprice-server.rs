@@ -0,0 +1,18 @@
+//! Standalone query API server (`cargo run --features server --bin eprice-server`)
+
+use eprice::services::AppServices;
+use eprice::settings::AppConfig;
+use std::sync::{Arc, RwLock};
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    env_logger::init();
+
+    let config = AppConfig::load().unwrap_or_else(|e| {
+        log::warn!("Failed to load config, using defaults: {}", e);
+        AppConfig::default()
+    });
+
+    let services = Arc::new(RwLock::new(AppServices::new()));
+    eprice::server::run(&config.server_settings, services).await
+}
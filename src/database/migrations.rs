@@ -12,10 +12,44 @@ pub async fn run_migrations(pool: &Pool<Sqlite>) -> Result<()> {
     create_price_alerts_table(pool).await?;
     create_ocr_results_table(pool).await?;
 
+    // Columns added after the tables were first shipped
+    add_column_if_missing(pool, "products", "version", "INTEGER NOT NULL DEFAULT 0").await?;
+    add_column_if_missing(pool, "price_records", "discount", "TEXT").await?; // JSON
+    add_column_if_missing(pool, "price_records", "expiry", "TEXT").await?; // YYYY-MM-DD
+    add_column_if_missing(
+        pool,
+        "price_records",
+        "source",
+        "TEXT NOT NULL DEFAULT 'Manual'",
+    )
+    .await?;
+
     log::info!("Database migrations completed successfully");
     Ok(())
 }
 
+/// Add a column to an existing table unless an earlier run already added it
+async fn add_column_if_missing(
+    pool: &Pool<Sqlite>,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<()> {
+    let columns: Vec<String> =
+        sqlx::query_scalar(&format!("SELECT name FROM pragma_table_info('{}')", table))
+            .fetch_all(pool)
+            .await?;
+    if !columns.iter().any(|name| name == column) {
+        sqlx::query(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            table, column, definition
+        ))
+        .execute(pool)
+        .await?;
+    }
+    Ok(())
+}
+
 /// Create users table
 async fn create_users_table(pool: &Pool<Sqlite>) -> Result<()> {
     sqlx::query(
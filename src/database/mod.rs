@@ -1,9 +1,11 @@
 pub mod connection;
 pub mod migrations;
 pub mod repository;
+pub mod sync_queue;
 
 pub use connection::DatabaseManager;
 pub use repository::{PriceRepository, ProductRepository, StoreRepository, UserRepository};
+pub use sync_queue::{SyncQueue, SyncReport};
 
 use anyhow::Result;
 use sqlx::sqlite::SqlitePool;
@@ -1,4 +1,4 @@
-use crate::models::{PriceRecord, Product, Store, User};
+use crate::models::{PriceRecord, PriceSource, Product, Store, User};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use sqlx::{Pool, Row, Sqlite};
@@ -200,7 +200,7 @@ impl ProductRepository {
     /// Find products by category
     pub async fn find_by_category(&self, category: &str) -> Result<Vec<Product>> {
         let rows = sqlx::query(
-            "SELECT id, name, category, description, barcode, images, tags, created_at 
+            "SELECT id, name, category, description, barcode, images, tags, created_at, version 
              FROM products WHERE category = ? ORDER BY name",
         )
         .bind(category)
@@ -220,6 +220,7 @@ impl ProductRepository {
                 tags: serde_json::from_str(&row.get::<String, _>("tags")).unwrap_or_default(),
                 created_at: DateTime::from_timestamp(row.get::<i64, _>("created_at"), 0)
                     .unwrap_or(Utc::now()),
+                version: row.get::<i64, _>("version") as u64,
             })
             .collect();
 
@@ -229,7 +230,7 @@ impl ProductRepository {
     /// Find product by barcode
     pub async fn find_by_barcode(&self, barcode: &str) -> Result<Option<Product>> {
         let row = sqlx::query(
-            "SELECT id, name, category, description, barcode, images, tags, created_at 
+            "SELECT id, name, category, description, barcode, images, tags, created_at, version 
              FROM products WHERE barcode = ?",
         )
         .bind(barcode)
@@ -248,6 +249,7 @@ impl ProductRepository {
                 tags: serde_json::from_str(&row.get::<String, _>("tags")).unwrap_or_default(),
                 created_at: DateTime::from_timestamp(row.get::<i64, _>("created_at"), 0)
                     .unwrap_or(Utc::now()),
+                version: row.get::<i64, _>("version") as u64,
             }))
         } else {
             Ok(None)
@@ -258,7 +260,7 @@ impl ProductRepository {
     pub async fn search_by_name(&self, name: &str) -> Result<Vec<Product>> {
         let search_term = format!("%{}%", name);
         let rows = sqlx::query(
-            "SELECT id, name, category, description, barcode, images, tags, created_at 
+            "SELECT id, name, category, description, barcode, images, tags, created_at, version 
              FROM products WHERE name LIKE ? ORDER BY name",
         )
         .bind(search_term)
@@ -278,6 +280,7 @@ impl ProductRepository {
                 tags: serde_json::from_str(&row.get::<String, _>("tags")).unwrap_or_default(),
                 created_at: DateTime::from_timestamp(row.get::<i64, _>("created_at"), 0)
                     .unwrap_or(Utc::now()),
+                version: row.get::<i64, _>("version") as u64,
             })
             .collect();
 
@@ -291,8 +294,8 @@ impl Repository<Product> for ProductRepository {
         let tags_json = serde_json::to_string(&product.tags)?;
 
         sqlx::query(
-            "INSERT INTO products (id, name, category, description, barcode, images, tags, created_at) 
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+            "INSERT INTO products (id, name, category, description, barcode, images, tags, created_at, version) 
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
         )
         .bind(&product.id)
         .bind(&product.name)
@@ -302,6 +305,7 @@ impl Repository<Product> for ProductRepository {
         .bind(images_json)
         .bind(tags_json)
         .bind(product.created_at.timestamp())
+        .bind(product.version as i64)
         .execute(&self.pool)
         .await?;
         Ok(())
@@ -309,7 +313,7 @@ impl Repository<Product> for ProductRepository {
 
     async fn find_by_id(&self, id: &str) -> Result<Option<Product>> {
         let row = sqlx::query(
-            "SELECT id, name, category, description, barcode, images, tags, created_at 
+            "SELECT id, name, category, description, barcode, images, tags, created_at, version 
              FROM products WHERE id = ?",
         )
         .bind(id)
@@ -328,6 +332,7 @@ impl Repository<Product> for ProductRepository {
                 tags: serde_json::from_str(&row.get::<String, _>("tags")).unwrap_or_default(),
                 created_at: DateTime::from_timestamp(row.get::<i64, _>("created_at"), 0)
                     .unwrap_or(Utc::now()),
+                version: row.get::<i64, _>("version") as u64,
             }))
         } else {
             Ok(None)
@@ -339,7 +344,7 @@ impl Repository<Product> for ProductRepository {
         let tags_json = serde_json::to_string(&product.tags)?;
 
         sqlx::query(
-            "UPDATE products SET name = ?, category = ?, description = ?, barcode = ?, images = ?, tags = ? 
+            "UPDATE products SET name = ?, category = ?, description = ?, barcode = ?, images = ?, tags = ?, version = ? 
              WHERE id = ?"
         )
         .bind(&product.name)
@@ -348,6 +353,7 @@ impl Repository<Product> for ProductRepository {
         .bind(&product.barcode)
         .bind(images_json)
         .bind(tags_json)
+        .bind(product.version as i64)
         .bind(&product.id)
         .execute(&self.pool)
         .await?;
@@ -364,7 +370,7 @@ impl Repository<Product> for ProductRepository {
 
     async fn find_all(&self) -> Result<Vec<Product>> {
         let rows = sqlx::query(
-            "SELECT id, name, category, description, barcode, images, tags, created_at 
+            "SELECT id, name, category, description, barcode, images, tags, created_at, version 
              FROM products ORDER BY name",
         )
         .fetch_all(&self.pool)
@@ -383,6 +389,7 @@ impl Repository<Product> for ProductRepository {
                 tags: serde_json::from_str(&row.get::<String, _>("tags")).unwrap_or_default(),
                 created_at: DateTime::from_timestamp(row.get::<i64, _>("created_at"), 0)
                     .unwrap_or(Utc::now()),
+                version: row.get::<i64, _>("version") as u64,
             })
             .collect();
 
@@ -551,7 +558,7 @@ impl PriceRepository {
     /// Find prices for a specific product
     pub async fn find_by_product_id(&self, product_id: &str) -> Result<Vec<PriceRecord>> {
         let rows = sqlx::query(
-            "SELECT id, product_id, store_id, user_id, price, timestamp, is_on_sale, receipt_image, verification_status 
+            "SELECT id, product_id, store_id, user_id, price, timestamp, is_on_sale, receipt_image, verification_status, discount, expiry, source 
              FROM price_records WHERE product_id = ? ORDER BY timestamp DESC"
         )
         .bind(product_id)
@@ -571,6 +578,11 @@ impl PriceRepository {
                 is_on_sale: row.get("is_on_sale"),
                 receipt_image: row.get("receipt_image"),
                 verification_status: row.get("verification_status"),
+                discount: row
+                    .get::<Option<String>, _>("discount")
+                    .and_then(|json| serde_json::from_str(&json).ok()),
+                expiry: row.get("expiry"),
+                source: PriceSource::from_name(&row.get::<String, _>("source")).unwrap_or_default(),
             })
             .collect();
 
@@ -584,7 +596,7 @@ impl PriceRepository {
         limit: i32,
     ) -> Result<Vec<PriceRecord>> {
         let rows = sqlx::query(
-            "SELECT id, product_id, store_id, user_id, price, timestamp, is_on_sale, receipt_image, verification_status 
+            "SELECT id, product_id, store_id, user_id, price, timestamp, is_on_sale, receipt_image, verification_status, discount, expiry, source 
              FROM price_records WHERE product_id = ? AND verification_status = 'verified' 
              ORDER BY timestamp DESC LIMIT ?"
         )
@@ -606,6 +618,11 @@ impl PriceRepository {
                 is_on_sale: row.get("is_on_sale"),
                 receipt_image: row.get("receipt_image"),
                 verification_status: row.get("verification_status"),
+                discount: row
+                    .get::<Option<String>, _>("discount")
+                    .and_then(|json| serde_json::from_str(&json).ok()),
+                expiry: row.get("expiry"),
+                source: PriceSource::from_name(&row.get::<String, _>("source")).unwrap_or_default(),
             })
             .collect();
 
@@ -614,9 +631,15 @@ impl PriceRepository {
 
     /// Create a new price record
     pub async fn create_price_record(&self, price_record: &PriceRecord) -> Result<()> {
+        let discount_json = price_record
+            .discount
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()?;
+
         sqlx::query(
-            "INSERT INTO price_records (id, product_id, store_id, user_id, price, timestamp, is_on_sale, receipt_image, verification_status) 
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            "INSERT INTO price_records (id, product_id, store_id, user_id, price, timestamp, is_on_sale, receipt_image, verification_status, discount, expiry, source) 
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
         )
         .bind(&price_record.id)
         .bind(&price_record.product_id)
@@ -627,8 +650,145 @@ impl PriceRepository {
         .bind(price_record.is_on_sale)
         .bind(&price_record.receipt_image)
         .bind(&price_record.verification_status)
+        .bind(discount_json)
+        .bind(price_record.expiry)
+        .bind(price_record.source.name())
         .execute(&self.pool)
         .await?;
         Ok(())
     }
+
+    /// Insert several price records, returning one result per record so
+    /// callers can retry only the ones that failed
+    pub async fn create_price_records(&self, price_records: &[PriceRecord]) -> Vec<Result<()>> {
+        let mut results = Vec::with_capacity(price_records.len());
+        for price_record in price_records {
+            results.push(self.create_price_record(price_record).await);
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{DatabaseManager, migrations};
+    use crate::models::Discount;
+
+    async fn open_database(dir: &std::path::Path) -> DatabaseManager {
+        let url = format!("sqlite://{}?mode=rwc", dir.join("eprice.db").display());
+        let database = DatabaseManager::new(&url).await.unwrap();
+        migrations::run_migrations(database.pool()).await.unwrap();
+        database
+    }
+
+    #[tokio::test]
+    async fn test_product_version_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = open_database(dir.path()).await;
+        // 再次迁移不会重复添加列
+        migrations::run_migrations(database.pool()).await.unwrap();
+        let repository = ProductRepository::new(database.pool().clone());
+
+        let mut product = Product::new(
+            "Milk".to_string(),
+            "Dairy".to_string(),
+            String::new(),
+            None,
+            Vec::new(),
+            Vec::new(),
+        );
+        product.version = 3;
+        repository.create(&product).await.unwrap();
+        let id = product.id.clone();
+        assert_eq!(
+            repository.find_by_id(&id).await.unwrap().unwrap().version,
+            3
+        );
+
+        product.version = 4;
+        repository.update(&product).await.unwrap();
+        assert_eq!(repository.find_all().await.unwrap()[0].version, 4);
+    }
+
+    /// Repository with one product and one store for price records to reference
+    async fn price_repository(database: &DatabaseManager) -> PriceRepository {
+        sqlx::query(
+            "INSERT INTO products (id, name, category, description, images, tags, created_at)
+             VALUES ('p1', 'Milk', 'Dairy', '', '[]', '[]', 0)",
+        )
+        .execute(database.pool())
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO stores (id, name, address, latitude, longitude, opening_hours, phone, tags, symbol, created_at)
+             VALUES ('s1', 'Store', '', 0, 0, '', '', '[]', '', 0)",
+        )
+        .execute(database.pool())
+        .await
+        .unwrap();
+        PriceRepository::new(database.pool().clone())
+    }
+
+    fn price_record() -> PriceRecord {
+        PriceRecord::new(
+            Some("p1".to_string()),
+            "s1".to_string(),
+            None,
+            2.49,
+            true,
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_price_discount_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = open_database(dir.path()).await;
+        let repository = price_repository(&database).await;
+
+        let mut record = price_record();
+        record.discount = Some(Discount::percentage(20.0));
+        repository.create_price_record(&record).await.unwrap();
+        repository
+            .create_price_record(&price_record())
+            .await
+            .unwrap();
+
+        let stored = repository.find_by_product_id("p1").await.unwrap();
+        let discounts: Vec<_> = stored.iter().map(|r| r.discount.clone()).collect();
+        assert!(discounts.contains(&Some(Discount::percentage(20.0))));
+        assert!(discounts.contains(&None));
+    }
+
+    #[tokio::test]
+    async fn test_price_expiry_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = open_database(dir.path()).await;
+        let repository = price_repository(&database).await;
+
+        let mut record = price_record();
+        record.expiry = chrono::NaiveDate::from_ymd_opt(2026, 12, 31);
+        record.verify();
+        repository.create_price_record(&record).await.unwrap();
+
+        let stored = repository
+            .find_latest_verified_prices("p1", 1)
+            .await
+            .unwrap();
+        assert_eq!(stored[0].expiry, record.expiry);
+    }
+
+    #[tokio::test]
+    async fn test_price_source_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = open_database(dir.path()).await;
+        let repository = price_repository(&database).await;
+
+        let record = price_record().with_source(PriceSource::Ocr);
+        repository.create_price_record(&record).await.unwrap();
+
+        let stored = repository.find_by_product_id("p1").await.unwrap();
+        assert_eq!(stored[0].source, PriceSource::Ocr);
+    }
 }
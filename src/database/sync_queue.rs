@@ -0,0 +1,213 @@
+use crate::database::{DatabaseManager, PriceRepository};
+use crate::models::PriceRecord;
+use crate::utils::{self, file_utils};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// File name of the queue inside the data directory
+const QUEUE_FILE_NAME: &str = "sync_queue.json";
+
+/// Outcome of a flush attempt
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    pub online: bool,     // 健康检查是否通过
+    pub synced: usize,    // 本次成功写入的记录数
+    pub remaining: usize, // 仍在队列中的记录数
+}
+
+/// Price submissions made while offline, persisted to disk until they can be
+/// written to the database
+#[derive(Debug)]
+pub struct SyncQueue {
+    path: PathBuf,
+    pending: Vec<PriceRecord>,
+}
+
+impl SyncQueue {
+    /// Open the queue stored at `path`, loading any items left from a previous run
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let pending = if path.exists() {
+            serde_json::from_slice(&file_utils::load_from_file(&path)?)?
+        } else {
+            Vec::new()
+        };
+        Ok(Self { path, pending })
+    }
+
+    /// Open the queue in the application data directory
+    pub fn open_default() -> Result<Self> {
+        Self::open(utils::get_data_directory()?.join(QUEUE_FILE_NAME))
+    }
+
+    /// Number of submissions waiting to be synced
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn pending(&self) -> &[PriceRecord] {
+        &self.pending
+    }
+
+    /// Queue a submission and persist the queue
+    pub fn enqueue(&mut self, mut record: PriceRecord) -> Result<()> {
+        // 数据库主键不能为空，离线时先分配 ID，重试时保持不变
+        if record.id.is_none() {
+            record.id = Some(uuid::Uuid::new_v4().to_string());
+        }
+        self.pending.push(record);
+        self.save()
+    }
+
+    /// Write pending submissions if the database health check passes.
+    /// Records that fail to insert stay queued for the next attempt.
+    pub async fn flush(&mut self, database: &DatabaseManager) -> Result<SyncReport> {
+        if self.pending.is_empty() {
+            return Ok(SyncReport {
+                online: true,
+                ..SyncReport::default()
+            });
+        }
+
+        if let Err(e) = database.health_check().await {
+            log::info!(
+                "Database unavailable, keeping {} queued prices: {}",
+                self.pending.len(),
+                e
+            );
+            return Ok(SyncReport {
+                online: false,
+                synced: 0,
+                remaining: self.pending.len(),
+            });
+        }
+
+        let repository = PriceRepository::new(database.pool().clone());
+        let results = repository.create_price_records(&self.pending).await;
+
+        let total = self.pending.len();
+        let mut remaining = Vec::new();
+        for (record, result) in self.pending.drain(..).zip(results) {
+            if let Err(e) = result {
+                log::warn!("Failed to sync price record {:?}: {}", record.id, e);
+                remaining.push(record);
+            }
+        }
+        self.pending = remaining;
+        self.save()?;
+
+        Ok(SyncReport {
+            online: true,
+            synced: total - self.pending.len(),
+            remaining: self.pending.len(),
+        })
+    }
+
+    fn save(&self) -> Result<()> {
+        let bytes = serde_json::to_vec_pretty(&self.pending)?;
+        file_utils::save_to_file(&self.path, &bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::migrations;
+
+    async fn open_database(dir: &Path) -> DatabaseManager {
+        let url = format!("sqlite://{}?mode=rwc", dir.join("eprice.db").display());
+        let database = DatabaseManager::new(&url).await.unwrap();
+        migrations::run_migrations(database.pool()).await.unwrap();
+        sqlx::query(
+            "INSERT OR IGNORE INTO products (id, name, category, description, images, tags, created_at)
+             VALUES ('p1', 'Milk', 'Dairy', '', '[]', '[]', 0)",
+        )
+        .execute(database.pool())
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT OR IGNORE INTO stores (id, name, address, latitude, longitude, opening_hours, phone, tags, symbol, created_at)
+             VALUES ('s1', 'Store', '', 0, 0, '', '', '[]', '', 0)",
+        )
+        .execute(database.pool())
+        .await
+        .unwrap();
+        database
+    }
+
+    fn record(product_id: &str) -> PriceRecord {
+        PriceRecord::new(
+            Some(product_id.to_string()),
+            "s1".to_string(),
+            None,
+            1.99,
+            false,
+            None,
+        )
+    }
+
+    async fn stored_count(database: &DatabaseManager) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM price_records")
+            .fetch_one(database.pool())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_queues_offline_and_flushes_online() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue_path = dir.path().join(QUEUE_FILE_NAME);
+
+        // 连接池关闭即模拟离线
+        let offline = open_database(dir.path()).await;
+        offline.pool().close().await;
+
+        let mut queue = SyncQueue::open(&queue_path).unwrap();
+        queue.enqueue(record("p1")).unwrap();
+        queue.enqueue(record("p1")).unwrap();
+        let report = queue.flush(&offline).await.unwrap();
+        assert!(!report.online);
+        assert_eq!(report.remaining, 2);
+
+        // 重启后仍能从磁盘恢复队列
+        let mut queue = SyncQueue::open(&queue_path).unwrap();
+        assert_eq!(queue.pending_count(), 2);
+
+        let online = open_database(dir.path()).await;
+        let report = queue.flush(&online).await.unwrap();
+        assert_eq!(
+            report,
+            SyncReport {
+                online: true,
+                synced: 2,
+                remaining: 0
+            }
+        );
+        assert_eq!(queue.pending_count(), 0);
+        assert_eq!(stored_count(&online).await, 2);
+        assert_eq!(SyncQueue::open(&queue_path).unwrap().pending_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_failed_flush_retains_item() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue_path = dir.path().join(QUEUE_FILE_NAME);
+        let database = open_database(dir.path()).await;
+
+        let mut queue = SyncQueue::open(&queue_path).unwrap();
+        queue.enqueue(record("p1")).unwrap();
+        // 商品尚未同步到数据库，外键约束导致写入失败
+        queue.enqueue(record("unknown_product")).unwrap();
+
+        let report = queue.flush(&database).await.unwrap();
+        assert!(report.online);
+        assert_eq!(report.synced, 1);
+        assert_eq!(report.remaining, 1);
+        assert_eq!(
+            queue.pending()[0].product_id.as_deref(),
+            Some("unknown_product")
+        );
+        assert_eq!(SyncQueue::open(&queue_path).unwrap().pending_count(), 1);
+        assert_eq!(stored_count(&database).await, 1);
+    }
+}
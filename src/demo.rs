@@ -0,0 +1,180 @@
+//! Deterministic sample data for demos and tests.
+
+use crate::models::{PriceRecord, PriceSource, Product, Store};
+use crate::utils::Clock;
+
+/// Sample stores around Tokyo, ids "1".."5"
+pub fn sample_stores(clock: &dyn Clock) -> Vec<Store> {
+    let now = clock.now();
+    vec![
+        Store {
+            id: "1".to_string(),
+            name: "全家便利店 - 东京站店".to_string(),
+            address: "东京都千代田区丸の内1-9-1".to_string(),
+            latitude: 35.6812,
+            longitude: 139.7671,
+            rating: 4.5,
+            opening_hours: "24小时营业".to_string(),
+            phone: "03-1234-5678".to_string(),
+            tags: vec!["便利店".to_string(), "24小时".to_string()],
+            symbol: '🏪',
+            created_at: now,
+        },
+        Store {
+            id: "2".to_string(),
+            name: "松本清 - 新宿店".to_string(),
+            address: "东京都新宿区新宿3-1-1".to_string(),
+            latitude: 35.6895,
+            longitude: 139.6917,
+            rating: 4.2,
+            opening_hours: "10:00-22:00".to_string(),
+            phone: "03-2345-6789".to_string(),
+            tags: vec![
+                "药妆店".to_string(),
+                "化妆品".to_string(),
+                "免税".to_string(),
+            ],
+            symbol: '🏪',
+            created_at: now,
+        },
+        Store {
+            id: "3".to_string(),
+            name: "唐吉诃德 - 涩谷店".to_string(),
+            address: "东京都涩谷区道玄坂2-25-5".to_string(),
+            latitude: 35.6580,
+            longitude: 139.6994,
+            rating: 4.0,
+            opening_hours: "24小时营业".to_string(),
+            phone: "03-3456-7890".to_string(),
+            tags: vec![
+                "综合商店".to_string(),
+                "免税".to_string(),
+                "24小时".to_string(),
+            ],
+            symbol: '🏪',
+            created_at: now,
+        },
+        Store {
+            id: "4".to_string(),
+            name: "无印良品 - 银座店".to_string(),
+            address: "东京都中央区银座3-3-5".to_string(),
+            latitude: 35.6721,
+            longitude: 139.7636,
+            rating: 4.3,
+            opening_hours: "11:00-20:00".to_string(),
+            phone: "03-4567-8901".to_string(),
+            tags: vec![
+                "生活用品".to_string(),
+                "服装".to_string(),
+                "家居".to_string(),
+            ],
+            symbol: '🏪',
+            created_at: now,
+        },
+        Store {
+            id: "5".to_string(),
+            name: "优衣库 - 原宿店".to_string(),
+            address: "东京都涩谷区神宫前1-14-30".to_string(),
+            latitude: 35.6716,
+            longitude: 139.7031,
+            rating: 4.4,
+            opening_hours: "10:00-21:00".to_string(),
+            phone: "03-5678-9012".to_string(),
+            tags: vec!["服装".to_string(), "时尚".to_string()],
+            symbol: '🏪',
+            created_at: now,
+        },
+    ]
+}
+
+/// Sample products with one verified price each, ids "1" and "2"
+pub fn sample_products(clock: &dyn Clock) -> Vec<Product> {
+    let now = clock.now();
+    vec![
+        Product {
+            id: "1".to_string(),
+            name: "可口可乐".to_string(),
+            category: "饮料".to_string(),
+            description: "碳酸饮料，330ml".to_string(),
+            barcode: Some("1234567890123".to_string()),
+            images: vec!["cola.jpg".to_string()],
+            prices: vec![PriceRecord {
+                id: Some("price1".to_string()),
+                product_id: Some("1".to_string()),
+                store_id: "1".to_string(),
+                user_id: None,
+                price: 3.5,
+                timestamp: now,
+                is_on_sale: false,
+                receipt_image: None,
+                verification_status: "verified".to_string(),
+                discount: None,
+                expiry: None,
+                source: PriceSource::Manual,
+            }],
+            tags: vec!["饮料".to_string(), "碳酸".to_string()],
+            created_at: now,
+            version: 0,
+        },
+        Product {
+            id: "2".to_string(),
+            name: "百事可乐".to_string(),
+            category: "饮料".to_string(),
+            description: "碳酸饮料，330ml".to_string(),
+            barcode: Some("1234567890124".to_string()),
+            images: vec!["pepsi.jpg".to_string()],
+            prices: vec![PriceRecord {
+                id: Some("price2".to_string()),
+                product_id: Some("2".to_string()),
+                store_id: "2".to_string(),
+                user_id: None,
+                price: 3.0,
+                timestamp: now,
+                is_on_sale: true,
+                receipt_image: None,
+                verification_status: "verified".to_string(),
+                discount: None,
+                expiry: None,
+                source: PriceSource::Manual,
+            }],
+            tags: vec!["饮料".to_string(), "碳酸".to_string()],
+            created_at: now,
+            version: 0,
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::FixedClock;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_seeder_is_deterministic() {
+        let clock = FixedClock(Utc.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap());
+
+        let stores = sample_stores(&clock);
+        let products = sample_products(&clock);
+        assert_eq!(stores.len(), 5);
+        assert_eq!(products.len(), 2);
+        assert_eq!(
+            stores.iter().map(|s| s.id.as_str()).collect::<Vec<_>>(),
+            ["1", "2", "3", "4", "5"]
+        );
+        assert_eq!(
+            products.iter().map(|p| p.id.as_str()).collect::<Vec<_>>(),
+            ["1", "2"]
+        );
+        assert!(stores.iter().all(|s| s.created_at == clock.0));
+        assert!(products.iter().all(|p| p.created_at == clock.0
+            && p.prices.iter().all(|r| r.timestamp == clock.0)));
+
+        // 价格记录指向存在的门店
+        for price in products.iter().flat_map(|p| &p.prices) {
+            assert!(stores.iter().any(|s| s.id == price.store_id));
+        }
+        assert_eq!(sample_stores(&clock), stores);
+        assert_eq!(sample_products(&clock), products);
+    }
+}
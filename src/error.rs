@@ -84,7 +84,44 @@ impl From<sqlx::Error> for AppError {
 
 impl From<crate::services::ServiceError> for AppError {
     fn from(error: crate::services::ServiceError) -> Self {
-        AppError::Service(error.to_string())
+        use crate::services::ServiceError;
+        // 可重试的错误映射到对应的可恢复变体，保留重试语义
+        match error {
+            ServiceError::DatabaseError(msg) => AppError::Database(msg),
+            ServiceError::ExternalServiceError(msg) => AppError::ExternalService(msg),
+            other => AppError::Service(other.to_string()),
+        }
+    }
+}
+
+impl From<crate::async_ops::OperationError> for AppError {
+    fn from(error: crate::async_ops::OperationError) -> Self {
+        use crate::async_ops::OperationError;
+        // 超时按网络错误处理，资源不可用按外部服务处理，沿用其重试策略
+        match error {
+            OperationError::Source(error) => error,
+            OperationError::NetworkError(msg) | OperationError::TimeoutError(msg) => {
+                AppError::Network(msg)
+            }
+            OperationError::DatabaseError(msg) => AppError::Database(msg),
+            OperationError::ResourceUnavailable(msg) => AppError::ExternalService(msg),
+            OperationError::PermissionDenied(msg) => AppError::Authentication(msg),
+            OperationError::InvalidInput(msg) => AppError::Validation(msg),
+            other => AppError::Internal(other.to_string()),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<crate::scanner::ScannerError> for AppError {
+    fn from(error: crate::scanner::ScannerError) -> Self {
+        AppError::Scanner(error.to_string())
+    }
+}
+
+impl From<crate::ocr::OcrError> for AppError {
+    fn from(error: crate::ocr::OcrError) -> Self {
+        AppError::Ocr(error.to_string())
     }
 }
 
@@ -170,35 +207,7 @@ impl ContextualError {
 
     /// Get user-friendly error message
     pub fn user_message(&self) -> String {
-        match &self.error {
-            AppError::Authentication(_) => {
-                "Authentication failed. Please check your credentials and try again.".to_string()
-            }
-            AppError::Database(_) => {
-                "A database error occurred. Please try again later.".to_string()
-            }
-            AppError::Validation(msg) => format!("Validation failed: {}", msg),
-            AppError::Network(_) => {
-                "Network error. Please check your connection and try again.".to_string()
-            }
-            AppError::FileSystem(_) => {
-                "File operation failed. Please check permissions and try again.".to_string()
-            }
-            AppError::Configuration(_) => {
-                "Configuration error. Please contact support.".to_string()
-            }
-            AppError::Ocr(_) => {
-                "OCR processing failed. Please try with a clearer image.".to_string()
-            }
-            AppError::Scanner(_) => {
-                "Scanner error. Please check camera permissions and try again.".to_string()
-            }
-            AppError::BusinessLogic(msg) => msg.clone(),
-            AppError::ExternalService(_) => {
-                "External service unavailable. Please try again later.".to_string()
-            }
-            _ => "An unexpected error occurred. Please try again.".to_string(),
-        }
+        ErrorHandler::user_message(&self.error, Locale::En)
     }
 }
 
@@ -233,6 +242,65 @@ where
     }
 }
 
+/// User-facing message locale
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Locale {
+    #[default]
+    En,
+    Zh,
+}
+
+impl Locale {
+    /// Parse a locale code such as "en", "zh" or "zh-CN"; unknown codes fall back to English
+    pub fn from_code(code: &str) -> Self {
+        let code = code.trim().to_lowercase();
+        if code.starts_with("zh") {
+            Locale::Zh
+        } else {
+            Locale::En
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Zh => "zh",
+        }
+    }
+}
+
+/// Message catalog keyed by `AppError::code()`
+fn catalog_message(code: &str, locale: Locale) -> Option<&'static str> {
+    let message = match (code, locale) {
+        ("authentication", Locale::En) => {
+            "Authentication failed. Please check your credentials and try again."
+        }
+        ("authentication", Locale::Zh) => "身份验证失败，请检查账号和密码后重试。",
+        ("database", Locale::En) => "A database error occurred. Please try again later.",
+        ("database", Locale::Zh) => "数据库出错，请稍后重试。",
+        ("validation", Locale::En) => "Validation failed",
+        ("validation", Locale::Zh) => "输入校验失败",
+        ("network", Locale::En) => "Network error. Please check your connection and try again.",
+        ("network", Locale::Zh) => "网络错误，请检查网络连接后重试。",
+        ("file_system", Locale::En) => {
+            "File operation failed. Please check permissions and try again."
+        }
+        ("file_system", Locale::Zh) => "文件操作失败，请检查权限后重试。",
+        ("configuration", Locale::En) => "Configuration error. Please contact support.",
+        ("configuration", Locale::Zh) => "配置错误，请联系技术支持。",
+        ("ocr", Locale::En) => "OCR processing failed. Please try with a clearer image.",
+        ("ocr", Locale::Zh) => "文字识别失败，请使用更清晰的图片重试。",
+        ("scanner", Locale::En) => "Scanner error. Please check camera permissions and try again.",
+        ("scanner", Locale::Zh) => "扫码失败，请检查摄像头权限后重试。",
+        ("external_service", Locale::En) => "External service unavailable. Please try again later.",
+        ("external_service", Locale::Zh) => "外部服务暂不可用，请稍后重试。",
+        ("unknown", Locale::En) => "An unexpected error occurred. Please try again.",
+        ("unknown", Locale::Zh) => "发生未知错误，请重试。",
+        _ => return None,
+    };
+    Some(message)
+}
+
 /// Error handler for UI components
 pub struct ErrorHandler {
     current_error: Option<ContextualError>,
@@ -263,6 +331,43 @@ impl ErrorHandler {
         self.current_error = Some(error);
     }
 
+    /// Handle any module error, converting it into `AppError` with the given operation
+    pub fn handle<E: Into<AppError>>(&mut self, error: E, operation: impl Into<String>) {
+        self.handle_error(ContextualError::new(
+            error.into(),
+            ErrorContext::new(operation),
+        ));
+    }
+
+    /// Localized, user-friendly message; internal details (SQL text, paths) are hidden
+    pub fn user_message(error: &AppError, locale: Locale) -> String {
+        match error {
+            // 校验和业务错误的细节本身就是给用户看的
+            AppError::Validation(msg) => {
+                format!("{}: {}", Self::message_for_code("validation", locale), msg)
+            }
+            AppError::BusinessLogic(msg) => msg.clone(),
+            _ => Self::message_for_code(error.code(), locale).to_string(),
+        }
+    }
+
+    /// Raw error detail for logs and debugging
+    pub fn debug_message(error: &AppError) -> String {
+        error.to_string()
+    }
+
+    /// Look up a catalog message, falling back to the generic message for unknown codes
+    pub fn message_for_code(code: &str, locale: Locale) -> &'static str {
+        catalog_message(code, locale)
+            .or_else(|| catalog_message("unknown", locale))
+            .unwrap_or("An unexpected error occurred. Please try again.")
+    }
+
+    /// Format any module error uniformly as `<category> error: <detail>`
+    pub fn format_error<E: Into<AppError>>(error: E) -> String {
+        error.into().to_string()
+    }
+
     /// Get the current error
     pub fn current_error(&self) -> Option<&ContextualError> {
         self.current_error.as_ref()
@@ -333,6 +438,26 @@ pub enum RecoveryStrategy {
 }
 
 impl AppError {
+    /// Stable code used to look up localized messages
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Authentication(_) => "authentication",
+            AppError::Alert(_) => "alert",
+            AppError::Database(_) => "database",
+            AppError::Service(_) => "service",
+            AppError::FileSystem(_) => "file_system",
+            AppError::Validation(_) => "validation",
+            AppError::Configuration(_) => "configuration",
+            AppError::Network(_) => "network",
+            AppError::Ocr(_) => "ocr",
+            AppError::Scanner(_) => "scanner",
+            AppError::Serialization(_) => "serialization",
+            AppError::Internal(_) => "internal",
+            AppError::BusinessLogic(_) => "business_logic",
+            AppError::ExternalService(_) => "external_service",
+        }
+    }
+
     /// Get the recommended recovery strategy for this error
     pub fn recovery_strategy(&self) -> RecoveryStrategy {
         match self {
@@ -353,6 +478,14 @@ impl AppError {
         }
     }
 
+    /// Check if retrying the same operation may succeed
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self.recovery_strategy(),
+            RecoveryStrategy::Retry | RecoveryStrategy::RetryWithDelay(_)
+        )
+    }
+
     /// Check if this error is recoverable
     pub fn is_recoverable(&self) -> bool {
         matches!(
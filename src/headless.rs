@@ -0,0 +1,351 @@
+//! Business wiring without any UI, for CLIs, servers and tests.
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::database::repository::Repository;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::database::{
+    DatabaseManager, ProductRepository, StoreRepository, SyncQueue, SyncReport, migrations,
+};
+use crate::models::{PriceRecord, Product, Store};
+use crate::services::store_service::StoreDistance;
+use crate::services::{AppServices, ServiceResult};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+
+/// Services plus optional database, with methods mirroring the common UI actions
+pub struct HeadlessApp {
+    pub services: AppServices,
+    #[cfg(not(target_arch = "wasm32"))]
+    database: Option<Arc<DatabaseManager>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    sync_queue: Option<SyncQueue>, // 等待写入数据库的价格提交
+}
+
+impl HeadlessApp {
+    pub fn new() -> Self {
+        Self {
+            services: AppServices::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            database: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            sync_queue: None,
+        }
+    }
+
+    /// Use an already opened database
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_database(mut self, database: Arc<DatabaseManager>) -> Self {
+        self.database = Some(database);
+        self
+    }
+
+    /// Keep price submissions in `queue` until the database accepts them
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_sync_queue(mut self, queue: SyncQueue) -> Self {
+        self.sync_queue = Some(queue);
+        self
+    }
+
+    /// Migrate the data directory, open the default SQLite database and the
+    /// sync queue, and write prices queued by an earlier session.
+    /// Blocks on its own runtime, so it must not be called from async code
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn open_default_database(&mut self) -> anyhow::Result<Arc<DatabaseManager>> {
+        if let Err(e) = crate::utils::file_utils::run_data_migration() {
+            log::error!("Failed to migrate data directory: {}", e);
+        }
+        // 队列与数据库无关，数据库打不开时提交也先存入队列
+        match SyncQueue::open_default() {
+            Ok(queue) => self.sync_queue = Some(queue),
+            Err(e) => log::error!("Failed to open sync queue: {}", e),
+        }
+        let runtime = tokio::runtime::Runtime::new()?;
+        let database = Arc::new(runtime.block_on(async {
+            let database = DatabaseManager::new_default().await?;
+            migrations::run_migrations(database.pool()).await?;
+            anyhow::Ok(database)
+        })?);
+        self.database = Some(database.clone());
+        log::info!("Database connection initialized successfully");
+        if let Err(e) = self.flush_sync_queue() {
+            log::warn!("Failed to sync queued prices: {}", e);
+        }
+        Ok(database)
+    }
+
+    /// Queue `record` for the database and try to write the queue.
+    /// Blocks on its own runtime, so it must not be called from async code
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn sync_price(&mut self, record: &PriceRecord) -> anyhow::Result<Option<SyncReport>> {
+        let Some(queue) = self.sync_queue.as_mut() else {
+            return Ok(None);
+        };
+        queue.enqueue(record.clone())?;
+        self.flush_sync_queue()
+    }
+
+    /// Write queued prices if the database health check passes; they stay
+    /// queued otherwise. Products and stores the prices refer to are written
+    /// first so the foreign keys hold. None without a database or queue.
+    /// Blocks on its own runtime, so it must not be called from async code
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn flush_sync_queue(&mut self) -> anyhow::Result<Option<SyncReport>> {
+        let (Some(database), Some(queue)) = (self.database.clone(), self.sync_queue.as_mut())
+        else {
+            return Ok(None);
+        };
+        if queue.pending_count() == 0 {
+            return Ok(None);
+        }
+        let services = &self.services;
+        let runtime = tokio::runtime::Runtime::new()?;
+        let report = runtime.block_on(async {
+            if database.health_check().await.is_ok() {
+                write_price_references(services, &database, queue.pending()).await;
+            }
+            queue.flush(&database).await
+        })?;
+        if report.remaining > 0 {
+            log::info!("{} price submissions waiting for sync", report.remaining);
+        }
+        Ok(Some(report))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn database(&self) -> Option<&Arc<DatabaseManager>> {
+        self.database.as_ref()
+    }
+
+    /// Load stores and products keeping their ids (e.g. [`crate::demo`] data)
+    pub fn seed(&mut self, stores: &[Store], products: &[Product]) {
+        for store in stores {
+            if let Err(e) = self.services.store_service.restore_store(store.clone()) {
+                log::warn!("Skipping seed store {}: {}", store.id, e);
+            }
+        }
+        for product in products {
+            if let Err(e) = self
+                .services
+                .product_service
+                .restore_product(product.clone())
+            {
+                log::warn!("Skipping seed product {}: {}", product.id, e);
+            }
+            self.services.price_service.register_product(product);
+        }
+    }
+
+    /// Products matching `query` by name, description or tag
+    pub fn search_products(
+        &self,
+        query: &str,
+        category: Option<&str>,
+    ) -> ServiceResult<Vec<Product>> {
+        self.services
+            .product_service
+            .search_products(query, category)
+    }
+
+    /// Create a product and make it known to price matching (barcode, package size)
+    pub fn add_product(
+        &mut self,
+        name: &str,
+        category: &str,
+        description: &str,
+        barcode: Option<String>,
+        tags: Vec<String>,
+    ) -> ServiceResult<Product> {
+        let product = self.services.product_service.create_product(
+            name.to_string(),
+            category.to_string(),
+            description.to_string(),
+            barcode,
+            tags,
+        )?;
+        self.services.price_service.register_product(&product);
+        Ok(product)
+    }
+
+    /// Submit a price for an existing product
+    pub fn add_price(
+        &mut self,
+        product_id: &str,
+        store_id: &str,
+        user_id: Option<String>,
+        price: f64,
+        is_on_sale: bool,
+    ) -> ServiceResult<PriceRecord> {
+        // 商品必须存在
+        self.services.product_service.get_product(product_id)?;
+        let record = self.services.price_service.submit_price(
+            product_id.to_string(),
+            store_id.to_string(),
+            user_id,
+            price,
+            is_on_sale,
+            None,
+        )?;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Err(e) = self.sync_price(&record) {
+            log::warn!("Failed to queue price {:?} for sync: {}", record.id, e);
+        }
+        Ok(record)
+    }
+
+    /// Mark a submitted price as verified
+    pub fn verify_price(&mut self, price_id: &str) -> ServiceResult<PriceRecord> {
+        self.services.price_service.verify_price(price_id, true)
+    }
+
+    /// Lowest price of a product under the price service's verification policy
+    pub fn lowest_price(&self, product_id: &str) -> ServiceResult<Option<PriceRecord>> {
+        self.services
+            .price_service
+            .get_current_lowest_price(product_id)
+    }
+
+    /// Stores within `radius_km`, nearest first
+    pub fn nearby_stores(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        radius_km: f64,
+    ) -> ServiceResult<Vec<StoreDistance>> {
+        self.services
+            .store_service
+            .find_stores_near(latitude, longitude, radius_km)
+    }
+}
+
+/// Insert the products and stores of `records` that the database does not have yet
+#[cfg(not(target_arch = "wasm32"))]
+async fn write_price_references(
+    services: &AppServices,
+    database: &DatabaseManager,
+    records: &[PriceRecord],
+) {
+    let products = ProductRepository::new(database.pool().clone());
+    let stores = StoreRepository::new(database.pool().clone());
+    for record in records {
+        if let Some(product_id) = record.product_id.as_deref() {
+            if matches!(products.find_by_id(product_id).await, Ok(None)) {
+                if let Ok(product) = services.product_service.get_product(product_id) {
+                    if let Err(e) = products.create(&product).await {
+                        log::warn!("Failed to store product {}: {}", product_id, e);
+                    }
+                }
+            }
+        }
+        if matches!(stores.find_by_id(&record.store_id).await, Ok(None)) {
+            if let Ok(store) = services.store_service.get_store(&record.store_id) {
+                if let Err(e) = stores.create(&store).await {
+                    log::warn!("Failed to store store {}: {}", record.store_id, e);
+                }
+            }
+        }
+    }
+}
+
+impl Default for HeadlessApp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::demo;
+    use crate::utils::FixedClock;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_add_product_and_price_then_read_lowest() {
+        let mut app = HeadlessApp::new();
+        let product = app
+            .add_product(
+                "Matcha Latte",
+                "Beverages",
+                "Bottled matcha latte 500ml",
+                Some("4901234567892".to_string()),
+                vec!["tea".to_string()],
+            )
+            .unwrap();
+
+        let high = app
+            .add_price(&product.id, "store_1", None, 1.8, false)
+            .unwrap();
+        let low = app
+            .add_price(&product.id, "store_2", None, 1.5, true)
+            .unwrap();
+        // 未验证的价格默认不计入最低价
+        assert!(app.lowest_price(&product.id).unwrap().is_none());
+
+        app.verify_price(high.id.as_deref().unwrap()).unwrap();
+        app.verify_price(low.id.as_deref().unwrap()).unwrap();
+        let lowest = app.lowest_price(&product.id).unwrap().unwrap();
+        assert_eq!(lowest.price, 1.5);
+        assert_eq!(lowest.store_id, "store_2");
+
+        assert_eq!(app.search_products("matcha", None).unwrap().len(), 1);
+        assert!(
+            app.add_price("missing", "store_1", None, 1.0, false)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_prices_queue_offline_and_sync_when_online() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!(
+            "sqlite://{}?mode=rwc",
+            dir.path().join("eprice.db").display()
+        );
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let open = || {
+            runtime.block_on(async {
+                let database = DatabaseManager::new(&url).await.unwrap();
+                migrations::run_migrations(database.pool()).await.unwrap();
+                Arc::new(database)
+            })
+        };
+        let stored_count = |database: &DatabaseManager| -> i64 {
+            runtime.block_on(async {
+                sqlx::query_scalar("SELECT COUNT(*) FROM price_records")
+                    .fetch_one(database.pool())
+                    .await
+                    .unwrap()
+            })
+        };
+
+        // 连接池关闭即模拟离线
+        let offline = open();
+        runtime.block_on(offline.pool().close());
+        let clock = FixedClock(Utc.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap());
+        let mut app = HeadlessApp::new()
+            .with_database(offline)
+            .with_sync_queue(SyncQueue::open(dir.path().join("sync_queue.json")).unwrap());
+        app.seed(&demo::sample_stores(&clock), &demo::sample_products(&clock));
+        app.add_price("1", "1", None, 158.0, false).unwrap();
+        assert_eq!(app.sync_queue.as_ref().unwrap().pending_count(), 1);
+
+        let online = open();
+        app.database = Some(online.clone());
+        app.add_price("1", "2", None, 168.0, false).unwrap();
+        assert_eq!(app.sync_queue.as_ref().unwrap().pending_count(), 0);
+        assert_eq!(stored_count(&online), 2);
+    }
+
+    #[test]
+    fn test_seeded_stores_are_found_nearby() {
+        let clock = FixedClock(Utc.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap());
+        let mut app = HeadlessApp::new();
+        app.seed(&demo::sample_stores(&clock), &demo::sample_products(&clock));
+
+        // 东京站店就在东京站（内置示例门店也可能在同一位置）
+        let nearby = app.nearby_stores(35.6812, 139.7671, 1.0).unwrap();
+        let tokyo = nearby.iter().find(|d| d.store.id == "1").unwrap();
+        assert!(tokyo.distance_km < 0.01);
+        assert!(nearby.iter().all(|d| d.distance_km <= 1.0));
+    }
+}
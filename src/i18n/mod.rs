@@ -0,0 +1,125 @@
+//! UI string localization backed by embedded JSON tables.
+
+pub use crate::error::Locale;
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+const EN_TABLE: &str = include_str!("locales/en.json");
+const ZH_TABLE: &str = include_str!("locales/zh.json");
+
+/// Resolves translation keys for the current locale
+#[derive(Debug, Clone)]
+pub struct Translator {
+    locale: Locale,
+    tables: HashMap<Locale, HashMap<String, String>>,
+}
+
+impl Translator {
+    /// Create a translator with the embedded tables
+    pub fn new(locale: Locale) -> Self {
+        let mut tables = HashMap::new();
+        tables.insert(Locale::En, parse_table(EN_TABLE));
+        tables.insert(Locale::Zh, parse_table(ZH_TABLE));
+        Self { locale, tables }
+    }
+
+    pub fn locale(&self) -> Locale {
+        self.locale
+    }
+
+    pub fn set_locale(&mut self, locale: Locale) {
+        self.locale = locale;
+    }
+
+    /// Translate `key`; falls back to English, then to the key itself
+    pub fn translate(&self, key: &str) -> String {
+        self.lookup(self.locale, key)
+            .or_else(|| self.lookup(Locale::En, key))
+            .unwrap_or(key)
+            .to_string()
+    }
+
+    fn lookup(&self, locale: Locale, key: &str) -> Option<&str> {
+        self.tables
+            .get(&locale)
+            .and_then(|table| table.get(key))
+            .map(String::as_str)
+    }
+}
+
+impl Default for Translator {
+    fn default() -> Self {
+        Self::new(Locale::Zh)
+    }
+}
+
+fn parse_table(source: &str) -> HashMap<String, String> {
+    serde_json::from_str(source).unwrap_or_else(|e| {
+        log::error!("Invalid translation table: {}", e);
+        HashMap::new()
+    })
+}
+
+static TRANSLATOR: Lazy<RwLock<Translator>> = Lazy::new(|| RwLock::new(Translator::default()));
+
+/// Switch the global UI locale
+pub fn set_locale(locale: Locale) {
+    if let Ok(mut translator) = TRANSLATOR.write() {
+        translator.set_locale(locale);
+    }
+}
+
+/// Current global UI locale
+pub fn current_locale() -> Locale {
+    TRANSLATOR.read().map(|t| t.locale()).unwrap_or(Locale::Zh)
+}
+
+/// Translate `key` with the global translator
+pub fn tr(key: &str) -> String {
+    TRANSLATOR
+        .read()
+        .map(|t| t.translate(key))
+        .unwrap_or_else(|_| key.to_string())
+}
+
+/// Translate a UI string key, e.g. `tr!("nav.stores")`
+#[macro_export]
+macro_rules! tr {
+    ($key:expr) => {
+        $crate::i18n::tr($key)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_switching_locale_changes_strings() {
+        let mut translator = Translator::new(Locale::Zh);
+        assert_eq!(translator.translate("nav.stores"), "门店管理");
+
+        translator.set_locale(Locale::En);
+        assert_eq!(translator.translate("nav.stores"), "Stores");
+    }
+
+    #[test]
+    fn test_missing_key_falls_back_to_key() {
+        let translator = Translator::new(Locale::En);
+        assert_eq!(translator.translate("no.such.key"), "no.such.key");
+        assert_eq!(tr!("no.such.key"), "no.such.key");
+    }
+
+    #[test]
+    fn test_tables_have_same_keys() {
+        let translator = Translator::default();
+        let en = &translator.tables[&Locale::En];
+        let zh = &translator.tables[&Locale::Zh];
+        assert!(!en.is_empty());
+        for key in en.keys() {
+            assert!(zh.contains_key(key), "missing zh translation for {}", key);
+        }
+    }
+}
@@ -6,7 +6,10 @@ pub mod async_ops;
 pub mod auth;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod database;
+pub mod demo;
 pub mod error;
+pub mod headless;
+pub mod i18n;
 pub mod models;
 pub mod ocr;
 pub mod search;
@@ -15,14 +18,19 @@ pub mod settings;
 pub mod utils;
 pub mod verification;
 
+// HTTP query API, only built with the `server` feature
+#[cfg(all(feature = "server", not(target_arch = "wasm32")))]
+pub mod server;
+
 // Scanner module is only available for native targets (not WASM)
 #[cfg(not(target_arch = "wasm32"))]
 pub mod scanner;
 
 pub use app::TemplateApp;
+pub use headless::HeadlessApp;
 
 // Re-export commonly used types
-pub use error::{AppError, AppResult, ContextualError, ErrorHandler};
+pub use error::{AppError, AppResult, ContextualError, ErrorHandler, Locale};
 pub use models::{PriceRecord, Product, Store};
 // pub use auth::{User, AuthManager}; // Disabled for now
 // pub use database::Database; // Disabled for now
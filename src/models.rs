@@ -1,4 +1,6 @@
-use chrono::{DateTime, Utc};
+use crate::utils::percent_encode;
+use crate::utils::validation::validate_image_ref;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
 // use sqlx::FromRow; // Disabled for now
 use uuid::Uuid;
@@ -48,6 +50,10 @@ pub struct UserReview {
     pub comment: String,
     #[serde(with = "chrono::serde::ts_seconds")]
     pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub helpful_count: u32, // 认为有用的投票数
+    #[serde(default)]
+    pub unhelpful_count: u32, // 认为无用的投票数
 }
 
 impl UserReview {
@@ -67,8 +73,15 @@ impl UserReview {
             rating,
             comment,
             created_at: Utc::now(),
+            helpful_count: 0,
+            unhelpful_count: 0,
         }
     }
+
+    /// Net helpfulness score (helpful minus unhelpful votes)
+    pub fn helpfulness_score(&self) -> i64 {
+        i64::from(self.helpful_count) - i64::from(self.unhelpful_count)
+    }
 }
 
 /// Price alert model for price monitoring
@@ -81,6 +94,37 @@ pub struct PriceAlert {
     pub is_active: bool,
     #[serde(with = "chrono::serde::ts_seconds")]
     pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>, // 过期后自动移除
+    #[serde(default)]
+    pub snoozed_until: Option<DateTime<Utc>>, // 暂停提醒至该时间
+    #[serde(default)]
+    pub condition: AlertCondition, // 触发条件，缺省为价格低于目标价
+    #[serde(default = "default_use_effective_price")]
+    pub use_effective_price: bool, // 按折扣后价格判断是否触发
+}
+
+fn default_use_effective_price() -> bool {
+    true
+}
+
+/// What a price alert watches for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum AlertCondition {
+    /// Price at or below `target_price`
+    #[default]
+    PriceBelow,
+    /// Latest record expires within `within_days` days
+    ExpiringSoon { within_days: u32 },
+}
+
+/// Display state of a price alert at a given moment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertStatus {
+    Active,
+    Snoozed,
+    Paused,
+    Expired,
 }
 
 impl PriceAlert {
@@ -93,6 +137,41 @@ impl PriceAlert {
             target_price,
             is_active: true,
             created_at: Utc::now(),
+            expires_at: None,
+            snoozed_until: None,
+            condition: AlertCondition::PriceBelow,
+            use_effective_price: true,
+        }
+    }
+
+    /// Create an alert that fires when the product's expiry date is within `within_days`
+    pub fn expiring_soon(user_id: String, product_id: String, within_days: u32) -> Self {
+        Self {
+            condition: AlertCondition::ExpiringSoon { within_days },
+            ..Self::new(user_id, product_id, 0.0)
+        }
+    }
+
+    /// Whether the alert has passed its expiry time
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// Whether the alert is currently snoozed
+    pub fn is_snoozed(&self, now: DateTime<Utc>) -> bool {
+        self.snoozed_until.is_some_and(|until| until > now)
+    }
+
+    /// Current display state
+    pub fn status_at(&self, now: DateTime<Utc>) -> AlertStatus {
+        if self.is_expired(now) {
+            AlertStatus::Expired
+        } else if !self.is_active {
+            AlertStatus::Paused
+        } else if self.is_snoozed(now) {
+            AlertStatus::Snoozed
+        } else {
+            AlertStatus::Active
         }
     }
 
@@ -107,6 +186,196 @@ impl PriceAlert {
     }
 }
 
+/// Product category node; `parent` is None for top-level categories
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Category {
+    pub id: String,             // 顶级分类 id 与名称相同，子分类为 "父id/名称"
+    pub name: String,           // 显示名称
+    pub parent: Option<String>, // 父分类 id
+}
+
+impl Category {
+    /// Create a top-level category
+    pub fn root(name: String) -> Self {
+        Self {
+            id: name.clone(),
+            name,
+            parent: None,
+        }
+    }
+}
+
+/// Package size unit parsed from product names
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Unit {
+    Milliliter,
+    Liter,
+    Gram,
+    Kilogram,
+}
+
+impl Unit {
+    /// Base unit this unit normalizes to (ml for volume, g for weight)
+    pub fn base(&self) -> Unit {
+        match self {
+            Unit::Milliliter | Unit::Liter => Unit::Milliliter,
+            Unit::Gram | Unit::Kilogram => Unit::Gram,
+        }
+    }
+
+    /// Multiplier converting a value in this unit to the base unit
+    pub fn base_factor(&self) -> f64 {
+        match self {
+            Unit::Milliliter | Unit::Gram => 1.0,
+            Unit::Liter | Unit::Kilogram => 1000.0,
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Unit::Milliliter => "ml",
+            Unit::Liter => "L",
+            Unit::Gram => "g",
+            Unit::Kilogram => "kg",
+        }
+    }
+}
+
+/// Package size such as "330ml" or "1.5L"
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Quantity {
+    pub value: f64,
+    pub unit: Unit,
+}
+
+impl Quantity {
+    pub fn new(value: f64, unit: Unit) -> Self {
+        Self { value, unit }
+    }
+
+    /// Convert to the base unit (ml or g)
+    pub fn to_base(&self) -> Quantity {
+        Quantity {
+            value: self.value * self.unit.base_factor(),
+            unit: self.unit.base(),
+        }
+    }
+}
+
+/// Barcode symbology, shared by the scanner and barcode rendering
+#[derive(Debug, Clone, Eq, Hash, PartialEq)]
+pub enum BarcodeType {
+    Ean13,
+    Ean8,
+    Code128,
+    QrCode,
+    Unknown,
+}
+
+impl BarcodeType {
+    /// Best symbology for a stored product code: EAN by length, otherwise Code 128
+    pub fn for_code(code: &str) -> Self {
+        let digits = code.bytes().all(|b| b.is_ascii_digit());
+        match code.len() {
+            13 if digits => BarcodeType::Ean13,
+            8 if digits => BarcodeType::Ean8,
+            _ => BarcodeType::Code128,
+        }
+    }
+}
+
+/// In-app navigation target, e.g. from a notification click
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeepLink {
+    Product {
+        product_id: String,
+        store_id: Option<String>, // 可选：关联的商店
+    },
+    Store {
+        store_id: String,
+    },
+}
+
+impl DeepLink {
+    /// URI scheme of shareable links
+    pub const SCHEME: &'static str = "eprice";
+
+    /// Canonical URI: `eprice://product/{id}[?store={id}]` or `eprice://store/{id}`
+    pub fn to_uri(&self) -> String {
+        match self {
+            DeepLink::Product {
+                product_id,
+                store_id,
+            } => {
+                let mut uri = format!("{}://product/{}", Self::SCHEME, percent_encode(product_id));
+                if let Some(store_id) = store_id {
+                    uri.push_str(&format!("?store={}", percent_encode(store_id)));
+                }
+                uri
+            }
+            DeepLink::Store { store_id } => {
+                format!("{}://store/{}", Self::SCHEME, percent_encode(store_id))
+            }
+        }
+    }
+
+    /// Parse a URI produced by [`Self::to_uri`]; anything else yields None
+    pub fn parse(uri: &str) -> Option<DeepLink> {
+        let (scheme, rest) = uri.trim().split_once("://")?;
+        if !scheme.eq_ignore_ascii_case(Self::SCHEME) {
+            return None;
+        }
+        let (path, query) = match rest.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (rest, None),
+        };
+        let (kind, id) = path.trim_end_matches('/').split_once('/')?;
+        if id.is_empty() || id.contains('/') {
+            return None;
+        }
+        let id = uri_decode(id)?;
+
+        match kind {
+            "product" => {
+                let store_id = match query {
+                    None => None,
+                    Some(query) => {
+                        let value = query.strip_prefix("store=")?;
+                        if value.is_empty() || value.contains('&') {
+                            return None;
+                        }
+                        Some(uri_decode(value)?)
+                    }
+                };
+                Some(DeepLink::Product {
+                    product_id: id,
+                    store_id,
+                })
+            }
+            "store" if query.is_none() => Some(DeepLink::Store { store_id: id }),
+            _ => None,
+        }
+    }
+}
+
+/// Decode `%XX` escapes; malformed escapes or invalid UTF-8 yield None
+fn uri_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
 /// OCR result model for receipt scanning
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OcrResult {
@@ -114,6 +383,10 @@ pub struct OcrResult {
     pub image_path: String,
     pub extracted_text: String,
     pub parsed_items: Vec<ReceiptItem>,
+    /// Items recognized below the configured minimum confidence, kept for review
+    #[serde(default)]
+    pub uncertain_items: Vec<ReceiptItem>,
+    /// Overall recognition confidence (0.0 to 1.0)
     pub confidence_score: f32,
     #[serde(with = "chrono::serde::ts_seconds")]
     pub created_at: DateTime<Utc>,
@@ -132,6 +405,7 @@ impl OcrResult {
             image_path,
             extracted_text,
             parsed_items,
+            uncertain_items: Vec::new(),
             confidence_score,
             created_at: Utc::now(),
         }
@@ -145,6 +419,13 @@ pub struct ReceiptItem {
     pub price: f64,
     pub quantity: i32,
     pub category: Option<String>,
+    /// Recognition confidence of the item's line (0.0 to 1.0)
+    #[serde(default = "default_item_confidence")]
+    pub confidence: f32,
+}
+
+fn default_item_confidence() -> f32 {
+    1.0
 }
 
 impl ReceiptItem {
@@ -155,9 +436,15 @@ impl ReceiptItem {
             price,
             quantity,
             category,
+            confidence: default_item_confidence(),
         }
     }
 
+    /// Whether the item was recognized with at least `min_confidence`
+    pub fn is_confident(&self, min_confidence: f32) -> bool {
+        self.confidence >= min_confidence
+    }
+
     /// Calculate total price for this item
     pub fn total_price(&self) -> f64 {
         self.price * self.quantity as f64
@@ -178,6 +465,8 @@ pub struct Product {
     pub tags: Vec<String>,        // 商品标签
     #[serde(with = "chrono::serde::ts_seconds")]
     pub created_at: DateTime<Utc>, // 创建时间
+    #[serde(default)]
+    pub version: u64, // 乐观并发版本号，每次更新递增
 }
 
 impl Product {
@@ -200,9 +489,29 @@ impl Product {
             prices: Vec::new(),
             tags,
             created_at: Utc::now(),
+            version: 0,
         }
     }
 
+    /// First image that is a valid relative path or allowed URL
+    pub fn primary_image(&self) -> Option<&str> {
+        self.images
+            .iter()
+            .map(String::as_str)
+            .find(|image| validate_image_ref(image).is_ok())
+    }
+
+    /// Move the image at `index` to the front so it becomes the primary one;
+    /// false when `index` is out of range
+    pub fn set_primary_image(&mut self, index: usize) -> bool {
+        if index >= self.images.len() {
+            return false;
+        }
+        let image = self.images.remove(index);
+        self.images.insert(0, image);
+        true
+    }
+
     /// 获取当前最低价格的价格记录
     pub fn current_lowest_price(&self) -> Option<&PriceRecord> {
         self.prices
@@ -246,6 +555,18 @@ impl Product {
     }
 }
 
+/// One changed field of a product edit; revisions are append-only
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProductRevision {
+    pub product_id: String,     // 商品ID
+    pub field: String,          // 被修改的字段名
+    pub old: String,            // 修改前的值
+    pub new: String,            // 修改后的值
+    pub editor: Option<String>, // 修改人
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub timestamp: DateTime<Utc>, // 修改时间
+}
+
 /// 价格记录结构体，包含价格信息和时间戳
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq /* , FromRow */)]
 pub struct PriceRecord {
@@ -259,6 +580,71 @@ pub struct PriceRecord {
     pub is_on_sale: bool,           // 是否在促销
     pub receipt_image: Option<String>, // 小票图片路径
     pub verification_status: String, // 验证状态：pending, verified, rejected
+    #[serde(default)]
+    pub discount: Option<Discount>, // 折扣/优惠券，缺省表示无折扣
+    #[serde(default)]
+    pub expiry: Option<NaiveDate>, // 商品有效期（如 GS1 AI 17）
+    #[serde(default)]
+    pub source: PriceSource, // 价格来源，旧数据缺省为手动录入
+}
+
+/// Where a price record came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PriceSource {
+    #[default]
+    Manual, // 手动录入
+    Scan,   // 扫码后录入
+    Ocr,    // 小票识别
+    Import, // 文件导入（CSV 等）
+    Api,    // 外部接口
+}
+
+impl PriceSource {
+    /// How much a price from this source is trusted, from 0 to 1
+    pub fn trust_weight(&self) -> f64 {
+        match self {
+            PriceSource::Api => 0.9,
+            PriceSource::Scan => 0.8,
+            PriceSource::Import => 0.7,
+            PriceSource::Ocr => 0.5,
+            PriceSource::Manual => 0.3,
+        }
+    }
+
+    /// Name stored in the database, the same as the serde name
+    pub fn name(&self) -> &'static str {
+        match self {
+            PriceSource::Manual => "Manual",
+            PriceSource::Scan => "Scan",
+            PriceSource::Ocr => "Ocr",
+            PriceSource::Import => "Import",
+            PriceSource::Api => "Api",
+        }
+    }
+
+    /// Source with the given [`Self::name`]
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            PriceSource::Manual,
+            PriceSource::Scan,
+            PriceSource::Ocr,
+            PriceSource::Import,
+            PriceSource::Api,
+        ]
+        .into_iter()
+        .find(|source| source.name() == name)
+    }
+
+    /// Icon shown next to prices in the UI
+    pub fn icon(&self) -> &'static str {
+        match self {
+            PriceSource::Manual => "✍",
+            PriceSource::Scan => "📷",
+            PriceSource::Ocr => "🧾",
+            PriceSource::Import => "📥",
+            PriceSource::Api => "🌐",
+        }
+    }
 }
 
 impl PriceRecord {
@@ -281,9 +667,18 @@ impl PriceRecord {
             is_on_sale,
             receipt_image,
             verification_status: "pending".to_string(),
+            discount: None,
+            expiry: None,
+            source: PriceSource::Manual,
         }
     }
 
+    /// Set where this record came from
+    pub fn with_source(mut self, source: PriceSource) -> Self {
+        self.source = source;
+        self
+    }
+
     /// Mark the price record as verified
     pub fn verify(&mut self) {
         self.verification_status = "verified".to_string();
@@ -293,6 +688,149 @@ impl PriceRecord {
     pub fn reject(&mut self) {
         self.verification_status = "rejected".to_string();
     }
+
+    /// Attach a discount to this price record
+    pub fn with_discount(mut self, discount: Discount) -> Self {
+        self.discount = Some(discount);
+        self
+    }
+
+    /// Set the product expiry date carried by this record
+    pub fn with_expiry(mut self, expiry: NaiveDate) -> Self {
+        self.expiry = Some(expiry);
+        self
+    }
+
+    /// Whether the expiry date falls between `today` and `today + days` (inclusive)
+    pub fn expires_within(&self, today: NaiveDate, days: u32) -> bool {
+        self.expiry
+            .is_some_and(|expiry| expiry >= today && (expiry - today).num_days() <= i64::from(days))
+    }
+
+    /// Price after applying the discount, never below zero
+    pub fn effective_price(&self) -> f64 {
+        match &self.discount {
+            Some(discount) => discount.apply(self.price),
+            None => self.price.max(0.0),
+        }
+    }
+
+    /// Whether the price requires a coupon code
+    pub fn has_coupon(&self) -> bool {
+        self.discount
+            .as_ref()
+            .is_some_and(|d| d.coupon_code.is_some())
+    }
+}
+
+/// How a discount reduces the shelf price
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum DiscountKind {
+    Percentage(f64),  // 百分比折扣，例如 20.0 表示减 20%
+    FixedAmount(f64), // 立减金额
+}
+
+/// Discount attached to a price record
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Discount {
+    pub kind: DiscountKind,
+    pub coupon_code: Option<String>, // 需要使用的优惠券码
+}
+
+impl Discount {
+    pub fn percentage(percent: f64) -> Self {
+        Self {
+            kind: DiscountKind::Percentage(percent),
+            coupon_code: None,
+        }
+    }
+
+    pub fn fixed_amount(amount: f64) -> Self {
+        Self {
+            kind: DiscountKind::FixedAmount(amount),
+            coupon_code: None,
+        }
+    }
+
+    pub fn with_coupon(mut self, code: String) -> Self {
+        self.coupon_code = Some(code);
+        self
+    }
+
+    /// Apply the discount to `price`, clamping the result at zero
+    pub fn apply(&self, price: f64) -> f64 {
+        let discounted = match self.kind {
+            DiscountKind::Percentage(percent) => price * (1.0 - percent.clamp(0.0, 100.0) / 100.0),
+            DiscountKind::FixedAmount(amount) => price - amount.max(0.0),
+        };
+        discounted.max(0.0)
+    }
+}
+
+/// One purchased line of a shopping trip
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TripItem {
+    pub product_id: String,   // 商品ID
+    pub product_name: String, // 商品名称
+    pub quantity: u32,        // 购买数量
+    pub unit_price: f64,      // 标价
+    #[serde(default)]
+    pub discount: Option<Discount>, // 折扣/优惠券
+}
+
+impl TripItem {
+    pub fn new(product_id: String, product_name: String, quantity: u32, unit_price: f64) -> Self {
+        Self {
+            product_id,
+            product_name,
+            quantity,
+            unit_price,
+            discount: None,
+        }
+    }
+
+    pub fn with_discount(mut self, discount: Discount) -> Self {
+        self.discount = Some(discount);
+        self
+    }
+
+    /// Unit price after the discount, never below zero
+    pub fn effective_price(&self) -> f64 {
+        match &self.discount {
+            Some(discount) => discount.apply(self.unit_price),
+            None => self.unit_price.max(0.0),
+        }
+    }
+
+    /// quantity × effective price
+    pub fn line_total(&self) -> f64 {
+        self.quantity as f64 * self.effective_price()
+    }
+}
+
+/// Completed shopping trip at one store
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ShoppingTrip {
+    pub id: String,           // 购物记录ID
+    pub store_id: String,     // 门店ID
+    pub items: Vec<TripItem>, // 购买明细
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub timestamp: DateTime<Utc>, // 购物时间
+    pub total: f64,           // 合计，等于各行小计之和
+}
+
+impl ShoppingTrip {
+    /// Create a trip with generated ID; the total is computed from the items
+    pub fn new(store_id: String, items: Vec<TripItem>, timestamp: DateTime<Utc>) -> Self {
+        let total = items.iter().map(TripItem::line_total).sum();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            store_id,
+            items,
+            timestamp,
+            total,
+        }
+    }
 }
 
 /// 门店结构体，包含门店的基本信息
@@ -356,42 +894,232 @@ impl Store {
 
         EARTH_RADIUS * c // 返回计算出的距离
     }
-    /// 判断当前是否在营业时间内（简易实现）
-    /// 支持格式："24 hours" 或 "HH:MM-HH:MM"（不考虑时区，仅比较时间）
+    /// 判断当前是否在营业时间内（本地时间）
     pub fn is_open_now(&self) -> bool {
-        let hours = self.opening_hours.trim();
-        if hours.eq_ignore_ascii_case("24 hours") {
-            return true;
+        self.open_status(chrono::Local::now().naive_local())
+            .is_open()
+    }
+
+    /// Open/closed status at local time `now`, with the next closing or opening time.
+    /// 支持格式见 [`OpeningHours::from_string`]，区间可跨午夜（如 "22:00-02:00"）
+    pub fn open_status(&self, now: NaiveDateTime) -> OpenStatus {
+        match OpeningHours::from_string(&self.opening_hours) {
+            Some(hours) => hours.status_at(now),
+            None => OpenStatus::Unknown,
         }
-        let Some((open, close)) = hours.split_once('-') else {
-            return false;
-        };
-        let parse_hm = |s: &str| -> Option<(u32, u32)> {
+    }
+}
+
+/// Whether a store is open at a given time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpenStatus {
+    /// Open; `closes_at` is None for stores open 24 hours
+    Open {
+        closes_at: Option<NaiveDateTime>,
+    },
+    Closed {
+        opens_at: NaiveDateTime,
+    },
+    /// Opening hours could not be parsed, or the store is closed every day
+    Unknown,
+}
+
+impl OpenStatus {
+    pub fn is_open(&self) -> bool {
+        matches!(self, OpenStatus::Open { .. })
+    }
+}
+
+/// Opening hours of a single day, in minutes since midnight
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DayHours {
+    Closed,
+    AllDay,
+    /// `end <= start` means the range runs past midnight into the next day
+    Range {
+        start: u32,
+        end: u32,
+    },
+}
+
+impl DayHours {
+    /// Parse "closed", "24 hours" / "24h" or "HH:MM-HH:MM"
+    pub fn parse(text: &str) -> Option<Self> {
+        let hours = text.trim();
+        if hours.eq_ignore_ascii_case("closed") || hours == "休息" {
+            return Some(DayHours::Closed);
+        }
+        if hours.eq_ignore_ascii_case("24 hours")
+            || hours.eq_ignore_ascii_case("24h")
+            || hours == "24小时营业"
+        {
+            return Some(DayHours::AllDay);
+        }
+        let (open, close) = hours.split_once('-')?;
+        let parse_hm = |s: &str| -> Option<u32> {
             let (h, m) = s.trim().split_once(':')?;
-            Some((h.parse().ok()?, m.parse().ok()?))
+            let (h, m): (u32, u32) = (h.parse().ok()?, m.parse().ok()?);
+            // 允许 "24:00" 表示午夜结束
+            (m < 60 && (h < 24 || (h == 24 && m == 0))).then_some(h * 60 + m)
         };
-        let (oh, om) = match parse_hm(open) {
-            Some(v) => v,
-            None => return false,
-        };
-        let (ch, cm) = match parse_hm(close) {
-            Some(v) => v,
-            None => return false,
+        let start = parse_hm(open)?;
+        let end = parse_hm(close)?;
+        if start >= 24 * 60 {
+            return None;
+        }
+        // 起止相同（如 00:00-24:00）视为全天营业
+        if start == end % (24 * 60) {
+            return Some(DayHours::AllDay);
+        }
+        Some(DayHours::Range { start, end })
+    }
+
+    /// Canonical text accepted by [`Self::parse`]
+    pub fn to_canonical_string(&self) -> String {
+        let hm = |minutes: u32| format!("{:02}:{:02}", minutes / 60, minutes % 60);
+        match self {
+            DayHours::Closed => "closed".to_string(),
+            DayHours::AllDay => "24 hours".to_string(),
+            DayHours::Range { start, end } => format!("{}-{}", hm(*start), hm(*end)),
+        }
+    }
+}
+
+/// Weekly opening hours, Monday first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpeningHours {
+    pub days: [DayHours; 7],
+}
+
+/// Day abbreviations used in the canonical weekly format, Monday first
+const WEEKDAY_NAMES: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+impl OpeningHours {
+    /// The same hours every day
+    pub fn daily(hours: DayHours) -> Self {
+        Self { days: [hours; 7] }
+    }
+
+    /// Hours on `weekday`
+    pub fn day(&self, weekday: chrono::Weekday) -> DayHours {
+        self.days[weekday.num_days_from_monday() as usize]
+    }
+
+    /// Parse either one daily spec (see [`DayHours::parse`]) or a weekly list such as
+    /// "Mon-Fri 09:00-21:00; Sat 10:00-18:00; Sun closed". Days not listed are closed.
+    pub fn from_string(text: &str) -> Option<Self> {
+        if let Some(hours) = DayHours::parse(text) {
+            return Some(Self::daily(hours));
+        }
+
+        let day_index = |name: &str| {
+            WEEKDAY_NAMES
+                .iter()
+                .position(|day| day.eq_ignore_ascii_case(name.trim()))
         };
+        let mut days = [DayHours::Closed; 7];
+        for entry in text.split(';').filter(|e| !e.trim().is_empty()) {
+            let (day_spec, hours) = entry.trim().split_once(char::is_whitespace)?;
+            let hours = DayHours::parse(hours)?;
+            let (first, last) = match day_spec.split_once('-') {
+                Some((first, last)) => (day_index(first)?, day_index(last)?),
+                None => (day_index(day_spec)?, day_index(day_spec)?),
+            };
+            if first > last {
+                return None;
+            }
+            days[first..=last].fill(hours);
+        }
+        Some(Self { days })
+    }
 
-        use chrono::Timelike;
-        let now = chrono::Local::now();
-        let (nh, nm) = (now.hour(), now.minute());
+    /// Canonical text accepted by [`Self::from_string`]: a single daily spec when every
+    /// day is the same, otherwise runs of equal consecutive days, e.g. "Mon-Fri 09:00-21:00"
+    pub fn to_canonical_string(&self) -> String {
+        if self.days.iter().all(|day| *day == self.days[0]) {
+            return self.days[0].to_canonical_string();
+        }
 
-        let start = oh * 60 + om;
-        let end = ch * 60 + cm;
-        let cur = nh * 60 + nm;
+        let mut entries = Vec::new();
+        let mut first = 0;
+        while first < 7 {
+            let mut last = first;
+            while last + 1 < 7 && self.days[last + 1] == self.days[first] {
+                last += 1;
+            }
+            let days = if first == last {
+                WEEKDAY_NAMES[first].to_string()
+            } else {
+                format!("{}-{}", WEEKDAY_NAMES[first], WEEKDAY_NAMES[last])
+            };
+            entries.push(format!(
+                "{} {}",
+                days,
+                self.days[first].to_canonical_string()
+            ));
+            first = last + 1;
+        }
+        entries.join("; ")
+    }
 
-        if start <= end {
-            cur >= start && cur <= end
-        } else {
-            // 跨午夜区间，如 22:00-06:00
-            cur >= start || cur <= end
+    /// Open/closed status at local time `now`
+    pub fn status_at(&self, now: NaiveDateTime) -> OpenStatus {
+        use chrono::{Datelike, Timelike};
+        let today = now.date();
+        let cur = now.hour() * 60 + now.minute();
+        let at = |date: NaiveDate, minutes: u32| {
+            date.and_time(chrono::NaiveTime::MIN) + chrono::Duration::minutes(minutes as i64)
+        };
+        let date_after = |days: i64| today + chrono::Duration::days(days);
+        let hours_on = |date: NaiveDate| self.day(date.weekday());
+
+        // 前一天跨午夜的营业时段
+        if let DayHours::Range { start, end } = hours_on(date_after(-1)) {
+            if end <= start && cur < end {
+                return OpenStatus::Open {
+                    closes_at: Some(at(today, end)),
+                };
+            }
+        }
+
+        match hours_on(today) {
+            DayHours::AllDay => {
+                // 一直营业到第一个非全天营业的日子
+                let closes_at = (1..=7)
+                    .find(|&days| hours_on(date_after(days)) != DayHours::AllDay)
+                    .map(|days| at(date_after(days), 0));
+                return OpenStatus::Open { closes_at };
+            }
+            DayHours::Range { start, end } if (start..end).contains(&cur) => {
+                return OpenStatus::Open {
+                    closes_at: Some(at(today, end)),
+                };
+            }
+            DayHours::Range { start, end } if end <= start && cur >= start => {
+                return OpenStatus::Open {
+                    closes_at: Some(at(date_after(1), end)),
+                };
+            }
+            DayHours::Range { start, .. } if cur < start => {
+                return OpenStatus::Closed {
+                    opens_at: at(today, start),
+                };
+            }
+            _ => {}
         }
+
+        // 今天已打烊或休息：找下一个营业日
+        (1..=7)
+            .find_map(|days| {
+                let date = date_after(days);
+                match hours_on(date) {
+                    DayHours::Closed => None,
+                    DayHours::AllDay => Some(at(date, 0)),
+                    DayHours::Range { start, .. } => Some(at(date, start)),
+                }
+            })
+            .map_or(OpenStatus::Unknown, |opens_at| OpenStatus::Closed {
+                opens_at,
+            })
     }
 }
@@ -0,0 +1,125 @@
+//! Pre-filling a price submission from a receipt photo.
+
+use crate::ocr::text_extractor::TextExtractionResult;
+use crate::ocr::{OcrConfig, OcrError, OcrResult, ReceiptParser, TextExtractor};
+
+/// Turns receipt image bytes into text
+pub trait TextRecognizer: Send + Sync {
+    fn recognize(&self, image_data: &[u8], format: &str) -> anyhow::Result<TextExtractionResult>;
+}
+
+impl TextRecognizer for TextExtractor {
+    fn recognize(&self, image_data: &[u8], format: &str) -> anyhow::Result<TextExtractionResult> {
+        self.extract_text_from_data(image_data, format)
+    }
+}
+
+/// Values suggested for the price form; None fields stay as the user left them
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ReceiptAutofill {
+    /// Receipt total
+    pub price: Option<f64>,
+    /// Store name from the receipt header
+    pub store_name: Option<String>,
+    /// Lower of the text and parsing confidence
+    pub confidence: f32,
+}
+
+/// Recognize and parse a receipt, suggesting its total and store when the
+/// result reaches `config.min_confidence`
+pub fn autofill_from_receipt(
+    recognizer: &dyn TextRecognizer,
+    parser: &ReceiptParser,
+    image_data: &[u8],
+    format: &str,
+    config: &OcrConfig,
+) -> OcrResult<ReceiptAutofill> {
+    let extraction = recognizer
+        .recognize(image_data, format)
+        .map_err(|e| OcrError::TextExtraction(e.to_string()))?;
+    let receipt = parser
+        .parse_extraction(&extraction)
+        .map_err(|e| OcrError::ReceiptParsing(e.to_string()))?;
+
+    let confidence = receipt.confidence.min(receipt.parsing_confidence);
+    if confidence < config.min_confidence {
+        log::info!("Receipt confidence {:.2} too low for auto-fill", confidence);
+        return Ok(ReceiptAutofill {
+            confidence,
+            ..ReceiptAutofill::default()
+        });
+    }
+
+    let store_name = receipt.store_info.name.trim().to_string();
+    Ok(ReceiptAutofill {
+        price: receipt.totals.total.filter(|total| *total > 0.0),
+        store_name: (!store_name.is_empty() && store_name != "Unknown Store").then_some(store_name),
+        confidence,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    struct StubRecognizer {
+        text: &'static str,
+        confidence: f32,
+    }
+
+    impl TextRecognizer for StubRecognizer {
+        fn recognize(&self, _: &[u8], _: &str) -> anyhow::Result<TextExtractionResult> {
+            Ok(TextExtractionResult {
+                text: self.text.to_string(),
+                confidence: self.confidence,
+                language_detected: "jpn".to_string(),
+                word_confidences: HashMap::new(),
+                line_count: self.text.lines().count(),
+                processing_time_ms: 0,
+                layout_preserved: true,
+            })
+        }
+    }
+
+    const RECEIPT: &str = "FamilyMart\n東京駅店\n\nコカコーラ 330ml    ¥150\nポテトチップス      ¥120\n\n小計            ¥270\n合計            ¥297";
+
+    #[test]
+    fn test_recognized_receipt_prefills_total_and_store() {
+        let stub = StubRecognizer {
+            text: RECEIPT,
+            confidence: 0.9,
+        };
+        let autofill = autofill_from_receipt(
+            &stub,
+            &ReceiptParser::new(),
+            b"",
+            "png",
+            &OcrConfig::default(),
+        )
+        .unwrap();
+
+        assert_eq!(autofill.price, Some(297.0));
+        assert!(autofill.store_name.unwrap().contains("FamilyMart"));
+    }
+
+    #[test]
+    fn test_low_confidence_leaves_fields_empty() {
+        let stub = StubRecognizer {
+            text: RECEIPT,
+            confidence: 0.3,
+        };
+        let autofill = autofill_from_receipt(
+            &stub,
+            &ReceiptParser::new(),
+            b"",
+            "png",
+            &OcrConfig::default(),
+        )
+        .unwrap();
+
+        assert_eq!(autofill.price, None);
+        assert_eq!(autofill.store_name, None);
+        assert!(autofill.confidence < OcrConfig::default().min_confidence);
+    }
+}
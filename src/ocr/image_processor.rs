@@ -1,23 +1,89 @@
-use crate::utils::file_utils::{get_file_extension, save_to_file};
+use crate::ocr::{OcrConfig, OcrError, OcrResult};
+use crate::utils::file_utils::save_to_file;
 use anyhow::Result;
+use image::metadata::Orientation;
+use image::{DynamicImage, GrayImage, ImageDecoder, ImageFormat, ImageReader, Luma, Rgba};
+use imageproc::filter::median_filter;
+use imageproc::geometric_transformations::{Interpolation, rotate_about_center};
+use imageproc::hough::{LineDetectionOptions, detect_lines};
+use std::io::Cursor;
 use std::path::Path;
 
+/// Largest tilt `OcrConfig::deskew` corrects, in degrees
+pub const MAX_DESKEW_DEGREES: f32 = 10.0;
+/// Luma below this counts as ink when estimating skew
+const INK_LUMA: u8 = 128;
+/// Contrast applied by `ImageProcessor::with_config` when enhancement is on
+const ENHANCED_CONTRAST: f32 = 20.0;
+
+/// Format of an image file judged by its leading bytes
+fn sniff_format(bytes: &[u8]) -> Option<ImageFormat> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some(ImageFormat::Png)
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some(ImageFormat::Jpeg)
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some(ImageFormat::WebP)
+    } else if bytes.starts_with(b"BM") {
+        Some(ImageFormat::Bmp)
+    } else {
+        None
+    }
+}
+
+/// Load an image for OCR. The format is taken from the file content, not its
+/// extension; only PNG, JPEG, WEBP and BMP are accepted
+pub fn load_image<P: AsRef<Path>>(path: P) -> OcrResult<DynamicImage> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => OcrError::FileNotFound(path.display().to_string()),
+        _ => OcrError::ImageProcessing(e.to_string()),
+    })?;
+    decode_image(&bytes).map_err(|e| match e {
+        OcrError::UnsupportedFormat(reason) => {
+            OcrError::UnsupportedFormat(format!("{}: {}", path.display(), reason))
+        }
+        e => e,
+    })
+}
+
+/// Decode encoded image bytes and turn the image upright according to its
+/// EXIF orientation; images without EXIF are returned as stored
+pub fn decode_image(bytes: &[u8]) -> OcrResult<DynamicImage> {
+    let format = sniff_format(bytes).ok_or_else(|| {
+        OcrError::UnsupportedFormat("not a PNG, JPEG, WEBP or BMP image".to_string())
+    })?;
+    if !format.reading_enabled() {
+        return Err(OcrError::UnsupportedFormat(format!(
+            "{:?} decoding is not enabled in this build",
+            format
+        )));
+    }
+
+    let decode_error = |e: image::ImageError| OcrError::ImageProcessing(e.to_string());
+    let mut decoder = ImageReader::with_format(Cursor::new(bytes), format)
+        .into_decoder()
+        .map_err(decode_error)?;
+    // 损坏的 EXIF 不影响识别，按原方向处理
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let mut image = DynamicImage::from_decoder(decoder).map_err(decode_error)?;
+    image.apply_orientation(orientation);
+    Ok(image)
+}
+
 /// Image processor for OCR preprocessing
 pub struct ImageProcessor {
     /// Quality threshold for image processing (0.0 to 1.0)
     pub quality_threshold: f32,
-    /// Whether to apply noise reduction
-    pub noise_reduction: bool,
-    /// Whether to apply contrast enhancement
-    pub contrast_enhancement: bool,
+    /// Preprocessing steps to apply
+    pub config: OcrConfig,
 }
 
 impl Default for ImageProcessor {
     fn default() -> Self {
         Self {
             quality_threshold: 0.7,
-            noise_reduction: true,
-            contrast_enhancement: true,
+            config: OcrConfig::default(),
         }
     }
 }
@@ -35,52 +101,32 @@ impl ImageProcessor {
     ) -> Self {
         Self {
             quality_threshold,
-            noise_reduction,
-            contrast_enhancement,
+            config: OcrConfig {
+                denoise: noise_reduction,
+                contrast: if contrast_enhancement {
+                    ENHANCED_CONTRAST
+                } else {
+                    0.0
+                },
+                ..OcrConfig::default()
+            },
+        }
+    }
+
+    /// Configure the image processor with custom preprocessing settings
+    pub fn with_ocr_config(config: OcrConfig) -> Self {
+        Self {
+            config,
+            ..Self::default()
         }
     }
 
     /// Process an image file for OCR
     pub fn process_image_file<P: AsRef<Path>>(&self, image_path: P) -> Result<ProcessedImage> {
         let path = image_path.as_ref();
-
-        // Validate file extension
-        let extension = get_file_extension(path)
-            .ok_or_else(|| anyhow::anyhow!("Unable to determine file extension"))?;
-
-        if !self.is_supported_format(&extension) {
-            return Err(anyhow::anyhow!("Unsupported image format: {}", extension));
-        }
-
-        // In a real implementation, this would:
-        // 1. Load the image using image crate
-        // 2. Apply preprocessing (noise reduction, contrast enhancement, etc.)
-        // 3. Convert to grayscale
-        // 4. Apply binarization
-        // 5. Detect and correct rotation
-
-        // For now, return a mock processed image
-        Ok(ProcessedImage {
-            original_path: path.to_string_lossy().to_string(),
-            processed_data: vec![0u8; 1024], // Mock processed image data
-            width: 800,
-            height: 600,
-            confidence: self.calculate_quality_score(path)?,
-            preprocessing_applied: vec![
-                if self.noise_reduction {
-                    "noise_reduction".to_string()
-                } else {
-                    "none".to_string()
-                },
-                if self.contrast_enhancement {
-                    "contrast_enhancement".to_string()
-                } else {
-                    "none".to_string()
-                },
-                "grayscale_conversion".to_string(),
-                "binarization".to_string(),
-            ],
-        })
+        let mut processed = self.process(load_image(path)?);
+        processed.original_path = path.to_string_lossy().to_string();
+        Ok(processed)
     }
 
     /// Process raw image data
@@ -89,20 +135,54 @@ impl ImageProcessor {
             return Err(anyhow::anyhow!("Unsupported image format: {}", format));
         }
 
-        // Mock implementation
-        Ok(ProcessedImage {
+        Ok(self.process(decode_image(image_data)?))
+    }
+
+    /// Apply the configured preprocessing to an upright image (see
+    /// `decode_image` for EXIF orientation), in this order:
+    /// grayscale, denoise, contrast, deskew, threshold.
+    ///
+    /// Denoising before the contrast boost keeps speckles from being amplified,
+    /// and the skew is estimated before binarization so faint strokes still count.
+    /// Thresholding always yields a single-channel image.
+    pub fn process(&self, image: DynamicImage) -> ProcessedImage {
+        let config = &self.config;
+        let mut image = image;
+        let mut applied = Vec::new();
+
+        if config.grayscale {
+            image = DynamicImage::ImageLuma8(image.to_luma8());
+            applied.push("grayscale_conversion".to_string());
+        }
+        if config.denoise {
+            image = denoise(&image);
+            applied.push("noise_reduction".to_string());
+        }
+        if config.contrast != 0.0 {
+            image = image.adjust_contrast(config.contrast);
+            applied.push("contrast_enhancement".to_string());
+        }
+        if config.deskew {
+            let angle = estimate_skew(&image.to_luma8());
+            if angle != 0.0 {
+                image = rotate(&image, -angle);
+            }
+            applied.push(format!("deskew({:.1}°)", angle));
+        }
+        if let Some(threshold) = config.threshold {
+            image = DynamicImage::ImageLuma8(binarize(&image.to_luma8(), threshold));
+            applied.push("binarization".to_string());
+        }
+
+        ProcessedImage {
             original_path: "memory".to_string(),
-            processed_data: image_data.to_vec(),
-            width: 800,
-            height: 600,
-            confidence: 0.8,
-            preprocessing_applied: vec![
-                "noise_reduction".to_string(),
-                "contrast_enhancement".to_string(),
-                "grayscale_conversion".to_string(),
-                "binarization".to_string(),
-            ],
-        })
+            width: image.width(),
+            height: image.height(),
+            channels: image.color().channel_count(),
+            confidence: quality_score(&image.to_luma8()),
+            processed_data: image.into_bytes(),
+            preprocessing_applied: applied,
+        }
     }
 
     /// Check if the image format is supported
@@ -113,18 +193,6 @@ impl ImageProcessor {
         )
     }
 
-    /// Calculate quality score for an image
-    fn calculate_quality_score<P: AsRef<Path>>(&self, _image_path: P) -> Result<f32> {
-        // In a real implementation, this would analyze:
-        // - Image resolution
-        // - Contrast levels
-        // - Noise levels
-        // - Text clarity
-
-        // For now, return a mock score
-        Ok(0.85)
-    }
-
     /// Save processed image to disk
     pub fn save_processed_image(
         &self,
@@ -135,28 +203,119 @@ impl ImageProcessor {
         Ok(())
     }
 
-    /// Auto-detect and correct image rotation
-    pub fn detect_rotation(&self, _image_data: &[u8]) -> Result<f32> {
-        // Mock implementation - would use image processing algorithms
-        // to detect text orientation and return rotation angle in degrees
-        Ok(0.0)
+    /// Detect the text tilt of an encoded image in degrees, positive when
+    /// lines slope down to the right
+    pub fn detect_rotation(&self, image_data: &[u8]) -> Result<f32> {
+        let image = image::load_from_memory(image_data)
+            .map_err(|e| anyhow::anyhow!("Failed to decode image: {}", e))?;
+        Ok(estimate_skew(&image.to_luma8()))
     }
 
-    /// Apply rotation correction to image
+    /// Straighten an encoded image tilted by `angle` degrees (as returned by
+    /// `detect_rotation`); the result is PNG encoded
     pub fn correct_rotation(&self, image_data: &[u8], angle: f32) -> Result<Vec<u8>> {
-        // Mock implementation - would rotate the image by the specified angle
         log::info!("Applying rotation correction: {} degrees", angle);
-        Ok(image_data.to_vec())
+        let image = image::load_from_memory(image_data)
+            .map_err(|e| anyhow::anyhow!("Failed to decode image: {}", e))?;
+        let mut png = Vec::new();
+        rotate(&image, -angle).write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+        Ok(png)
+    }
+}
+
+/// Black and white copy of `image`: luma at or above `threshold` becomes white
+fn binarize(image: &GrayImage, threshold: u8) -> GrayImage {
+    GrayImage::from_fn(image.width(), image.height(), |x, y| {
+        if image.get_pixel(x, y)[0] >= threshold {
+            Luma([255])
+        } else {
+            Luma([0])
+        }
+    })
+}
+
+/// 3x3 median of every channel
+fn denoise(image: &DynamicImage) -> DynamicImage {
+    match image {
+        DynamicImage::ImageLuma8(gray) => DynamicImage::ImageLuma8(median_filter(gray, 1, 1)),
+        _ => DynamicImage::ImageRgba8(median_filter(&image.to_rgba8(), 1, 1)),
+    }
+}
+
+/// Tilt of the text lines in degrees (positive when they slope down to the
+/// right, i.e. rotated clockwise): ink pixels vote in a Hough transform and
+/// the angle shared by the most near-horizontal lines wins
+fn estimate_skew(image: &GrayImage) -> f32 {
+    let ink = GrayImage::from_fn(image.width(), image.height(), |x, y| {
+        Luma([if image.get_pixel(x, y)[0] < INK_LUMA {
+            255
+        } else {
+            0
+        }])
+    });
+    // 至少要有图宽四分之一的墨迹落在同一条线上
+    let options = LineDetectionOptions {
+        vote_threshold: (image.width() / 4).max(2),
+        suppression_radius: 8,
+    };
+
+    // 水平线的法线角为 90°，偏离量即倾斜角
+    let mut counts = std::collections::HashMap::new();
+    for line in detect_lines(&ink, options) {
+        let angle = line.angle_in_degrees as i32 - 90;
+        if angle.unsigned_abs() as f32 <= MAX_DESKEW_DEGREES {
+            *counts.entry(angle).or_insert(0u32) += 1;
+        }
+    }
+    // 票数相同时偏向更小的角度
+    counts
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then(b.0.abs().cmp(&a.0.abs())))
+        .map_or(0.0, |(angle, _)| angle as f32)
+}
+
+/// Rotate clockwise by `angle` degrees about the centre, keeping the size;
+/// uncovered corners are filled white
+fn rotate(image: &DynamicImage, angle: f32) -> DynamicImage {
+    let theta = angle.to_radians();
+    match image {
+        DynamicImage::ImageLuma8(gray) => DynamicImage::ImageLuma8(rotate_about_center(
+            gray,
+            theta,
+            Interpolation::Nearest,
+            Luma([255]),
+        )),
+        _ => DynamicImage::ImageRgba8(rotate_about_center(
+            &image.to_rgba8(),
+            theta,
+            Interpolation::Nearest,
+            Rgba([255, 255, 255, 255]),
+        )),
     }
 }
 
-/// Represents a processed image ready for OCR
+/// Rough OCR suitability from the spread of brightness: flat images score low
+fn quality_score(image: &GrayImage) -> f32 {
+    let count = image.pixels().len().max(1) as f32;
+    let mean = image.pixels().map(|p| p[0] as f32).sum::<f32>() / count;
+    let variance = image
+        .pixels()
+        .map(|p| (p[0] as f32 - mean).powi(2))
+        .sum::<f32>()
+        / count;
+    (variance.sqrt() / 64.0).min(1.0)
+}
+
+/// Represents a processed image ready for OCR; `processed_data` holds raw
+/// 8-bit pixels, row by row
 #[derive(Debug, Clone)]
 pub struct ProcessedImage {
     pub original_path: String,
     pub processed_data: Vec<u8>,
     pub width: u32,
     pub height: u32,
+    /// Interleaved channels per pixel in `processed_data` (1 for grayscale)
+    pub channels: u8,
     pub confidence: f32,
     pub preprocessing_applied: Vec<String>,
 }
@@ -182,3 +341,234 @@ impl ProcessedImage {
         self.width as f32 / self.height as f32
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    /// Angle resolution of the Hough accumulator, in degrees
+    const DESKEW_STEP_DEGREES: f32 = 1.0;
+
+    fn processor(config: OcrConfig) -> ImageProcessor {
+        ImageProcessor::with_ocr_config(config)
+    }
+
+    fn gradient() -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(64, 8, |x, _| {
+            let v = (x * 4) as u8;
+            Rgb([v, v, v])
+        }))
+    }
+
+    /// White page with dark lines sloping down to the right by `degrees`
+    fn tilted_lines(degrees: f32) -> DynamicImage {
+        let slope = degrees.to_radians().tan();
+        DynamicImage::ImageLuma8(GrayImage::from_fn(200, 200, |x, y| {
+            let offset = y as f32 - x as f32 * slope;
+            let on_line = (40..160).contains(&x) && (offset as i32 - 40).rem_euclid(30) < 3;
+            if on_line && offset > 30.0 && offset < 150.0 {
+                Luma([0])
+            } else {
+                Luma([255])
+            }
+        }))
+    }
+
+    #[test]
+    fn test_with_config_maps_legacy_flags() {
+        let processor = ImageProcessor::with_config(0.5, true, false);
+        assert_eq!(processor.quality_threshold, 0.5);
+        assert!(processor.config.denoise);
+        assert_eq!(processor.config.contrast, 0.0);
+        assert!(
+            ImageProcessor::with_config(0.7, false, true)
+                .config
+                .contrast
+                > 0.0
+        );
+    }
+
+    #[test]
+    fn test_load_image_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(matches!(
+            load_image(dir.path().join("missing.png")),
+            Err(OcrError::FileNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_image_sniffs_content() {
+        let dir = tempfile::tempdir().unwrap();
+        // 扩展名与内容不符时以内容为准
+        let png = dir.path().join("receipt.jpg");
+        gradient().save_with_format(&png, ImageFormat::Png).unwrap();
+        assert_eq!(load_image(&png).unwrap().width(), 64);
+
+        let bmp = dir.path().join("receipt.bmp");
+        gradient().save_with_format(&bmp, ImageFormat::Bmp).unwrap();
+        assert_eq!(load_image(&bmp).unwrap().height(), 8);
+    }
+
+    #[test]
+    fn test_load_image_decodes_webp() {
+        let dir = tempfile::tempdir().unwrap();
+        let webp = dir.path().join("receipt.webp");
+        gradient()
+            .save_with_format(&webp, ImageFormat::WebP)
+            .unwrap();
+
+        let image = load_image(&webp).unwrap();
+        assert_eq!((image.width(), image.height()), (64, 8));
+        assert!(
+            ImageProcessor::new()
+                .process_image_file(&webp)
+                .is_ok_and(|processed| processed.channels == 1)
+        );
+    }
+
+    #[test]
+    fn test_load_image_rejects_text_with_png_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let fake = dir.path().join("receipt.png");
+        std::fs::write(&fake, "合計 ¥418").unwrap();
+
+        assert!(matches!(
+            load_image(&fake),
+            Err(OcrError::UnsupportedFormat(_))
+        ));
+        assert!(ImageProcessor::new().process_image_file(&fake).is_err());
+    }
+
+    /// JPEG whose left half is black and right half white, optionally tagged
+    /// with an EXIF orientation
+    fn jpeg_fixture(orientation: Option<u16>) -> Vec<u8> {
+        let image = GrayImage::from_fn(32, 16, |x, _| Luma([if x < 16 { 0 } else { 255 }]));
+        let mut jpeg = Vec::new();
+        DynamicImage::ImageLuma8(image)
+            .write_to(&mut Cursor::new(&mut jpeg), ImageFormat::Jpeg)
+            .unwrap();
+        let Some(orientation) = orientation else {
+            return jpeg;
+        };
+
+        // 大端 TIFF 头 + 仅含 Orientation(0x0112) 一项的 IFD
+        let mut tiff = b"MM\0\x2a\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01".to_vec();
+        tiff.extend_from_slice(&orientation.to_be_bytes());
+        tiff.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+        let mut app1 = vec![0xFF, 0xE1];
+        app1.extend_from_slice(&((tiff.len() + 8) as u16).to_be_bytes());
+        app1.extend_from_slice(b"Exif\0\0");
+        app1.extend_from_slice(&tiff);
+        // 紧跟在 SOI 之后插入 APP1
+        jpeg.splice(2..2, app1);
+        jpeg
+    }
+
+    fn luma_at(image: &DynamicImage, x: u32, y: u32) -> u8 {
+        image.to_luma8().get_pixel(x, y)[0]
+    }
+
+    #[test]
+    fn test_exif_orientation_6_rotates_clockwise() {
+        let image = decode_image(&jpeg_fixture(Some(6))).unwrap();
+        assert_eq!((image.width(), image.height()), (16, 32));
+        // 原图左半（黑）转到上方
+        assert!(luma_at(&image, 8, 4) < 64);
+        assert!(luma_at(&image, 8, 28) > 192);
+    }
+
+    #[test]
+    fn test_exif_orientation_8_rotates_counter_clockwise() {
+        let image = decode_image(&jpeg_fixture(Some(8))).unwrap();
+        assert_eq!((image.width(), image.height()), (16, 32));
+        assert!(luma_at(&image, 8, 4) > 192);
+        assert!(luma_at(&image, 8, 28) < 64);
+    }
+
+    #[test]
+    fn test_image_without_exif_is_untouched() {
+        let image = decode_image(&jpeg_fixture(None)).unwrap();
+        assert_eq!((image.width(), image.height()), (32, 16));
+        assert!(luma_at(&image, 4, 8) < 64);
+        assert!(luma_at(&image, 28, 8) > 192);
+    }
+
+    #[test]
+    fn test_grayscale_yields_single_channel() {
+        let processed = processor(OcrConfig::default()).process(gradient());
+        assert_eq!(processed.channels, 1);
+        assert_eq!(processed.processed_data.len(), 64 * 8);
+
+        let color = processor(OcrConfig {
+            grayscale: false,
+            ..OcrConfig::default()
+        })
+        .process(gradient());
+        assert_eq!(color.channels, 3);
+    }
+
+    #[test]
+    fn test_threshold_produces_binary_image() {
+        let processed = processor(OcrConfig {
+            grayscale: false,
+            threshold: Some(128),
+            ..OcrConfig::default()
+        })
+        .process(gradient());
+
+        assert_eq!(processed.channels, 1);
+        assert!(processed.processed_data.iter().all(|&v| v == 0 || v == 255));
+        // x = 32 是第一个亮度达到 128 的列
+        assert_eq!(processed.processed_data[31], 0);
+        assert_eq!(processed.processed_data[32], 255);
+    }
+
+    #[test]
+    fn test_contrast_spreads_values_around_midpoint() {
+        let plain = processor(OcrConfig::default()).process(gradient());
+        let boosted = processor(OcrConfig {
+            contrast: 50.0,
+            ..OcrConfig::default()
+        })
+        .process(gradient());
+
+        let (dark, light) = (8, 56); // 亮度 32 与 224
+        assert!(boosted.processed_data[dark] < plain.processed_data[dark]);
+        assert!(boosted.processed_data[light] > plain.processed_data[light]);
+        assert!(
+            boosted
+                .preprocessing_applied
+                .contains(&"contrast_enhancement".to_string())
+        );
+    }
+
+    #[test]
+    fn test_denoise_removes_isolated_speckle() {
+        let mut page = GrayImage::from_pixel(9, 9, Luma([255]));
+        page.put_pixel(4, 4, Luma([0]));
+        let processed = processor(OcrConfig {
+            denoise: true,
+            ..OcrConfig::default()
+        })
+        .process(DynamicImage::ImageLuma8(page));
+        assert!(processed.processed_data.iter().all(|&v| v == 255));
+    }
+
+    #[test]
+    fn test_deskew_straightens_tilted_lines() {
+        let tilted = tilted_lines(5.0);
+        assert!((estimate_skew(&tilted.to_luma8()) - 5.0).abs() <= DESKEW_STEP_DEGREES);
+
+        let processed = processor(OcrConfig {
+            deskew: true,
+            ..OcrConfig::default()
+        })
+        .process(tilted);
+        let straightened =
+            GrayImage::from_raw(processed.width, processed.height, processed.processed_data)
+                .unwrap();
+        assert!(estimate_skew(&straightened).abs() <= DESKEW_STEP_DEGREES);
+    }
+}
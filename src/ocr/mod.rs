@@ -1,11 +1,15 @@
+pub mod autofill;
 pub mod image_processor;
 pub mod models;
 pub mod receipt_parser;
+pub mod scan;
 pub mod text_extractor;
 
-pub use image_processor::ImageProcessor;
-pub use models::{OcrConfig, ReceiptItem};
+pub use autofill::{ReceiptAutofill, TextRecognizer, autofill_from_receipt};
+pub use image_processor::{ImageProcessor, decode_image, load_image};
+pub use models::{DEFAULT_MIN_CONFIDENCE, OcrConfig, ReceiptItem, ReceiptLocale};
 pub use receipt_parser::ReceiptParser;
+pub use scan::scan_receipt;
 pub use text_extractor::TextExtractor;
 // Re-export OcrResult from models with a different name to avoid conflict
 pub use models::OcrResult as OcrData;
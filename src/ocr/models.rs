@@ -1,10 +1,108 @@
 // OCR models - reexport from main models module
 pub use crate::models::{OcrResult, ReceiptItem};
+use crate::utils::parse_price;
+
+/// Default `OcrConfig::min_confidence`
+pub const DEFAULT_MIN_CONFIDENCE: f32 = 0.6;
+
+/// Price notation used on a receipt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReceiptLocale {
+    /// Guess from the currency symbols and separators in the text
+    #[default]
+    Auto,
+    /// `¥1,280` / `1,280円`, no decimals
+    Japan,
+    /// `$12.99`, `,` groups thousands
+    UnitedStates,
+    /// `12,99 €`, `.` groups thousands
+    Europe,
+}
+
+impl ReceiptLocale {
+    /// Best-effort locale of `text`: currency symbols first, then the decimal separator
+    pub fn detect(text: &str) -> ReceiptLocale {
+        if text.contains(['¥', '￥', '円']) {
+            return ReceiptLocale::Japan;
+        }
+        if text.contains('€') {
+            return ReceiptLocale::Europe;
+        }
+        if text.contains('$') {
+            return ReceiptLocale::UnitedStates;
+        }
+        let comma_decimals = regex::Regex::new(r"\d,\d{2}\b")
+            .map(|re| re.is_match(text))
+            .unwrap_or(false);
+        let point_decimals = regex::Regex::new(r"\d\.\d{2}\b")
+            .map(|re| re.is_match(text))
+            .unwrap_or(false);
+        if comma_decimals && !point_decimals {
+            ReceiptLocale::Europe
+        } else {
+            ReceiptLocale::UnitedStates
+        }
+    }
+
+    /// This locale, or the one detected from `text` for `Auto`
+    pub fn resolve(self, text: &str) -> ReceiptLocale {
+        match self {
+            ReceiptLocale::Auto => Self::detect(text),
+            locale => locale,
+        }
+    }
+
+    /// Regex matching an amount with its optional currency symbol; the number is
+    /// captured as `amount`
+    pub fn amount_pattern(&self) -> &'static str {
+        match self {
+            ReceiptLocale::Japan => r"[¥￥]?\s*(?P<amount>\d[\d,]*)\s*円?",
+            ReceiptLocale::Europe => r"€?\s*(?P<amount>\d[\d.]*(?:,\d{1,2})?)\s*€?",
+            ReceiptLocale::UnitedStates | ReceiptLocale::Auto => {
+                r"\$?\s*(?P<amount>\d[\d,]*(?:\.\d{1,2})?)"
+            }
+        }
+    }
+
+    /// Parse an amount written in this locale's notation, e.g. "1.280,50" for `Europe`
+    pub fn parse_price(&self, text: &str) -> Result<f64, String> {
+        let number: String = text
+            .chars()
+            .filter(|c| c.is_ascii_digit() || matches!(c, '.' | ','))
+            .collect();
+        let normalized = match self {
+            ReceiptLocale::Europe => number.replace('.', "").replace(',', "."),
+            _ => number.replace(',', ""),
+        };
+        // parse_price 把不带小数点的输入视为最小单位，这里补上小数点按主单位解析
+        let minor = if normalized.contains('.') {
+            parse_price(&normalized)
+        } else {
+            parse_price(&format!("{}.", normalized))
+        }?;
+        Ok(minor as f64 / 100.0)
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct OcrConfig {
     pub language: String,
     pub confidence_threshold: f32,
+    /// Convert to 8-bit grayscale before the other steps
+    pub grayscale: bool,
+    /// Binarize: luma at or above this becomes white, the rest black
+    pub threshold: Option<u8>,
+    /// Straighten text lines tilted by up to `MAX_DESKEW_DEGREES`
+    pub deskew: bool,
+    /// Contrast change in percent; positive increases, 0 leaves the image as is
+    pub contrast: f32,
+    /// 3x3 median filter against speckle noise
+    pub denoise: bool,
+    /// Price notation of the receipts being parsed
+    pub receipt_locale: ReceiptLocale,
+    /// Items recognized below this are set aside as uncertain, and receipts
+    /// below it are not used to pre-fill prices
+    pub min_confidence: f32,
 }
 
 impl Default for OcrConfig {
@@ -12,6 +110,13 @@ impl Default for OcrConfig {
         Self {
             language: "eng".to_string(),
             confidence_threshold: 0.5,
+            grayscale: true,
+            threshold: None,
+            deskew: false,
+            contrast: 0.0,
+            denoise: false,
+            receipt_locale: ReceiptLocale::Auto,
+            min_confidence: DEFAULT_MIN_CONFIDENCE,
         }
     }
 }
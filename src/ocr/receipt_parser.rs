@@ -1,10 +1,20 @@
 use crate::models::{Product, ReceiptItem};
+use crate::ocr::models::ReceiptLocale;
 use crate::ocr::text_extractor::TextExtractionResult;
+use crate::ocr::{OcrError, OcrResult};
 use anyhow::Result;
-use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
+use once_cell::sync::Lazy;
 use regex::Regex;
 use std::collections::HashMap;
 
+/// Payment lines start with a payment word; Latin words must be whole words
+/// so items such as "Chocolate Bar" or "Change Purse" are kept
+static PAYMENT_LINE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)^\s*(?:現金|现金|お釣り|お預り|找零|(?:cash|change|bar|rückgeld)\b)")
+        .expect("Invalid payment line regex")
+});
+
 /// Receipt parser for extracting structured data from OCR text
 pub struct ReceiptParser {
     /// Store-specific parsing patterns
@@ -13,8 +23,15 @@ pub struct ReceiptParser {
     pub currency_patterns: Vec<String>,
     /// Date parsing patterns
     pub date_patterns: Vec<String>,
+    /// Price notation; `Auto` detects it per receipt
+    pub locale: ReceiptLocale,
+    /// Largest accepted difference between the printed total and the summed items
+    pub total_tolerance: f64,
 }
 
+/// How many lines from the top are searched for the store name
+const STORE_NAME_LINES: usize = 3;
+
 impl Default for ReceiptParser {
     fn default() -> Self {
         let mut parser = Self {
@@ -30,7 +47,11 @@ impl Default for ReceiptParser {
                 r"\d{1,2}/\d{1,2}/\d{4}".to_string(),
                 r"\d{4}-\d{1,2}-\d{1,2}".to_string(),
                 r"\d{1,2}-\d{1,2}-\d{4}".to_string(),
+                r"\d{1,2}\.\d{1,2}\.\d{4}".to_string(),
+                r"\d{4}年\d{1,2}月\d{1,2}日".to_string(),
             ],
+            locale: ReceiptLocale::Auto,
+            total_tolerance: 0.05,
         };
 
         // Initialize common store patterns
@@ -44,37 +65,119 @@ impl ReceiptParser {
         Self::default()
     }
 
-    /// Parse receipt text and extract structured data
-    pub fn parse_receipt(
+    /// Parse prices in the given notation instead of detecting it
+    pub fn with_locale(mut self, locale: ReceiptLocale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Parse receipt text into its store, line items, totals and date.
+    /// Fields the text does not contain are `None`
+    pub fn parse_receipt(&self, text: &str) -> OcrResult<ParsedReceipt> {
+        self.parse_text(text, &|_| 1.0)
+    }
+
+    /// `parse_receipt` with each item's confidence taken from its line
+    fn parse_text(
+        &self,
+        text: &str,
+        line_confidence: &dyn Fn(&str) -> f32,
+    ) -> OcrResult<ParsedReceipt> {
+        log::info!("Parsing receipt text: {} characters", text.len());
+        let locale = self.locale.resolve(text);
+        let parse_error = |e: anyhow::Error| OcrError::ReceiptParsing(e.to_string());
+
+        let items = self
+            .extract_items(text, locale, line_confidence)
+            .map_err(parse_error)?;
+        let totals = self.extract_totals(text, locale).map_err(parse_error)?;
+        let items_total: f64 = items.iter().map(|i| i.price * i.quantity as f64).sum();
+
+        // 合计可以是商品合计，也可以是商品合计加单列的税额
+        let total_mismatch = match totals.total {
+            Some(total) if !items.is_empty() => {
+                let with_tax = items_total + totals.tax.unwrap_or(0.0);
+                (total - items_total).abs() > self.total_tolerance
+                    && (total - with_tax).abs() > self.total_tolerance
+            }
+            _ => false,
+        };
+        if total_mismatch {
+            log::warn!(
+                "Receipt total {:?} does not match the items ({:.2})",
+                totals.total,
+                items_total
+            );
+        }
+
+        Ok(ParsedReceipt {
+            store_name: self.extract_store_name(text, locale),
+            items,
+            subtotal: totals.subtotal,
+            tax: totals.tax,
+            total: totals.total,
+            date: self.extract_date(text, locale),
+            items_total,
+            total_mismatch,
+        })
+    }
+
+    /// Parse an OCR result, keeping its confidence and store details. Item
+    /// confidences are the lowest word confidence on the item's line
+    pub fn parse_extraction(
         &self,
         extraction_result: &TextExtractionResult,
     ) -> Result<ReceiptParseResult> {
         let text = &extraction_result.text;
-        log::info!("Parsing receipt text: {} characters", text.len());
-
-        // Extract store information
+        let parsed = self.parse_text(text, &|line| {
+            line.split_whitespace()
+                .filter_map(|word| extraction_result.word_confidences.get(word).copied())
+                .reduce(f32::min)
+                .unwrap_or(extraction_result.confidence)
+        })?;
         let store_info = self.extract_store_info(text)?;
-
-        // Extract items
-        let items = self.extract_items(text)?;
-
-        // Extract totals
-        let totals = self.extract_totals(text)?;
-
-        // Extract date and time
-        let datetime = self.extract_datetime(text)?;
+        let totals = ReceiptTotals {
+            subtotal: parsed.subtotal,
+            tax: parsed.tax,
+            total: parsed.total,
+            discount: None, // Could be extracted similarly
+        };
 
         Ok(ReceiptParseResult {
             store_info,
-            items: items.clone(),
-            totals: totals.clone(),
-            datetime,
+            parsing_confidence: self.calculate_parsing_confidence(&parsed.items, &totals),
+            items: parsed.items,
+            totals,
+            datetime: parsed.date.map(|date| {
+                DateTime::from_naive_utc_and_offset(date.and_time(chrono::NaiveTime::MIN), Utc)
+            }),
             raw_text: text.clone(),
             confidence: extraction_result.confidence,
-            parsing_confidence: self.calculate_parsing_confidence(&items, &totals),
         })
     }
 
+    /// Store name: a known chain anywhere in the text, otherwise the first
+    /// line near the top that is neither an amount nor a date
+    fn extract_store_name(&self, text: &str, locale: ReceiptLocale) -> Option<String> {
+        for (store_name, pattern) in &self.store_patterns {
+            if Regex::new(&pattern.name_pattern).is_ok_and(|regex| regex.is_match(text)) {
+                return Some(store_name.clone());
+            }
+        }
+
+        let amount_line = Regex::new(&format!(r"{}$", locale.amount_pattern())).ok()?;
+        text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .take(STORE_NAME_LINES)
+            .find(|line| {
+                line.chars().any(char::is_alphabetic)
+                    && !amount_line.is_match(line)
+                    && self.extract_date(line, locale).is_none()
+            })
+            .map(str::to_string)
+    }
+
     /// Extract store information from receipt text
     fn extract_store_info(&self, text: &str) -> Result<StoreInfo> {
         let lines: Vec<&str> = text.lines().collect();
@@ -110,31 +213,42 @@ impl ReceiptParser {
     }
 
     /// Extract items from receipt text
-    fn extract_items(&self, text: &str) -> Result<Vec<ReceiptItem>> {
+    fn extract_items(
+        &self,
+        text: &str,
+        locale: ReceiptLocale,
+        line_confidence: &dyn Fn(&str) -> f32,
+    ) -> Result<Vec<ReceiptItem>> {
         let mut items = Vec::new();
 
         // Pattern for item lines: [name] [price]
-        let item_pattern = Regex::new(r"(.+?)\s+[¥$€£]?([0-9,]+\.?[0-9]*)$")?;
+        let item_pattern = Regex::new(&format!(r"^(.+?)\s+{}$", locale.amount_pattern()))?;
 
         for line in text.lines() {
             let line = line.trim();
 
-            // Skip header lines, empty lines, and total lines
-            if self.is_header_line(line) || line.is_empty() || self.is_total_line(line) {
+            // Skip header lines, empty lines, total and payment lines
+            if self.is_header_line(line)
+                || line.is_empty()
+                || self.is_total_line(line)
+                || self.is_payment_line(line)
+            {
                 continue;
             }
 
             if let Some(captures) = item_pattern.captures(line) {
-                if let (Some(name_match), Some(price_match)) = (captures.get(1), captures.get(2)) {
+                if let (Some(name_match), Some(price_match)) =
+                    (captures.get(1), captures.name("amount"))
+                {
                     let name = name_match.as_str().trim().to_string();
-                    let price_str = price_match.as_str().replace(',', "");
 
-                    if let Ok(price) = price_str.parse::<f64>() {
+                    if let Ok(price) = locale.parse_price(price_match.as_str()) {
                         let item = ReceiptItem {
                             name: name.clone(),
                             price,
                             quantity: 1, // Default quantity
                             category: self.classify_item_category(&name),
+                            confidence: line_confidence(line),
                         };
                         items.push(item);
                     }
@@ -147,36 +261,39 @@ impl ReceiptParser {
     }
 
     /// Extract total amounts from receipt text
-    fn extract_totals(&self, text: &str) -> Result<ReceiptTotals> {
+    fn extract_totals(&self, text: &str, locale: ReceiptLocale) -> Result<ReceiptTotals> {
         let mut subtotal = None;
         let mut tax = None;
         let mut total = None;
 
         // Patterns for different total types
-        let subtotal_pattern =
-            Regex::new(r"(?i)(小計|小计|subtotal|sub total)\s*[¥$€£]?([0-9,]+\.?[0-9]*)")?;
-        let tax_pattern =
-            Regex::new(r"(?i)(税|消費税|消费税|tax|vat)\s*[¥$€£]?([0-9,]+\.?[0-9]*)")?;
-        let total_pattern =
-            Regex::new(r"(?i)(合計|合计|total|grand total|final)\s*[¥$€£]?([0-9,]+\.?[0-9]*)")?;
+        // 关键词后可带括号说明，如 "合計(税込)"
+        let label_pattern = |labels: &str| {
+            Regex::new(&format!(
+                r"(?i)({})\s*(?:[(（][^)）]*[)）])?\s*[:：]?\s*{}",
+                labels,
+                locale.amount_pattern()
+            ))
+        };
+        let subtotal_pattern = label_pattern("小計|小计|subtotal|sub total|zwischensumme")?;
+        let tax_pattern = label_pattern("税|消費税|消费税|tax|vat|mwst")?;
+        let total_pattern = label_pattern("合計|合计|total|grand total|final|summe|gesamt")?;
 
         for line in text.lines() {
-            if let Some(captures) = subtotal_pattern.captures(line) {
-                if let Some(amount) = captures.get(2) {
-                    subtotal = amount.as_str().replace(',', "").parse().ok();
-                }
-            }
-
-            if let Some(captures) = tax_pattern.captures(line) {
-                if let Some(amount) = captures.get(2) {
-                    tax = amount.as_str().replace(',', "").parse().ok();
-                }
-            }
-
-            if let Some(captures) = total_pattern.captures(line) {
-                if let Some(amount) = captures.get(2) {
-                    total = amount.as_str().replace(',', "").parse().ok();
-                }
+            let amount_of = |pattern: &Regex| {
+                pattern
+                    .captures(line)
+                    .and_then(|captures| captures.name("amount"))
+                    .and_then(|amount| locale.parse_price(amount.as_str()).ok())
+            };
+
+            // 小计与税额行也含 "total" 等字样，按顺序只取第一个匹配
+            if let Some(amount) = amount_of(&subtotal_pattern) {
+                subtotal = Some(amount);
+            } else if let Some(amount) = amount_of(&tax_pattern) {
+                tax = Some(amount);
+            } else if let Some(amount) = amount_of(&total_pattern) {
+                total = Some(amount);
             }
         }
 
@@ -188,25 +305,38 @@ impl ReceiptParser {
         })
     }
 
-    /// Extract date and time from receipt text
-    fn extract_datetime(&self, text: &str) -> Result<Option<DateTime<Utc>>> {
+    /// First date in the text; all-numeric dates with the year last are read
+    /// day-first for `Europe` and month-first otherwise
+    fn extract_date(&self, text: &str, locale: ReceiptLocale) -> Option<NaiveDate> {
+        let number = Regex::new(r"\d+").ok()?;
         for pattern in &self.date_patterns {
-            if let Ok(regex) = Regex::new(pattern) {
-                if let Some(date_match) = regex.find(text) {
-                    // Try to parse the date (simplified)
-                    // In a real implementation, this would handle various date formats
-                    if let Ok(naive_dt) = NaiveDateTime::parse_from_str(
-                        &format!("{} 00:00:00", date_match.as_str()),
-                        "%Y/%m/%d %H:%M:%S",
-                    ) {
-                        return Ok(Some(DateTime::from_naive_utc_and_offset(naive_dt, Utc)));
-                    }
+            let Ok(regex) = Regex::new(pattern) else {
+                continue;
+            };
+            let Some(date_match) = regex.find(text) else {
+                continue;
+            };
+            let parts: Vec<&str> = number
+                .find_iter(date_match.as_str())
+                .map(|m| m.as_str())
+                .collect();
+            let [a, b, c] = parts[..] else {
+                continue;
+            };
+            let (year, month, day) = if a.len() == 4 {
+                (a, b, c)
+            } else if locale == ReceiptLocale::Europe || date_match.as_str().contains('.') {
+                (c, b, a)
+            } else {
+                (c, a, b)
+            };
+            if let (Ok(year), Ok(month), Ok(day)) = (year.parse(), month.parse(), day.parse()) {
+                if let Some(date) = NaiveDate::from_ymd_opt(year, month, day) {
+                    return Some(date);
                 }
             }
         }
-
-        // Fallback to current time if no date found
-        Ok(Some(Utc::now()))
+        None
     }
 
     /// Match receipt items with known products
@@ -299,6 +429,13 @@ impl ReceiptParser {
             || line_lower.contains("合計")
             || line_lower.contains("小計")
             || line_lower.contains("税")
+            || line_lower.contains("tax")
+            || line_lower.contains("summe")
+            || line_lower.contains("mwst")
+    }
+
+    fn is_payment_line(&self, line: &str) -> bool {
+        PAYMENT_LINE_REGEX.is_match(line)
     }
 
     fn classify_item_category(&self, name: &str) -> Option<String> {
@@ -378,6 +515,21 @@ pub struct StorePattern {
     pub item_pattern: String,
 }
 
+/// Receipt fields found by `ReceiptParser::parse_receipt`
+#[derive(Debug, Clone)]
+pub struct ParsedReceipt {
+    pub store_name: Option<String>,
+    pub items: Vec<ReceiptItem>,
+    pub subtotal: Option<f64>,
+    pub tax: Option<f64>,
+    pub total: Option<f64>,
+    pub date: Option<NaiveDate>,
+    /// Sum of the line items
+    pub items_total: f64,
+    /// The printed total matches neither the items nor the items plus tax
+    pub total_mismatch: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct ReceiptParseResult {
     pub store_info: StoreInfo,
@@ -419,3 +571,105 @@ pub enum MatchType {
     Partial,
     None,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JPY_RECEIPT: &str =
+        "ローソン\n\nお茶 500ml    ¥1,280\nおにぎり      120円\n\n合計            ¥1,400";
+    const USD_RECEIPT: &str =
+        "Corner Grocery\n\nMILK 1GAL     $3.49\nBREAD         $1,002.50\n\nTOTAL: $1,005.99";
+
+    #[test]
+    fn test_same_parser_handles_jpy_and_usd_locales() {
+        let mut parser = ReceiptParser::new().with_locale(ReceiptLocale::Japan);
+        let jpy = parser.parse_receipt(JPY_RECEIPT).unwrap();
+        let prices: Vec<f64> = jpy.items.iter().map(|i| i.price).collect();
+        assert_eq!(prices, vec![1280.0, 120.0]);
+        assert_eq!(jpy.total, Some(1400.0));
+
+        parser.locale = ReceiptLocale::UnitedStates;
+        let usd = parser.parse_receipt(USD_RECEIPT).unwrap();
+        let prices: Vec<f64> = usd.items.iter().map(|i| i.price).collect();
+        assert_eq!(prices, vec![3.49, 1002.5]);
+        assert_eq!(usd.items[0].name, "MILK 1GAL");
+        assert_eq!(usd.total, Some(1005.99));
+    }
+
+    const FULL_RECEIPT: &str = "FamilyMart\n東京駅店\n\nコカコーラ 330ml    ¥150\nポテトチップス      ¥120\nおにぎり ツナマヨ   ¥110\n\n小計            ¥380\n消費税           ¥38\n合計(税込)      ¥418\n\n現金            ¥500\nお釣り           ¥82\n\n2024/09/08 14:30\nありがとうございました";
+
+    #[test]
+    fn test_full_receipt_extracts_store_total_and_date() {
+        let receipt = ReceiptParser::new().parse_receipt(FULL_RECEIPT).unwrap();
+
+        assert_eq!(receipt.store_name.as_deref(), Some("FamilyMart"));
+        assert_eq!(receipt.items.len(), 3);
+        assert_eq!(receipt.subtotal, Some(380.0));
+        assert_eq!(receipt.tax, Some(38.0));
+        assert_eq!(receipt.total, Some(418.0));
+        assert_eq!(receipt.date, NaiveDate::from_ymd_opt(2024, 9, 8));
+        assert!(!receipt.total_mismatch);
+
+        let plain = ReceiptParser::new()
+            .parse_receipt("Corner Grocery\nMILK   $3.49\nTotal $3.49")
+            .unwrap();
+        assert_eq!(plain.store_name.as_deref(), Some("Corner Grocery"));
+        assert_eq!(plain.date, None);
+        assert_eq!(plain.tax, None);
+    }
+
+    #[test]
+    fn test_items_containing_payment_words_are_kept() {
+        let receipt = ReceiptParser::new()
+            .with_locale(ReceiptLocale::UnitedStates)
+            .parse_receipt(
+                "Corner Grocery\nChocolate Bar   $1.99\nCashew Nuts   $4.50\nTotal $6.49\nCash   $10.00\nChange   $3.51",
+            )
+            .unwrap();
+
+        let names: Vec<&str> = receipt.items.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, vec!["Chocolate Bar", "Cashew Nuts"]);
+        assert!(!receipt.total_mismatch);
+
+        let eur = ReceiptParser::new()
+            .parse_receipt(
+                "Bäckerei\nBrot   2,49 €\nSumme  2,49 €\nBAR   5,00 €\nRückgeld   2,51 €",
+            )
+            .unwrap();
+        assert_eq!(eur.items.len(), 1);
+    }
+
+    #[test]
+    fn test_total_mismatch_is_flagged() {
+        let receipt = ReceiptParser::new()
+            .parse_receipt("ローソン\nお茶   ¥150\nパン   ¥120\n合計   ¥500\n2024年9月8日")
+            .unwrap();
+
+        assert_eq!(receipt.items_total, 270.0);
+        assert_eq!(receipt.total, Some(500.0));
+        assert!(receipt.total_mismatch);
+        assert_eq!(receipt.date, NaiveDate::from_ymd_opt(2024, 9, 8));
+    }
+
+    #[test]
+    fn test_auto_locale_detects_comma_decimals() {
+        assert_eq!(ReceiptLocale::detect(JPY_RECEIPT), ReceiptLocale::Japan);
+        assert_eq!(
+            ReceiptLocale::detect(USD_RECEIPT),
+            ReceiptLocale::UnitedStates
+        );
+        assert_eq!(
+            ReceiptLocale::detect("Brot 2,49\nKäse 1.234,50"),
+            ReceiptLocale::Europe
+        );
+
+        let parser = ReceiptParser::new();
+        let eur = parser
+            .parse_receipt("Bäckerei\n\nBrot   2,49 €\nKäse   1.234,50 €\n\nSumme  1.236,99 €")
+            .unwrap();
+        let prices: Vec<f64> = eur.items.iter().map(|i| i.price).collect();
+        assert_eq!(prices, vec![2.49, 1234.5]);
+        assert_eq!(eur.total, Some(1236.99));
+    }
+}
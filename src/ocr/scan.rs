@@ -0,0 +1,119 @@
+//! Receipt photo to `OcrData`, with unreliable items set aside.
+
+use crate::ocr::{OcrConfig, OcrData, OcrError, OcrResult, ReceiptParser, TextRecognizer};
+
+/// Recognize and parse a receipt photo. Items whose line confidence is below
+/// `config.min_confidence` go to `uncertain_items` instead of `parsed_items`
+pub fn scan_receipt(
+    recognizer: &dyn TextRecognizer,
+    parser: &ReceiptParser,
+    image_path: &str,
+    image_data: &[u8],
+    format: &str,
+    config: &OcrConfig,
+) -> OcrResult<OcrData> {
+    let extraction = recognizer
+        .recognize(image_data, format)
+        .map_err(|e| OcrError::TextExtraction(e.to_string()))?;
+    let receipt = parser
+        .parse_extraction(&extraction)
+        .map_err(|e| OcrError::ReceiptParsing(e.to_string()))?;
+
+    let (confident, uncertain): (Vec<_>, Vec<_>) = receipt
+        .items
+        .into_iter()
+        .partition(|item| item.is_confident(config.min_confidence));
+    if !uncertain.is_empty() {
+        log::info!(
+            "{} receipt items below confidence {:.2}",
+            uncertain.len(),
+            config.min_confidence
+        );
+    }
+
+    let mut result = OcrData::new(
+        image_path.to_string(),
+        extraction.text,
+        confident,
+        receipt.confidence.min(receipt.parsing_confidence),
+    );
+    result.uncertain_items = uncertain;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocr::text_extractor::TextExtractionResult;
+    use std::collections::HashMap;
+
+    /// Emits fixed text with the given per-word confidences
+    struct StubRecognizer {
+        text: &'static str,
+        words: &'static [(&'static str, f32)],
+    }
+
+    impl TextRecognizer for StubRecognizer {
+        fn recognize(&self, _: &[u8], _: &str) -> anyhow::Result<TextExtractionResult> {
+            Ok(TextExtractionResult {
+                text: self.text.to_string(),
+                confidence: 0.9,
+                language_detected: "jpn".to_string(),
+                word_confidences: self
+                    .words
+                    .iter()
+                    .map(|(word, confidence)| (word.to_string(), *confidence))
+                    .collect::<HashMap<_, _>>(),
+                line_count: self.text.lines().count(),
+                processing_time_ms: 0,
+                layout_preserved: true,
+            })
+        }
+    }
+
+    const STUB: StubRecognizer = StubRecognizer {
+        text: "ローソン\nお茶    ¥150\nガム    ¥80\n合計    ¥230",
+        words: &[("お茶", 0.95), ("¥150", 0.9), ("ガム", 0.4), ("¥80", 0.85)],
+    };
+
+    fn scan(min_confidence: f32) -> OcrData {
+        let config = OcrConfig {
+            min_confidence,
+            ..OcrConfig::default()
+        };
+        scan_receipt(
+            &STUB,
+            &ReceiptParser::new(),
+            "receipt.png",
+            b"",
+            "png",
+            &config,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_low_confidence_items_are_set_aside() {
+        let result = scan(0.6);
+
+        let names: Vec<&str> = result
+            .parsed_items
+            .iter()
+            .map(|i| i.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["お茶"]);
+        assert_eq!(result.parsed_items[0].confidence, 0.9);
+        assert_eq!(result.uncertain_items.len(), 1);
+        assert_eq!(result.uncertain_items[0].name, "ガム");
+        assert_eq!(result.uncertain_items[0].confidence, 0.4);
+    }
+
+    #[test]
+    fn test_lower_threshold_accepts_all_items() {
+        let result = scan(0.3);
+
+        assert_eq!(result.parsed_items.len(), 2);
+        assert!(result.uncertain_items.is_empty());
+        assert!(result.confidence_score > 0.0);
+    }
+}
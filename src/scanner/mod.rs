@@ -8,7 +8,7 @@ pub use barcode_decoder::BarcodeDecoder;
 pub use camera_manager::{CameraInfo, CameraManager};
 pub use models::{BarcodeType, CameraConfig, ScanResult};
 pub use product_matcher::{ProductMatch, ProductMatchType, ProductMatcher};
-pub use ui::ScannerUI;
+pub use ui::{ScanHistoryItem, ScannerPriceEntry, ScannerUI};
 
 use anyhow::Result;
 use thiserror::Error;
@@ -29,6 +29,12 @@ pub enum ScannerError {
 
 pub type ScannerResult<T> = Result<T, ScannerError>;
 
+/// Source of scanned products, implemented by [`ScannerService`] and test doubles
+pub trait ScanBackend {
+    /// Scan once and return the matched product, if any
+    fn scan_product(&self) -> ScannerResult<Option<crate::models::Product>>;
+}
+
 /// Main scanner service that integrates camera, barcode decoding, and product matching
 pub struct ScannerService {
     camera_manager: CameraManager,
@@ -107,3 +113,13 @@ impl Default for ScannerService {
         Self::new()
     }
 }
+
+impl ScanBackend for ScannerService {
+    fn scan_product(&self) -> ScannerResult<Option<crate::models::Product>> {
+        // 摄像头未启动时先启动
+        if !self.is_camera_running() {
+            self.start_camera()?;
+        }
+        self.scan_and_match()
+    }
+}
@@ -5,14 +5,7 @@ pub struct ScanResult {
     pub confidence: f32,
 }
 
-#[derive(Debug, Clone, Eq, Hash, PartialEq)]
-pub enum BarcodeType {
-    Ean13,
-    Ean8,
-    Code128,
-    QrCode,
-    Unknown,
-}
+pub use crate::models::BarcodeType;
 
 #[derive(Debug, Clone)]
 pub struct CameraConfig {
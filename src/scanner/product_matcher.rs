@@ -248,6 +248,7 @@ impl ProductMatcher {
             prices: vec![],
             tags: vec![],
             created_at: chrono::Utc::now(),
+            version: 0,
         })
     }
 
@@ -327,6 +328,7 @@ impl ProductMatcher {
                     prices: vec![],
                     tags: vec!["beverage".to_string(), "cola".to_string()],
                     created_at: chrono::Utc::now(),
+                    version: 0,
                 },
             ),
             (
@@ -341,6 +343,7 @@ impl ProductMatcher {
                     prices: vec![],
                     tags: vec!["snack".to_string(), "chips".to_string()],
                     created_at: chrono::Utc::now(),
+                    version: 0,
                 },
             ),
             (
@@ -355,6 +358,7 @@ impl ProductMatcher {
                     prices: vec![],
                     tags: vec!["water".to_string(), "beverage".to_string()],
                     created_at: chrono::Utc::now(),
+                    version: 0,
                 },
             ),
         ];
@@ -1,7 +1,13 @@
-use crate::models::Product;
+use crate::app::price_form::parse_amount;
+use crate::models::{DeepLink, Product};
 use crate::scanner::{BarcodeType, CameraInfo, ProductMatch, ScanResult, ScannerService};
-use crate::utils::{generate_barcode_checksum, validate_barcode};
+use crate::services::Metrics;
+use crate::utils::{
+    copy_to_clipboard, generate_barcode_checksum, has_gs1_marker, normalize_width, parse_gs1,
+    validate_barcode,
+};
 use eframe::egui;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 /// Enhanced Scanner UI component with improved camera controls and user experience
@@ -49,6 +55,19 @@ pub struct ScannerUI {
     manual_barcode_type: BarcodeType,
     manual_barcode_info: Option<String>,
 
+    // Shared eprice:// link decoded from a QR code, consumed by the app
+    pending_deep_link: Option<DeepLink>,
+    // Entries removed by "Clear History", handed to the app's undo stack
+    cleared_history: Option<Vec<ScanHistoryItem>>,
+    // Shared operation counters, set by the app
+    metrics: Option<Arc<Metrics>>,
+
+    // Shelf price entry, saved by the app through the price service
+    price_stores: Vec<(String, String)>, // (门店ID, 门店名称)，由应用设置
+    price_store_id: Option<String>,
+    price_input: String,
+    pending_price_entry: Option<ScannerPriceEntry>,
+
     // Enhanced status and feedback
     status_message: String,
     error_message: Option<String>,
@@ -151,6 +170,15 @@ impl ScannerUI {
             manual_barcode_type: BarcodeType::Ean13,
             manual_barcode_info: None,
 
+            pending_deep_link: None,
+            cleared_history: None,
+            metrics: None,
+
+            price_stores: Vec::new(),
+            price_store_id: None,
+            price_input: String::new(),
+            pending_price_entry: None,
+
             status_message: "Ready to scan - Point camera at barcode".to_string(),
             error_message: None,
             success_animation: false,
@@ -586,6 +614,13 @@ impl ScannerUI {
                 ui.horizontal(|ui| {
                     ui.label("Barcode:");
                     ui.monospace(&scan_result.barcode);
+                    if ui
+                        .small_button("📋")
+                        .on_hover_text("Copy barcode")
+                        .clicked()
+                    {
+                        copy_to_clipboard(ui.ctx(), &scan_result.barcode);
+                    }
                 });
                 ui.horizontal(|ui| {
                     ui.label("Type:");
@@ -595,6 +630,32 @@ impl ScannerUI {
                     ui.label("Confidence:");
                     ui.label(format!("{:.1}%", scan_result.confidence * 100.0));
                 });
+
+                // GS1-128 码中附带的有效期 / 批号；EAN 等纯数字码不按 GS1 解析
+                let gs1 = (scan_result.barcode_type == BarcodeType::Code128
+                    || has_gs1_marker(&scan_result.barcode))
+                .then(|| parse_gs1(&scan_result.barcode).ok())
+                .flatten();
+                if let Some(gs1) = gs1 {
+                    if let Some(gtin) = gs1.gtin() {
+                        ui.horizontal(|ui| {
+                            ui.label("GTIN:");
+                            ui.monospace(gtin);
+                        });
+                    }
+                    if let Some(expiry) = gs1.expiry_date() {
+                        ui.horizontal(|ui| {
+                            ui.label("Expiry:");
+                            ui.label(expiry.format("%Y-%m-%d").to_string());
+                        });
+                    }
+                    if let Some(batch) = gs1.batch() {
+                        ui.horizontal(|ui| {
+                            ui.label("Batch:");
+                            ui.monospace(batch);
+                        });
+                    }
+                }
             });
         }
 
@@ -639,6 +700,79 @@ impl ScannerUI {
                 }
             });
         }
+        self.show_price_entry(ui);
+    }
+
+    /// Store picker, price field for the scanned product and CSV price import
+    fn show_price_entry(&mut self, ui: &mut egui::Ui) {
+        ui.group(|ui| {
+            ui.label("💰 Shelf Price");
+            let selected = self
+                .price_store_id
+                .as_ref()
+                .and_then(|id| {
+                    self.price_stores
+                        .iter()
+                        .find(|(store_id, _)| store_id == id)
+                })
+                .map(|(_, name)| name.as_str())
+                .unwrap_or("Select store");
+            egui::ComboBox::from_label("Store")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    for (id, name) in &self.price_stores {
+                        ui.selectable_value(&mut self.price_store_id, Some(id.clone()), name);
+                    }
+                });
+
+            ui.horizontal(|ui| {
+                ui.label("Price:");
+                ui.text_edit_singleline(&mut self.price_input);
+                let scanned = self.current_product.is_some();
+                if ui
+                    .add_enabled(scanned, egui::Button::new("Submit Price"))
+                    .clicked()
+                {
+                    self.request_scanned_price();
+                }
+            });
+
+            if ui.button("📥 Import Prices (CSV)").clicked() {
+                match self.price_store_id.clone() {
+                    Some(store_id) => {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("CSV", &["csv"])
+                            .pick_file()
+                        {
+                            self.pending_price_entry =
+                                Some(ScannerPriceEntry::CsvFile { path, store_id });
+                        }
+                    }
+                    None => self.error_message = Some("Select a store first".to_string()),
+                }
+            }
+        });
+    }
+
+    /// Queue the typed price for the scanned barcode if the inputs are valid
+    fn request_scanned_price(&mut self) {
+        let Some(scan) = &self.current_scan else {
+            return;
+        };
+        let Some(store_id) = self.price_store_id.clone() else {
+            self.error_message = Some("Select a store first".to_string());
+            return;
+        };
+        match parse_amount(&self.price_input) {
+            Ok(price) if price > 0.0 => {
+                self.pending_price_entry = Some(ScannerPriceEntry::Scanned {
+                    barcode: scan.barcode.clone(),
+                    store_id,
+                    price,
+                });
+            }
+            _ => self.error_message = Some(format!("Invalid price: {}", self.price_input)),
+        }
     }
 
     /// Show manual search section
@@ -714,10 +848,10 @@ impl ScannerUI {
                 });
 
             if ui.button("✔ Validate").clicked() {
-                let code = self.manual_barcode_input.trim();
-                if validate_barcode(code) {
+                let code = normalize_width(self.manual_barcode_input.trim());
+                if validate_barcode(&code) {
                     // Optionally compute checksum for EAN/UPC
-                    if let Some(chk) = generate_barcode_checksum(code) {
+                    if let Some(chk) = generate_barcode_checksum(&code) {
                         self.manual_barcode_info = Some(format!("Valid. Checksum={}", chk));
                     } else {
                         self.manual_barcode_info = Some("Valid".to_string());
@@ -731,6 +865,8 @@ impl ScannerUI {
                 let code = self.manual_barcode_input.trim();
                 if code.is_empty() {
                     self.error_message = Some("Please enter a barcode".to_string());
+                } else if let Some(link) = DeepLink::parse(code) {
+                    self.open_shared_link(link);
                 } else {
                     match self.scanner_service.matcher().find_product_by_barcode(code) {
                         Ok(Some(product)) => {
@@ -762,9 +898,9 @@ impl ScannerUI {
         ui.horizontal(|ui| {
             ui.label(format!("Total scans: {}", self.scan_history.len()));
 
-            if ui.small_button("🗑️ Clear History").clicked() {
-                self.scan_history.clear();
-                self.status_message = "Scan history cleared".to_string();
+            if ui.small_button("🗑️ Clear History").clicked() && !self.scan_history.is_empty() {
+                self.cleared_history = Some(std::mem::take(&mut self.scan_history));
+                self.status_message = "Scan history cleared (Ctrl+Z to undo)".to_string();
             }
         });
 
@@ -820,11 +956,72 @@ impl ScannerUI {
         }
     }
 
+    /// Turn on auto scan; scanning starts once the camera is running
+    pub fn start_auto_scan(&mut self) {
+        self.is_scanning = true;
+        self.status_message = "Auto scan on - Point camera at barcode".to_string();
+    }
+
+    /// Queue navigation to a shared product/store link
+    fn open_shared_link(&mut self, link: DeepLink) {
+        self.status_message = format!("Opening shared link: {}", link.to_uri());
+        self.error_message = None;
+        self.pending_deep_link = Some(link);
+    }
+
+    /// Navigation requested by a scanned share QR code, if any
+    pub fn take_deep_link(&mut self) -> Option<DeepLink> {
+        self.pending_deep_link.take()
+    }
+
+    /// History entries removed by the last "Clear History", if not yet taken
+    pub fn take_cleared_history(&mut self) -> Option<Vec<ScanHistoryItem>> {
+        self.cleared_history.take()
+    }
+
+    /// Stores offered when entering a shelf price, as (id, name)
+    pub fn set_price_stores(&mut self, stores: Vec<(String, String)>) {
+        self.price_stores = stores;
+    }
+
+    /// Price entered on this tab, if not yet taken by the app
+    pub fn take_price_entry(&mut self) -> Option<ScannerPriceEntry> {
+        self.pending_price_entry.take()
+    }
+
+    /// Show the outcome of saving a price entry
+    pub fn report_price_result(&mut self, result: Result<String, String>) {
+        match result {
+            Ok(message) => {
+                self.price_input.clear();
+                self.status_message = message;
+                self.error_message = None;
+            }
+            Err(e) => self.error_message = Some(e),
+        }
+    }
+
+    /// Count scans in the app-wide metrics
+    pub fn set_metrics(&mut self, metrics: Arc<Metrics>) {
+        self.metrics = Some(metrics);
+    }
+
+    pub fn scan_history_mut(&mut self) -> &mut Vec<ScanHistoryItem> {
+        &mut self.scan_history
+    }
+
     /// Perform a barcode scan
     fn perform_scan(&mut self) {
         self.last_scan_time = Some(Instant::now());
+        let result = self.scanner_service.scan_and_match();
+        if let Some(metrics) = &self.metrics {
+            metrics.increment(Metrics::SCANS);
+            if result.is_err() {
+                metrics.increment(Metrics::SCANS_FAILED);
+            }
+        }
 
-        match self.scanner_service.scan_and_match() {
+        match result {
             Ok(Some(product)) => {
                 // Found both barcode and product
                 if let Ok(frame) = self.scanner_service.camera().capture_frame() {
@@ -849,6 +1046,12 @@ impl ScannerUI {
                 // Found barcode but no matching product
                 if let Ok(frame) = self.scanner_service.camera().capture_frame() {
                     if let Ok(scan_result) = self.scanner_service.decoder().decode(&frame) {
+                        // 分享的二维码直接跳转到对应商品/商店
+                        if let Some(link) = DeepLink::parse(&scan_result.barcode) {
+                            self.current_scan = Some(scan_result);
+                            self.open_shared_link(link);
+                            return;
+                        }
                         self.current_scan = Some(scan_result.clone());
                         self.current_product = None;
 
@@ -904,10 +1107,26 @@ impl Default for ScannerUI {
     }
 }
 
+/// Price entered on the scanner tab, saved by the app through the price service
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScannerPriceEntry {
+    /// Shelf price for the scanned barcode
+    Scanned {
+        barcode: String,
+        store_id: String,
+        price: f64,
+    },
+    /// `barcode,price[,on_sale]` file picked by the user
+    CsvFile {
+        path: std::path::PathBuf,
+        store_id: String,
+    },
+}
+
 /// Scan history item
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
-struct ScanHistoryItem {
+pub struct ScanHistoryItem {
     barcode: String,
     barcode_type: BarcodeType,
     product_name: Option<String>,
@@ -1,6 +1,7 @@
 use crate::models::{PriceRecord, Product, Store};
 use crate::search::filters::{SearchFilters, SortDirection, SortField};
-use crate::services::ServiceResult;
+use crate::services::{ServiceError, ServiceResult};
+use crate::utils::{normalize_width, sanitize_search_query, validate_barcode};
 use chrono::{DateTime, Utc};
 use std::collections::HashMap;
 
@@ -11,6 +12,9 @@ pub struct SearchEngine {
     store_index: HashMap<String, Vec<String>>,   // term -> store_ids
     category_index: HashMap<String, Vec<String>>, // category -> product_ids
     tag_index: HashMap<String, Vec<String>>,     // tag -> product_ids
+    barcode_index: HashMap<String, Product>,     // barcode -> product
+    price_index: HashMap<String, Vec<PriceRecord>>, // product_id -> price records
+    stock_heuristic: StockHeuristic,
 
     // Cache for search results
     search_cache: HashMap<String, (SearchResult, DateTime<Utc>)>,
@@ -23,9 +27,12 @@ pub struct SearchQuery {
     pub text: String,
     pub filters: SearchFilters,
     pub fuzzy_matching: bool,
+    pub fuzzy_max_distance: usize, // 模糊匹配允许的最大编辑距离
+    pub fuzzy_min_length: usize,   // 短于此长度的词只做精确匹配
     pub include_suggestions: bool,
     pub max_results: usize,
     pub min_relevance_score: f32,
+    pub explain: bool, // 为每个结果附上得分明细
 }
 
 /// Comprehensive search results with metadata
@@ -33,6 +40,10 @@ pub struct SearchQuery {
 pub struct SearchResult {
     pub items: Vec<SearchResultItem>,
     pub total_count: usize,
+    /// Whether matches beyond `max_results` were cut off
+    pub truncated: bool,
+    /// Number of matches before applying `max_results`
+    pub total_before_cap: usize,
     pub search_time_ms: u64,
     pub suggestions: Vec<String>,
     pub filters_applied: SearchFilters,
@@ -49,6 +60,78 @@ pub struct SearchResultItem {
     pub match_reasons: Vec<MatchReason>,
     pub price_trend: PriceTrend,
     pub availability_info: AvailabilityInfo,
+    pub matched_terms: Vec<String>, // 命中的查询词（小写）
+    /// Score breakdown summing to `relevance_score`, only when the query asked to explain
+    pub explanation: Option<Vec<ScoreComponent>>,
+}
+
+/// One labelled contribution to a result's relevance score
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreComponent {
+    pub label: String,
+    pub value: f32,
+}
+
+impl ScoreComponent {
+    fn new(label: impl Into<String>, value: f32) -> Self {
+        Self {
+            label: label.into(),
+            value,
+        }
+    }
+}
+
+/// A query term that matched an indexed term, and its score weight
+#[derive(Debug, Clone)]
+struct TermHit {
+    term: String,
+    indexed_term: String,
+    weight: f32,
+}
+
+/// Piece of display text, marked if it matched a query term
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextSpan {
+    pub text: String,
+    pub matched: bool,
+}
+
+impl SearchResultItem {
+    /// Product name split into matched / unmatched spans for highlighting.
+    /// Matching is case-insensitive; overlapping or adjacent matches merge into one span.
+    pub fn highlighted_name(&self) -> Vec<TextSpan> {
+        let name: Vec<char> = self.product.name.chars().collect();
+        // 逐字符转小写，保证下标与原文对应
+        let lower: Vec<char> = name
+            .iter()
+            .map(|c| c.to_lowercase().next().unwrap_or(*c))
+            .collect();
+
+        let mut mask = vec![false; name.len()];
+        for term in &self.matched_terms {
+            let needle: Vec<char> = term.chars().flat_map(char::to_lowercase).collect();
+            if needle.is_empty() || needle.len() > lower.len() {
+                continue;
+            }
+            for start in 0..=lower.len() - needle.len() {
+                if lower[start..start + needle.len()] == needle[..] {
+                    mask[start..start + needle.len()].fill(true);
+                }
+            }
+        }
+
+        let mut spans: Vec<TextSpan> = Vec::new();
+        for (c, matched) in name.into_iter().zip(mask) {
+            match spans.last_mut() {
+                Some(span) if span.matched == matched => span.text.push(c),
+                _ => spans.push(TextSpan {
+                    text: c.to_string(),
+                    matched,
+                }),
+            }
+        }
+        spans
+    }
 }
 
 /// Explanation of why item matched the search
@@ -82,7 +165,7 @@ pub struct AvailabilityInfo {
     pub store_count: usize,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StockLevel {
     High,
     Medium,
@@ -91,6 +174,87 @@ pub enum StockLevel {
     Unknown,
 }
 
+/// Estimates stock from price activity: a product whose price is updated often
+/// and recently is probably well stocked, one nobody has reported in a while
+/// may not be. Rejected records are ignored
+#[derive(Debug, Clone, PartialEq)]
+pub struct StockHeuristic {
+    /// `High` when the latest update is at most this old...
+    pub high_max_age_days: i64,
+    /// ...and at least this many updates fall within `recent_window_days`
+    pub high_min_updates: usize,
+    /// Window counted for `high_min_updates`
+    pub recent_window_days: i64,
+    /// `Medium` when the latest update is at most this old
+    pub medium_max_age_days: i64,
+    /// `Low` when the latest update is at most this old; older is `Unknown`.
+    /// Also the age up to which a store counts towards `store_count`
+    pub low_max_age_days: i64,
+}
+
+impl Default for StockHeuristic {
+    fn default() -> Self {
+        Self {
+            high_max_age_days: 3,
+            high_min_updates: 3,
+            recent_window_days: 7,
+            medium_max_age_days: 7,
+            low_max_age_days: 30,
+        }
+    }
+}
+
+impl StockHeuristic {
+    /// Availability of a product with price `records`, as of `now`
+    pub fn estimate(&self, records: &[PriceRecord], now: DateTime<Utc>) -> AvailabilityInfo {
+        let records: Vec<&PriceRecord> = records
+            .iter()
+            .filter(|r| r.verification_status != "rejected")
+            .collect();
+        let Some(last_seen) = records.iter().map(|r| r.timestamp).max() else {
+            return AvailabilityInfo {
+                in_stock: false,
+                stock_level: StockLevel::Unknown,
+                last_seen: now,
+                store_count: 0,
+            };
+        };
+
+        let age_days = (now - last_seen).num_days();
+        let within = |days: i64| {
+            records
+                .iter()
+                .filter(move |r| (now - r.timestamp).num_days() <= days)
+        };
+        let recent_updates = within(self.recent_window_days).count();
+        let store_count = within(self.low_max_age_days)
+            .map(|r| r.store_id.as_str())
+            .collect::<std::collections::HashSet<_>>()
+            .len();
+
+        let stock_level =
+            if age_days <= self.high_max_age_days && recent_updates >= self.high_min_updates {
+                StockLevel::High
+            } else if age_days <= self.medium_max_age_days {
+                StockLevel::Medium
+            } else if age_days <= self.low_max_age_days {
+                StockLevel::Low
+            } else {
+                StockLevel::Unknown
+            };
+
+        AvailabilityInfo {
+            in_stock: matches!(
+                stock_level,
+                StockLevel::High | StockLevel::Medium | StockLevel::Low
+            ),
+            stock_level,
+            last_seen,
+            store_count,
+        }
+    }
+}
+
 /// Search facets for filtering UI
 #[derive(Debug, Clone)]
 pub struct SearchFacets {
@@ -123,6 +287,9 @@ impl SearchEngine {
             store_index: HashMap::new(),
             category_index: HashMap::new(),
             tag_index: HashMap::new(),
+            barcode_index: HashMap::new(),
+            price_index: HashMap::new(),
+            stock_heuristic: StockHeuristic::default(),
             search_cache: HashMap::new(),
             cache_ttl_minutes: 15,
         }
@@ -145,9 +312,11 @@ impl SearchEngine {
         Ok(())
     }
 
-    /// Perform advanced search with intelligent ranking
-    pub fn search(&mut self, query: SearchQuery) -> ServiceResult<SearchResult> {
+    /// Perform advanced search with intelligent ranking. A query that is a
+    /// barcode of an indexed product puts that product first
+    pub fn search(&mut self, mut query: SearchQuery) -> ServiceResult<SearchResult> {
         let start_time = std::time::Instant::now();
+        query.text = sanitize_search_query(&query.text);
 
         // Check cache first
         let cache_key = self.generate_cache_key(&query);
@@ -157,17 +326,20 @@ impl SearchEngine {
             }
         }
 
+        // 输入为条码时直接按条码查找商品
+        let barcode_item = self.barcode_lookup(&query.text, query.explain);
+
         // Perform search
         let mut items = Vec::new();
         let query_terms = self.tokenize_query(&query.text);
 
         // Find matching products
-        let matching_products = self.find_matching_products(&query_terms, &query.filters)?;
+        let matching_products = self.find_matching_products(&query_terms, &query)?;
 
         // Score and rank results
-        for (product, base_score) in matching_products {
+        for (product, base_score, hits) in matching_products {
             if let Some(result_item) =
-                self.create_search_result_item(product, base_score, &query)?
+                self.create_search_result_item(product, base_score, &hits, &query)?
             {
                 if result_item.relevance_score >= query.min_relevance_score {
                     items.push(result_item);
@@ -178,7 +350,14 @@ impl SearchEngine {
         // Sort results
         self.sort_results(&mut items, &query.filters.sort_options);
 
+        // 条码命中的商品排在最前
+        if let Some(barcode_item) = barcode_item {
+            items.retain(|item| item.product.id != barcode_item.product.id);
+            items.insert(0, barcode_item);
+        }
+
         // Limit results
+        let total_before_cap = items.len();
         items.truncate(query.max_results);
 
         // Generate suggestions
@@ -193,6 +372,8 @@ impl SearchEngine {
 
         let result = SearchResult {
             total_count: items.len(),
+            truncated: total_before_cap > items.len(),
+            total_before_cap,
             items,
             search_time_ms: start_time.elapsed().as_millis() as u64,
             suggestions,
@@ -207,6 +388,49 @@ impl SearchEngine {
         Ok(result)
     }
 
+    /// Single-item result for a product identified by barcode (e.g. a camera scan)
+    pub fn barcode_result(&self, product: Product) -> SearchResult {
+        let items: Vec<SearchResultItem> = self.barcode_item(product, false).into_iter().collect();
+        let facets = self.generate_facets(&items);
+
+        SearchResult {
+            total_count: items.len(),
+            truncated: false,
+            total_before_cap: items.len(),
+            items,
+            search_time_ms: 0,
+            suggestions: Vec::new(),
+            filters_applied: SearchFilters::default(),
+            facets,
+        }
+    }
+
+    /// Indexed product whose barcode is `text`, when `text` looks like a barcode
+    fn barcode_lookup(&self, text: &str, explain: bool) -> Option<SearchResultItem> {
+        let code = normalize_width(text.trim());
+        if !validate_barcode(&code) {
+            return None;
+        }
+        let product = self.barcode_index.get(&code)?.clone();
+        self.barcode_item(product, explain)
+    }
+
+    fn barcode_item(&self, product: Product, explain: bool) -> Option<SearchResultItem> {
+        let query = SearchQuery {
+            text: product.name.clone(),
+            ..SearchQuery::default()
+        };
+        self.create_search_result_item(product, 1.0, &[], &query)
+            .ok()
+            .flatten()
+            .map(|mut item| {
+                item.match_reasons = vec![MatchReason::BarcodeMatch];
+                item.matched_terms.clear();
+                item.explanation = explain.then(|| vec![ScoreComponent::new("barcode match", 1.0)]);
+                item
+            })
+    }
+
     /// Auto-complete suggestions
     pub fn get_suggestions(&self, partial_query: &str, limit: usize) -> Vec<String> {
         let partial_lower = partial_query.to_lowercase();
@@ -254,9 +478,37 @@ impl SearchEngine {
         self.store_index.clear();
         self.category_index.clear();
         self.tag_index.clear();
+        self.barcode_index.clear();
+        self.price_index.clear();
+    }
+
+    /// Thresholds used to estimate stock levels
+    pub fn set_stock_heuristic(&mut self, heuristic: StockHeuristic) {
+        self.stock_heuristic = heuristic;
+        self.search_cache.clear();
+    }
+
+    /// Use `records` (e.g. from `PriceService`) for availability, replacing the
+    /// prices embedded in the indexed products
+    pub fn set_price_records(&mut self, records: &[PriceRecord]) {
+        self.price_index.clear();
+        for record in records {
+            if let Some(product_id) = &record.product_id {
+                self.price_index
+                    .entry(product_id.clone())
+                    .or_default()
+                    .push(record.clone());
+            }
+        }
+        self.search_cache.clear();
     }
 
     fn index_product(&mut self, product: &Product) -> ServiceResult<()> {
+        if !product.prices.is_empty() {
+            self.price_index
+                .insert(product.id.clone(), product.prices.clone());
+        }
+
         // Index product name
         let name_terms = self.tokenize(&product.name);
         for term in name_terms {
@@ -292,6 +544,11 @@ impl SearchEngine {
                 .push(product.id.clone());
         }
 
+        // Index barcode
+        if let Some(barcode) = &product.barcode {
+            self.barcode_index.insert(barcode.clone(), product.clone());
+        }
+
         Ok(())
     }
 
@@ -308,7 +565,8 @@ impl SearchEngine {
     }
 
     fn tokenize(&self, text: &str) -> Vec<String> {
-        text.to_lowercase()
+        normalize_width(text)
+            .to_lowercase()
             .split_whitespace()
             .filter(|s| s.len() > 1)
             .map(|s| s.to_string())
@@ -322,35 +580,70 @@ impl SearchEngine {
     fn find_matching_products(
         &self,
         query_terms: &[String],
-        _filters: &SearchFilters,
-    ) -> ServiceResult<Vec<(Product, f32)>> {
+        query: &SearchQuery,
+    ) -> ServiceResult<Vec<(Product, f32, Vec<TermHit>)>> {
         // This is a simplified implementation
         // In a real system, this would use more sophisticated matching
-        let mut product_scores: HashMap<String, f32> = HashMap::new();
+        let mut product_scores: HashMap<String, (f32, Vec<TermHit>)> = HashMap::new();
 
         // Score products based on query terms
         for term in query_terms {
-            if let Some(product_ids) = self.product_index.get(term) {
+            for (indexed_term, product_ids) in &self.product_index {
+                let Some(weight) = Self::term_match_weight(term, indexed_term, query) else {
+                    continue;
+                };
                 for product_id in product_ids {
-                    *product_scores.entry(product_id.clone()).or_insert(0.0) += 1.0;
+                    let (score, hits) = product_scores.entry(product_id.clone()).or_default();
+                    *score += weight;
+                    hits.push(TermHit {
+                        term: term.clone(),
+                        indexed_term: indexed_term.clone(),
+                        weight,
+                    });
                 }
             }
         }
 
         // Convert to products (this would use actual product service in real implementation)
-        let products: Vec<(Product, f32)> = product_scores
+        let products: Vec<(Product, f32, Vec<TermHit>)> = product_scores
             .into_iter()
-            .map(|(id, score)| (self.create_mock_product(&id), score))
+            .map(|(id, (score, hits))| (self.create_mock_product(&id), score, hits))
             .collect();
 
         Ok(products)
     }
 
+    /// Score weight of a query term against an indexed term, or None if they don't match.
+    /// Exact matches weigh 1.0; fuzzy matches 1 / (1 + distance), only when both terms are
+    /// at least `fuzzy_min_length` chars and within `fuzzy_max_distance` edits.
+    fn term_match_weight(term: &str, indexed_term: &str, query: &SearchQuery) -> Option<f32> {
+        if term == indexed_term {
+            return Some(1.0);
+        }
+        if !query.fuzzy_matching || query.fuzzy_max_distance == 0 {
+            return None;
+        }
+
+        let term_len = term.chars().count();
+        let indexed_len = indexed_term.chars().count();
+        if term_len < query.fuzzy_min_length || indexed_len < query.fuzzy_min_length {
+            return None;
+        }
+        // 长度差已超过上限时无需计算编辑距离
+        if term_len.abs_diff(indexed_len) > query.fuzzy_max_distance {
+            return None;
+        }
+
+        let distance = edit_distance(term, indexed_term);
+        (distance <= query.fuzzy_max_distance).then(|| 1.0 / (1.0 + distance as f32))
+    }
+
     fn create_search_result_item(
         &self,
         product: Product,
         base_score: f32,
-        _query: &SearchQuery,
+        hits: &[TermHit],
+        query: &SearchQuery,
     ) -> ServiceResult<Option<SearchResultItem>> {
         // Create match reasons
         let match_reasons = vec![MatchReason::NameMatch(base_score)];
@@ -365,13 +658,12 @@ impl SearchEngine {
             confidence: 0.85,
         };
 
-        // Create availability info
-        let availability_info = AvailabilityInfo {
-            in_stock: true,
-            stock_level: StockLevel::High,
-            last_seen: Utc::now(),
-            store_count: 5,
-        };
+        let records = self
+            .price_index
+            .get(&product.id)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let availability_info = self.stock_heuristic.estimate(records, Utc::now());
 
         Ok(Some(SearchResultItem {
             product,
@@ -381,9 +673,35 @@ impl SearchEngine {
             match_reasons,
             price_trend,
             availability_info,
+            matched_terms: self.tokenize_query(&query.text),
+            explanation: query.explain.then(|| Self::explain_score(hits)),
         }))
     }
 
+    /// Human-readable breakdown of a score built from `hits`. Only name/description
+    /// terms are weighted today; the other signals are listed at zero so the
+    /// breakdown shows they were considered.
+    fn explain_score(hits: &[TermHit]) -> Vec<ScoreComponent> {
+        let mut components: Vec<ScoreComponent> = hits
+            .iter()
+            .map(|hit| {
+                let label = if hit.term == hit.indexed_term {
+                    format!("name/description: \"{}\" (exact)", hit.term)
+                } else {
+                    format!(
+                        "name/description: \"{}\" ~ \"{}\" (fuzzy)",
+                        hit.term, hit.indexed_term
+                    )
+                };
+                ScoreComponent::new(label, hit.weight)
+            })
+            .collect();
+        for signal in ["category", "tag", "synonym", "price", "recency"] {
+            components.push(ScoreComponent::new(signal, 0.0));
+        }
+        components
+    }
+
     fn sort_results(
         &self,
         items: &mut [SearchResultItem],
@@ -462,7 +780,15 @@ impl SearchEngine {
     }
 
     fn generate_cache_key(&self, query: &SearchQuery) -> String {
-        format!("{}_{}", query.text, query.max_results)
+        format!(
+            "{}_{}_{}_{}_{}_{}",
+            query.text,
+            query.max_results,
+            query.fuzzy_matching,
+            query.fuzzy_max_distance,
+            query.fuzzy_min_length,
+            query.explain
+        )
     }
 
     fn is_cache_valid(&self, cached_time: &DateTime<Utc>) -> bool {
@@ -482,10 +808,38 @@ impl SearchEngine {
             prices: Vec::new(),
             tags: Vec::new(),
             created_at: Utc::now(),
+            version: 0,
         }
     }
 }
 
+/// Levenshtein distance between two strings, by chars
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for (i, ca) in a.iter().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != cb);
+            curr[j + 1] = substitution.min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()]
+}
+
+/// Similarity of two terms in `0.0..=1.0`: 1 minus the edit distance over the longer length
+pub fn similarity(a: &str, b: &str) -> f32 {
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - edit_distance(a, b) as f32 / longest as f32
+}
+
 impl Default for SearchEngine {
     fn default() -> Self {
         Self::new()
@@ -497,10 +851,475 @@ impl Default for SearchQuery {
         Self {
             text: String::new(),
             filters: SearchFilters::default(),
-            fuzzy_matching: true,
+            fuzzy_matching: false,
+            fuzzy_max_distance: 1,
+            fuzzy_min_length: 5,
             include_suggestions: true,
             max_results: 50,
             min_relevance_score: 0.1,
+            explain: false,
         }
     }
 }
+
+impl SearchQuery {
+    /// Start building a query with default settings
+    pub fn builder() -> SearchQueryBuilder {
+        SearchQueryBuilder::new()
+    }
+}
+
+/// Builder for validated search queries
+pub struct SearchQueryBuilder {
+    query: SearchQuery,
+}
+
+impl SearchQueryBuilder {
+    pub fn new() -> Self {
+        Self {
+            query: SearchQuery::default(),
+        }
+    }
+
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.query.text = text.into();
+        self
+    }
+
+    pub fn filters(mut self, filters: SearchFilters) -> Self {
+        self.query.filters = filters;
+        self
+    }
+
+    pub fn fuzzy_matching(mut self, enabled: bool) -> Self {
+        self.query.fuzzy_matching = enabled;
+        self
+    }
+
+    pub fn fuzzy_max_distance(mut self, distance: usize) -> Self {
+        self.query.fuzzy_max_distance = distance;
+        self
+    }
+
+    pub fn fuzzy_min_length(mut self, length: usize) -> Self {
+        self.query.fuzzy_min_length = length;
+        self
+    }
+
+    pub fn include_suggestions(mut self, enabled: bool) -> Self {
+        self.query.include_suggestions = enabled;
+        self
+    }
+
+    pub fn max_results(mut self, max_results: usize) -> Self {
+        self.query.max_results = max_results;
+        self
+    }
+
+    pub fn min_relevance_score(mut self, score: f32) -> Self {
+        self.query.min_relevance_score = score;
+        self
+    }
+
+    pub fn explain(mut self, enabled: bool) -> Self {
+        self.query.explain = enabled;
+        self
+    }
+
+    /// Validate and return the query
+    pub fn build(self) -> ServiceResult<SearchQuery> {
+        if self.query.max_results == 0 {
+            return Err(ServiceError::ValidationError(
+                "max_results must be greater than 0".to_string(),
+            ));
+        }
+        // NaN 也视为越界
+        if !(0.0..=1.0).contains(&self.query.min_relevance_score) {
+            return Err(ServiceError::ValidationError(format!(
+                "min_relevance_score must be between 0.0 and 1.0, got {}",
+                self.query.min_relevance_score
+            )));
+        }
+        Ok(self.query)
+    }
+}
+
+impl Default for SearchQueryBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_query_matches_exactly() {
+        let query = SearchQuery::builder().text("milk").build().unwrap();
+        assert!(!query.fuzzy_matching);
+    }
+
+    #[test]
+    fn test_builder_valid_query() {
+        let query = SearchQuery::builder()
+            .text("milk")
+            .fuzzy_matching(false)
+            .max_results(10)
+            .min_relevance_score(0.5)
+            .build()
+            .unwrap();
+        assert_eq!(query.text, "milk");
+        assert!(!query.fuzzy_matching);
+        assert!(query.include_suggestions);
+        assert_eq!(query.max_results, 10);
+        assert_eq!(query.min_relevance_score, 0.5);
+    }
+
+    #[test]
+    fn test_builder_rejects_out_of_range_relevance() {
+        let result = SearchQuery::builder().min_relevance_score(1.5).build();
+        assert!(matches!(result, Err(ServiceError::ValidationError(_))));
+        let result = SearchQuery::builder().min_relevance_score(-0.1).build();
+        assert!(matches!(result, Err(ServiceError::ValidationError(_))));
+    }
+
+    #[test]
+    fn test_builder_rejects_zero_max_results() {
+        let result = SearchQuery::builder().text("milk").max_results(0).build();
+        assert!(matches!(result, Err(ServiceError::ValidationError(_))));
+    }
+
+    fn item_for(name: &str, query: &str) -> SearchResultItem {
+        let engine = SearchEngine::new();
+        let mut product = engine.create_mock_product("p1");
+        product.name = name.to_string();
+        let query = SearchQuery::builder().text(query).build().unwrap();
+        engine
+            .create_search_result_item(product, 1.0, &[], &query)
+            .unwrap()
+            .unwrap()
+    }
+
+    fn span(text: &str, matched: bool) -> TextSpan {
+        TextSpan {
+            text: text.to_string(),
+            matched,
+        }
+    }
+
+    #[test]
+    fn test_highlight_single_token_case_insensitive() {
+        let item = item_for("Fresh Milk 1L", "MILK");
+        assert_eq!(
+            item.highlighted_name(),
+            vec![
+                span("Fresh ", false),
+                span("Milk", true),
+                span(" 1L", false)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_highlight_multi_token_without_overlap() {
+        // "choco" 与 "chocolate" 重叠，合并为一个片段
+        let item = item_for("Dark Chocolate Milk", "chocolate milk choco");
+        assert_eq!(
+            item.highlighted_name(),
+            vec![
+                span("Dark ", false),
+                span("Chocolate", true),
+                span(" ", false),
+                span("Milk", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_highlight_no_match() {
+        let item = item_for("Green Tea", "coffee");
+        assert_eq!(item.highlighted_name(), vec![span("Green Tea", false)]);
+    }
+
+    fn product_named(name: &str) -> Product {
+        let mut product = SearchEngine::new().create_mock_product(name);
+        product.name = name.to_string();
+        product.description = String::new();
+        product.category = String::new();
+        product
+    }
+
+    fn search_ids(products: &[Product], query: SearchQuery) -> Vec<String> {
+        let mut engine = SearchEngine::new();
+        engine.build_indices(products, &[]).unwrap();
+        let mut ids: Vec<String> = engine
+            .search(query)
+            .unwrap()
+            .items
+            .into_iter()
+            .map(|item| item.product.id)
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn test_fuzzy_one_edit_matches() {
+        let products = vec![product_named("chocolate")];
+        let query = SearchQuery::builder()
+            .text("chocolat")
+            .fuzzy_matching(true)
+            .build()
+            .unwrap();
+        assert_eq!(search_ids(&products, query), vec!["chocolate".to_string()]);
+    }
+
+    #[test]
+    fn test_fuzzy_rejects_beyond_distance_cap() {
+        let products = vec![product_named("chocolate")];
+        // "chacalote" 与 "chocolate" 相差 3 处编辑
+        let query = SearchQuery::builder()
+            .text("chacalote")
+            .fuzzy_matching(true)
+            .fuzzy_max_distance(2)
+            .fuzzy_min_length(3)
+            .build()
+            .unwrap();
+        assert!(search_ids(&products, query).is_empty());
+
+        let query = SearchQuery::builder()
+            .text("chacolote")
+            .fuzzy_matching(true)
+            .fuzzy_max_distance(2)
+            .fuzzy_min_length(3)
+            .build()
+            .unwrap();
+        assert_eq!(search_ids(&products, query), vec!["chocolate".to_string()]);
+    }
+
+    #[test]
+    fn test_short_tokens_require_exact_match() {
+        let products = vec![product_named("cola"), product_named("soda")];
+        let query = SearchQuery::builder()
+            .text("cola")
+            .fuzzy_matching(true)
+            .fuzzy_max_distance(2)
+            .build()
+            .unwrap();
+        assert_eq!(search_ids(&products, query), vec!["cola".to_string()]);
+
+        // 放宽最小长度后短词也会模糊命中
+        let query = SearchQuery::builder()
+            .text("cola")
+            .fuzzy_matching(true)
+            .fuzzy_max_distance(2)
+            .fuzzy_min_length(2)
+            .build()
+            .unwrap();
+        assert_eq!(
+            search_ids(&products, query),
+            vec!["cola".to_string(), "soda".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_barcode_query_returns_product_first() {
+        let mut tea = product_named("Green Tea");
+        tea.barcode = Some("4901234567892".to_string());
+        let mut engine = SearchEngine::new();
+        engine
+            .build_indices(&[tea, product_named("Barley Tea")], &[])
+            .unwrap();
+
+        let query = SearchQuery::builder()
+            .text(" 4901234567892 ")
+            .build()
+            .unwrap();
+        let result = engine.search(query).unwrap();
+        assert_eq!(result.items.len(), 1);
+        assert_eq!(result.items[0].product.name, "Green Tea");
+        assert!(matches!(
+            result.items[0].match_reasons.as_slice(),
+            [MatchReason::BarcodeMatch]
+        ));
+    }
+
+    #[test]
+    fn test_full_width_query_matches_half_width_name_and_barcode() {
+        let mut tea = product_named("Green Tea");
+        tea.barcode = Some("4901234567892".to_string());
+        let products = vec![tea, product_named("Barley Water")];
+
+        let query = SearchQuery::builder()
+            .text("ｇｒｅｅｎ")
+            .fuzzy_matching(false)
+            .build()
+            .unwrap();
+        assert_eq!(search_ids(&products, query), vec!["Green Tea".to_string()]);
+
+        let query = SearchQuery::builder()
+            .text("４９０１２３４５６７８９２")
+            .build()
+            .unwrap();
+        assert_eq!(search_ids(&products, query), vec!["Green Tea".to_string()]);
+    }
+
+    #[test]
+    fn test_numeric_non_barcode_query_uses_text_search() {
+        let mut water = product_named("Water 500");
+        water.barcode = Some("4901234567892".to_string());
+        let products = vec![water, product_named("Juice 1000")];
+
+        // 3 位数字不是条码长度，按文本搜索
+        let query = SearchQuery::builder()
+            .text("500")
+            .fuzzy_matching(false)
+            .build()
+            .unwrap();
+        assert_eq!(search_ids(&products, query), vec!["Water 500".to_string()]);
+
+        // 条码长度但没有对应商品，同样退回文本搜索
+        let query = SearchQuery::builder().text("12345678").build().unwrap();
+        assert!(search_ids(&products, query).is_empty());
+    }
+
+    #[test]
+    fn test_explain_components_sum_to_score() {
+        let products = vec![product_named("chocolate milk"), product_named("milk")];
+        let mut engine = SearchEngine::new();
+        engine.build_indices(&products, &[]).unwrap();
+
+        let query = SearchQuery::builder()
+            .text("chocolat milk")
+            .fuzzy_matching(true)
+            .explain(true)
+            .build()
+            .unwrap();
+        let result = engine.search(query).unwrap();
+        assert_eq!(result.items.len(), 2);
+        for item in &result.items {
+            let components = item.explanation.as_ref().unwrap();
+            let sum: f32 = components.iter().map(|c| c.value).sum();
+            assert!((sum - item.relevance_score).abs() < 1e-5);
+            assert!(components.iter().any(|c| c.label == "recency"));
+        }
+        let top = &result.items[0];
+        assert_eq!(top.product.id, "chocolate milk");
+        assert!(
+            top.explanation
+                .as_ref()
+                .unwrap()
+                .iter()
+                .any(|c| c.label.contains("(fuzzy)") && c.value == 0.5)
+        );
+
+        let query = SearchQuery::builder()
+            .text("chocolat milk")
+            .build()
+            .unwrap();
+        let result = engine.search(query).unwrap();
+        assert!(result.items.iter().all(|item| item.explanation.is_none()));
+    }
+
+    #[test]
+    fn test_capped_results_report_total_matches() {
+        let products: Vec<Product> = (0..8)
+            .map(|i| product_named(&format!("milk {}", i)))
+            .collect();
+        let mut engine = SearchEngine::new();
+        engine.build_indices(&products, &[]).unwrap();
+
+        let query = SearchQuery::builder()
+            .text("milk")
+            .max_results(3)
+            .build()
+            .unwrap();
+        let result = engine.search(query).unwrap();
+        assert_eq!(result.items.len(), 3);
+        assert!(result.truncated);
+        assert_eq!(result.total_before_cap, 8);
+
+        let query = SearchQuery::builder()
+            .text("milk")
+            .max_results(20)
+            .build()
+            .unwrap();
+        let result = engine.search(query).unwrap();
+        assert!(!result.truncated);
+        assert_eq!(result.total_before_cap, 8);
+    }
+
+    fn record_at(store_id: &str, now: DateTime<Utc>, days_ago: i64) -> PriceRecord {
+        let mut record = PriceRecord::new(
+            Some("tea".to_string()),
+            store_id.to_string(),
+            None,
+            120.0,
+            false,
+            None,
+        );
+        record.timestamp = now - chrono::Duration::days(days_ago);
+        record
+    }
+
+    #[test]
+    fn test_stock_level_follows_price_activity() {
+        let heuristic = StockHeuristic::default();
+        let now = Utc::now();
+        let level = |records: &[PriceRecord]| heuristic.estimate(records, now).stock_level;
+
+        let busy = [
+            record_at("s1", now, 0),
+            record_at("s2", now, 1),
+            record_at("s1", now, 5),
+        ];
+        assert_eq!(level(&busy), StockLevel::High);
+        assert_eq!(heuristic.estimate(&busy, now).store_count, 2);
+        // 最近有更新但次数不足
+        assert_eq!(level(&busy[..1]), StockLevel::Medium);
+        assert_eq!(level(&[record_at("s1", now, 6)]), StockLevel::Medium);
+        assert_eq!(level(&[record_at("s1", now, 20)]), StockLevel::Low);
+        let stale = heuristic.estimate(&[record_at("s1", now, 45)], now);
+        assert_eq!(stale.stock_level, StockLevel::Unknown);
+        assert_eq!(stale.store_count, 0);
+        assert!(!stale.in_stock);
+        assert_eq!(level(&[]), StockLevel::Unknown);
+
+        let mut rejected = record_at("s1", now, 0);
+        rejected.reject();
+        assert_eq!(level(&[rejected]), StockLevel::Unknown);
+    }
+
+    #[test]
+    fn test_stock_thresholds_are_configurable() {
+        let now = Utc::now();
+        let records = [record_at("s1", now, 10)];
+        let strict = StockHeuristic {
+            low_max_age_days: 7,
+            ..StockHeuristic::default()
+        };
+        assert_eq!(
+            strict.estimate(&records, now).stock_level,
+            StockLevel::Unknown
+        );
+
+        let mut engine = SearchEngine::new();
+        engine.build_indices(&[product_named("tea")], &[]).unwrap();
+        engine.set_price_records(&records);
+        let query = SearchQuery::builder().text("tea").build().unwrap();
+        let item = &engine.search(query.clone()).unwrap().items[0];
+        assert_eq!(item.availability_info.stock_level, StockLevel::Low);
+
+        engine.set_stock_heuristic(strict);
+        let item = &engine.search(query).unwrap().items[0];
+        assert_eq!(item.availability_info.stock_level, StockLevel::Unknown);
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("可乐", "可口可乐"), 2);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+}
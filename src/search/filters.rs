@@ -1,3 +1,5 @@
+use crate::models::{DeepLink, PriceRecord};
+use crate::utils::percent_encode;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
@@ -81,6 +83,20 @@ pub enum PromotionFilter {
     NoPromotions,
 }
 
+impl PromotionFilter {
+    /// Whether a price record satisfies this promotion filter
+    pub fn matches(&self, record: &PriceRecord) -> bool {
+        match self {
+            PromotionFilter::All => true,
+            PromotionFilter::OnSale => record.is_on_sale || record.discount.is_some(),
+            PromotionFilter::HasCoupon => record.has_coupon(),
+            // 暂无买一送一/批量折扣数据，不匹配任何记录
+            PromotionFilter::BuyOneGetOne | PromotionFilter::BulkDiscount => false,
+            PromotionFilter::NoPromotions => !record.is_on_sale && record.discount.is_none(),
+        }
+    }
+}
+
 /// Verification status filter for price records
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum VerificationFilter {
@@ -221,6 +237,73 @@ impl SearchFilters {
             && matches!(self.promotion_filter, PromotionFilter::All)
     }
 
+    /// Shareable `eprice://search?…` link for `query` with these filters.
+    /// Only filters that narrow the search are included
+    pub fn share_string(&self, query: &str) -> String {
+        let mut params: Vec<(&str, String)> = Vec::new();
+        let query = query.trim();
+        if !query.is_empty() {
+            params.push(("q", query.to_string()));
+        }
+        if let Some(ref range) = self.price_range {
+            if let Some(min) = range.min_price {
+                params.push(("min_price", min.to_string()));
+            }
+            if let Some(max) = range.max_price {
+                params.push(("max_price", max.to_string()));
+            }
+        }
+        params.extend(
+            self.categories
+                .iter()
+                .map(|c| ("category", c.category_id.clone())),
+        );
+        params.extend(self.stores.iter().map(|s| ("store", s.store_id.clone())));
+        params.extend(self.tags.iter().map(|t| ("tag", t.clone())));
+
+        let promotion = match self.promotion_filter {
+            PromotionFilter::All => None,
+            PromotionFilter::OnSale => Some("on_sale"),
+            PromotionFilter::HasCoupon => Some("coupon"),
+            PromotionFilter::BuyOneGetOne => Some("bogo"),
+            PromotionFilter::BulkDiscount => Some("bulk"),
+            PromotionFilter::NoPromotions => Some("none"),
+        };
+        if let Some(promotion) = promotion {
+            params.push(("promotion", promotion.to_string()));
+        }
+
+        // 默认按相关度排序时不写入排序参数
+        if self.sort_options.primary_sort != SortField::Relevance {
+            let field = match self.sort_options.primary_sort {
+                SortField::Relevance => "relevance",
+                SortField::Price => "price",
+                SortField::Name => "name",
+                SortField::Rating => "rating",
+                SortField::Distance => "distance",
+                SortField::LastUpdated => "updated",
+                SortField::PopularityScore => "popularity",
+                SortField::PriceChange => "price_change",
+            };
+            let direction = match self.sort_options.sort_direction {
+                SortDirection::Ascending => "asc",
+                SortDirection::Descending => "desc",
+            };
+            params.push(("sort", format!("{}_{}", field, direction)));
+        }
+
+        let mut link = format!("{}://search", DeepLink::SCHEME);
+        if !params.is_empty() {
+            let encoded: Vec<String> = params
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, percent_encode(value)))
+                .collect();
+            link.push('?');
+            link.push_str(&encoded.join("&"));
+        }
+        link
+    }
+
     /// Reset all filters to default
     pub fn reset(&mut self) {
         *self = Self::default();
@@ -259,3 +342,34 @@ impl SearchFilters {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_share_string_encodes_filters() {
+        let mut filters = SearchFilters::with_price_range(Some(1.5), Some(10.0));
+        filters.add_category("drinks".to_string(), "Drinks".to_string(), true);
+        filters.add_store("store_1".to_string(), "Store 1".to_string(), None);
+        filters.add_tags(vec!["organic & fair".to_string()]);
+        filters.set_promotion_filter(PromotionFilter::OnSale);
+        filters.set_sort(SortField::Price, SortDirection::Ascending);
+
+        assert_eq!(
+            filters.share_string(" green tea "),
+            "eprice://search?q=green%20tea&min_price=1.5&max_price=10&category=drinks\
+             &store=store_1&tag=organic%20%26%20fair&promotion=on_sale&sort=price_asc"
+        );
+    }
+
+    #[test]
+    fn test_share_string_without_filters() {
+        let filters = SearchFilters::default();
+        assert_eq!(filters.share_string(""), "eprice://search");
+        assert_eq!(
+            filters.share_string("牛奶"),
+            "eprice://search?q=%E7%89%9B%E5%A5%B6"
+        );
+    }
+}
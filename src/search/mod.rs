@@ -1,7 +1,12 @@
 pub mod engine;
 pub mod filters;
 pub mod ui;
+pub mod voice;
 
-pub use engine::{SearchEngine, SearchQuery, SearchResult, SearchResultItem};
+pub use engine::{
+    AvailabilityInfo, ScoreComponent, SearchEngine, SearchQuery, SearchQueryBuilder, SearchResult,
+    SearchResultItem, StockHeuristic, StockLevel, TextSpan,
+};
 pub use filters::{CategoryFilter, PriceRange, SearchFilters, StoreFilter};
 pub use ui::AdvancedSearchUI;
+pub use voice::{AudioRecorder, VoiceTranscriber};
@@ -1,8 +1,15 @@
+#[cfg(not(target_arch = "wasm32"))]
+use crate::models::Product;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::scanner::{ScanBackend, ScannerError, ScannerService};
 use crate::search::filters::{
     AvailabilityFilter, PriceRange, PromotionFilter, SearchFilters, SortField,
 };
-use crate::search::{SearchEngine, SearchQuery, SearchResult, SearchResultItem};
+use crate::search::{
+    AudioRecorder, SearchEngine, SearchQuery, SearchResult, SearchResultItem, VoiceTranscriber,
+};
 use crate::services::AppServices;
+use crate::utils::{ClipboardSink, PriceFormatter, copy_to_clipboard};
 use egui::{Color32, RichText, Ui};
 use std::collections::HashSet;
 
@@ -33,6 +40,19 @@ pub struct AdvancedSearchUI {
     auto_complete_enabled: bool,
     save_search_enabled: bool,
     search_analytics: SearchAnalytics,
+
+    // Camera search
+    #[cfg(not(target_arch = "wasm32"))]
+    scanner: Option<Box<dyn ScanBackend>>, // 首次使用时创建
+    camera_message: Option<String>,
+
+    // Voice search
+    voice_recorder: Option<Box<dyn AudioRecorder>>,
+    voice_transcriber: Option<Box<dyn VoiceTranscriber>>,
+    voice_message: Option<String>,
+
+    /// Formats prices with the configured currency, precision and rounding
+    price_formatter: PriceFormatter,
 }
 
 #[derive(Debug, Clone)]
@@ -78,12 +98,50 @@ impl AdvancedSearchUI {
                 avg_results_count: 0.0,
                 last_search_time: std::time::Instant::now(),
             },
+            #[cfg(not(target_arch = "wasm32"))]
+            scanner: None,
+            camera_message: None,
+            voice_recorder: None,
+            voice_transcriber: None,
+            voice_message: None,
+            price_formatter: PriceFormatter::default(),
         };
 
         ui.initialize_quick_filters();
+        #[cfg(all(feature = "voice", not(target_arch = "wasm32")))]
+        if let Some(transcriber) = crate::search::voice::CommandTranscriber::from_env() {
+            ui.set_voice_input(
+                Box::new(crate::search::voice::ArecordRecorder::default()),
+                Box::new(transcriber),
+            );
+        }
         ui
     }
 
+    /// Format prices with `formatter`, usually built from the app settings
+    pub fn set_price_formatter(&mut self, formatter: PriceFormatter) {
+        self.price_formatter = formatter;
+    }
+
+    /// Enable voice search with the given recorder and transcriber.
+    /// The button stays disabled if the recorder has no microphone.
+    pub fn set_voice_input(
+        &mut self,
+        recorder: Box<dyn AudioRecorder>,
+        transcriber: Box<dyn VoiceTranscriber>,
+    ) {
+        // 只在配置时探测一次麦克风，避免每帧启动外部进程
+        self.voice_search_enabled = recorder.is_available();
+        self.voice_recorder = Some(recorder);
+        self.voice_transcriber = Some(transcriber);
+    }
+
+    /// Use a specific scanner for camera search instead of the default camera
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_scanner(&mut self, scanner: Box<dyn ScanBackend>) {
+        self.scanner = Some(scanner);
+    }
+
     /// Show the advanced search interface
     pub fn show(&mut self, ui: &mut Ui, app_services: &mut AppServices) {
         ui.heading("🔍 Advanced Search");
@@ -147,9 +205,12 @@ impl AdvancedSearchUI {
                     .desired_width(f32::INFINITY),
             );
 
-            // Voice search button (placeholder)
+            // Voice search button
             if ui
                 .add_enabled(self.voice_search_enabled, egui::Button::new("🎤"))
+                .on_disabled_hover_text(
+                    "Voice search needs the `voice` feature, a configured transcriber and a microphone",
+                )
                 .clicked()
             {
                 self.start_voice_search();
@@ -173,6 +234,13 @@ impl AdvancedSearchUI {
             }
         });
 
+        for message in [&self.voice_message, &self.camera_message]
+            .into_iter()
+            .flatten()
+        {
+            ui.colored_label(Color32::from_rgb(200, 120, 0), message);
+        }
+
         // Search history dropdown
         if !self.search_history.is_empty() {
             ui.horizontal(|ui| {
@@ -368,16 +436,18 @@ impl AdvancedSearchUI {
                 }
 
                 if ui.button("📤 Share Search").clicked() {
-                    self.share_search();
+                    self.share_search(ui.ctx());
                 }
             });
         });
     }
 
     fn show_search_results(&mut self, ui: &mut Ui, _app_services: &mut AppServices) {
+        let formatter = self.price_formatter;
         if let Some(ref results) = self.search_results {
             let suggestions: Vec<String> = results.suggestions.clone();
             let total_count = results.total_count;
+            let total_before_cap = results.truncated.then_some(results.total_before_cap);
             let search_time_ms = results.search_time_ms;
             let items: Vec<_> = results.items.iter().collect();
 
@@ -385,7 +455,10 @@ impl AdvancedSearchUI {
             let mut new_query = String::new();
 
             ui.horizontal(|ui| {
-                ui.label(format!("Found {} results", total_count));
+                match total_before_cap {
+                    Some(total) => ui.label(format!("显示前{}条，共{}条", total_count, total)),
+                    None => ui.label(format!("Found {} results", total_count)),
+                };
                 ui.separator();
                 ui.label(format!("Search time: {}ms", search_time_ms));
 
@@ -408,7 +481,7 @@ impl AdvancedSearchUI {
                 .max_height(400.0)
                 .show(ui, |ui| {
                     for item in items {
-                        self.show_search_result_item(ui, item);
+                        self.show_search_result_item(ui, item, &formatter);
                         ui.separator();
                     }
                 });
@@ -431,12 +504,30 @@ impl AdvancedSearchUI {
         }
     }
 
-    fn show_search_result_item(&self, ui: &mut Ui, item: &SearchResultItem) {
+    fn show_search_result_item(
+        &self,
+        ui: &mut Ui,
+        item: &SearchResultItem,
+        formatter: &PriceFormatter,
+    ) {
         ui.group(|ui| {
             ui.horizontal(|ui| {
                 // Product info
                 ui.vertical(|ui| {
-                    ui.label(RichText::new(&item.product.name).strong());
+                    ui.horizontal(|ui| {
+                        ui.spacing_mut().item_spacing.x = 0.0;
+                        for span in item.highlighted_name() {
+                            if span.matched {
+                                ui.label(
+                                    RichText::new(span.text)
+                                        .strong()
+                                        .color(Color32::from_rgb(255, 165, 0)),
+                                );
+                            } else {
+                                ui.label(span.text);
+                            }
+                        }
+                    });
                     ui.label(format!("Category: {}", item.product.category));
                     ui.label(&item.product.description);
 
@@ -474,7 +565,7 @@ impl AdvancedSearchUI {
                 // Price info
                 ui.vertical(|ui| {
                     ui.label(
-                        RichText::new(format!("¥{:.2}", item.price_trend.current_price)).strong(),
+                        RichText::new(formatter.format(item.price_trend.current_price)).strong(),
                     );
 
                     if let Some(change_24h) = item.price_trend.price_change_24h {
@@ -486,7 +577,7 @@ impl AdvancedSearchUI {
                         let symbol = if change_24h > 0.0 { "↗" } else { "↘" };
                         ui.colored_label(
                             color,
-                            format!("{} ¥{:.2} (24h)", symbol, change_24h.abs()),
+                            format!("{} {} (24h)", symbol, formatter.format(change_24h.abs())),
                         );
                     }
 
@@ -630,17 +721,17 @@ impl AdvancedSearchUI {
         }
 
         // Create search query
-        let query = SearchQuery {
-            text: self.search_query.clone(),
-            filters: self.current_filters.clone(),
-            fuzzy_matching: true,
-            include_suggestions: true,
-            max_results: 50,
-            min_relevance_score: 0.1,
-        };
+        let query = SearchQuery::builder()
+            .text(self.search_query.clone())
+            .filters(self.current_filters.clone())
+            .fuzzy_matching(true)
+            .include_suggestions(true)
+            .max_results(50)
+            .min_relevance_score(0.1)
+            .build();
 
         // Perform search (in real app, this would be async)
-        match self.search_engine.search(query) {
+        match query.and_then(|query| self.search_engine.search(query)) {
             Ok(results) => {
                 self.search_results = Some(results);
                 self.update_search_analytics();
@@ -676,22 +767,75 @@ impl AdvancedSearchUI {
         }
     }
 
+    /// Record, transcribe and search for the spoken text
     fn start_voice_search(&mut self) {
-        // Placeholder for voice search functionality
-        self.search_query = "Voice search activated".to_string();
+        let (Some(recorder), Some(transcriber)) = (&self.voice_recorder, &self.voice_transcriber)
+        else {
+            self.voice_message = Some("Voice search is not configured".to_string());
+            return;
+        };
+
+        let text = recorder
+            .record()
+            .and_then(|audio| transcriber.transcribe(&audio));
+        match text {
+            Ok(text) if !text.trim().is_empty() => {
+                self.voice_message = None;
+                self.search_query = text.trim().to_string();
+                self.perform_search();
+            }
+            Ok(_) => {
+                self.voice_message = Some("No speech recognized".to_string());
+            }
+            Err(e) => {
+                self.voice_message = Some(format!("Voice search failed: {}", e));
+            }
+        }
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
     fn start_camera_search(&mut self) {
-        // Placeholder for camera search functionality
-        self.search_query = "Camera search activated".to_string();
+        let scanner = self
+            .scanner
+            .get_or_insert_with(|| Box::new(ScannerService::new()));
+
+        match scanner.scan_product() {
+            Ok(Some(product)) => self.show_scanned_product(product),
+            Ok(None) => {
+                self.camera_message = Some("No product matches the scanned barcode".to_string());
+            }
+            Err(ScannerError::NoCameraAvailable) => {
+                self.camera_message = Some("No camera available for camera search".to_string());
+            }
+            Err(e) => {
+                self.camera_message = Some(format!("Camera search failed: {}", e));
+            }
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn start_camera_search(&mut self) {
+        self.camera_message = Some("Camera search is not available in the web version".to_string());
+    }
+
+    /// Show a scanned product as the only search result
+    #[cfg(not(target_arch = "wasm32"))]
+    fn show_scanned_product(&mut self, product: Product) {
+        self.search_query = product.name.clone();
+        self.search_results = Some(self.search_engine.barcode_result(product));
+        self.camera_message = None;
+        self.update_search_analytics();
     }
 
     fn save_current_search(&mut self) {
         // Placeholder for saving search functionality
     }
 
-    fn share_search(&mut self) {
-        // Placeholder for sharing search functionality
+    /// Copy a shareable link of the current query and filters
+    fn share_search(&mut self, clipboard: &dyn ClipboardSink) -> String {
+        let link = self.current_filters.share_string(&self.search_query);
+        copy_to_clipboard(clipboard, &link);
+        link
     }
 
     fn update_search_analytics(&mut self) {
@@ -710,3 +854,115 @@ impl Default for AdvancedSearchUI {
         Self::new()
     }
 }
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::scanner::ScannerResult;
+    use crate::services::ServiceResult;
+
+    struct MockScanner(Option<Product>);
+
+    impl ScanBackend for MockScanner {
+        fn scan_product(&self) -> ScannerResult<Option<Product>> {
+            Ok(self.0.clone())
+        }
+    }
+
+    struct FixedTranscriber(&'static str);
+
+    impl VoiceTranscriber for FixedTranscriber {
+        fn transcribe(&self, _audio: &[u8]) -> ServiceResult<String> {
+            Ok(self.0.to_string())
+        }
+    }
+
+    struct MockRecorder;
+
+    impl AudioRecorder for MockRecorder {
+        fn is_available(&self) -> bool {
+            true
+        }
+
+        fn record(&self) -> ServiceResult<Vec<u8>> {
+            Ok(vec![0u8; 16])
+        }
+    }
+
+    struct NoCamera;
+
+    impl ScanBackend for NoCamera {
+        fn scan_product(&self) -> ScannerResult<Option<Product>> {
+            Err(ScannerError::NoCameraAvailable)
+        }
+    }
+
+    #[test]
+    fn test_camera_search_populates_results() {
+        let product = Product::new(
+            "Oolong Tea 500ml".to_string(),
+            "Beverages".to_string(),
+            "Bottled tea".to_string(),
+            Some("4901777123456".to_string()),
+            Vec::new(),
+            Vec::new(),
+        );
+        let mut search_ui = AdvancedSearchUI::new();
+        search_ui.set_scanner(Box::new(MockScanner(Some(product.clone()))));
+
+        search_ui.start_camera_search();
+
+        assert_eq!(search_ui.search_query, "Oolong Tea 500ml");
+        assert!(search_ui.camera_message.is_none());
+        let results = search_ui.search_results.as_ref().unwrap();
+        assert_eq!(results.total_count, 1);
+        assert_eq!(results.items[0].product.id, product.id);
+        assert!(matches!(
+            results.items[0].match_reasons[0],
+            crate::search::engine::MatchReason::BarcodeMatch
+        ));
+    }
+
+    #[test]
+    fn test_share_search_copies_link() {
+        let mut search_ui = AdvancedSearchUI::new();
+        search_ui.search_query = "milk".to_string();
+        search_ui.current_filters = SearchFilters::with_price_range(None, Some(3.0));
+
+        let clipboard = crate::utils::MemoryClipboard::new();
+        let link = search_ui.share_search(&clipboard);
+
+        assert_eq!(link, "eprice://search?q=milk&max_price=3");
+        assert_eq!(clipboard.last(), Some(link));
+    }
+
+    #[test]
+    fn test_camera_search_without_camera_reports_message() {
+        let mut search_ui = AdvancedSearchUI::new();
+        search_ui.set_scanner(Box::new(NoCamera));
+
+        search_ui.start_camera_search();
+
+        assert!(search_ui.search_results.is_none());
+        assert_eq!(
+            search_ui.camera_message.as_deref(),
+            Some("No camera available for camera search")
+        );
+    }
+
+    #[test]
+    fn test_voice_search_runs_transcribed_query() {
+        let mut search_ui = AdvancedSearchUI::new();
+        assert!(!search_ui.voice_search_enabled);
+        search_ui.set_voice_input(Box::new(MockRecorder), Box::new(FixedTranscriber(" milk ")));
+        assert!(search_ui.voice_search_enabled);
+
+        search_ui.start_voice_search();
+
+        assert_eq!(search_ui.search_query, "milk");
+        assert!(search_ui.voice_message.is_none());
+        assert_eq!(search_ui.search_history, vec!["milk".to_string()]);
+        assert_eq!(search_ui.search_analytics.total_searches, 1);
+        assert!(search_ui.search_results.is_some());
+    }
+}
@@ -0,0 +1,126 @@
+//! Voice input for search: audio recording and speech-to-text.
+//!
+//! The command-line backends are built only with `--features voice`.
+
+#[cfg(all(feature = "voice", not(target_arch = "wasm32")))]
+use crate::services::ServiceError;
+use crate::services::ServiceResult;
+
+/// Captures a short audio clip from the microphone
+pub trait AudioRecorder {
+    /// Whether a microphone is available for recording
+    fn is_available(&self) -> bool;
+
+    /// Record a clip and return the encoded audio
+    fn record(&self) -> ServiceResult<Vec<u8>>;
+}
+
+/// Converts recorded speech into text
+pub trait VoiceTranscriber {
+    fn transcribe(&self, audio: &[u8]) -> ServiceResult<String>;
+}
+
+/// Records WAV audio with ALSA `arecord` (Linux)
+#[cfg(all(feature = "voice", not(target_arch = "wasm32")))]
+#[derive(Debug, Clone)]
+pub struct ArecordRecorder {
+    pub duration_secs: u32,
+}
+
+#[cfg(all(feature = "voice", not(target_arch = "wasm32")))]
+impl Default for ArecordRecorder {
+    fn default() -> Self {
+        Self { duration_secs: 5 }
+    }
+}
+
+#[cfg(all(feature = "voice", not(target_arch = "wasm32")))]
+impl AudioRecorder for ArecordRecorder {
+    fn is_available(&self) -> bool {
+        // `arecord -l` 列出录音设备，没有声卡时输出为空
+        std::process::Command::new("arecord")
+            .arg("-l")
+            .output()
+            .map(|output| {
+                output.status.success() && String::from_utf8_lossy(&output.stdout).contains("card ")
+            })
+            .unwrap_or(false)
+    }
+
+    fn record(&self) -> ServiceResult<Vec<u8>> {
+        let output = std::process::Command::new("arecord")
+            .args(["-q", "-f", "S16_LE", "-r", "16000", "-c", "1", "-t", "wav"])
+            .arg("-d")
+            .arg(self.duration_secs.to_string())
+            .arg("-")
+            .output()
+            .map_err(|e| ServiceError::ExternalServiceError(format!("arecord failed: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(ServiceError::ExternalServiceError(format!(
+                "arecord failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(output.stdout)
+    }
+}
+
+/// Pipes audio to an external speech-to-text command and reads the text from stdout
+#[cfg(all(feature = "voice", not(target_arch = "wasm32")))]
+#[derive(Debug, Clone)]
+pub struct CommandTranscriber {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+#[cfg(all(feature = "voice", not(target_arch = "wasm32")))]
+impl CommandTranscriber {
+    /// Environment variable holding the transcription command line
+    pub const ENV_VAR: &'static str = "EPRICE_STT_COMMAND";
+
+    pub fn new(program: impl Into<String>, args: Vec<String>) -> Self {
+        Self {
+            program: program.into(),
+            args,
+        }
+    }
+
+    /// Transcriber configured by `EPRICE_STT_COMMAND`, if set
+    pub fn from_env() -> Option<Self> {
+        let command = std::env::var(Self::ENV_VAR).ok()?;
+        let mut parts = command.split_whitespace().map(str::to_string);
+        let program = parts.next()?;
+        Some(Self::new(program, parts.collect()))
+    }
+}
+
+#[cfg(all(feature = "voice", not(target_arch = "wasm32")))]
+impl VoiceTranscriber for CommandTranscriber {
+    fn transcribe(&self, audio: &[u8]) -> ServiceResult<String> {
+        use std::io::Write;
+        use std::process::{Command, Stdio};
+
+        let error = |e: &dyn std::fmt::Display| {
+            ServiceError::ExternalServiceError(format!("{} failed: {}", self.program, e))
+        };
+
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| error(&e))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(audio).map_err(|e| error(&e))?;
+        } // 关闭 stdin，通知命令输入结束
+
+        let output = child.wait_with_output().map_err(|e| error(&e))?;
+        if !output.status.success() {
+            return Err(error(&String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
@@ -0,0 +1,223 @@
+//! REST-style read-only query API over the application services.
+//!
+//! Built only with `--features server`; the GUI build does not include it.
+
+use crate::models::{PriceRecord, Product};
+use crate::services::{AppServices, ServiceError};
+use crate::settings::ServerSettings;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+use tokio::net::TcpListener;
+
+/// Services shared between request handlers
+pub type SharedServices = Arc<RwLock<AppServices>>;
+
+/// One price record in an API response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceEntry {
+    pub id: Option<String>,
+    pub store_id: String,
+    pub price: f64,
+    pub effective_price: f64,
+    pub is_on_sale: bool,
+    pub verification_status: String,
+    pub timestamp: String, // RFC 3339
+}
+
+impl From<&PriceRecord> for PriceEntry {
+    fn from(record: &PriceRecord) -> Self {
+        Self {
+            id: record.id.clone(),
+            store_id: record.store_id.clone(),
+            price: record.price,
+            effective_price: record.effective_price(),
+            is_on_sale: record.is_on_sale,
+            verification_status: record.verification_status.clone(),
+            timestamp: record.timestamp.to_rfc3339(),
+        }
+    }
+}
+
+/// Response of `GET /products/{id}/prices`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductPricesResponse {
+    pub product_id: String,
+    pub product_name: String,
+    pub lowest_price: Option<f64>,
+    pub prices: Vec<PriceEntry>,
+}
+
+/// Product entry of `GET /products/search`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductSummary {
+    pub id: String,
+    pub name: String,
+    pub category: String,
+    pub barcode: Option<String>,
+}
+
+impl From<&Product> for ProductSummary {
+    fn from(product: &Product) -> Self {
+        Self {
+            id: product.id.clone(),
+            name: product.name.clone(),
+            category: product.category.clone(),
+            barcode: product.barcode.clone(),
+        }
+    }
+}
+
+/// Store entry of `GET /stores/nearby`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NearbyStore {
+    pub id: String,
+    pub name: String,
+    pub address: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub distance_km: f64,
+}
+
+/// Error body returned for failed requests
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorBody {
+    pub error: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchParams {
+    #[serde(default)]
+    q: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct NearbyParams {
+    lat: f64,
+    lon: f64,
+    #[serde(default = "default_radius_km")]
+    radius: f64,
+}
+
+fn default_radius_km() -> f64 {
+    5.0
+}
+
+/// Handler error mapped onto an HTTP status
+struct ApiError(StatusCode, String);
+
+impl From<ServiceError> for ApiError {
+    fn from(error: ServiceError) -> Self {
+        let status = match error {
+            ServiceError::NotFound(_) => StatusCode::NOT_FOUND,
+            ServiceError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            ServiceError::PermissionDenied(_) => StatusCode::FORBIDDEN,
+            ServiceError::Conflict(_) => StatusCode::CONFLICT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        ApiError(status, error.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(ErrorBody { error: self.1 })).into_response()
+    }
+}
+
+fn read_services(
+    services: &SharedServices,
+) -> Result<std::sync::RwLockReadGuard<'_, AppServices>, ApiError> {
+    services.read().map_err(|e| {
+        ApiError(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to acquire services lock: {}", e),
+        )
+    })
+}
+
+async fn product_prices(
+    State(services): State<SharedServices>,
+    Path(product_id): Path<String>,
+) -> Result<Json<ProductPricesResponse>, ApiError> {
+    let services = read_services(&services)?;
+    let product = services.product_service.get_product(&product_id)?;
+
+    let mut records = services.price_service.get_product_prices(&product_id)?;
+    records.sort_by_key(|r| r.timestamp);
+    let lowest_price = services
+        .price_service
+        .get_current_lowest_price(&product_id)?
+        .map(|r| r.price);
+
+    Ok(Json(ProductPricesResponse {
+        product_id: product.id,
+        product_name: product.name,
+        lowest_price,
+        prices: records.iter().map(PriceEntry::from).collect(),
+    }))
+}
+
+async fn search_products(
+    State(services): State<SharedServices>,
+    Query(params): Query<SearchParams>,
+) -> Result<Json<Vec<ProductSummary>>, ApiError> {
+    let services = read_services(&services)?;
+    let mut products = services.product_service.search_products(&params.q, None)?;
+    products.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(Json(products.iter().map(ProductSummary::from).collect()))
+}
+
+async fn nearby_stores(
+    State(services): State<SharedServices>,
+    Query(params): Query<NearbyParams>,
+) -> Result<Json<Vec<NearbyStore>>, ApiError> {
+    if params.radius <= 0.0 {
+        return Err(ServiceError::ValidationError("Radius must be positive".to_string()).into());
+    }
+    let services = read_services(&services)?;
+    let stores = services
+        .store_service
+        .find_stores_near(params.lat, params.lon, params.radius)?;
+
+    Ok(Json(
+        stores
+            .into_iter()
+            .map(|sd| NearbyStore {
+                id: sd.store.id,
+                name: sd.store.name,
+                address: sd.store.address,
+                latitude: sd.store.latitude,
+                longitude: sd.store.longitude,
+                distance_km: sd.distance_km,
+            })
+            .collect(),
+    ))
+}
+
+/// Build the API router over the shared services
+pub fn router(services: SharedServices) -> Router {
+    Router::new()
+        .route("/products/search", get(search_products))
+        .route("/products/{id}/prices", get(product_prices))
+        .route("/stores/nearby", get(nearby_stores))
+        .with_state(services)
+}
+
+/// Serve the API on an already bound listener until the task is cancelled
+pub async fn serve(listener: TcpListener, services: SharedServices) -> std::io::Result<()> {
+    if let Ok(addr) = listener.local_addr() {
+        log::info!("Query API listening on http://{}", addr);
+    }
+    axum::serve(listener, router(services)).await
+}
+
+/// Bind to the configured address and serve the API
+pub async fn run(settings: &ServerSettings, services: SharedServices) -> std::io::Result<()> {
+    let listener = TcpListener::bind(settings.socket_addr()).await?;
+    serve(listener, services).await
+}
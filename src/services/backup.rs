@@ -0,0 +1,653 @@
+//! Single-file backup of all user data, for moving to another machine.
+
+use crate::models::{PriceAlert, Product, Store, UserReview};
+use crate::services::{
+    AppServices, ImportReport, ReviewSort, ServiceError, ServiceResult, json_import,
+};
+use crate::settings::AppConfig;
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::{Cursor, Read, Write};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Format version written to the manifest; bump when a section changes shape
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_FILE: &str = "manifest.json";
+const PRODUCTS_FILE: &str = "products.json";
+const STORES_FILE: &str = "stores.json";
+const REVIEWS_FILE: &str = "reviews.json";
+const ALERTS_FILE: &str = "alerts.json";
+const SETTINGS_FILE: &str = "settings.json";
+
+/// Data kept outside `AppServices` that is included in a backup: alerts
+/// live in the alert monitor and settings in the settings UI
+#[derive(Debug, Clone, Default)]
+pub struct BackupExtras {
+    pub alerts: Vec<PriceAlert>,
+    pub config: AppConfig,
+}
+
+/// Describes a backup bundle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    pub app_version: String,
+}
+
+impl AppServices {
+    /// Zip of JSON exports of products (with prices), stores, reviews,
+    /// and the alerts and settings in `extras`, plus a manifest
+    pub fn export_backup(&self, extras: &BackupExtras) -> ServiceResult<Vec<u8>> {
+        let manifest = BackupManifest {
+            version: BACKUP_FORMAT_VERSION,
+            created_at: Utc::now(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+        };
+
+        // 按 ID 排序，保证同样的数据生成同样的文件内容
+        let mut products = self.product_service.get_all_products()?;
+        products.sort_by(|a, b| a.id.cmp(&b.id));
+        let mut stores = self.store_service.list_stores(0, usize::MAX)?;
+        stores.sort_by(|a, b| a.id.cmp(&b.id));
+        let mut reviews = self.review_service.get_reviews_sorted(ReviewSort::Newest)?;
+        reviews.sort_by(|a, b| a.id.cmp(&b.id));
+        let mut alerts = extras.alerts.clone();
+        alerts.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        write_section(&mut writer, MANIFEST_FILE, &manifest)?;
+        write_section(&mut writer, PRODUCTS_FILE, &products)?;
+        write_section(&mut writer, STORES_FILE, &stores)?;
+        write_section(&mut writer, REVIEWS_FILE, &reviews)?;
+        write_section(&mut writer, ALERTS_FILE, &alerts)?;
+        write_section(&mut writer, SETTINGS_FILE, &extras.config)?;
+
+        let cursor = writer
+            .finish()
+            .map_err(|e| ServiceError::ValidationError(format!("Cannot write backup: {}", e)))?;
+        log::info!(
+            "Backup exported: {} products, {} stores, {} reviews",
+            products.len(),
+            stores.len(),
+            reviews.len()
+        );
+        Ok(cursor.into_inner())
+    }
+
+    /// Replace all data, including `extras`, with the contents of a bundle from
+    /// [`Self::export_backup`]. Every section is read and validated before
+    /// anything is changed, so a bad bundle leaves the current data untouched
+    pub fn import_backup(
+        &mut self,
+        bytes: &[u8],
+        extras: &mut BackupExtras,
+    ) -> ServiceResult<BackupManifest> {
+        let bundle = BackupBundle::read(bytes)?;
+
+        // 评论校验可能失败，放在最前；其余替换不会失败
+        self.review_service.replace_reviews(bundle.reviews)?;
+        self.product_service.replace_products(bundle.products);
+        self.store_service.replace_stores(bundle.stores);
+        extras.alerts = bundle.alerts;
+        extras.config = bundle.config;
+
+        log::info!("Backup imported (created {})", bundle.manifest.created_at);
+        Ok(bundle.manifest)
+    }
+
+    /// Restore the chosen sections of a backup into the current data and
+    /// `extras`, resolving records that already exist with `options.on_conflict`.
+    /// Like [`Self::import_backup`], nothing changes if the bundle is invalid
+    pub fn restore_backup(
+        &mut self,
+        bytes: &[u8],
+        options: &RestoreOptions,
+        extras: &mut BackupExtras,
+    ) -> ServiceResult<HashMap<BackupSection, ImportReport>> {
+        let bundle = BackupBundle::read(bytes)?;
+        let policy = options.on_conflict;
+        let mut reports = HashMap::new();
+
+        if options.includes(BackupSection::Reviews) {
+            for review in &bundle.reviews {
+                self.review_service.validate_review(review)?;
+            }
+        }
+
+        if options.includes(BackupSection::Products) {
+            let mut report = ImportReport::default();
+            for product in bundle.products {
+                let existing = self.product_service.get_product(&product.id).ok();
+                let id = product.id.clone();
+                match policy.resolve(existing, product, merge_products) {
+                    Some(product) => {
+                        let _ = self.product_service.delete_product(&id);
+                        self.product_service.restore_product(product)?;
+                        report.imported_ids.push(id);
+                    }
+                    None => report.skipped.push(id),
+                }
+            }
+            reports.insert(BackupSection::Products, report);
+        }
+
+        if options.includes(BackupSection::Stores) {
+            let mut report = ImportReport::default();
+            for store in bundle.stores {
+                let existing = self.store_service.get_store(&store.id).ok();
+                let id = store.id.clone();
+                match policy.resolve(existing, store, merge_stores) {
+                    Some(store) => {
+                        let _ = self.store_service.delete_store(&id);
+                        self.store_service.restore_store(store)?;
+                        report.imported_ids.push(id);
+                    }
+                    None => report.skipped.push(id),
+                }
+            }
+            reports.insert(BackupSection::Stores, report);
+        }
+
+        if options.includes(BackupSection::Reviews) {
+            let mut report = ImportReport::default();
+            for review in bundle.reviews {
+                let existing = self.review_service.get_review(&review.id).ok();
+                let id = review.id.clone();
+                match policy.resolve(existing, review, |local, _| local) {
+                    Some(review) => {
+                        self.review_service.create_review(&review)?;
+                        report.imported_ids.push(id);
+                    }
+                    None => report.skipped.push(id),
+                }
+            }
+            reports.insert(BackupSection::Reviews, report);
+        }
+
+        if options.includes(BackupSection::Alerts) {
+            let mut report = ImportReport::default();
+            for alert in bundle.alerts {
+                let position = extras.alerts.iter().position(|a| a.id == alert.id);
+                let existing = position.map(|i| extras.alerts[i].clone());
+                let id = alert.id.clone();
+                match (policy.resolve(existing, alert, |local, _| local), position) {
+                    (Some(alert), Some(i)) => {
+                        extras.alerts[i] = alert;
+                        report.imported_ids.push(id);
+                    }
+                    (Some(alert), None) => {
+                        extras.alerts.push(alert);
+                        report.imported_ids.push(id);
+                    }
+                    (None, _) => report.skipped.push(id),
+                }
+            }
+            reports.insert(BackupSection::Alerts, report);
+        }
+
+        if options.includes(BackupSection::Settings) {
+            // 设置总是存在，仅 Skip 时保留当前设置
+            let mut report = ImportReport::default();
+            if policy == ConflictPolicy::Skip {
+                report.skipped.push(SETTINGS_FILE.to_string());
+            } else {
+                extras.config = bundle.config;
+                report.imported_ids.push(SETTINGS_FILE.to_string());
+            }
+            reports.insert(BackupSection::Settings, report);
+        }
+
+        log::info!(
+            "Backup restored: {} sections (created {})",
+            reports.len(),
+            bundle.manifest.created_at
+        );
+        Ok(reports)
+    }
+}
+
+/// Part of a backup that can be restored on its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum BackupSection {
+    Products,
+    Stores,
+    Reviews,
+    Alerts,
+    Settings,
+}
+
+impl BackupSection {
+    pub const ALL: [BackupSection; 5] = [
+        BackupSection::Products,
+        BackupSection::Stores,
+        BackupSection::Reviews,
+        BackupSection::Alerts,
+        BackupSection::Settings,
+    ];
+}
+
+/// How a restored record is handled when one with the same id already exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictPolicy {
+    /// Keep the current record
+    Skip,
+    /// Replace the current record with the backup copy
+    #[default]
+    Overwrite,
+    /// Combine both: products union their prices and tags (fields of the
+    /// higher version win), stores union their tags; other records keep
+    /// the current copy
+    Merge,
+}
+
+impl ConflictPolicy {
+    /// Record to store for `incoming`, or None to leave the current one
+    fn resolve<T>(self, existing: Option<T>, incoming: T, merge: impl Fn(T, T) -> T) -> Option<T> {
+        match (existing, self) {
+            (None, _) => Some(incoming),
+            (Some(_), ConflictPolicy::Skip) => None,
+            (Some(_), ConflictPolicy::Overwrite) => Some(incoming),
+            (Some(local), ConflictPolicy::Merge) => Some(merge(local, incoming)),
+        }
+    }
+}
+
+/// Which sections [`AppServices::restore_backup`] restores and how
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestoreOptions {
+    pub sections: HashSet<BackupSection>,
+    pub on_conflict: ConflictPolicy,
+}
+
+impl RestoreOptions {
+    /// Restore every section with the given conflict policy
+    pub fn all(on_conflict: ConflictPolicy) -> Self {
+        Self {
+            sections: BackupSection::ALL.into_iter().collect(),
+            on_conflict,
+        }
+    }
+
+    /// Restore only `sections`
+    pub fn only(sections: &[BackupSection], on_conflict: ConflictPolicy) -> Self {
+        Self {
+            sections: sections.iter().copied().collect(),
+            on_conflict,
+        }
+    }
+
+    fn includes(&self, section: BackupSection) -> bool {
+        self.sections.contains(&section)
+    }
+}
+
+impl Default for RestoreOptions {
+    fn default() -> Self {
+        Self::all(ConflictPolicy::default())
+    }
+}
+
+fn merge_products(local: Product, incoming: Product) -> Product {
+    let (mut merged, other) = if incoming.version > local.version {
+        (incoming, local)
+    } else {
+        (local, incoming)
+    };
+    for price in other.prices {
+        if !merged
+            .prices
+            .iter()
+            .any(|p| p.id.is_some() && p.id == price.id)
+        {
+            merged.prices.push(price);
+        }
+    }
+    merged.prices.sort_by_key(|p| p.timestamp);
+    for tag in other.tags {
+        if !merged.tags.contains(&tag) {
+            merged.tags.push(tag);
+        }
+    }
+    merged
+}
+
+fn merge_stores(mut local: Store, incoming: Store) -> Store {
+    for tag in incoming.tags {
+        if !local.tags.contains(&tag) {
+            local.tags.push(tag);
+        }
+    }
+    local
+}
+
+/// Every section of a backup, read and validated
+struct BackupBundle {
+    manifest: BackupManifest,
+    products: Vec<Product>,
+    stores: Vec<Store>,
+    reviews: Vec<UserReview>,
+    alerts: Vec<PriceAlert>,
+    config: AppConfig,
+}
+
+impl BackupBundle {
+    fn read(bytes: &[u8]) -> ServiceResult<Self> {
+        let mut archive = ZipArchive::new(Cursor::new(bytes))
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid backup: {}", e)))?;
+
+        let manifest: BackupManifest = read_section(&mut archive, MANIFEST_FILE)?;
+        if manifest.version == 0 || manifest.version > BACKUP_FORMAT_VERSION {
+            return Err(ServiceError::ValidationError(format!(
+                "Unsupported backup version {} (supported up to {})",
+                manifest.version, BACKUP_FORMAT_VERSION
+            )));
+        }
+
+        Ok(Self {
+            manifest,
+            products: read_products(&mut archive)?,
+            stores: read_section(&mut archive, STORES_FILE)?,
+            reviews: read_section(&mut archive, REVIEWS_FILE)?,
+            alerts: read_section(&mut archive, ALERTS_FILE)?,
+            config: read_section(&mut archive, SETTINGS_FILE)?,
+        })
+    }
+}
+
+fn write_section<T: Serialize + ?Sized>(
+    writer: &mut ZipWriter<Cursor<Vec<u8>>>,
+    name: &str,
+    value: &T,
+) -> ServiceResult<()> {
+    let json = serde_json::to_vec_pretty(value)
+        .map_err(|e| ServiceError::ValidationError(format!("Cannot encode {}: {}", name, e)))?;
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    writer
+        .start_file(name, options)
+        .and_then(|_| writer.write_all(&json).map_err(Into::into))
+        .map_err(|e| ServiceError::ValidationError(format!("Cannot write {}: {}", name, e)))
+}
+
+fn read_section<T: DeserializeOwned>(
+    archive: &mut ZipArchive<Cursor<&[u8]>>,
+    name: &str,
+) -> ServiceResult<T> {
+    let mut file = archive
+        .by_name(name)
+        .map_err(|_| ServiceError::ValidationError(format!("Backup is missing {}", name)))?;
+    let mut json = Vec::new();
+    file.read_to_end(&mut json)
+        .map_err(|e| ServiceError::ValidationError(format!("Cannot read {}: {}", name, e)))?;
+    serde_json::from_slice(&json)
+        .map_err(|e| ServiceError::ValidationError(format!("Invalid {}: {}", name, e)))
+}
+
+/// Products are checked record by record so a damaged backup names the
+/// offending product and field
+fn read_products(archive: &mut ZipArchive<Cursor<&[u8]>>) -> ServiceResult<Vec<Product>> {
+    let json: serde_json::Value = read_section(archive, PRODUCTS_FILE)?;
+    json_import::parse_products_strict(&json)
+        .map_err(|e| ServiceError::ValidationError(format!("Invalid {}: {}", PRODUCTS_FILE, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PriceRecord;
+
+    fn sample_services() -> (AppServices, BackupExtras) {
+        let mut services = AppServices::new();
+
+        let store = services
+            .store_service
+            .create_store(
+                "Corner Mart".to_string(),
+                "1-2-3 Shibuya".to_string(),
+                35.658,
+                139.7016,
+                "09:00-21:00".to_string(),
+                "03-1234-5678".to_string(),
+                vec!["grocery".to_string()],
+                '🏪',
+            )
+            .unwrap();
+
+        let mut product = services
+            .product_service
+            .create_product(
+                "Oolong Tea".to_string(),
+                "Beverages".to_string(),
+                "500ml bottle".to_string(),
+                Some("4901234567894".to_string()),
+                vec!["tea".to_string()],
+            )
+            .unwrap();
+        product.prices.push(PriceRecord::new(
+            Some(product.id.clone()),
+            store.id.clone(),
+            None,
+            1.29,
+            true,
+            None,
+        ));
+        services
+            .product_service
+            .replace_products(vec![product.clone()]);
+
+        services
+            .review_service
+            .submit_review(
+                "user_1".to_string(),
+                Some(store.id.clone()),
+                None,
+                4,
+                "Friendly staff and fresh produce".to_string(),
+            )
+            .unwrap();
+
+        let mut extras = BackupExtras::default();
+        extras
+            .alerts
+            .push(PriceAlert::new("user_1".to_string(), product.id, 0.99));
+        extras.config.ui_settings.theme = "dark".to_string();
+        (services, extras)
+    }
+
+    /// Section contents as JSON, for comparing services without PartialEq
+    fn snapshot(services: &AppServices, extras: &BackupExtras) -> serde_json::Value {
+        let mut products = services.product_service.get_all_products().unwrap();
+        products.sort_by(|a, b| a.id.cmp(&b.id));
+        let mut stores = services.store_service.list_stores(0, usize::MAX).unwrap();
+        stores.sort_by(|a, b| a.id.cmp(&b.id));
+        let reviews = services
+            .review_service
+            .get_reviews_sorted(ReviewSort::Newest)
+            .unwrap();
+        serde_json::json!({
+            "products": products,
+            "stores": stores,
+            "reviews": reviews,
+            "alerts": extras.alerts,
+            "settings": extras.config,
+        })
+    }
+
+    #[test]
+    fn test_backup_round_trip_into_fresh_services() {
+        let (original, original_extras) = sample_services();
+        let bytes = original.export_backup(&original_extras).unwrap();
+
+        let mut restored = AppServices::new();
+        let mut restored_extras = BackupExtras::default();
+        let manifest = restored
+            .import_backup(&bytes, &mut restored_extras)
+            .unwrap();
+        assert_eq!(manifest.version, BACKUP_FORMAT_VERSION);
+        assert_eq!(
+            snapshot(&restored, &restored_extras),
+            snapshot(&original, &original_extras)
+        );
+
+        let products = restored.product_service.get_all_products().unwrap();
+        assert_eq!(products[0].prices.len(), 1);
+        assert_eq!(restored_extras.config.ui_settings.theme, "dark");
+        assert_eq!(restored_extras.alerts.len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_backup_leaves_data_untouched() {
+        let (mut services, mut extras) = sample_services();
+        let before = snapshot(&services, &extras);
+
+        assert!(matches!(
+            services.import_backup(b"not a zip", &mut extras),
+            Err(ServiceError::ValidationError(_))
+        ));
+
+        // 版本过新的备份被拒绝
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let manifest = BackupManifest {
+            version: BACKUP_FORMAT_VERSION + 1,
+            created_at: Utc::now(),
+            app_version: "9.9.9".to_string(),
+        };
+        write_section(&mut writer, MANIFEST_FILE, &manifest).unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+        assert!(matches!(
+            services.import_backup(&bytes, &mut extras),
+            Err(ServiceError::ValidationError(_))
+        ));
+
+        // 缺少分区的备份被拒绝
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        write_section(
+            &mut writer,
+            MANIFEST_FILE,
+            &BackupManifest {
+                version: BACKUP_FORMAT_VERSION,
+                ..manifest
+            },
+        )
+        .unwrap();
+        write_section(&mut writer, PRODUCTS_FILE, &Vec::<Product>::new()).unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+        assert!(services.import_backup(&bytes, &mut extras).is_err());
+
+        assert_eq!(snapshot(&services, &extras), before);
+    }
+
+    #[test]
+    fn test_restore_only_stores_section() {
+        let (original, original_extras) = sample_services();
+        let bytes = original.export_backup(&original_extras).unwrap();
+
+        let mut target = AppServices::new();
+        let mut target_extras = BackupExtras::default();
+        let local = target
+            .product_service
+            .create_product(
+                "Rice Crackers".to_string(),
+                "Snacks".to_string(),
+                "Soy sauce flavour".to_string(),
+                None,
+                vec![],
+            )
+            .unwrap();
+
+        let options = RestoreOptions::only(&[BackupSection::Stores], ConflictPolicy::Skip);
+        let reports = target
+            .restore_backup(&bytes, &options, &mut target_extras)
+            .unwrap();
+
+        let backup_stores = original.store_service.list_stores(0, usize::MAX).unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(
+            reports[&BackupSection::Stores].imported_count(),
+            backup_stores.len()
+        );
+        let corner_mart = backup_stores
+            .iter()
+            .find(|s| s.name == "Corner Mart")
+            .unwrap();
+        assert_eq!(
+            target
+                .store_service
+                .get_store(&corner_mart.id)
+                .unwrap()
+                .address,
+            corner_mart.address
+        );
+        // 未选择的分区保持不变
+        assert_eq!(
+            target.product_service.get_all_products().unwrap(),
+            vec![local]
+        );
+        assert!(target_extras.alerts.is_empty());
+        assert_eq!(
+            target_extras.config.ui_settings.theme,
+            AppConfig::default().ui_settings.theme
+        );
+    }
+
+    #[test]
+    fn test_restore_products_applies_conflict_policy() {
+        let (original, original_extras) = sample_services();
+        // 经 JSON 往返，时间戳精度与备份内一致
+        let backup_product: Product = serde_json::from_value(
+            serde_json::to_value(&original.product_service.get_all_products().unwrap()[0]).unwrap(),
+        )
+        .unwrap();
+        let bytes = original.export_backup(&original_extras).unwrap();
+
+        // 本地已有同 ID 商品，但价格和标签不同、版本更新
+        let mut local_product = backup_product.clone();
+        local_product.name = "Oolong Tea (local)".to_string();
+        local_product.version = 2;
+        local_product.tags = vec!["local".to_string()];
+        local_product.prices = vec![PriceRecord::new(
+            Some(local_product.id.clone()),
+            "store_local".to_string(),
+            None,
+            1.49,
+            false,
+            None,
+        )];
+
+        let restore = |policy| {
+            let mut target = AppServices::new();
+            target
+                .product_service
+                .restore_product(local_product.clone())
+                .unwrap();
+            let options = RestoreOptions::only(&[BackupSection::Products], policy);
+            let reports = target
+                .restore_backup(&bytes, &options, &mut BackupExtras::default())
+                .unwrap();
+            let product = target
+                .product_service
+                .get_product(&backup_product.id)
+                .unwrap();
+            (reports[&BackupSection::Products].clone(), product)
+        };
+
+        let (report, product) = restore(ConflictPolicy::Skip);
+        assert_eq!(report.skipped, vec![backup_product.id.clone()]);
+        assert_eq!(product, local_product);
+
+        let (report, product) = restore(ConflictPolicy::Overwrite);
+        assert_eq!(report.imported_ids, vec![backup_product.id.clone()]);
+        assert_eq!(product, backup_product);
+
+        let (report, product) = restore(ConflictPolicy::Merge);
+        assert_eq!(report.imported_ids, vec![backup_product.id.clone()]);
+        assert_eq!(product.name, "Oolong Tea (local)");
+        assert_eq!(product.version, 2);
+        assert_eq!(product.prices.len(), 2);
+        assert_eq!(product.tags, vec!["local".to_string(), "tea".to_string()]);
+    }
+}
@@ -0,0 +1,158 @@
+use crate::services::{ServiceError, ServiceResult};
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A flagged date (holiday, sale season) shown as context on price charts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotableDate {
+    pub date: NaiveDate,
+    pub label: String,
+    /// ISO country code; None applies to every region
+    #[serde(default)]
+    pub region: Option<String>,
+    /// Repeats every year on the same month/day
+    #[serde(default)]
+    pub yearly: bool,
+}
+
+impl NotableDate {
+    pub fn new(date: NaiveDate, label: &str) -> Self {
+        Self {
+            date,
+            label: label.to_string(),
+            region: None,
+            yearly: false,
+        }
+    }
+
+    pub fn yearly(mut self) -> Self {
+        self.yearly = true;
+        self
+    }
+
+    pub fn in_region(mut self, region: &str) -> Self {
+        self.region = Some(region.to_uppercase());
+        self
+    }
+
+    /// Days between `date` and the nearest occurrence of this entry
+    fn days_from(&self, date: NaiveDate) -> i64 {
+        if !self.yearly {
+            return (date - self.date).num_days().abs();
+        }
+        // 检查前后相邻年份，处理年末/年初跨年的情况
+        (date.year() - 1..=date.year() + 1)
+            .filter_map(|year| NaiveDate::from_ymd_opt(year, self.date.month(), self.date.day()))
+            .map(|occurrence| (date - occurrence).num_days().abs())
+            .min()
+            .unwrap_or(i64::MAX)
+    }
+}
+
+/// Region-aware list of notable dates
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calendar {
+    region: String,
+    /// Points within this many days of a flagged date count as "near"
+    window_days: i64,
+    dates: Vec<NotableDate>,
+}
+
+impl Calendar {
+    /// Empty calendar for `region` (ISO country code)
+    pub fn new(region: &str) -> Self {
+        Self {
+            region: region.trim().to_uppercase(),
+            window_days: 1,
+            dates: Vec::new(),
+        }
+    }
+
+    /// Calendar with common holidays for `region` (JP, CN; other regions get global dates only)
+    pub fn builtin(region: &str) -> Self {
+        let ymd = |m, d| NaiveDate::from_ymd_opt(2000, m, d).expect("valid builtin date");
+        let mut calendar = Self::new(region);
+        calendar.extend(vec![
+            NotableDate::new(ymd(1, 1), "New Year").yearly(),
+            NotableDate::new(ymd(12, 25), "Christmas").yearly(),
+            NotableDate::new(ymd(12, 31), "大晦日")
+                .yearly()
+                .in_region("JP"),
+            NotableDate::new(ymd(5, 3), "ゴールデンウィーク")
+                .yearly()
+                .in_region("JP"),
+            NotableDate::new(ymd(5, 4), "ゴールデンウィーク")
+                .yearly()
+                .in_region("JP"),
+            NotableDate::new(ymd(5, 5), "ゴールデンウィーク")
+                .yearly()
+                .in_region("JP"),
+            NotableDate::new(ymd(11, 11), "双十一")
+                .yearly()
+                .in_region("CN"),
+            NotableDate::new(ymd(6, 18), "618").yearly().in_region("CN"),
+            NotableDate::new(ymd(10, 1), "国庆节")
+                .yearly()
+                .in_region("CN"),
+        ]);
+        calendar
+    }
+
+    /// Parse a JSON list of `{date, label, region?, yearly?}` entries
+    pub fn from_json(region: &str, json: &str) -> ServiceResult<Self> {
+        let dates: Vec<NotableDate> = serde_json::from_str(json)
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid calendar data: {}", e)))?;
+        let mut calendar = Self::new(region);
+        calendar.extend(dates);
+        Ok(calendar)
+    }
+
+    /// Load a JSON calendar file (see [`Self::from_json`])
+    pub fn load(region: &str, path: &Path) -> ServiceResult<Self> {
+        let json = std::fs::read_to_string(path).map_err(|e| {
+            ServiceError::ValidationError(format!(
+                "Failed to read calendar {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Self::from_json(region, &json)
+    }
+
+    pub fn with_window_days(mut self, days: i64) -> Self {
+        self.window_days = days.max(0);
+        self
+    }
+
+    pub fn region(&self) -> &str {
+        &self.region
+    }
+
+    pub fn dates(&self) -> &[NotableDate] {
+        &self.dates
+    }
+
+    pub fn add(&mut self, date: NotableDate) {
+        self.dates.push(date);
+    }
+
+    pub fn extend(&mut self, dates: impl IntoIterator<Item = NotableDate>) {
+        self.dates.extend(dates);
+    }
+
+    /// Closest flagged date for this region within the window of `date`
+    pub fn annotation_for(&self, date: NaiveDate) -> Option<&NotableDate> {
+        self.dates
+            .iter()
+            .filter(|d| {
+                d.region
+                    .as_deref()
+                    .is_none_or(|r| r.eq_ignore_ascii_case(&self.region))
+            })
+            .map(|d| (d.days_from(date), d))
+            .filter(|(days, _)| *days <= self.window_days)
+            .min_by_key(|(days, _)| *days)
+            .map(|(_, d)| d)
+    }
+}
@@ -0,0 +1,280 @@
+//! Cross-service integrity checks: references between price records,
+//! products and stores that the individual services cannot see.
+
+use crate::services::{AppServices, PriceRecordFilter, ServiceResult};
+use crate::utils::validation::validate_coordinates;
+use std::collections::{BTreeMap, HashSet};
+
+/// Price record pointing at a product or store that does not exist
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrphanedPriceRecord {
+    pub record_id: String,
+    /// Set when the referenced product is missing
+    pub missing_product_id: Option<String>,
+    /// Set when the referenced store is missing
+    pub missing_store_id: Option<String>,
+}
+
+/// Barcode shared by more than one product
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateBarcode {
+    pub barcode: String,
+    /// Sorted product ids
+    pub product_ids: Vec<String>,
+}
+
+/// Store whose coordinates are out of range
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidStoreLocation {
+    pub store_id: String,
+    pub reason: String,
+}
+
+/// Result of `AppServices::check_consistency`, each list sorted by id
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConsistencyReport {
+    pub orphaned_prices: Vec<OrphanedPriceRecord>,
+    pub duplicate_barcodes: Vec<DuplicateBarcode>,
+    pub invalid_stores: Vec<InvalidStoreLocation>,
+}
+
+impl ConsistencyReport {
+    pub fn is_clean(&self) -> bool {
+        self.orphaned_prices.is_empty()
+            && self.duplicate_barcodes.is_empty()
+            && self.invalid_stores.is_empty()
+    }
+}
+
+/// What `repair_orphans` does with orphaned price records
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RepairPolicy {
+    /// Clear a missing product reference and keep the record. Records whose
+    /// store is missing are removed, since every record needs a store
+    #[default]
+    NullReferences,
+    /// Remove every orphaned record
+    RemoveRecords,
+}
+
+/// Changes made by `repair_orphans`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RepairSummary {
+    pub detached: usize,
+    pub removed: usize,
+}
+
+impl AppServices {
+    /// Look for dangling price references, duplicate barcodes and stores
+    /// with invalid coordinates
+    pub fn check_consistency(&self) -> ServiceResult<ConsistencyReport> {
+        let products = self.product_service.get_all_products()?;
+        let stores = self.store_service.list_stores(0, usize::MAX)?;
+        let product_ids: HashSet<&str> = products.iter().map(|p| p.id.as_str()).collect();
+        let store_ids: HashSet<&str> = stores.iter().map(|s| s.id.as_str()).collect();
+
+        let (records, _) =
+            self.price_service
+                .list_price_records(&PriceRecordFilter::default(), 0, usize::MAX);
+        let mut orphaned_prices: Vec<OrphanedPriceRecord> = records
+            .into_iter()
+            .filter_map(|record| {
+                let missing_product_id = record
+                    .product_id
+                    .filter(|id| !product_ids.contains(id.as_str()));
+                let missing_store_id =
+                    (!store_ids.contains(record.store_id.as_str())).then_some(record.store_id);
+                (missing_product_id.is_some() || missing_store_id.is_some()).then(|| {
+                    OrphanedPriceRecord {
+                        record_id: record.id.unwrap_or_default(),
+                        missing_product_id,
+                        missing_store_id,
+                    }
+                })
+            })
+            .collect();
+        orphaned_prices.sort_by(|a, b| a.record_id.cmp(&b.record_id));
+
+        let mut by_barcode: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        for product in &products {
+            if let Some(barcode) = product.barcode.as_deref() {
+                by_barcode
+                    .entry(barcode)
+                    .or_default()
+                    .push(product.id.clone());
+            }
+        }
+        let duplicate_barcodes = by_barcode
+            .into_iter()
+            .filter(|(_, ids)| ids.len() > 1)
+            .map(|(barcode, mut product_ids)| {
+                product_ids.sort();
+                DuplicateBarcode {
+                    barcode: barcode.to_string(),
+                    product_ids,
+                }
+            })
+            .collect();
+
+        let mut invalid_stores: Vec<InvalidStoreLocation> = stores
+            .iter()
+            .filter_map(|store| {
+                validate_coordinates(store.latitude, store.longitude)
+                    .err()
+                    .map(|reason| InvalidStoreLocation {
+                        store_id: store.id.clone(),
+                        reason,
+                    })
+            })
+            .collect();
+        invalid_stores.sort_by(|a, b| a.store_id.cmp(&b.store_id));
+
+        Ok(ConsistencyReport {
+            orphaned_prices,
+            duplicate_barcodes,
+            invalid_stores,
+        })
+    }
+
+    /// Fix the orphaned price records found by `check_consistency`
+    pub fn repair_orphans(&mut self, policy: RepairPolicy) -> ServiceResult<RepairSummary> {
+        let report = self.check_consistency()?;
+        let mut summary = RepairSummary::default();
+        for orphan in report.orphaned_prices {
+            let remove = policy == RepairPolicy::RemoveRecords || orphan.missing_store_id.is_some();
+            if remove {
+                self.price_service.delete_price_record(&orphan.record_id)?;
+                summary.removed += 1;
+            } else {
+                self.price_service
+                    .clear_product_reference(&orphan.record_id)?;
+                summary.detached += 1;
+            }
+        }
+        log::info!(
+            "Consistency repair: {} records detached, {} removed",
+            summary.detached,
+            summary.removed
+        );
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Product;
+
+    fn seeded_services() -> (AppServices, Product, String) {
+        let mut services = AppServices::new();
+        let store = services
+            .store_service
+            .create_store(
+                "Corner Mart".to_string(),
+                "1-2-3 Shibuya".to_string(),
+                35.658,
+                139.7016,
+                "09:00-21:00".to_string(),
+                "03-1234-5678".to_string(),
+                vec![],
+                '🏪',
+            )
+            .unwrap();
+        let product = services
+            .product_service
+            .create_product(
+                "Oolong Tea".to_string(),
+                "Beverages".to_string(),
+                "500ml bottle".to_string(),
+                Some("4901234567894".to_string()),
+                vec![],
+            )
+            .unwrap();
+        (services, product, store.id)
+    }
+
+    #[test]
+    fn test_report_lists_orphans_and_duplicate_barcodes() {
+        let (mut services, product, store_id) = seeded_services();
+        let orphan = services
+            .price_service
+            .submit_price(
+                "gone".to_string(),
+                store_id.clone(),
+                None,
+                120.0,
+                false,
+                None,
+            )
+            .unwrap();
+        services
+            .price_service
+            .submit_price(product.id.clone(), store_id, None, 130.0, false, None)
+            .unwrap();
+        // 恢复的旧数据可能绕过条码唯一性校验
+        let mut twin = product.clone();
+        twin.id = "twin".to_string();
+        services.product_service.restore_product(twin).unwrap();
+
+        let report = services.check_consistency().unwrap();
+        assert_eq!(
+            report.orphaned_prices,
+            vec![OrphanedPriceRecord {
+                record_id: orphan.id.clone().unwrap(),
+                missing_product_id: Some("gone".to_string()),
+                missing_store_id: None,
+            }]
+        );
+        assert_eq!(report.duplicate_barcodes.len(), 1);
+        assert_eq!(report.duplicate_barcodes[0].barcode, "4901234567894");
+        assert!(
+            report.duplicate_barcodes[0]
+                .product_ids
+                .contains(&"twin".to_string())
+        );
+        assert!(!report.is_clean());
+    }
+
+    #[test]
+    fn test_repair_follows_policy() {
+        let (mut services, _, store_id) = seeded_services();
+        let detached = services
+            .price_service
+            .submit_price("gone".to_string(), store_id, None, 120.0, false, None)
+            .unwrap();
+        let homeless = services
+            .price_service
+            .submit_price(
+                "gone".to_string(),
+                "closed".to_string(),
+                None,
+                99.0,
+                false,
+                None,
+            )
+            .unwrap();
+
+        let summary = services
+            .repair_orphans(RepairPolicy::NullReferences)
+            .unwrap();
+        assert_eq!(
+            summary,
+            RepairSummary {
+                detached: 1,
+                removed: 1
+            }
+        );
+        let kept = services
+            .price_service
+            .get_price_record(detached.id.as_deref().unwrap())
+            .unwrap();
+        assert_eq!(kept.product_id, None);
+        assert!(
+            services
+                .price_service
+                .get_price_record(homeless.id.as_deref().unwrap())
+                .is_err()
+        );
+        assert!(services.check_consistency().unwrap().is_clean());
+    }
+}
@@ -0,0 +1,164 @@
+use crate::utils::calculate_distance;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Distance in km between (lat1, lon1) and (lat2, lon2)
+pub type DistanceFn = Arc<dyn Fn(f64, f64, f64, f64) -> f64 + Send + Sync>;
+
+/// Coordinate rounded to a fixed grid, usable as a map key
+type GridPoint = (i64, i64);
+
+/// Memoizes point-to-point distances keyed by coordinates rounded to about 10 m
+pub struct DistanceCache {
+    distance_fn: DistanceFn,
+    entries: Mutex<HashMap<(GridPoint, GridPoint), f64>>,
+}
+
+impl DistanceCache {
+    /// 坐标取整精度：1e-4 度约 11 米
+    const SCALE: f64 = 10_000.0;
+    /// 超过该条目数时清空缓存，防止无限增长
+    const MAX_ENTRIES: usize = 4096;
+
+    /// Cache over the Haversine [`calculate_distance`]
+    pub fn new() -> Self {
+        Self::with_distance_fn(Arc::new(calculate_distance))
+    }
+
+    /// Cache over a custom distance function
+    pub fn with_distance_fn(distance_fn: DistanceFn) -> Self {
+        Self {
+            distance_fn,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn grid_point(latitude: f64, longitude: f64) -> GridPoint {
+        (
+            (latitude * Self::SCALE).round() as i64,
+            (longitude * Self::SCALE).round() as i64,
+        )
+    }
+
+    /// Distance in km between two points, computed at most once per rounded pair
+    pub fn distance(&self, from: (f64, f64), to: (f64, f64)) -> f64 {
+        let a = Self::grid_point(from.0, from.1);
+        let b = Self::grid_point(to.0, to.1);
+        // 距离对称，A→B 与 B→A 共用同一条目
+        let key = if a <= b { (a, b) } else { (b, a) };
+
+        if let Some(distance) = self.entries.lock().ok().and_then(|e| e.get(&key).copied()) {
+            return distance;
+        }
+
+        let distance = (self.distance_fn)(from.0, from.1, to.0, to.1);
+        if let Ok(mut entries) = self.entries.lock() {
+            if entries.len() >= Self::MAX_ENTRIES {
+                entries.clear();
+            }
+            entries.insert(key, distance);
+        }
+        distance
+    }
+
+    /// `matrix[i][j]` is the distance from `origins[i]` to `destinations[j]`
+    pub fn distance_matrix(
+        &self,
+        origins: &[(f64, f64)],
+        destinations: &[(f64, f64)],
+    ) -> Vec<Vec<f64>> {
+        origins
+            .iter()
+            .map(|&origin| {
+                destinations
+                    .iter()
+                    .map(|&destination| self.distance(origin, destination))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Number of cached pairs
+    pub fn len(&self) -> usize {
+        self.entries.lock().map(|e| e.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+}
+
+impl Default for DistanceCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn counting_cache() -> (DistanceCache, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let cache = DistanceCache::with_distance_fn(Arc::new(move |lat1, lon1, lat2, lon2| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            calculate_distance(lat1, lon1, lat2, lon2)
+        }));
+        (cache, calls)
+    }
+
+    #[test]
+    fn test_repeated_queries_hit_cache() {
+        let (cache, calls) = counting_cache();
+        let tokyo = (35.6812, 139.7671);
+        let shinjuku = (35.6896, 139.7006);
+
+        let first = cache.distance(tokyo, shinjuku);
+        assert_eq!(cache.distance(tokyo, shinjuku), first);
+        // 反向查询与微小坐标差异同样命中缓存
+        assert_eq!(cache.distance(shinjuku, tokyo), first);
+        assert_eq!(cache.distance((35.68121, 139.76711), shinjuku), first);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(cache.len(), 1);
+
+        cache.clear();
+        cache.distance(tokyo, shinjuku);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_distance_matrix_dimensions_and_values() {
+        let (cache, calls) = counting_cache();
+        let origins = [(35.6812, 139.7671), (34.7025, 135.4959)];
+        let destinations = [
+            (35.6812, 139.7671),
+            (35.6896, 139.7006),
+            (35.1709, 136.8815),
+        ];
+
+        let matrix = cache.distance_matrix(&origins, &destinations);
+        assert_eq!(matrix.len(), 2);
+        assert!(matrix.iter().all(|row| row.len() == 3));
+        for (i, origin) in origins.iter().enumerate() {
+            for (j, destination) in destinations.iter().enumerate() {
+                let expected = calculate_distance(origin.0, origin.1, destination.0, destination.1);
+                assert!((matrix[i][j] - expected).abs() < 1e-9);
+            }
+        }
+        assert_eq!(matrix[0][0], 0.0);
+        assert_eq!(calls.load(Ordering::SeqCst), 6);
+
+        // 再次计算全部命中缓存
+        assert_eq!(cache.distance_matrix(&origins, &destinations), matrix);
+        assert_eq!(calls.load(Ordering::SeqCst), 6);
+        assert!(cache.distance_matrix(&[], &destinations).is_empty());
+    }
+}
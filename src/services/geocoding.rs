@@ -0,0 +1,315 @@
+use crate::services::ServiceResult;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::services::{HttpClient, ServiceError};
+use crate::utils::calculate_distance;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::utils::percent_encode;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{Duration, Instant};
+
+/// Resolves a postal address to coordinates
+pub trait Geocoder: Send + Sync {
+    /// (latitude, longitude) of `address`
+    fn geocode(&self, address: &str) -> ServiceResult<(f64, f64)>;
+
+    /// Human-readable place name (neighborhood, city) at a coordinate
+    fn reverse(&self, latitude: f64, longitude: f64) -> ServiceResult<String>;
+}
+
+/// Wraps a geocoder and caches results; reverse lookups within `radius_km` of a
+/// cached coordinate reuse its label
+pub struct CachingGeocoder {
+    inner: Arc<dyn Geocoder>,
+    radius_km: f64,
+    forward: Mutex<HashMap<String, (f64, f64)>>,
+    reverse: Mutex<Vec<(f64, f64, String)>>,
+}
+
+impl CachingGeocoder {
+    /// 反向地理编码缓存的最大条目数
+    const MAX_REVERSE_ENTRIES: usize = 64;
+
+    pub fn new(inner: Arc<dyn Geocoder>) -> Self {
+        Self {
+            inner,
+            radius_km: 0.5,
+            forward: Mutex::new(HashMap::new()),
+            reverse: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Distance within which a cached reverse lookup is reused
+    pub fn with_radius_km(mut self, radius_km: f64) -> Self {
+        self.radius_km = radius_km;
+        self
+    }
+}
+
+impl Geocoder for CachingGeocoder {
+    fn geocode(&self, address: &str) -> ServiceResult<(f64, f64)> {
+        let key = address.trim().to_lowercase();
+        if let Some(coordinates) = self.forward.lock().ok().and_then(|c| c.get(&key).copied()) {
+            return Ok(coordinates);
+        }
+
+        let coordinates = self.inner.geocode(address)?;
+        if let Ok(mut cache) = self.forward.lock() {
+            cache.insert(key, coordinates);
+        }
+        Ok(coordinates)
+    }
+
+    fn reverse(&self, latitude: f64, longitude: f64) -> ServiceResult<String> {
+        let cached = self.reverse.lock().ok().and_then(|cache| {
+            cache
+                .iter()
+                .find(|(lat, lon, _)| {
+                    calculate_distance(*lat, *lon, latitude, longitude) <= self.radius_km
+                })
+                .map(|(_, _, label)| label.clone())
+        });
+        if let Some(label) = cached {
+            return Ok(label);
+        }
+
+        // 查询期间不持有锁，避免阻塞其他调用
+        let label = self.inner.reverse(latitude, longitude)?;
+        if let Ok(mut cache) = self.reverse.lock() {
+            if cache.len() >= Self::MAX_REVERSE_ENTRIES {
+                cache.remove(0);
+            }
+            cache.push((latitude, longitude, label.clone()));
+        }
+        Ok(label)
+    }
+}
+
+/// Geocoder backed by the OpenStreetMap Nominatim search API (native only).
+/// 请求之间至少间隔 `min_interval`，遵守 Nominatim 每秒一次的使用限制
+#[cfg(not(target_arch = "wasm32"))]
+pub struct NominatimGeocoder {
+    client: Arc<dyn HttpClient>,
+    base_url: String,
+    min_interval: Duration,
+    last_request: Mutex<Option<Instant>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl NominatimGeocoder {
+    pub const DEFAULT_BASE_URL: &'static str = "https://nominatim.openstreetmap.org";
+
+    pub fn new(client: Arc<dyn HttpClient>) -> Self {
+        Self {
+            client,
+            base_url: Self::DEFAULT_BASE_URL.to_string(),
+            min_interval: Duration::from_secs(1),
+            last_request: Mutex::new(None),
+        }
+    }
+
+    /// Use a self-hosted Nominatim instance
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Minimum delay between requests
+    pub fn with_min_interval(mut self, interval: Duration) -> Self {
+        self.min_interval = interval;
+        self
+    }
+
+    /// Search URL for `address`
+    pub fn search_url(&self, address: &str) -> String {
+        format!(
+            "{}/search?format=jsonv2&limit=1&q={}",
+            self.base_url,
+            percent_encode(address.trim())
+        )
+    }
+
+    /// Reverse-lookup URL for a coordinate (zoom 14 ≈ neighborhood level)
+    pub fn reverse_url(&self, latitude: f64, longitude: f64) -> String {
+        format!(
+            "{}/reverse?format=jsonv2&zoom=14&lat={}&lon={}",
+            self.base_url, latitude, longitude
+        )
+    }
+
+    /// 距上次请求不足 `min_interval` 时等待
+    fn throttle(&self) -> ServiceResult<()> {
+        let mut last_request = self.last_request.lock().map_err(|e| {
+            ServiceError::ExternalServiceError(format!("Failed to acquire geocoder lock: {}", e))
+        })?;
+        if let Some(last) = *last_request {
+            let elapsed = last.elapsed();
+            if elapsed < self.min_interval {
+                std::thread::sleep(self.min_interval - elapsed);
+            }
+        }
+        *last_request = Some(Instant::now());
+        Ok(())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Geocoder for NominatimGeocoder {
+    fn geocode(&self, address: &str) -> ServiceResult<(f64, f64)> {
+        if address.trim().is_empty() {
+            return Err(ServiceError::ValidationError(
+                "Address cannot be empty".to_string(),
+            ));
+        }
+
+        self.throttle()?;
+        let body = self.client.get(&self.search_url(address))?;
+        parse_nominatim_response(&body)?.ok_or_else(|| {
+            ServiceError::NotFound(format!("No geocoding result for address: {}", address))
+        })
+    }
+
+    fn reverse(&self, latitude: f64, longitude: f64) -> ServiceResult<String> {
+        self.throttle()?;
+        let body = self.client.get(&self.reverse_url(latitude, longitude))?;
+        parse_nominatim_reverse(&body)?.ok_or_else(|| {
+            ServiceError::NotFound(format!("No place found at ({}, {})", latitude, longitude))
+        })
+    }
+}
+
+/// First (lat, lon) of a Nominatim `jsonv2` search response; coordinates are strings
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_nominatim_response(body: &str) -> ServiceResult<Option<(f64, f64)>> {
+    let results: Vec<serde_json::Value> = serde_json::from_str(body).map_err(|e| {
+        ServiceError::ExternalServiceError(format!("Invalid geocoding response: {}", e))
+    })?;
+
+    let coordinate = |value: &serde_json::Value, key: &str| -> Option<f64> {
+        match value.get(key)? {
+            serde_json::Value::String(s) => s.parse().ok(),
+            other => other.as_f64(),
+        }
+    };
+
+    Ok(results
+        .first()
+        .and_then(|r| Some((coordinate(r, "lat")?, coordinate(r, "lon")?))))
+}
+
+/// Place label from a Nominatim reverse response: "neighborhood, city" when available,
+/// otherwise the full display name
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_nominatim_reverse(body: &str) -> ServiceResult<Option<String>> {
+    let json: serde_json::Value = serde_json::from_str(body).map_err(|e| {
+        ServiceError::ExternalServiceError(format!("Invalid geocoding response: {}", e))
+    })?;
+    if json.get("error").is_some() {
+        return Ok(None);
+    }
+
+    let address = json.get("address");
+    let first_of = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| address?.get(*key)?.as_str())
+            .map(str::to_string)
+    };
+    let area = first_of(&["neighbourhood", "quarter", "suburb", "city_district"]);
+    let city = first_of(&["city", "town", "village", "county", "state"]);
+
+    let label = match (area, city) {
+        (Some(area), Some(city)) if area != city => Some(format!("{}, {}", area, city)),
+        (Some(place), _) | (None, Some(place)) => Some(place),
+        (None, None) => json
+            .get("display_name")
+            .and_then(|v| v.as_str())
+            .map(str::to_string),
+    };
+    Ok(label)
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::services::MockHttpClient;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Delegates to Nominatim over a mock client and counts reverse lookups
+    struct CountingGeocoder {
+        inner: NominatimGeocoder,
+        reverse_calls: AtomicUsize,
+    }
+
+    impl Geocoder for CountingGeocoder {
+        fn geocode(&self, address: &str) -> ServiceResult<(f64, f64)> {
+            self.inner.geocode(address)
+        }
+
+        fn reverse(&self, latitude: f64, longitude: f64) -> ServiceResult<String> {
+            self.reverse_calls.fetch_add(1, Ordering::SeqCst);
+            self.inner.reverse(latitude, longitude)
+        }
+    }
+
+    #[test]
+    fn test_reverse_geocode_label_and_cache() {
+        let client = MockHttpClient::new();
+        let nominatim =
+            NominatimGeocoder::new(Arc::new(client.clone())).with_min_interval(Duration::ZERO);
+        client.set_response(
+            &nominatim.reverse_url(35.6812, 139.7671),
+            r#"{
+                "display_name": "Tokyo Station, Marunouchi, Chiyoda, Tokyo, Japan",
+                "address": {"quarter": "Marunouchi", "city": "Chiyoda", "state": "Tokyo"}
+            }"#,
+        );
+        let counting = Arc::new(CountingGeocoder {
+            inner: nominatim,
+            reverse_calls: AtomicUsize::new(0),
+        });
+        let geocoder = CachingGeocoder::new(counting.clone());
+
+        assert_eq!(
+            geocoder.reverse(35.6812, 139.7671).unwrap(),
+            "Marunouchi, Chiyoda"
+        );
+        // 约 100 米外的坐标直接命中缓存
+        assert_eq!(
+            geocoder.reverse(35.6820, 139.7675).unwrap(),
+            "Marunouchi, Chiyoda"
+        );
+        assert_eq!(counting.reverse_calls.load(Ordering::SeqCst), 1);
+
+        // 远处坐标需要重新查询（mock 无响应时报错）
+        assert!(geocoder.reverse(34.6937, 135.5023).is_err());
+        assert_eq!(counting.reverse_calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_nominatim_parses_first_result() {
+        let client = MockHttpClient::new();
+        let geocoder = NominatimGeocoder::new(Arc::new(client.clone()))
+            .with_base_url("https://geo.example.com/")
+            .with_min_interval(Duration::ZERO);
+        let url = geocoder.search_url("東京都千代田区丸の内1丁目");
+        assert!(
+            url.starts_with("https://geo.example.com/search?format=jsonv2&limit=1&q=%E6%9D%B1")
+        );
+
+        client.set_response(
+            &url,
+            r#"[{"lat": "35.6812", "lon": "139.7671", "display_name": "Tokyo Station"}]"#,
+        );
+        assert_eq!(
+            geocoder.geocode("東京都千代田区丸の内1丁目").unwrap(),
+            (35.6812, 139.7671)
+        );
+
+        client.set_response(&geocoder.search_url("nowhere"), "[]");
+        assert!(matches!(
+            geocoder.geocode("nowhere"),
+            Err(ServiceError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,81 @@
+use crate::services::{ServiceError, ServiceResult};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Minimal HTTP client used by importers, injectable for tests
+pub trait HttpClient: Send + Sync {
+    /// GET `url` and return the response body
+    fn get(&self, url: &str) -> ServiceResult<String>;
+}
+
+/// Blocking `reqwest` client (native only).
+/// 每次请求临时创建客户端，避免在异步上下文中创建/销毁阻塞客户端导致 panic
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReqwestClient;
+
+#[cfg(not(target_arch = "wasm32"))]
+impl HttpClient for ReqwestClient {
+    fn get(&self, url: &str) -> ServiceResult<String> {
+        // 部分公共服务（如 Nominatim）要求携带 User-Agent
+        let response = reqwest::blocking::Client::builder()
+            .user_agent(concat!("eprice/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .and_then(|client| client.get(url).send())
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| {
+                ServiceError::ExternalServiceError(format!("GET {} failed: {}", url, e))
+            })?;
+
+        response.text().map_err(|e| {
+            ServiceError::ExternalServiceError(format!(
+                "Failed to read response from {}: {}",
+                url, e
+            ))
+        })
+    }
+}
+
+/// Default client for the current platform
+pub fn default_client() -> Option<Arc<dyn HttpClient>> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        Some(Arc::new(ReqwestClient))
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        None
+    }
+}
+
+/// In-memory client serving canned responses by URL
+#[derive(Clone, Default)]
+pub struct MockHttpClient {
+    responses: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl MockHttpClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serve `body` for GET requests to `url`
+    pub fn set_response(&self, url: &str, body: &str) {
+        if let Ok(mut responses) = self.responses.lock() {
+            responses.insert(url.to_string(), body.to_string());
+        }
+    }
+}
+
+impl HttpClient for MockHttpClient {
+    fn get(&self, url: &str) -> ServiceResult<String> {
+        let responses = self.responses.lock().map_err(|e| {
+            ServiceError::ExternalServiceError(format!("Failed to acquire response lock: {}", e))
+        })?;
+
+        responses
+            .get(url)
+            .cloned()
+            .ok_or_else(|| ServiceError::ExternalServiceError(format!("GET {} failed: 404", url)))
+    }
+}
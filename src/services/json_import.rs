@@ -0,0 +1,221 @@
+//! Field-level checks for imported product and price JSON, so a bad record is
+//! reported by index and field instead of being defaulted or failing the
+//! whole file with a byte offset.
+
+use crate::models::{PriceRecord, Product};
+use crate::services::{ServiceError, ServiceResult};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// Expected JSON type of a field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    /// String that is not blank
+    Text,
+    /// String, possibly empty
+    String,
+    Number,
+    Bool,
+    Array,
+    /// Unix timestamp in seconds
+    Timestamp,
+}
+
+impl FieldKind {
+    fn check(&self, value: &Value) -> Result<(), &'static str> {
+        match self {
+            FieldKind::Text => match value.as_str() {
+                Some(text) if !text.trim().is_empty() => Ok(()),
+                Some(_) => Err("must not be empty"),
+                None => Err("must be a string"),
+            },
+            FieldKind::String => value.is_string().then_some(()).ok_or("must be a string"),
+            FieldKind::Number => value.is_number().then_some(()).ok_or("must be a number"),
+            FieldKind::Bool => value
+                .is_boolean()
+                .then_some(())
+                .ok_or("must be true or false"),
+            FieldKind::Array => value.is_array().then_some(()).ok_or("must be an array"),
+            FieldKind::Timestamp => value
+                .is_i64()
+                .then_some(())
+                .ok_or("must be a Unix timestamp in seconds"),
+        }
+    }
+}
+
+/// Required fields of one kind of record
+#[derive(Debug, Clone, Copy)]
+pub struct RecordSchema {
+    /// Record name used in messages, e.g. "product"
+    pub name: &'static str,
+    pub required: &'static [(&'static str, FieldKind)],
+}
+
+pub const PRODUCT_SCHEMA: RecordSchema = RecordSchema {
+    name: "product",
+    required: &[
+        ("id", FieldKind::Text),
+        ("name", FieldKind::Text),
+        ("category", FieldKind::Text),
+        ("description", FieldKind::String),
+        ("images", FieldKind::Array),
+        ("prices", FieldKind::Array),
+        ("tags", FieldKind::Array),
+        ("created_at", FieldKind::Timestamp),
+    ],
+};
+
+pub const PRICE_SCHEMA: RecordSchema = RecordSchema {
+    name: "price",
+    required: &[
+        ("store_id", FieldKind::Text),
+        ("price", FieldKind::Number),
+        ("timestamp", FieldKind::Timestamp),
+        ("is_on_sale", FieldKind::Bool),
+        ("verification_status", FieldKind::Text),
+    ],
+};
+
+impl RecordSchema {
+    /// First problem with `record`, e.g. "missing required field 'name'"
+    pub fn check(&self, record: &Value) -> Result<(), String> {
+        let object = record
+            .as_object()
+            .ok_or_else(|| format!("{} must be a JSON object", self.name))?;
+        for (field, kind) in self.required {
+            match object.get(*field) {
+                None | Some(Value::Null) => {
+                    return Err(format!("missing required field '{}'", field));
+                }
+                Some(value) => kind
+                    .check(value)
+                    .map_err(|problem| format!("field '{}' {}", field, problem))?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Check then deserialize one record
+    pub fn parse<T: DeserializeOwned>(&self, record: &Value) -> Result<T, String> {
+        self.check(record)?;
+        serde_json::from_value(record.clone()).map_err(|e| e.to_string())
+    }
+}
+
+/// Check a product including its embedded prices
+fn parse_product(record: &Value) -> Result<Product, String> {
+    PRODUCT_SCHEMA.check(record)?;
+    if let Some(prices) = record.get("prices").and_then(Value::as_array) {
+        for (index, price) in prices.iter().enumerate() {
+            PRICE_SCHEMA
+                .check(price)
+                .map_err(|e| format!("prices[{}]: {}", index, e))?;
+        }
+    }
+    serde_json::from_value(record.clone()).map_err(|e| e.to_string())
+}
+
+/// Accepted records with their index in the array, and one message per
+/// rejected record
+pub type ParsedRecords<T> = (Vec<(usize, T)>, Vec<String>);
+
+fn parse_json(json: &str) -> ServiceResult<Value> {
+    serde_json::from_str(json)
+        .map_err(|e| ServiceError::ValidationError(format!("Invalid JSON: {}", e)))
+}
+
+/// Elements of a JSON array, each checked separately
+fn parse_array<T>(
+    value: &Value,
+    schema: &RecordSchema,
+    parse: impl Fn(&Value) -> Result<T, String>,
+) -> ServiceResult<ParsedRecords<T>> {
+    let records = value.as_array().ok_or_else(|| {
+        ServiceError::ValidationError(format!("Expected an array of {} records", schema.name))
+    })?;
+
+    let mut parsed = Vec::new();
+    let mut errors = Vec::new();
+    for (index, record) in records.iter().enumerate() {
+        match parse(record) {
+            Ok(item) => parsed.push((index, item)),
+            Err(e) => errors.push(format!("{} record {}: {}", schema.name, index, e)),
+        }
+    }
+    Ok((parsed, errors))
+}
+
+/// Products from a JSON array; invalid records are reported, not defaulted
+pub fn parse_products(json: &str) -> ServiceResult<ParsedRecords<Product>> {
+    parse_array(&parse_json(json)?, &PRODUCT_SCHEMA, parse_product)
+}
+
+/// Price records from a JSON array; invalid records are reported, not defaulted
+pub fn parse_prices(json: &str) -> ServiceResult<ParsedRecords<PriceRecord>> {
+    parse_array(&parse_json(json)?, &PRICE_SCHEMA, |record| {
+        PRICE_SCHEMA.parse(record)
+    })
+}
+
+/// All products of a JSON array, failing on the first invalid record
+pub fn parse_products_strict(value: &Value) -> ServiceResult<Vec<Product>> {
+    let (products, errors) = parse_array(value, &PRODUCT_SCHEMA, parse_product)?;
+    match errors.into_iter().next() {
+        Some(error) => Err(ServiceError::ValidationError(error)),
+        None => Ok(products.into_iter().map(|(_, product)| product).collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_field_is_reported_with_record_index() {
+        let json = r#"[
+            {"id": "p1", "name": "Tea", "category": "Beverages", "description": "",
+             "images": [], "prices": [], "tags": [], "created_at": 1700000000},
+            {"id": "p2", "category": "Beverages", "description": "",
+             "images": [], "prices": [], "tags": [], "created_at": 1700000000}
+        ]"#;
+
+        let (products, errors) = parse_products(json).unwrap();
+        assert_eq!(products.len(), 1);
+        assert_eq!(products[0].1.name, "Tea");
+        assert_eq!(
+            errors,
+            vec!["product record 1: missing required field 'name'".to_string()]
+        );
+        let value: Value = serde_json::from_str(json).unwrap();
+        assert!(parse_products_strict(&value).is_err());
+    }
+
+    #[test]
+    fn test_embedded_and_standalone_prices_are_checked() {
+        let json = r#"[
+            {"id": "p1", "name": "Tea", "category": "Beverages", "description": "",
+             "images": [], "tags": [], "created_at": 1700000000,
+             "prices": [{"store_id": "s1", "price": "cheap", "timestamp": 1700000000,
+                         "is_on_sale": false, "verification_status": "pending"}]}
+        ]"#;
+        let (_, errors) = parse_products(json).unwrap();
+        assert_eq!(
+            errors,
+            vec!["product record 0: prices[0]: field 'price' must be a number".to_string()]
+        );
+
+        let json = r#"[
+            {"store_id": "s1", "price": 120.0, "timestamp": 1700000000,
+             "is_on_sale": false, "verification_status": "pending"},
+            {"store_id": " ", "price": 99.0, "timestamp": 1700000000,
+             "is_on_sale": true, "verification_status": "pending"}
+        ]"#;
+        let (prices, errors) = parse_prices(json).unwrap();
+        assert_eq!(prices.len(), 1);
+        assert_eq!(
+            errors,
+            vec!["price record 1: field 'store_id' must not be empty".to_string()]
+        );
+    }
+}
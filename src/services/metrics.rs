@@ -0,0 +1,231 @@
+//! In-process counters and histograms for service operations.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// Thread-safe registry of named counters and histograms, shared via `Arc`
+#[derive(Debug, Default)]
+pub struct Metrics {
+    counters: Mutex<BTreeMap<String, u64>>,
+    histograms: Mutex<BTreeMap<String, HistogramSummary>>,
+}
+
+impl Metrics {
+    pub const PRODUCTS_CREATED: &'static str = "products.created";
+    pub const PRODUCTS_CREATE_FAILED: &'static str = "products.create_failed";
+    pub const PRICES_ADDED: &'static str = "prices.added";
+    pub const PRICES_ADD_FAILED: &'static str = "prices.add_failed";
+    pub const SEARCHES: &'static str = "searches";
+    /// Histogram of the number of results per search
+    pub const SEARCH_RESULTS: &'static str = "search.results";
+    pub const SCANS: &'static str = "scans";
+    pub const SCANS_FAILED: &'static str = "scans.failed";
+    pub const PRICES_VERIFIED: &'static str = "verification.verified";
+    pub const PRICES_REJECTED: &'static str = "verification.rejected";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add one to a counter
+    pub fn increment(&self, name: &str) {
+        self.add(name, 1);
+    }
+
+    /// Add `amount` to a counter, creating it at zero
+    pub fn add(&self, name: &str, amount: u64) {
+        if let Ok(mut counters) = self.counters.lock() {
+            let counter = counters.entry(name.to_string()).or_insert(0);
+            *counter = counter.saturating_add(amount);
+        }
+    }
+
+    /// Record one value in a histogram
+    pub fn observe(&self, name: &str, value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+        if let Ok(mut histograms) = self.histograms.lock() {
+            histograms
+                .entry(name.to_string())
+                .or_default()
+                .record(value);
+        }
+    }
+
+    /// Current value of a counter (0 when never incremented)
+    pub fn counter(&self, name: &str) -> u64 {
+        self.counters
+            .lock()
+            .ok()
+            .and_then(|counters| counters.get(name).copied())
+            .unwrap_or(0)
+    }
+
+    /// Copy of all counters and histograms at this moment
+    pub fn snapshot(&self) -> MetricsReport {
+        MetricsReport {
+            counters: self.counters.lock().map(|c| c.clone()).unwrap_or_default(),
+            histograms: self
+                .histograms
+                .lock()
+                .map(|h| h.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Clear all counters and histograms
+    pub fn reset(&self) {
+        if let Ok(mut counters) = self.counters.lock() {
+            counters.clear();
+        }
+        if let Ok(mut histograms) = self.histograms.lock() {
+            histograms.clear();
+        }
+    }
+}
+
+/// Count, sum and range of the values recorded in a histogram
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct HistogramSummary {
+    pub count: u64,
+    pub sum: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl HistogramSummary {
+    fn record(&mut self, value: f64) {
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.count += 1;
+        self.sum += value;
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+}
+
+/// Point-in-time copy of a [`Metrics`] registry
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MetricsReport {
+    pub counters: BTreeMap<String, u64>,
+    pub histograms: BTreeMap<String, HistogramSummary>,
+}
+
+impl MetricsReport {
+    /// Value of a counter (0 when absent)
+    pub fn counter(&self, name: &str) -> u64 {
+        self.counters.get(name).copied().unwrap_or(0)
+    }
+
+    pub fn histogram(&self, name: &str) -> Option<&HistogramSummary> {
+        self.histograms.get(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::AppServices;
+    use crate::verification::VerificationManager;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_operations_increment_counters() {
+        let mut services = AppServices::new();
+        let product = services
+            .product_service
+            .create_product(
+                "Oolong Tea".to_string(),
+                "Beverages".to_string(),
+                "Bottled oolong tea".to_string(),
+                None,
+                vec![],
+            )
+            .unwrap();
+        // 空名称创建失败
+        assert!(
+            services
+                .product_service
+                .create_product(
+                    String::new(),
+                    "Beverages".to_string(),
+                    String::new(),
+                    None,
+                    vec![],
+                )
+                .is_err()
+        );
+
+        let record = services
+            .price_service
+            .submit_price(product.id.clone(), "1".to_string(), None, 1.2, false, None)
+            .unwrap();
+        services
+            .price_service
+            .submit_price(product.id.clone(), "2".to_string(), None, 1.4, false, None)
+            .unwrap();
+        services
+            .product_service
+            .search_products("oolong", None)
+            .unwrap();
+
+        let mut manager = VerificationManager::new();
+        manager
+            .verify_price_record(
+                &mut services.price_service,
+                record.id.as_deref().unwrap(),
+                "admin",
+                None,
+            )
+            .unwrap();
+
+        let report = services.metrics.snapshot();
+        assert_eq!(report.counter(Metrics::PRODUCTS_CREATED), 1);
+        assert_eq!(report.counter(Metrics::PRODUCTS_CREATE_FAILED), 1);
+        assert_eq!(report.counter(Metrics::PRICES_ADDED), 2);
+        assert_eq!(report.counter(Metrics::SEARCHES), 1);
+        assert_eq!(report.counter(Metrics::PRICES_VERIFIED), 1);
+        assert_eq!(report.counter(Metrics::PRICES_REJECTED), 0);
+        let results = report.histogram(Metrics::SEARCH_RESULTS).unwrap();
+        assert_eq!(results.count, 1);
+        assert_eq!(results.max, 1.0);
+    }
+
+    #[test]
+    fn test_counters_are_thread_safe() {
+        let metrics = Arc::new(Metrics::new());
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let metrics = metrics.clone();
+                std::thread::spawn(move || {
+                    for i in 0..100 {
+                        metrics.increment(Metrics::SCANS);
+                        metrics.observe("latency", i as f64);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let report = metrics.snapshot();
+        assert_eq!(report.counter(Metrics::SCANS), 400);
+        let latency = report.histogram("latency").unwrap();
+        assert_eq!(latency.count, 400);
+        assert_eq!((latency.min, latency.max), (0.0, 99.0));
+        assert_eq!(latency.mean(), Some(49.5));
+
+        metrics.reset();
+        assert!(metrics.snapshot().counters.is_empty());
+    }
+}
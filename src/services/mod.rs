@@ -1,16 +1,45 @@
+pub mod backup;
+pub mod calendar;
+pub mod consistency;
+pub mod distance;
+pub mod geocoding;
+pub mod http;
+pub mod json_import;
+pub mod metrics;
 pub mod price_service;
 pub mod product_service;
 pub mod review_service;
 pub mod store_service;
 pub mod user_service;
 
-pub use price_service::PriceService;
+pub use backup::{
+    BACKUP_FORMAT_VERSION, BackupExtras, BackupManifest, BackupSection, ConflictPolicy,
+    RestoreOptions,
+};
+pub use calendar::{Calendar, NotableDate};
+pub use consistency::{
+    ConsistencyReport, DuplicateBarcode, InvalidStoreLocation, OrphanedPriceRecord, RepairPolicy,
+    RepairSummary,
+};
+pub use distance::{DistanceCache, DistanceFn};
+#[cfg(not(target_arch = "wasm32"))]
+pub use geocoding::NominatimGeocoder;
+pub use geocoding::{CachingGeocoder, Geocoder};
+pub use http::{HttpClient, MockHttpClient};
+pub use metrics::{HistogramSummary, Metrics, MetricsReport};
+pub use price_service::{
+    AnnotatedPricePoint, AnomalyKind, AnomalySeverity, FieldMapping, Forecast, PriceAnomaly,
+    PriceRecordFilter, PriceService,
+};
 pub use product_service::ProductService;
-pub use review_service::ReviewService;
+pub use review_service::{
+    ProfanityMode, RatingSummary, ReviewPolicy, ReviewService, ReviewSort, ReviewValidationError,
+};
 pub use store_service::StoreService;
 pub use user_service::UserService;
 
 use anyhow::Result;
+use std::sync::Arc;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -27,10 +56,38 @@ pub enum ServiceError {
     BusinessRuleViolation(String),
     #[error("External service error: {0}")]
     ExternalServiceError(String),
+    #[error("Conflict: {0}")]
+    Conflict(String),
+}
+
+impl ServiceError {
+    /// Whether retrying the failed call may succeed (transient backend failures)
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            ServiceError::DatabaseError(_) | ServiceError::ExternalServiceError(_)
+        )
+    }
 }
 
 pub type ServiceResult<T> = Result<T, ServiceError>;
 
+/// Result of a bulk import (stores, prices)
+#[derive(Debug, Clone, Default)]
+pub struct ImportReport {
+    pub imported_ids: Vec<String>,
+    pub skipped: Vec<String>,
+    pub errors: Vec<String>,
+    /// Records imported with a problem worth checking
+    pub warnings: Vec<String>,
+}
+
+impl ImportReport {
+    pub fn imported_count(&self) -> usize {
+        self.imported_ids.len()
+    }
+}
+
 /// Application services aggregator
 pub struct AppServices {
     pub user_service: UserService,
@@ -38,17 +95,38 @@ pub struct AppServices {
     pub store_service: StoreService,
     pub price_service: PriceService,
     pub review_service: ReviewService,
+    /// Operation counters shared by all services
+    pub metrics: Arc<Metrics>,
 }
 
 impl AppServices {
     pub fn new() -> Self {
+        let metrics = Arc::new(Metrics::new());
         Self {
             user_service: UserService::new(),
-            product_service: ProductService::new(),
+            product_service: ProductService::new().with_metrics(metrics.clone()),
             store_service: StoreService::new(),
-            price_service: PriceService::new(),
+            price_service: PriceService::new().with_metrics(metrics.clone()),
             review_service: ReviewService::new(),
+            metrics,
+        }
+    }
+
+    /// Suspicious price records, using the current user reputations; the scan
+    /// only reruns after records or reputations change
+    pub fn find_price_anomalies(&mut self) -> Vec<PriceAnomaly> {
+        for user in self.user_service.search_users("").unwrap_or_default() {
+            self.price_service
+                .set_user_reputation(&user.id, user.reputation_score);
         }
+        self.price_service.cached_anomalies()
+    }
+
+    /// Recompute all store ratings from the current reviews
+    pub fn refresh_store_ratings(&mut self) -> ServiceResult<usize> {
+        let reviews = self.review_service.get_reviews_sorted(ReviewSort::Newest)?;
+        self.store_service.sync_reviews(&reviews);
+        self.store_service.recompute_all_ratings()
     }
 }
 
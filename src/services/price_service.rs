@@ -1,21 +1,140 @@
-use crate::models::PriceRecord;
-use crate::services::{ServiceError, ServiceResult};
-use chrono::{DateTime, Utc};
-use std::collections::HashMap;
+use crate::models::{PriceRecord, PriceSource, Product, Quantity, ShoppingTrip, TripItem};
+use crate::services::http::{self, HttpClient};
+use crate::services::{Calendar, ImportReport, Metrics, ServiceError, ServiceResult, json_import};
+use crate::utils::RoundingMode;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
 /// Price service for managing price operations and business logic
 pub struct PriceService {
     /// In-memory price cache (in real app would use database)
     price_records: HashMap<String, PriceRecord>,
+    /// Package size per product, used for per-unit comparison
+    product_quantities: HashMap<String, Quantity>,
+    /// Items per package for multipacks, used for per-item comparison
+    pack_counts: HashMap<String, u32>,
+    /// Products registered via `register_product`
+    products: HashMap<String, Product>,
+    /// Barcode -> product id, used to match imported prices
+    barcode_index: HashMap<String, String>,
+    /// Client used by `import_from_endpoint`
+    http_client: Option<Arc<dyn HttpClient>>,
+    /// Minimum verified prices required by `forecast_next_price`
+    forecast_min_points: usize,
+    /// Completed shopping trips, oldest first
+    trips: Vec<ShoppingTrip>,
+    /// Submissions whose source trust weight reaches this are verified on arrival
+    auto_verify_threshold: Option<f64>,
+    /// Whether statistics, lowest prices and trends count unverified records
+    include_unverified: bool,
+    /// Rounding applied to averages in price statistics
+    rounding_mode: RoundingMode,
+    /// Reputation of submitting users, used by `find_anomalies`
+    user_reputations: HashMap<String, i32>,
+    /// Last `find_anomalies` result, cleared whenever records or reputations change
+    anomaly_cache: Option<Vec<PriceAnomaly>>,
+    /// Operation counters, shared with the other services
+    metrics: Arc<Metrics>,
 }
 
+/// Modified z-score above which a price is an outlier (Iglewicz & Hoaglin)
+pub const OUTLIER_Z_SCORE: f64 = 3.5;
+/// Submissions from users with a reputation below this are flagged
+pub const LOW_REPUTATION_THRESHOLD: i32 = 0;
+/// Decimal places kept in average and median prices
+const AVERAGE_DECIMALS: usize = 2;
+/// Same price for the same product and store within this many minutes counts as a duplicate
+const DUPLICATE_WINDOW_MINUTES: i64 = 60;
+
 impl PriceService {
     pub fn new() -> Self {
         Self {
             price_records: HashMap::new(),
+            product_quantities: HashMap::new(),
+            pack_counts: HashMap::new(),
+            products: HashMap::new(),
+            barcode_index: HashMap::new(),
+            http_client: http::default_client(),
+            forecast_min_points: 5,
+            trips: Vec::new(),
+            auto_verify_threshold: None,
+            include_unverified: false,
+            rounding_mode: RoundingMode::default(),
+            user_reputations: HashMap::new(),
+            anomaly_cache: None,
+            metrics: Arc::default(),
         }
     }
 
+    /// Record operation counters in `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Use a specific HTTP client for endpoint imports
+    pub fn with_http_client(mut self, client: Arc<dyn HttpClient>) -> Self {
+        self.http_client = Some(client);
+        self
+    }
+
+    pub fn set_http_client(&mut self, client: Arc<dyn HttpClient>) {
+        self.http_client = Some(client);
+    }
+
+    /// Minimum number of verified prices needed for a forecast (at least 3)
+    pub fn set_forecast_min_points(&mut self, min_points: usize) {
+        self.forecast_min_points = min_points.max(3);
+    }
+
+    /// Verify new submissions automatically when their source's
+    /// [`PriceSource::trust_weight`] is at least `threshold`; None disables it
+    pub fn set_auto_verify_threshold(&mut self, threshold: Option<f64>) {
+        self.auto_verify_threshold = threshold;
+    }
+
+    /// Count pending and rejected records in statistics, lowest prices and
+    /// trends (off by default). The `*_with` methods override this per call
+    pub fn set_include_unverified(&mut self, include_unverified: bool) {
+        self.include_unverified = include_unverified;
+    }
+
+    pub fn include_unverified(&self) -> bool {
+        self.include_unverified
+    }
+
+    /// Round statistical averages with `rounding_mode`
+    pub fn set_rounding_mode(&mut self, rounding_mode: RoundingMode) {
+        self.rounding_mode = rounding_mode;
+    }
+
+    pub fn rounding_mode(&self) -> RoundingMode {
+        self.rounding_mode
+    }
+
+    /// Whether `record` counts under the current policy
+    pub fn counts_in_stats(&self, record: &PriceRecord) -> bool {
+        self.include_unverified || record.verification_status == "verified"
+    }
+
+    /// Records of a product counted in calculations
+    fn counted_product_prices(
+        &self,
+        product_id: &str,
+        include_unverified: bool,
+    ) -> Vec<PriceRecord> {
+        self.price_records
+            .values()
+            .filter(|p| {
+                p.product_id.as_deref() == Some(product_id)
+                    && (include_unverified || p.verification_status == "verified")
+            })
+            .cloned()
+            .collect()
+    }
+
     /// Submit a new price record
     pub fn submit_price(
         &mut self,
@@ -26,10 +145,7 @@ impl PriceService {
         is_on_sale: bool,
         receipt_image: Option<String>,
     ) -> ServiceResult<PriceRecord> {
-        // Validate input
-        self.validate_price_submission(price)?;
-
-        // Create price record
+        // Create price record (validated in submit_record)
         let price_record = PriceRecord::new(
             Some(product_id),
             store_id,
@@ -38,15 +154,69 @@ impl PriceService {
             is_on_sale,
             receipt_image,
         );
+        self.submit_record(price_record)
+    }
+
+    /// Submit a price entered by a logged-in user. The record starts out
+    /// pending unless the auto-verify threshold trusts manual entries.
+    pub fn add_price_record(
+        &mut self,
+        product_id: &str,
+        store_id: &str,
+        user_id: &str,
+        price: f64,
+        is_on_sale: bool,
+        receipt_image: Option<String>,
+    ) -> ServiceResult<PriceRecord> {
+        for (field, value) in [
+            ("Product", product_id),
+            ("Store", store_id),
+            ("User", user_id),
+        ] {
+            if value.trim().is_empty() {
+                self.metrics.increment(Metrics::PRICES_ADD_FAILED);
+                return Err(ServiceError::ValidationError(format!(
+                    "{} is required",
+                    field
+                )));
+            }
+        }
+        self.submit_price(
+            product_id.to_string(),
+            store_id.to_string(),
+            Some(user_id.to_string()),
+            price,
+            is_on_sale,
+            receipt_image,
+        )
+    }
+
+    /// Submit a prepared price record, keeping its `source`
+    pub fn submit_record(&mut self, mut price_record: PriceRecord) -> ServiceResult<PriceRecord> {
+        if let Err(e) = self.validate_price_submission(price_record.price) {
+            self.metrics.increment(Metrics::PRICES_ADD_FAILED);
+            return Err(e);
+        }
+
+        // 来源足够可信时直接通过验证
+        if self
+            .auto_verify_threshold
+            .is_some_and(|threshold| price_record.source.trust_weight() >= threshold)
+        {
+            price_record.verify();
+        }
 
         // Store price record
         if let Some(ref id) = price_record.id {
             self.price_records.insert(id.clone(), price_record.clone());
+            self.anomaly_cache = None;
         }
+        self.metrics.increment(Metrics::PRICES_ADDED);
 
         log::info!(
-            "Price submitted: ¥{:.2} for product {}",
-            price,
+            "Price submitted ({:?}): ¥{:.2} for product {}",
+            price_record.source,
+            price_record.price,
             price_record
                 .product_id
                 .as_ref()
@@ -55,6 +225,341 @@ impl PriceService {
         Ok(price_record)
     }
 
+    /// Submit a price for a scanned barcode (see `register_product`)
+    pub fn submit_scanned_price(
+        &mut self,
+        barcode: &str,
+        store_id: String,
+        user_id: Option<String>,
+        price: f64,
+    ) -> ServiceResult<PriceRecord> {
+        // 扫码次数由扫码界面统计，这里只记录价格
+        let Some(product_id) = self.barcode_index.get(barcode).cloned() else {
+            return Err(ServiceError::NotFound(format!(
+                "No product with barcode {}",
+                barcode
+            )));
+        };
+        let record = PriceRecord::new(Some(product_id), store_id, user_id, price, false, None)
+            .with_source(PriceSource::Scan);
+        self.submit_record(record)
+    }
+
+    /// Import `barcode,price[,on_sale]` lines for one store. A leading header
+    /// line is skipped; unknown barcodes are reported as skipped.
+    pub fn import_csv(&mut self, csv: &str, store_id: &str) -> ServiceResult<ImportReport> {
+        let mut report = ImportReport::default();
+        for (index, line) in csv.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || (index == 0 && line.to_lowercase().starts_with("barcode")) {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let barcode = fields[0];
+            let Some(price) = fields.get(1).and_then(|p| p.parse::<f64>().ok()) else {
+                report.errors.push(format!(
+                    "Line {} ({}): missing or invalid price",
+                    index + 1,
+                    barcode
+                ));
+                continue;
+            };
+            let is_on_sale = fields
+                .get(2)
+                .is_some_and(|s| matches!(s.to_lowercase().as_str(), "1" | "true" | "yes"));
+            let Some(product_id) = self.barcode_index.get(barcode).cloned() else {
+                report.skipped.push(barcode.to_string());
+                continue;
+            };
+
+            let record = PriceRecord::new(
+                Some(product_id),
+                store_id.to_string(),
+                None,
+                price,
+                is_on_sale,
+                None,
+            )
+            .with_source(PriceSource::Import);
+            match self.submit_record(record) {
+                Ok(record) => report.imported_ids.extend(record.id),
+                Err(e) => report
+                    .errors
+                    .push(format!("Line {} ({}): {}", index + 1, barcode, e)),
+            }
+        }
+
+        log::info!(
+            "Imported {} prices from CSV ({} skipped, {} errors)",
+            report.imported_count(),
+            report.skipped.len(),
+            report.errors.len()
+        );
+        Ok(report)
+    }
+
+    /// Import price records from a JSON array. Records missing a required
+    /// field are reported with their index instead of being defaulted;
+    /// records without an id get a new one.
+    pub fn import_json(&mut self, json: &str) -> ServiceResult<ImportReport> {
+        let (records, errors) = json_import::parse_prices(json)?;
+        let mut report = ImportReport {
+            errors,
+            ..ImportReport::default()
+        };
+        for (index, mut record) in records {
+            // 没有 ID 的记录按新记录导入
+            record
+                .id
+                .get_or_insert_with(|| uuid::Uuid::new_v4().to_string());
+            match self.submit_record(record.with_source(PriceSource::Import)) {
+                Ok(record) => report.imported_ids.extend(record.id),
+                Err(e) => report.errors.push(format!("price record {}: {}", index, e)),
+            }
+        }
+
+        log::info!(
+            "Imported {} prices from JSON ({} errors)",
+            report.imported_count(),
+            report.errors.len()
+        );
+        Ok(report)
+    }
+
+    /// Fetch a JSON price list from `url` and insert one price record per mapped item.
+    /// Items are matched to products by barcode (see `register_product`); unknown
+    /// barcodes are reported as skipped.
+    pub fn import_from_endpoint(
+        &mut self,
+        url: &str,
+        mapping: FieldMapping,
+        store_id: &str,
+    ) -> ServiceResult<ImportReport> {
+        let client = self.http_client.clone().ok_or_else(|| {
+            ServiceError::ExternalServiceError("No HTTP client available".to_string())
+        })?;
+        let body = client.get(url)?;
+        let json: Value = serde_json::from_str(&body).map_err(|e| {
+            ServiceError::ExternalServiceError(format!("Invalid JSON from {}: {}", url, e))
+        })?;
+
+        let items = lookup_json_path(&json, &mapping.items_path)
+            .and_then(Value::as_array)
+            .ok_or_else(|| {
+                ServiceError::ExternalServiceError(format!(
+                    "No item array at '{}' in response from {}",
+                    mapping.items_path, url
+                ))
+            })?;
+
+        let mut report = ImportReport::default();
+        for (index, item) in items.iter().enumerate() {
+            let Some(barcode) = lookup_json_path(item, &mapping.barcode_path).and_then(json_string)
+            else {
+                report
+                    .errors
+                    .push(format!("Item {}: missing barcode", index));
+                continue;
+            };
+            let Some(price) = lookup_json_path(item, &mapping.price_path).and_then(json_number)
+            else {
+                report.errors.push(format!(
+                    "Item {} ({}): missing or invalid price",
+                    index, barcode
+                ));
+                continue;
+            };
+            let Some(product_id) = self.barcode_index.get(&barcode).cloned() else {
+                report.skipped.push(barcode);
+                continue;
+            };
+            let is_on_sale = mapping
+                .sale_path
+                .as_deref()
+                .and_then(|path| lookup_json_path(item, path))
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
+
+            let record = PriceRecord::new(
+                Some(product_id),
+                store_id.to_string(),
+                None,
+                price,
+                is_on_sale,
+                None,
+            )
+            .with_source(PriceSource::Api);
+            match self.submit_record(record) {
+                Ok(record) => report.imported_ids.extend(record.id),
+                Err(e) => report
+                    .errors
+                    .push(format!("Item {} ({}): {}", index, barcode, e)),
+            }
+        }
+
+        log::info!(
+            "Imported {} prices from {} ({} skipped, {} errors)",
+            report.imported_count(),
+            url,
+            report.skipped.len(),
+            report.errors.len()
+        );
+        Ok(report)
+    }
+
+    /// Delete records older than `keep_days`, always keeping the `keep_min_per_store`
+    /// most recent records of each store. Returns the number of records removed.
+    pub fn prune_history(
+        &mut self,
+        keep_days: i64,
+        keep_min_per_store: usize,
+    ) -> ServiceResult<usize> {
+        if keep_days < 0 {
+            return Err(ServiceError::ValidationError(
+                "keep_days cannot be negative".to_string(),
+            ));
+        }
+        let cutoff = Utc::now() - chrono::Duration::days(keep_days);
+
+        let mut by_store: HashMap<&str, Vec<&PriceRecord>> = HashMap::new();
+        for record in self.price_records.values() {
+            by_store.entry(&record.store_id).or_default().push(record);
+        }
+
+        let mut to_remove = Vec::new();
+        for records in by_store.values_mut() {
+            records.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+            to_remove.extend(
+                records
+                    .iter()
+                    .skip(keep_min_per_store)
+                    .filter(|r| r.timestamp < cutoff)
+                    .filter_map(|r| r.id.clone()),
+            );
+        }
+
+        for id in &to_remove {
+            self.price_records.remove(id);
+        }
+        if !to_remove.is_empty() {
+            self.anomaly_cache = None;
+        }
+
+        log::info!(
+            "Pruned {} price records older than {} days",
+            to_remove.len(),
+            keep_days
+        );
+        Ok(to_remove.len())
+    }
+
+    /// Remove a single price record
+    pub fn delete_price_record(&mut self, price_id: &str) -> ServiceResult<PriceRecord> {
+        self.anomaly_cache = None;
+        self.price_records
+            .remove(price_id)
+            .ok_or_else(|| ServiceError::NotFound(format!("Price record {} not found", price_id)))
+    }
+
+    /// Detach a record from its product, e.g. when the product no longer exists
+    pub fn clear_product_reference(&mut self, price_id: &str) -> ServiceResult<PriceRecord> {
+        self.anomaly_cache = None;
+        let record = self.price_records.get_mut(price_id).ok_or_else(|| {
+            ServiceError::NotFound(format!("Price record {} not found", price_id))
+        })?;
+        record.product_id = None;
+        Ok(record.clone())
+    }
+
+    /// Heuristic forecast of the price `horizon_days` after the latest verified price,
+    /// from a least-squares line over the verified price series. Returns None with
+    /// fewer than the configured minimum points or when all prices share one timestamp.
+    pub fn forecast_next_price(
+        &self,
+        product_id: &str,
+        horizon_days: u32,
+    ) -> ServiceResult<Option<Forecast>> {
+        let mut records = self.get_verified_product_prices(product_id)?;
+        if records.len() < self.forecast_min_points {
+            return Ok(None);
+        }
+        records.sort_by_key(|r| r.timestamp);
+
+        // x 为距第一条记录的天数，y 为价格
+        let first = records[0].timestamp;
+        let points: Vec<(f64, f64)> = records
+            .iter()
+            .map(|r| {
+                let days = (r.timestamp - first).num_seconds() as f64 / 86_400.0;
+                (days, r.price)
+            })
+            .collect();
+
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let sxx: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+        if sxx <= f64::EPSILON {
+            return Ok(None);
+        }
+        let sxy: f64 = points
+            .iter()
+            .map(|(x, y)| (x - mean_x) * (y - mean_y))
+            .sum();
+        let slope = sxy / sxx;
+        let intercept = mean_y - slope * mean_x;
+
+        let last_x = points.last().map(|(x, _)| *x).unwrap_or(0.0);
+        let target_x = last_x + horizon_days as f64;
+        let predicted = intercept + slope * target_x;
+
+        // 约 95% 预测区间：残差标准误差 × 1.96
+        let sse: f64 = points
+            .iter()
+            .map(|(x, y)| (y - (intercept + slope * x)).powi(2))
+            .sum();
+        let std_error = (sse / (n - 2.0)).sqrt();
+        let margin = 1.96 * std_error * (1.0 + 1.0 / n + (target_x - mean_x).powi(2) / sxx).sqrt();
+
+        Ok(Some(Forecast {
+            predicted_price: predicted.max(0.0),
+            lower: (predicted - margin).max(0.0),
+            upper: (predicted + margin).max(0.0),
+            slope_per_day: slope,
+            horizon_days,
+            points_used: points.len(),
+        }))
+    }
+
+    /// Average verified price per weekday, indexed Monday (0) to Sunday (6), using the
+    /// local weekday of each record. Weekdays without data are None.
+    pub fn price_by_weekday(&self, product_id: &str) -> ServiceResult<[Option<f64>; 7]> {
+        use chrono::Datelike;
+
+        let mut sums = [(0.0, 0usize); 7];
+        for record in self.get_verified_product_prices(product_id)? {
+            let weekday = record.timestamp.with_timezone(&chrono::Local).weekday();
+            let entry = &mut sums[weekday.num_days_from_monday() as usize];
+            entry.0 += record.price;
+            entry.1 += 1;
+        }
+
+        Ok(sums.map(|(sum, count)| (count > 0).then(|| sum / count as f64)))
+    }
+
+    /// Weekday with the lowest average verified price (earliest weekday on ties)
+    pub fn best_day_to_buy(&self, product_id: &str) -> ServiceResult<Option<chrono::Weekday>> {
+        let averages = self.price_by_weekday(product_id)?;
+        let best = averages
+            .iter()
+            .enumerate()
+            .filter_map(|(day, avg)| avg.map(|avg| (day, avg)))
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        Ok(best.and_then(|(day, _)| chrono::Weekday::try_from(day as u8).ok()))
+    }
+
     /// Get price record by ID
     pub fn get_price_record(&self, price_id: &str) -> ServiceResult<PriceRecord> {
         self.price_records
@@ -65,14 +570,17 @@ impl PriceService {
 
     /// Verify a price record
     pub fn verify_price(&mut self, price_id: &str, verified: bool) -> ServiceResult<PriceRecord> {
+        self.anomaly_cache = None;
         let price_record = self.price_records.get_mut(price_id).ok_or_else(|| {
             ServiceError::NotFound(format!("Price record {} not found", price_id))
         })?;
 
         if verified {
             price_record.verify();
+            self.metrics.increment(Metrics::PRICES_VERIFIED);
         } else {
             price_record.reject();
+            self.metrics.increment(Metrics::PRICES_REJECTED);
         }
 
         log::info!(
@@ -85,6 +593,7 @@ impl PriceService {
 
     /// Reset price record status to pending
     pub fn reset_price_record_status(&mut self, price_id: &str) -> ServiceResult<PriceRecord> {
+        self.anomaly_cache = None;
         let price_record = self.price_records.get_mut(price_id).ok_or_else(|| {
             ServiceError::NotFound(format!("Price record {} not found", price_id))
         })?;
@@ -148,9 +657,18 @@ impl PriceService {
 
     /// Get current lowest price for a product
     pub fn get_current_lowest_price(&self, product_id: &str) -> ServiceResult<Option<PriceRecord>> {
-        let verified_prices = self.get_verified_product_prices(product_id)?;
+        self.get_current_lowest_price_with(product_id, self.include_unverified)
+    }
 
-        let lowest_price = verified_prices.into_iter().min_by(|a, b| {
+    /// [`Self::get_current_lowest_price`] with an explicit unverified-records policy
+    pub fn get_current_lowest_price_with(
+        &self,
+        product_id: &str,
+        include_unverified: bool,
+    ) -> ServiceResult<Option<PriceRecord>> {
+        let prices = self.counted_product_prices(product_id, include_unverified);
+
+        let lowest_price = prices.into_iter().min_by(|a, b| {
             a.price
                 .partial_cmp(&b.price)
                 .unwrap_or(std::cmp::Ordering::Equal)
@@ -159,12 +677,118 @@ impl PriceService {
         Ok(lowest_price)
     }
 
+    /// Get the counted price record with the lowest effective (discounted) price
+    pub fn get_current_lowest_effective_price(
+        &self,
+        product_id: &str,
+    ) -> ServiceResult<Option<PriceRecord>> {
+        let prices = self.counted_product_prices(product_id, self.include_unverified);
+
+        let lowest_price = prices.into_iter().min_by(|a, b| {
+            a.effective_price()
+                .partial_cmp(&b.effective_price())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        Ok(lowest_price)
+    }
+
+    /// Registered products with a non-rejected price record expiring within `days`,
+    /// paired with the earliest such expiry date and sorted by it
+    pub fn expiring_within(&self, days: u32) -> Vec<(Product, NaiveDate)> {
+        let today = Utc::now().date_naive();
+
+        let mut earliest: HashMap<&str, NaiveDate> = HashMap::new();
+        for record in self.price_records.values() {
+            if record.verification_status == "rejected" || !record.expires_within(today, days) {
+                continue;
+            }
+            let (Some(product_id), Some(expiry)) = (record.product_id.as_deref(), record.expiry)
+            else {
+                continue;
+            };
+            earliest
+                .entry(product_id)
+                .and_modify(|date| *date = (*date).min(expiry))
+                .or_insert(expiry);
+        }
+
+        let mut expiring: Vec<(Product, NaiveDate)> = earliest
+            .into_iter()
+            .filter_map(|(product_id, expiry)| {
+                self.products
+                    .get(product_id)
+                    .map(|product| (product.clone(), expiry))
+            })
+            .collect();
+        expiring.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.name.cmp(&b.0.name)));
+        expiring
+    }
+
+    /// Set the package size of a product
+    pub fn set_product_quantity(&mut self, product_id: &str, quantity: Quantity) {
+        self.product_quantities
+            .insert(product_id.to_string(), quantity);
+    }
+
+    /// Set the number of items in a multipack product
+    pub fn set_product_pack_count(&mut self, product_id: &str, count: u32) {
+        self.pack_counts
+            .insert(product_id.to_string(), count.max(1));
+    }
+
+    /// Remember a product (and its barcode) and parse its package size and
+    /// pack count from its name or description
+    pub fn register_product(&mut self, product: &Product) -> Option<Quantity> {
+        if let Some(ref barcode) = product.barcode {
+            self.barcode_index
+                .insert(barcode.clone(), product.id.clone());
+        }
+        self.products.insert(product.id.clone(), product.clone());
+
+        if let Some(count) = crate::utils::parse_pack_count(&product.name)
+            .or_else(|| crate::utils::parse_pack_count(&product.description))
+        {
+            self.set_product_pack_count(&product.id, count);
+        }
+        let quantity = crate::utils::parse_quantity(&product.name)
+            .or_else(|| crate::utils::parse_quantity(&product.description))?;
+        self.set_product_quantity(&product.id, quantity);
+        Some(quantity)
+    }
+
+    /// Package size of a product, if known
+    pub fn product_quantity(&self, product_id: &str) -> Option<Quantity> {
+        self.product_quantities.get(product_id).copied()
+    }
+
+    /// Items per package, 1 unless the product is a known multipack
+    pub fn product_pack_count(&self, product_id: &str) -> u32 {
+        self.pack_counts.get(product_id).copied().unwrap_or(1)
+    }
+
+    /// Lowest verified price per item, dividing multipack prices by their pack count
+    pub fn unit_price(&self, product_id: &str) -> Option<f64> {
+        let lowest = self.get_current_lowest_price(product_id).ok()??;
+        Some(lowest.price / self.product_pack_count(product_id) as f64)
+    }
+
+    /// Lowest verified price per base unit (¥/ml or ¥/g). The package size is
+    /// per item, so multipacks are divided by their pack count first
+    pub fn price_per_unit(&self, product_id: &str) -> Option<f64> {
+        let base = self.product_quantity(product_id)?.to_base();
+        if base.value <= 0.0 {
+            return None;
+        }
+        Some(self.unit_price(product_id)? / base.value)
+    }
+
     /// Get price comparison across stores for a product
     pub fn get_price_comparison(
         &self,
         product_id: &str,
     ) -> ServiceResult<Vec<StorePriceComparison>> {
-        let verified_prices = self.get_verified_product_prices(product_id)?;
+        let verified_prices = self.counted_product_prices(product_id, self.include_unverified);
 
         // Group by store and find latest price for each store
         let mut store_prices: HashMap<String, PriceRecord> = HashMap::new();
@@ -199,10 +823,20 @@ impl PriceService {
         &self,
         product_id: &str,
         days: i64,
+    ) -> ServiceResult<Vec<PriceHistoryPoint>> {
+        self.get_price_history_with(product_id, days, self.include_unverified)
+    }
+
+    /// [`Self::get_price_history`] with an explicit unverified-records policy
+    pub fn get_price_history_with(
+        &self,
+        product_id: &str,
+        days: i64,
+        include_unverified: bool,
     ) -> ServiceResult<Vec<PriceHistoryPoint>> {
         let cutoff_date = Utc::now() - chrono::Duration::days(days);
 
-        let verified_prices = self.get_verified_product_prices(product_id)?;
+        let verified_prices = self.counted_product_prices(product_id, include_unverified);
 
         let history: Vec<PriceHistoryPoint> = verified_prices
             .into_iter()
@@ -218,13 +852,43 @@ impl PriceService {
         Ok(history)
     }
 
+    /// Price history with each point tagged by the nearest flagged calendar date
+    pub fn annotated_price_history(
+        &self,
+        product_id: &str,
+        days: i64,
+        calendar: &Calendar,
+    ) -> ServiceResult<Vec<AnnotatedPricePoint>> {
+        let mut history = self.get_price_history(product_id, days)?;
+        history.sort_by_key(|p| p.timestamp);
+
+        Ok(history
+            .into_iter()
+            .map(|point| {
+                let date = point.timestamp.with_timezone(&chrono::Local).date_naive();
+                let annotation = calendar.annotation_for(date).map(|d| d.label.clone());
+                AnnotatedPricePoint { point, annotation }
+            })
+            .collect())
+    }
+
     /// Calculate price statistics for a product
     pub fn get_price_statistics(&self, product_id: &str) -> ServiceResult<PriceStatistics> {
-        let verified_prices = self.get_verified_product_prices(product_id)?;
+        self.get_price_statistics_with(product_id, self.include_unverified)
+    }
+
+    /// [`Self::get_price_statistics`] with an explicit unverified-records policy
+    pub fn get_price_statistics_with(
+        &self,
+        product_id: &str,
+        include_unverified: bool,
+    ) -> ServiceResult<PriceStatistics> {
+        let verified_prices = self.counted_product_prices(product_id, include_unverified);
 
         if verified_prices.is_empty() {
             return Ok(PriceStatistics {
                 min_price: 0.0,
+                min_effective_price: 0.0,
                 max_price: 0.0,
                 avg_price: 0.0,
                 median_price: 0.0,
@@ -236,14 +900,24 @@ impl PriceService {
 
         let prices: Vec<f64> = verified_prices.iter().map(|p| p.price).collect();
         let min_price = prices.iter().fold(f64::INFINITY, |a, &b| a.min(b));
+        let min_effective_price = verified_prices
+            .iter()
+            .map(|p| p.effective_price())
+            .fold(f64::INFINITY, f64::min);
         let max_price = prices.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
-        let avg_price = prices.iter().sum::<f64>() / prices.len() as f64;
+        let avg_price = self.rounding_mode.round(
+            prices.iter().sum::<f64>() / prices.len() as f64,
+            AVERAGE_DECIMALS,
+        );
 
         let mut sorted_prices = prices.clone();
         sorted_prices.sort_by(|a, b| a.partial_cmp(b).unwrap());
         let median_price = if sorted_prices.len() % 2 == 0 {
             let mid = sorted_prices.len() / 2;
-            (sorted_prices[mid - 1] + sorted_prices[mid]) / 2.0
+            self.rounding_mode.round(
+                (sorted_prices[mid - 1] + sorted_prices[mid]) / 2.0,
+                AVERAGE_DECIMALS,
+            )
         } else {
             sorted_prices[sorted_prices.len() / 2]
         };
@@ -256,6 +930,7 @@ impl PriceService {
 
         Ok(PriceStatistics {
             min_price,
+            min_effective_price,
             max_price,
             avg_price,
             median_price,
@@ -267,6 +942,15 @@ impl PriceService {
 
     /// Get trending prices (products with recent price changes)
     pub fn get_trending_prices(&self, limit: usize) -> ServiceResult<Vec<TrendingPrice>> {
+        self.get_trending_prices_with(limit, self.include_unverified)
+    }
+
+    /// [`Self::get_trending_prices`] with an explicit unverified-records policy
+    pub fn get_trending_prices_with(
+        &self,
+        limit: usize,
+        include_unverified: bool,
+    ) -> ServiceResult<Vec<TrendingPrice>> {
         let recent_cutoff = Utc::now() - chrono::Duration::hours(24);
 
         // Group by product and count recent price updates
@@ -275,7 +959,9 @@ impl PriceService {
 
         for price in self.price_records.values() {
             if let Some(ref product_id) = price.product_id {
-                if price.timestamp > recent_cutoff && price.verification_status == "verified" {
+                if price.timestamp > recent_cutoff
+                    && (include_unverified || price.verification_status == "verified")
+                {
                     *product_activity.entry(product_id.clone()).or_insert(0) += 1;
 
                     if let Some(existing) = product_latest_price.get(product_id) {
@@ -333,6 +1019,127 @@ impl PriceService {
         Ok(triggered_alerts)
     }
 
+    /// One page of records matching `filter`, newest first, with the total number of matches
+    pub fn list_price_records(
+        &self,
+        filter: &PriceRecordFilter,
+        offset: usize,
+        limit: usize,
+    ) -> (Vec<PriceRecord>, usize) {
+        let mut matches: Vec<&PriceRecord> = self
+            .price_records
+            .values()
+            .filter(|record| filter.matches(record))
+            .collect();
+        matches.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then_with(|| a.id.cmp(&b.id)));
+        let total = matches.len();
+        let page = matches
+            .into_iter()
+            .skip(offset)
+            .take(limit)
+            .cloned()
+            .collect();
+        (page, total)
+    }
+
+    /// Record a user's reputation for anomaly checks
+    pub fn set_user_reputation(&mut self, user_id: &str, score: i32) {
+        if self.user_reputations.insert(user_id.to_string(), score) != Some(score) {
+            self.anomaly_cache = None;
+        }
+    }
+
+    /// [`Self::find_anomalies`], recomputed only after records or reputations change
+    pub fn cached_anomalies(&mut self) -> Vec<PriceAnomaly> {
+        if let Some(anomalies) = &self.anomaly_cache {
+            return anomalies.clone();
+        }
+        let anomalies = self.find_anomalies();
+        self.anomaly_cache = Some(anomalies.clone());
+        anomalies
+    }
+
+    /// Suspicious records among those not yet rejected: per-product price
+    /// outliers (median absolute deviation), submissions from low-reputation
+    /// users and near-identical duplicates. Most severe first
+    pub fn find_anomalies(&self) -> Vec<PriceAnomaly> {
+        let mut records: Vec<&PriceRecord> = self
+            .price_records
+            .values()
+            .filter(|p| p.verification_status != "rejected" && p.id.is_some())
+            .collect();
+        records.sort_by_key(|p| p.timestamp);
+
+        let mut anomalies = Vec::new();
+        let mut by_product: HashMap<&str, Vec<&PriceRecord>> = HashMap::new();
+        for record in &records {
+            if let Some(product_id) = record.product_id.as_deref() {
+                by_product.entry(product_id).or_default().push(record);
+            }
+        }
+        for group in by_product.values() {
+            let prices: Vec<f64> = group.iter().map(|p| p.price).collect();
+            for (record, score) in group.iter().zip(modified_z_scores(&prices)) {
+                if score > OUTLIER_Z_SCORE {
+                    anomalies.push(PriceAnomaly::new(
+                        record,
+                        AnomalyKind::Outlier { score },
+                        if score > 2.0 * OUTLIER_Z_SCORE {
+                            AnomalySeverity::High
+                        } else {
+                            AnomalySeverity::Medium
+                        },
+                        format!("价格 {:.2} 明显偏离该商品的中位价", record.price),
+                    ));
+                }
+            }
+        }
+
+        for record in &records {
+            let Some(score) = record
+                .user_id
+                .as_ref()
+                .and_then(|user_id| self.user_reputations.get(user_id))
+            else {
+                continue;
+            };
+            if *score < LOW_REPUTATION_THRESHOLD {
+                anomalies.push(PriceAnomaly::new(
+                    record,
+                    AnomalyKind::LowReputation { score: *score },
+                    AnomalySeverity::Medium,
+                    format!("提交用户声望较低 ({})", score),
+                ));
+            }
+        }
+
+        // 与更早的记录相同即视为重复，只标记后提交的一条
+        for (index, record) in records.iter().enumerate() {
+            let original = records[..index].iter().rev().find(|earlier| {
+                earlier.product_id == record.product_id
+                    && earlier.store_id == record.store_id
+                    && earlier.user_id == record.user_id
+                    && (earlier.price - record.price).abs() < 0.005
+                    && (record.timestamp - earlier.timestamp).num_minutes()
+                        < DUPLICATE_WINDOW_MINUTES
+            });
+            if let Some(original) = original {
+                let original_id = original.id.clone().unwrap_or_default();
+                anomalies.push(PriceAnomaly::new(
+                    record,
+                    AnomalyKind::Duplicate {
+                        original_id: original_id.clone(),
+                    },
+                    AnomalySeverity::Low,
+                    format!("与记录 {} 重复", original_id),
+                ));
+            }
+        }
+
+        anomalies.sort_by(|a, b| b.severity.cmp(&a.severity));
+        anomalies
+    }
+
     /// Get price submission statistics
     pub fn get_submission_stats(&self) -> ServiceResult<SubmissionStats> {
         let total_submissions = self.price_records.len();
@@ -377,6 +1184,74 @@ impl PriceService {
         })
     }
 
+    /// Record a completed shopping trip at `store_id`
+    pub fn record_trip(
+        &mut self,
+        store_id: String,
+        items: Vec<TripItem>,
+        timestamp: DateTime<Utc>,
+    ) -> ServiceResult<ShoppingTrip> {
+        if items.is_empty() {
+            return Err(ServiceError::ValidationError(
+                "A trip needs at least one item".to_string(),
+            ));
+        }
+        for item in &items {
+            if item.quantity == 0 {
+                return Err(ServiceError::ValidationError(format!(
+                    "Quantity of {} must be positive",
+                    item.product_name
+                )));
+            }
+            self.validate_price_submission(item.unit_price)?;
+        }
+
+        let trip = ShoppingTrip::new(store_id, items, timestamp);
+        self.trips.push(trip.clone());
+        log::info!(
+            "Trip recorded: ¥{:.2} at store {}",
+            trip.total,
+            trip.store_id
+        );
+        Ok(trip)
+    }
+
+    /// Recorded shopping trips, oldest first
+    pub fn trips(&self) -> &[ShoppingTrip] {
+        &self.trips
+    }
+
+    /// Whether `record` is at most `max_age_days` old
+    pub fn is_price_fresh(record: &PriceRecord, max_age_days: u32) -> bool {
+        Self::is_price_fresh_at(record, max_age_days, Utc::now())
+    }
+
+    /// `is_price_fresh` as of `now`; a record exactly `max_age_days` old is still fresh
+    pub fn is_price_fresh_at(record: &PriceRecord, max_age_days: u32, now: DateTime<Utc>) -> bool {
+        now.signed_duration_since(record.timestamp) <= chrono::Duration::days(max_age_days as i64)
+    }
+
+    /// Receipt-style CSV of a trip: one row per item followed by a totals row
+    pub fn export_trip_csv(trip: &ShoppingTrip) -> String {
+        let mut csv = String::from(
+            "product_id,product_name,quantity,unit_price,effective_price,line_total\n",
+        );
+        for item in &trip.items {
+            csv.push_str(&format!(
+                "{},{},{},{:.2},{:.2},{:.2}\n",
+                csv_field(&item.product_id),
+                csv_field(&item.product_name),
+                item.quantity,
+                item.unit_price,
+                item.effective_price(),
+                item.line_total()
+            ));
+        }
+        let quantity: u32 = trip.items.iter().map(|i| i.quantity).sum();
+        csv.push_str(&format!("TOTAL,,{},,,{:.2}\n", quantity, trip.total));
+        csv
+    }
+
     // Helper methods
 
     fn validate_price_submission(&self, price: f64) -> ServiceResult<()> {
@@ -394,6 +1269,68 @@ impl PriceService {
     }
 }
 
+/// Dot-separated JSON paths locating price fields in an endpoint response,
+/// e.g. `data.items` / `product.ean` / `price.amount` (array indices allowed)
+#[derive(Debug, Clone)]
+pub struct FieldMapping {
+    pub items_path: String, // 商品数组位置，空字符串表示根节点
+    pub barcode_path: String,
+    pub price_path: String,
+    pub sale_path: Option<String>,
+}
+
+impl FieldMapping {
+    pub fn new(items_path: &str, barcode_path: &str, price_path: &str) -> Self {
+        Self {
+            items_path: items_path.to_string(),
+            barcode_path: barcode_path.to_string(),
+            price_path: price_path.to_string(),
+            sale_path: None,
+        }
+    }
+
+    pub fn with_sale_path(mut self, sale_path: &str) -> Self {
+        self.sale_path = Some(sale_path.to_string());
+        self
+    }
+}
+
+/// Quote a CSV field when it contains a separator, quote or newline
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn lookup_json_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .filter(|segment| !segment.is_empty())
+        .try_fold(value, |current, segment| match current {
+            Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => current.get(segment),
+        })
+}
+
+// 条码可能是字符串或数字
+fn json_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+// 价格可能是数字或 "3.50" 形式的字符串
+fn json_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
 impl Default for PriceService {
     fn default() -> Self {
         Self::new()
@@ -418,10 +1355,19 @@ pub struct PriceHistoryPoint {
     pub is_on_sale: bool,
 }
 
+/// Price history point with an optional holiday/season label
+#[derive(Debug, Clone)]
+pub struct AnnotatedPricePoint {
+    pub point: PriceHistoryPoint,
+    pub annotation: Option<String>,
+}
+
 /// Price statistics
 #[derive(Debug, Clone)]
 pub struct PriceStatistics {
     pub min_price: f64,
+    /// Lowest price after discounts
+    pub min_effective_price: f64,
     pub max_price: f64,
     pub avg_price: f64,
     pub median_price: f64,
@@ -430,6 +1376,20 @@ pub struct PriceStatistics {
     pub sale_percentage: f64,
 }
 
+/// Heuristic price forecast (linear trend, not a model)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Forecast {
+    pub predicted_price: f64,
+    /// Lower bound of the ~95% prediction interval
+    pub lower: f64,
+    /// Upper bound of the ~95% prediction interval
+    pub upper: f64,
+    /// Fitted price change per day
+    pub slope_per_day: f64,
+    pub horizon_days: u32,
+    pub points_used: usize,
+}
+
 /// Trending price information
 #[derive(Debug, Clone)]
 pub struct TrendingPrice {
@@ -449,6 +1409,112 @@ pub struct PriceAlert {
     pub timestamp: DateTime<Utc>,
 }
 
+/// Modified z-score of each value: 0.6745·|x − median| / MAD, falling back to
+/// the mean absolute deviation when more than half the values are equal.
+/// Groups of fewer than 3 values score 0
+fn modified_z_scores(values: &[f64]) -> Vec<f64> {
+    fn median(values: &mut [f64]) -> f64 {
+        values.sort_by(|a, b| a.total_cmp(b));
+        let mid = values.len() / 2;
+        if values.len() % 2 == 0 {
+            (values[mid - 1] + values[mid]) / 2.0
+        } else {
+            values[mid]
+        }
+    }
+
+    if values.len() < 3 {
+        return vec![0.0; values.len()];
+    }
+    let center = median(&mut values.to_vec());
+    let mut deviations: Vec<f64> = values.iter().map(|v| (v - center).abs()).collect();
+    let mad = median(&mut deviations);
+    let scale = if mad > 0.0 {
+        mad / 0.6745
+    } else {
+        1.253314 * deviations.iter().sum::<f64>() / deviations.len() as f64
+    };
+    if scale <= 0.0 {
+        return vec![0.0; values.len()];
+    }
+    values.iter().map(|v| (v - center).abs() / scale).collect()
+}
+
+/// Criteria for `PriceService::list_price_records`; None fields match everything
+#[derive(Debug, Clone, Default)]
+pub struct PriceRecordFilter {
+    /// Verification status ("pending", "verified", "rejected")
+    pub status: Option<String>,
+    /// Only records of these products
+    pub product_ids: Option<HashSet<String>>,
+    /// Only these records
+    pub record_ids: Option<HashSet<String>>,
+}
+
+impl PriceRecordFilter {
+    pub fn matches(&self, record: &PriceRecord) -> bool {
+        self.status
+            .as_ref()
+            .is_none_or(|status| &record.verification_status == status)
+            && self.product_ids.as_ref().is_none_or(|ids| {
+                record
+                    .product_id
+                    .as_ref()
+                    .is_some_and(|id| ids.contains(id))
+            })
+            && self
+                .record_ids
+                .as_ref()
+                .is_none_or(|ids| record.id.as_ref().is_some_and(|id| ids.contains(id)))
+    }
+}
+
+/// Why a record looks suspicious
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnomalyKind {
+    /// Far from the product's other prices
+    Outlier { score: f64 },
+    /// Submitted by a user with a low reputation
+    LowReputation { score: i32 },
+    /// Repeats an earlier record
+    Duplicate { original_id: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AnomalySeverity {
+    Low,
+    Medium,
+    High,
+}
+
+/// A price record flagged by `PriceService::find_anomalies`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceAnomaly {
+    pub price_id: String,
+    pub product_id: Option<String>,
+    pub kind: AnomalyKind,
+    pub severity: AnomalySeverity,
+    /// Reason shown to moderators
+    pub reason: String,
+}
+
+impl PriceAnomaly {
+    fn new(
+        record: &PriceRecord,
+        kind: AnomalyKind,
+        severity: AnomalySeverity,
+        reason: String,
+    ) -> Self {
+        Self {
+            price_id: record.id.clone().unwrap_or_default(),
+            product_id: record.product_id.clone(),
+            kind,
+            severity,
+            reason,
+        }
+    }
+}
+
 /// Price submission statistics
 #[derive(Debug, Clone)]
 pub struct SubmissionStats {
@@ -459,3 +1525,977 @@ pub struct SubmissionStats {
     pub unique_products: usize,
     pub unique_stores: usize,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Discount, Unit};
+    use crate::services::MockHttpClient;
+
+    fn verified_price(service: &mut PriceService, product_id: &str, price: f64) {
+        let record = service
+            .submit_price(
+                product_id.to_string(),
+                "store_1".to_string(),
+                None,
+                price,
+                false,
+                None,
+            )
+            .unwrap();
+        service
+            .verify_price(record.id.as_deref().unwrap(), true)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_add_price_record_from_user_is_pending() {
+        let mut service = PriceService::new();
+        let record = service
+            .add_price_record(
+                "product_1",
+                "store_1",
+                "user_1",
+                4.98,
+                true,
+                Some("receipts/r1.png".to_string()),
+            )
+            .unwrap();
+
+        assert_eq!(record.product_id.as_deref(), Some("product_1"));
+        assert_eq!(record.store_id, "store_1");
+        assert_eq!(record.user_id.as_deref(), Some("user_1"));
+        assert_eq!(record.price, 4.98);
+        assert!(record.is_on_sale);
+        assert_eq!(record.receipt_image.as_deref(), Some("receipts/r1.png"));
+        assert_eq!(record.source, PriceSource::Manual);
+        assert_eq!(record.verification_status, "pending");
+        assert_eq!(
+            service
+                .get_price_record(record.id.as_deref().unwrap())
+                .unwrap()
+                .verification_status,
+            "pending"
+        );
+
+        assert!(matches!(
+            service.add_price_record("product_1", "", "user_1", 4.98, false, None),
+            Err(ServiceError::ValidationError(_))
+        ));
+        assert!(matches!(
+            service.add_price_record("product_1", "store_1", "user_1", 0.0, false, None),
+            Err(ServiceError::ValidationError(_))
+        ));
+    }
+
+    fn submit_at(
+        service: &mut PriceService,
+        user_id: &str,
+        price: f64,
+        minutes_ago: i64,
+    ) -> PriceRecord {
+        let mut record = PriceRecord::new(
+            Some("product_1".to_string()),
+            "store_1".to_string(),
+            Some(user_id.to_string()),
+            price,
+            false,
+            None,
+        );
+        record.timestamp = Utc::now() - chrono::Duration::minutes(minutes_ago);
+        service.submit_record(record).unwrap()
+    }
+
+    #[test]
+    fn test_find_anomalies_flags_outlier() {
+        let mut service = PriceService::new();
+        for (i, price) in [3.2, 3.4, 3.3, 3.5, 3.3].into_iter().enumerate() {
+            submit_at(
+                &mut service,
+                &format!("user_{}", i),
+                price,
+                1000 * (i as i64 + 1),
+            );
+        }
+        let outlier = submit_at(&mut service, "user_9", 33.0, 10);
+
+        let anomalies = service.find_anomalies();
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(Some(&anomalies[0].price_id), outlier.id.as_ref());
+        assert!(matches!(anomalies[0].kind, AnomalyKind::Outlier { .. }));
+        assert_eq!(anomalies[0].severity, AnomalySeverity::High);
+        assert!(!anomalies[0].reason.is_empty());
+    }
+
+    #[test]
+    fn test_find_anomalies_flags_duplicate_and_low_reputation() {
+        let mut service = PriceService::new();
+        let original = submit_at(&mut service, "user_1", 3.3, 30);
+        let duplicate = submit_at(&mut service, "user_1", 3.3, 5);
+        // 超出时间窗口的相同价格不算重复
+        submit_at(&mut service, "user_2", 3.4, 600);
+        submit_at(&mut service, "user_2", 3.4, 300);
+
+        let anomalies = service.find_anomalies();
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(Some(&anomalies[0].price_id), duplicate.id.as_ref());
+        assert_eq!(
+            anomalies[0].kind,
+            AnomalyKind::Duplicate {
+                original_id: original.id.clone().unwrap()
+            }
+        );
+        assert_eq!(anomalies[0].severity, AnomalySeverity::Low);
+
+        service.set_user_reputation("user_2", -5);
+        let low_reputation = service
+            .find_anomalies()
+            .into_iter()
+            .filter(|a| matches!(a.kind, AnomalyKind::LowReputation { score: -5 }))
+            .count();
+        assert_eq!(low_reputation, 2);
+    }
+
+    #[test]
+    fn test_cached_anomalies_refresh_after_changes() {
+        let mut service = PriceService::new();
+        submit_at(&mut service, "user_1", 3.3, 30);
+        let duplicate = submit_at(&mut service, "user_1", 3.3, 5);
+        assert_eq!(service.cached_anomalies().len(), 1);
+        assert_eq!(service.cached_anomalies(), service.find_anomalies());
+
+        service
+            .verify_price(duplicate.id.as_deref().unwrap(), false)
+            .unwrap();
+        assert!(service.cached_anomalies().is_empty());
+
+        service.set_user_reputation("user_1", -5);
+        assert_eq!(service.cached_anomalies().len(), 1);
+
+        submit_at(&mut service, "user_2", 3.3, 0);
+        assert_eq!(service.cached_anomalies(), service.find_anomalies());
+    }
+
+    #[test]
+    fn test_find_anomalies_clean_dataset() {
+        let mut service = PriceService::new();
+        for (i, price) in [3.2, 3.4, 3.3, 3.5, 3.6, 3.1].into_iter().enumerate() {
+            submit_at(&mut service, &format!("user_{}", i), price, 100 * i as i64);
+        }
+        service.set_user_reputation("user_0", 10);
+        assert!(service.find_anomalies().is_empty());
+    }
+
+    #[test]
+    fn test_list_price_records_filters_then_pages() {
+        let mut service = PriceService::new();
+        let mut pending = Vec::new();
+        for i in 0..7 {
+            let mut record = PriceRecord::new(
+                Some(if i % 2 == 0 { "cola" } else { "chips" }.to_string()),
+                "store_1".to_string(),
+                None,
+                1.0 + i as f64,
+                false,
+                None,
+            );
+            record.timestamp = Utc::now() - chrono::Duration::minutes(i);
+            let record = service.submit_record(record).unwrap();
+            if i == 2 {
+                service
+                    .verify_price(record.id.as_deref().unwrap(), true)
+                    .unwrap();
+            } else if i % 2 == 0 {
+                pending.push(record.id.unwrap());
+            }
+        }
+
+        let filter = PriceRecordFilter {
+            status: Some("pending".to_string()),
+            product_ids: Some(HashSet::from(["cola".to_string()])),
+            ..Default::default()
+        };
+        // 待验证的 cola 记录（最新在前）：i = 0, 4, 6
+        let (page, total) = service.list_price_records(&filter, 1, 1);
+        assert_eq!(total, 3);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].id.as_ref(), Some(&pending[1]));
+
+        let (page, total) = service.list_price_records(&filter, 2, 5);
+        assert_eq!(total, 3);
+        assert_eq!(page[0].id.as_ref(), Some(&pending[2]));
+        assert!(service.list_price_records(&filter, 3, 5).0.is_empty());
+
+        let (all, total) = service.list_price_records(&PriceRecordFilter::default(), 0, 5);
+        assert_eq!((all.len(), total), (5, 7));
+    }
+
+    #[test]
+    fn test_price_per_100ml_for_different_sizes() {
+        let mut service = PriceService::new();
+
+        let can = Product::new(
+            "Cola 330ml".to_string(),
+            "饮料".to_string(),
+            "Canned cola".to_string(),
+            None,
+            vec![],
+            vec![],
+        );
+        let bottle = Product::new(
+            "Cola".to_string(),
+            "饮料".to_string(),
+            "Family size 1.5L bottle".to_string(),
+            None,
+            vec![],
+            vec![],
+        );
+        assert_eq!(
+            service.register_product(&can),
+            Some(Quantity::new(330.0, Unit::Milliliter))
+        );
+        assert_eq!(
+            service.register_product(&bottle),
+            Some(Quantity::new(1.5, Unit::Liter))
+        );
+
+        verified_price(&mut service, &can.id, 3.3);
+        verified_price(&mut service, &bottle.id, 6.0);
+
+        let can_per_100ml = service.price_per_unit(&can.id).unwrap() * 100.0;
+        let bottle_per_100ml = service.price_per_unit(&bottle.id).unwrap() * 100.0;
+        assert!((can_per_100ml - 1.0).abs() < 1e-9);
+        assert!((bottle_per_100ml - 0.4).abs() < 1e-9);
+        assert!(bottle_per_100ml < can_per_100ml);
+    }
+
+    #[test]
+    fn test_unit_price_divides_multipack_by_pack_count() {
+        let mut service = PriceService::new();
+        let single = Product::new(
+            "Beer 350ml".to_string(),
+            "饮料".to_string(),
+            "Single can".to_string(),
+            None,
+            vec![],
+            vec![],
+        );
+        let six_pack = Product::new(
+            "Beer 350ml 6缶".to_string(),
+            "饮料".to_string(),
+            "Case".to_string(),
+            None,
+            vec![],
+            vec![],
+        );
+        service.register_product(&single);
+        service.register_product(&six_pack);
+        assert_eq!(service.product_pack_count(&single.id), 1);
+        assert_eq!(service.product_pack_count(&six_pack.id), 6);
+
+        verified_price(&mut service, &single.id, 250.0);
+        verified_price(&mut service, &six_pack.id, 1290.0);
+
+        assert_eq!(service.unit_price(&single.id), Some(250.0));
+        assert_eq!(service.unit_price(&six_pack.id), Some(215.0));
+        // 单价按每罐规格计算
+        let per_ml = service.price_per_unit(&six_pack.id).unwrap();
+        assert!((per_ml - 215.0 / 350.0).abs() < 1e-9);
+        assert_eq!(service.unit_price("unknown"), None);
+    }
+
+    #[test]
+    fn test_price_per_unit_requires_quantity_and_price() {
+        let mut service = PriceService::new();
+        verified_price(&mut service, "unknown_size", 5.0);
+        assert_eq!(service.price_per_unit("unknown_size"), None);
+
+        service.set_product_quantity("no_price", Quantity::new(200.0, Unit::Gram));
+        assert_eq!(service.price_per_unit("no_price"), None);
+    }
+
+    #[test]
+    fn test_percentage_and_fixed_discounts() {
+        let base = PriceRecord::new(None, "store_1".to_string(), None, 10.0, true, None);
+
+        let percent = base.clone().with_discount(Discount::percentage(20.0));
+        assert!((percent.effective_price() - 8.0).abs() < 1e-9);
+        assert!(!percent.has_coupon());
+
+        let fixed = base
+            .clone()
+            .with_discount(Discount::fixed_amount(3.5).with_coupon("SAVE35".to_string()));
+        assert!((fixed.effective_price() - 6.5).abs() < 1e-9);
+        assert!(fixed.has_coupon());
+
+        assert_eq!(base.effective_price(), 10.0);
+    }
+
+    #[test]
+    fn test_effective_price_never_negative() {
+        let base = PriceRecord::new(None, "store_1".to_string(), None, 5.0, false, None);
+        let fixed = base.clone().with_discount(Discount::fixed_amount(8.0));
+        assert_eq!(fixed.effective_price(), 0.0);
+
+        let percent = base.with_discount(Discount::percentage(150.0));
+        assert_eq!(percent.effective_price(), 0.0);
+    }
+
+    #[test]
+    fn test_record_without_discount_deserializes() {
+        let record = PriceRecord::new(None, "store_1".to_string(), None, 4.0, false, None);
+        let mut json = serde_json::to_value(&record).unwrap();
+        json.as_object_mut().unwrap().remove("discount");
+
+        let restored: PriceRecord = serde_json::from_value(json).unwrap();
+        assert_eq!(restored.discount, None);
+        assert_eq!(restored.effective_price(), 4.0);
+    }
+
+    #[test]
+    fn test_lowest_effective_price() {
+        let mut service = PriceService::new();
+        verified_price(&mut service, "cola", 3.0);
+
+        let record = PriceRecord::new(
+            Some("cola".to_string()),
+            "store_2".to_string(),
+            None,
+            4.0,
+            true,
+            None,
+        )
+        .with_discount(Discount::percentage(50.0));
+        let id = record.id.clone().unwrap();
+        service.price_records.insert(id.clone(), record);
+        service.verify_price(&id, true).unwrap();
+
+        assert_eq!(
+            service
+                .get_current_lowest_price("cola")
+                .unwrap()
+                .unwrap()
+                .price,
+            3.0
+        );
+        let lowest = service
+            .get_current_lowest_effective_price("cola")
+            .unwrap()
+            .unwrap();
+        assert_eq!(lowest.id.as_deref(), Some(id.as_str()));
+
+        let stats = service.get_price_statistics("cola").unwrap();
+        assert_eq!(stats.min_price, 3.0);
+        assert_eq!(stats.min_effective_price, 2.0);
+    }
+
+    #[test]
+    fn test_import_from_endpoint() {
+        let url = "http://prices.example/api/items";
+        let client = MockHttpClient::new();
+        client.set_response(
+            url,
+            r#"{"data": {"items": [
+                {"ean": "4901234567890", "price": {"amount": 3.2}, "promo": true},
+                {"ean": 4901234567891, "price": {"amount": "5.80"}},
+                {"ean": "0000000000000", "price": {"amount": 1.0}}
+            ]}}"#,
+        );
+        let mut service = PriceService::new().with_http_client(Arc::new(client));
+
+        let cola = Product::new(
+            "Cola".to_string(),
+            "Beverages".to_string(),
+            "Cola".to_string(),
+            Some("4901234567890".to_string()),
+            vec![],
+            vec![],
+        );
+        let chips = Product::new(
+            "Chips".to_string(),
+            "Snacks".to_string(),
+            "Chips".to_string(),
+            Some("4901234567891".to_string()),
+            vec![],
+            vec![],
+        );
+        service.register_product(&cola);
+        service.register_product(&chips);
+
+        let mapping =
+            FieldMapping::new("data.items", "ean", "price.amount").with_sale_path("promo");
+        let report = service
+            .import_from_endpoint(url, mapping, "store_1")
+            .unwrap();
+
+        assert_eq!(report.imported_count(), 2);
+        assert_eq!(report.skipped, vec!["0000000000000".to_string()]);
+        assert!(report.errors.is_empty());
+
+        let cola_prices = service.get_product_prices(&cola.id).unwrap();
+        assert_eq!(cola_prices.len(), 1);
+        assert_eq!(cola_prices[0].price, 3.2);
+        assert!(cola_prices[0].is_on_sale);
+        assert_eq!(cola_prices[0].store_id, "store_1");
+
+        let chips_prices = service.get_product_prices(&chips.id).unwrap();
+        assert_eq!(chips_prices.len(), 1);
+        assert_eq!(chips_prices[0].price, 5.8);
+        assert!(!chips_prices[0].is_on_sale);
+        assert_eq!(chips_prices[0].source, PriceSource::Api);
+    }
+
+    fn barcode_product(name: &str, barcode: &str) -> Product {
+        Product::new(
+            name.to_string(),
+            "Beverages".to_string(),
+            name.to_string(),
+            Some(barcode.to_string()),
+            vec![],
+            vec![],
+        )
+    }
+
+    #[test]
+    fn test_scanned_and_csv_prices_carry_source() {
+        let mut service = PriceService::new();
+        let tea = barcode_product("Green Tea", "4901234567890");
+        let water = barcode_product("Water", "4901234567891");
+        service.register_product(&tea);
+        service.register_product(&water);
+
+        let scanned = service
+            .submit_scanned_price("4901234567890", "store_1".to_string(), None, 1.5)
+            .unwrap();
+        assert_eq!(scanned.source, PriceSource::Scan);
+        assert_eq!(scanned.product_id.as_deref(), Some(tea.id.as_str()));
+        assert!(matches!(
+            service.submit_scanned_price("0000000000000", "store_1".to_string(), None, 1.0),
+            Err(ServiceError::NotFound(_))
+        ));
+
+        let csv = "barcode,price,on_sale\n4901234567891,0.99,yes\n0000000000000,1.00\n4901234567890,abc\n";
+        let report = service.import_csv(csv, "store_2").unwrap();
+        assert_eq!(report.imported_count(), 1);
+        assert_eq!(report.skipped, vec!["0000000000000".to_string()]);
+        assert_eq!(report.errors.len(), 1);
+
+        let water_prices = service.get_product_prices(&water.id).unwrap();
+        assert_eq!(water_prices.len(), 1);
+        assert_eq!(water_prices[0].source, PriceSource::Import);
+        assert!(water_prices[0].is_on_sale);
+
+        let manual = service
+            .submit_price(
+                tea.id.clone(),
+                "store_1".to_string(),
+                None,
+                1.6,
+                false,
+                None,
+            )
+            .unwrap();
+        assert_eq!(manual.source, PriceSource::Manual);
+    }
+
+    #[test]
+    fn test_import_json_rejects_record_without_price() {
+        let mut service = PriceService::new();
+        let json = r#"[
+            {"product_id": "tea", "store_id": "store_1", "price": 1.5,
+             "timestamp": 1700000000, "is_on_sale": false, "verification_status": "pending"},
+            {"product_id": "tea", "store_id": "store_1",
+             "timestamp": 1700000000, "is_on_sale": false, "verification_status": "pending"}
+        ]"#;
+
+        let report = service.import_json(json).unwrap();
+        assert_eq!(report.imported_count(), 1);
+        assert_eq!(
+            report.errors,
+            vec!["price record 1: missing required field 'price'".to_string()]
+        );
+        let prices = service.get_product_prices("tea").unwrap();
+        assert_eq!(prices.len(), 1);
+        assert_eq!(prices[0].source, PriceSource::Import);
+    }
+
+    #[test]
+    fn test_auto_verify_by_source_weight() {
+        let mut service = PriceService::new();
+        let tea = barcode_product("Green Tea", "4901234567890");
+        service.register_product(&tea);
+        service.set_auto_verify_threshold(Some(PriceSource::Scan.trust_weight()));
+
+        let scanned = service
+            .submit_scanned_price("4901234567890", "store_1".to_string(), None, 1.5)
+            .unwrap();
+        assert_eq!(scanned.verification_status, "verified");
+        let manual = service
+            .submit_price(
+                tea.id.clone(),
+                "store_1".to_string(),
+                None,
+                1.6,
+                false,
+                None,
+            )
+            .unwrap();
+        assert_eq!(manual.verification_status, "pending");
+    }
+
+    #[test]
+    fn test_average_follows_rounding_mode() {
+        let mut service = PriceService::new();
+        verified_price(&mut service, "cola", 1.0);
+        verified_price(&mut service, "cola", 1.25);
+
+        // 平均值 1.125 恰好落在半分上
+        let average = |service: &PriceService| {
+            let stats = service.get_price_statistics("cola").unwrap();
+            (stats.avg_price, stats.median_price)
+        };
+        assert_eq!(average(&service), (1.13, 1.13));
+        service.set_rounding_mode(RoundingMode::HalfEven);
+        assert_eq!(average(&service), (1.12, 1.12));
+        service.set_rounding_mode(RoundingMode::Floor);
+        assert_eq!(average(&service), (1.12, 1.12));
+        service.set_rounding_mode(RoundingMode::Ceil);
+        assert_eq!(average(&service), (1.13, 1.13));
+    }
+
+    #[test]
+    fn test_statistics_with_and_without_unverified_records() {
+        let mut service = PriceService::new();
+        verified_price(&mut service, "cola", 3.0);
+        verified_price(&mut service, "cola", 5.0);
+        // 未验证的低价记录
+        service
+            .submit_price(
+                "cola".to_string(),
+                "store_2".to_string(),
+                None,
+                1.0,
+                false,
+                None,
+            )
+            .unwrap();
+
+        let verified_only = service.get_price_statistics_with("cola", false).unwrap();
+        assert_eq!(verified_only.total_records, 2);
+        assert_eq!(verified_only.min_price, 3.0);
+        assert_eq!(verified_only.avg_price, 4.0);
+
+        let all = service.get_price_statistics_with("cola", true).unwrap();
+        assert_eq!(all.total_records, 3);
+        assert_eq!(all.min_price, 1.0);
+        assert_eq!(all.avg_price, 3.0);
+        assert_eq!(all.stores_count, 2);
+
+        // 默认策略不计入未验证记录，可在服务上切换
+        assert_eq!(
+            service
+                .get_current_lowest_price("cola")
+                .unwrap()
+                .unwrap()
+                .price,
+            3.0
+        );
+        service.set_include_unverified(true);
+        assert_eq!(
+            service.get_price_statistics("cola").unwrap().total_records,
+            3
+        );
+        assert_eq!(
+            service
+                .get_current_lowest_price("cola")
+                .unwrap()
+                .unwrap()
+                .price,
+            1.0
+        );
+        assert_eq!(service.get_price_history("cola", 1).unwrap().len(), 3);
+        assert_eq!(
+            service
+                .get_current_lowest_price_with("cola", false)
+                .unwrap()
+                .unwrap()
+                .price,
+            3.0
+        );
+    }
+
+    #[test]
+    fn test_old_price_records_default_to_manual_source() {
+        let json = r#"{"id": "p1", "product_id": "cola", "store_id": "s1", "user_id": null,
+            "price": 3.5, "timestamp": 0, "is_on_sale": false, "receipt_image": null,
+            "verification_status": "verified"}"#;
+        let record: PriceRecord = serde_json::from_str(json).unwrap();
+        assert_eq!(record.source, PriceSource::Manual);
+    }
+
+    #[test]
+    fn test_import_from_endpoint_failures_are_external_errors() {
+        let client = MockHttpClient::new();
+        client.set_response("http://bad.example/json", "not json");
+        let mut service = PriceService::new().with_http_client(Arc::new(client));
+        let mapping = FieldMapping::new("", "ean", "price");
+
+        let err = service
+            .import_from_endpoint("http://bad.example/json", mapping.clone(), "s")
+            .unwrap_err();
+        assert!(matches!(err, ServiceError::ExternalServiceError(_)));
+
+        let err = service
+            .import_from_endpoint("http://missing.example/", mapping, "s")
+            .unwrap_err();
+        assert!(matches!(err, ServiceError::ExternalServiceError(_)));
+    }
+
+    fn expiring_product(
+        service: &mut PriceService,
+        name: &str,
+        expiry: Option<NaiveDate>,
+    ) -> Product {
+        let product = Product::new(
+            name.to_string(),
+            "Food".to_string(),
+            name.to_string(),
+            None,
+            vec![],
+            vec![],
+        );
+        service.register_product(&product);
+
+        let mut record = PriceRecord::new(
+            Some(product.id.clone()),
+            "store_1".to_string(),
+            None,
+            2.0,
+            false,
+            None,
+        );
+        record.expiry = expiry;
+        service
+            .price_records
+            .insert(record.id.clone().unwrap(), record);
+        product
+    }
+
+    #[test]
+    fn test_expiring_within() {
+        let mut service = PriceService::new();
+        let today = Utc::now().date_naive();
+
+        let milk = expiring_product(
+            &mut service,
+            "Milk",
+            Some(today + chrono::Duration::days(2)),
+        );
+        expiring_product(
+            &mut service,
+            "Rice",
+            Some(today + chrono::Duration::days(30)),
+        );
+        expiring_product(&mut service, "Salt", None);
+
+        let expiring = service.expiring_within(3);
+        assert_eq!(expiring.len(), 1);
+        assert_eq!(expiring[0].0.id, milk.id);
+        assert_eq!(expiring[0].1, today + chrono::Duration::days(2));
+
+        assert_eq!(service.expiring_within(30).len(), 2);
+    }
+
+    fn aged_record(service: &mut PriceService, store_id: &str, days_ago: i64) -> String {
+        let mut record = PriceRecord::new(
+            Some("cola".to_string()),
+            store_id.to_string(),
+            None,
+            3.0,
+            false,
+            None,
+        );
+        record.timestamp = Utc::now() - chrono::Duration::days(days_ago);
+        let id = record.id.clone().unwrap();
+        service.price_records.insert(id.clone(), record);
+        id
+    }
+
+    #[test]
+    fn test_price_freshness_cutoff() {
+        let now = Utc::now();
+        let mut record = PriceRecord::new(
+            Some("p1".to_string()),
+            "s1".to_string(),
+            None,
+            100.0,
+            false,
+            None,
+        );
+
+        record.timestamp = now - chrono::Duration::days(2);
+        assert!(PriceService::is_price_fresh_at(&record, 7, now));
+
+        record.timestamp = now - chrono::Duration::days(10);
+        assert!(!PriceService::is_price_fresh_at(&record, 7, now));
+
+        // 恰好等于上限仍算新鲜，多一秒即过期
+        record.timestamp = now - chrono::Duration::days(7);
+        assert!(PriceService::is_price_fresh_at(&record, 7, now));
+        record.timestamp = now - chrono::Duration::days(7) - chrono::Duration::seconds(1);
+        assert!(!PriceService::is_price_fresh_at(&record, 7, now));
+    }
+
+    #[test]
+    fn test_prune_history_removes_old_records() {
+        let mut service = PriceService::new();
+        let recent = aged_record(&mut service, "s1", 1);
+        aged_record(&mut service, "s1", 100);
+        aged_record(&mut service, "s1", 200);
+
+        let pruned = service.prune_history(30, 1).unwrap();
+        assert_eq!(pruned, 2);
+        assert!(service.get_price_record(&recent).is_ok());
+        assert_eq!(service.get_product_prices("cola").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_prune_history_keeps_per_store_minimum() {
+        let mut service = PriceService::new();
+        let newest = aged_record(&mut service, "s1", 90);
+        let second = aged_record(&mut service, "s1", 120);
+        aged_record(&mut service, "s1", 150);
+        let other_store = aged_record(&mut service, "s2", 400);
+
+        let pruned = service.prune_history(30, 2).unwrap();
+        assert_eq!(pruned, 1);
+        for id in [&newest, &second, &other_store] {
+            assert!(service.get_price_record(id).is_ok());
+        }
+    }
+
+    fn verified_on(service: &mut PriceService, price: f64, days_ago: i64) {
+        let mut record = PriceRecord::new(
+            Some("milk".to_string()),
+            "s1".to_string(),
+            None,
+            price,
+            false,
+            None,
+        );
+        record.timestamp = Utc::now() - chrono::Duration::days(days_ago);
+        record.verification_status = "verified".to_string();
+        service
+            .price_records
+            .insert(record.id.clone().unwrap(), record);
+    }
+
+    #[test]
+    fn test_forecast_continues_downward_trend() {
+        let mut service = PriceService::new();
+        // 每天下降约 0.5
+        for (days_ago, price) in [(5, 10.0), (4, 9.6), (3, 9.0), (2, 8.4), (1, 8.1), (0, 7.5)] {
+            verified_on(&mut service, price, days_ago);
+        }
+
+        let forecast = service.forecast_next_price("milk", 2).unwrap().unwrap();
+        assert_eq!(forecast.points_used, 6);
+        assert!(forecast.slope_per_day < -0.4 && forecast.slope_per_day > -0.6);
+        assert!(forecast.predicted_price < 7.5);
+        assert!((forecast.predicted_price - 6.5).abs() < 0.3);
+        assert!(forecast.lower <= forecast.predicted_price);
+        assert!(forecast.upper >= forecast.predicted_price);
+    }
+
+    #[test]
+    fn test_forecast_requires_minimum_points() {
+        let mut service = PriceService::new();
+        for (days_ago, price) in [(3, 10.0), (2, 9.0), (1, 8.0)] {
+            verified_on(&mut service, price, days_ago);
+        }
+        assert!(service.forecast_next_price("milk", 7).unwrap().is_none());
+
+        service.set_forecast_min_points(3);
+        let forecast = service.forecast_next_price("milk", 1).unwrap().unwrap();
+        assert!((forecast.predicted_price - 7.0).abs() < 1e-6);
+        assert!(service.forecast_next_price("unknown", 1).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_weekend_prices_are_cheapest() {
+        use chrono::{Datelike, TimeZone};
+
+        let mut service = PriceService::new();
+        // 2025-03-03 是星期一；取当地正午避免跨日
+        for week in 0..3 {
+            for day in 0..7u32 {
+                let date = NaiveDate::from_ymd_opt(2025, 3, 3 + week * 7 + day).unwrap();
+                let local_noon = chrono::Local
+                    .from_local_datetime(&date.and_hms_opt(12, 0, 0).unwrap())
+                    .single()
+                    .unwrap();
+                let price = if day >= 5 { 8.0 + week as f64 } else { 10.0 };
+                let mut record = PriceRecord::new(
+                    Some("rice".to_string()),
+                    "s1".to_string(),
+                    None,
+                    price,
+                    false,
+                    None,
+                );
+                record.timestamp = local_noon.with_timezone(&Utc);
+                record.verification_status = "verified".to_string();
+                assert_eq!(date.weekday().num_days_from_monday(), day);
+                service
+                    .price_records
+                    .insert(record.id.clone().unwrap(), record);
+            }
+        }
+
+        let averages = service.price_by_weekday("rice").unwrap();
+        for weekday in &averages[..5] {
+            assert_eq!(*weekday, Some(10.0));
+        }
+        assert_eq!(averages[5], Some(9.0));
+        assert_eq!(averages[6], Some(9.0));
+        // 并列时取较早的星期六
+        assert_eq!(
+            service.best_day_to_buy("rice").unwrap(),
+            Some(chrono::Weekday::Sat)
+        );
+    }
+
+    #[test]
+    fn test_price_by_weekday_without_data() {
+        let service = PriceService::new();
+        assert_eq!(service.price_by_weekday("none").unwrap(), [None; 7]);
+        assert_eq!(service.best_day_to_buy("none").unwrap(), None);
+    }
+
+    #[test]
+    fn test_price_on_flagged_date_is_annotated() {
+        use chrono::TimeZone;
+
+        let today = chrono::Local::now().date_naive();
+        let holiday = today - chrono::Duration::days(10);
+        let normal_day = today - chrono::Duration::days(5);
+        let mut calendar = Calendar::new("JP");
+        calendar.add(crate::services::NotableDate::new(holiday, "Summer Sale").in_region("JP"));
+        calendar.add(crate::services::NotableDate::new(normal_day, "US only").in_region("US"));
+
+        let mut service = PriceService::new();
+        for (date, price) in [(holiday, 12.0), (normal_day, 9.0)] {
+            let local_noon = chrono::Local
+                .from_local_datetime(&date.and_hms_opt(12, 0, 0).unwrap())
+                .single()
+                .unwrap();
+            let mut record = PriceRecord::new(
+                Some("tea".to_string()),
+                "s1".to_string(),
+                None,
+                price,
+                false,
+                None,
+            );
+            record.timestamp = local_noon.with_timezone(&Utc);
+            record.verification_status = "verified".to_string();
+            service
+                .price_records
+                .insert(record.id.clone().unwrap(), record);
+        }
+
+        let points = service
+            .annotated_price_history("tea", 30, &calendar)
+            .unwrap();
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].point.price, 12.0);
+        assert_eq!(points[0].annotation.as_deref(), Some("Summer Sale"));
+        // 其他地区的日期不标注
+        assert_eq!(points[1].annotation, None);
+    }
+
+    #[test]
+    fn test_yearly_calendar_dates_match_nearby_days() {
+        let calendar = Calendar::builtin("JP");
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        assert_eq!(
+            calendar
+                .annotation_for(date(2026, 1, 1))
+                .map(|d| d.label.as_str()),
+            Some("New Year")
+        );
+        // 窗口为 1 天：12/30 靠近大晦日
+        assert_eq!(
+            calendar
+                .annotation_for(date(2025, 12, 30))
+                .map(|d| d.label.as_str()),
+            Some("大晦日")
+        );
+        assert!(calendar.annotation_for(date(2025, 11, 11)).is_none());
+        assert!(calendar.annotation_for(date(2025, 3, 15)).is_none());
+    }
+
+    #[test]
+    fn test_record_trip_computes_total() {
+        let mut service = PriceService::new();
+        let items = vec![
+            TripItem::new("milk".to_string(), "Milk".to_string(), 3, 1.50),
+            TripItem::new("bread".to_string(), "Bread".to_string(), 1, 2.00)
+                .with_discount(Discount::percentage(25.0)),
+        ];
+
+        let trip = service
+            .record_trip("store_1".to_string(), items, Utc::now())
+            .unwrap();
+        // 3 × 1.50 + 1 × 1.50
+        assert!((trip.total - 6.0).abs() < 1e-9);
+        let line_sum: f64 = trip.items.iter().map(|i| i.line_total()).sum();
+        assert!((trip.total - line_sum).abs() < 1e-9);
+        assert_eq!(service.trips().len(), 1);
+
+        assert!(matches!(
+            service.record_trip("store_1".to_string(), vec![], Utc::now()),
+            Err(ServiceError::ValidationError(_))
+        ));
+        assert!(matches!(
+            service.record_trip(
+                "store_1".to_string(),
+                vec![TripItem::new(
+                    "milk".to_string(),
+                    "Milk".to_string(),
+                    0,
+                    1.5
+                )],
+                Utc::now()
+            ),
+            Err(ServiceError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_export_trip_csv_structure() {
+        let mut service = PriceService::new();
+        let trip = service
+            .record_trip(
+                "store_1".to_string(),
+                vec![
+                    TripItem::new("milk".to_string(), "Milk".to_string(), 2, 1.25),
+                    TripItem::new("eggs".to_string(), "Eggs, large".to_string(), 1, 3.10),
+                ],
+                Utc::now(),
+            )
+            .unwrap();
+
+        let csv = PriceService::export_trip_csv(&trip);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[0],
+            "product_id,product_name,quantity,unit_price,effective_price,line_total"
+        );
+        assert_eq!(lines[1], "milk,Milk,2,1.25,1.25,2.50");
+        // 含逗号的字段加引号
+        assert_eq!(lines[2], "eggs,\"Eggs, large\",1,3.10,3.10,3.10");
+        assert_eq!(lines[3], "TOTAL,,3,,,5.60");
+    }
+}
@@ -1,31 +1,43 @@
-use crate::models::{PriceRecord, Product};
-use crate::services::{ServiceError, ServiceResult};
+use crate::models::{Category, PriceRecord, Product, ProductRevision};
+use crate::search::engine::similarity;
+use crate::services::{ImportReport, Metrics, ServiceError, ServiceResult, json_import};
+use crate::utils::validation::validate_image_ref;
 use chrono::Utc;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 /// Product service for managing product operations and business logic
 pub struct ProductService {
     /// In-memory product cache (in real app would use database)
     products: HashMap<String, Product>,
-    /// Category mappings
-    categories: Vec<String>,
+    /// Category tree, in insertion order
+    categories: Vec<Category>,
+    /// Append-only edit history of all products
+    revisions: Vec<ProductRevision>,
+    /// Operation counters, shared with the other services
+    metrics: Arc<Metrics>,
 }
 
 impl ProductService {
     pub fn new() -> Self {
         let service = Self {
             products: HashMap::new(),
-            categories: vec![
-                "Beverages".to_string(),
-                "Snacks".to_string(),
-                "Food".to_string(),
-                "Personal Care".to_string(),
-                "Household".to_string(),
-                "Electronics".to_string(),
-                "Clothing".to_string(),
-                "Books".to_string(),
-                "Other".to_string(),
-            ],
+            revisions: Vec::new(),
+            metrics: Arc::default(),
+            categories: [
+                "Beverages",
+                "Snacks",
+                "Food",
+                "Personal Care",
+                "Household",
+                "Electronics",
+                "Clothing",
+                "Books",
+                "Other",
+            ]
+            .iter()
+            .map(|name| Category::root(name.to_string()))
+            .collect(),
         };
 
         // Initialize with some sample products (skip during tests)
@@ -42,6 +54,12 @@ impl ProductService {
         }
     }
 
+    /// Record operation counters in `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
     /// Create a new product
     pub fn create_product(
         &mut self,
@@ -50,9 +68,49 @@ impl ProductService {
         description: String,
         barcode: Option<String>,
         tags: Vec<String>,
+    ) -> ServiceResult<Product> {
+        self.create_product_with_images(name, category, description, barcode, vec![], tags)
+    }
+
+    /// Create a new product with images; each must be a relative path or an http(s) URL
+    pub fn create_product_with_images(
+        &mut self,
+        name: String,
+        category: String,
+        description: String,
+        barcode: Option<String>,
+        images: Vec<String>,
+        tags: Vec<String>,
+    ) -> ServiceResult<Product> {
+        let product = match self.build_product(name, category, description, barcode, images, tags) {
+            Ok(product) => product,
+            Err(e) => {
+                self.metrics.increment(Metrics::PRODUCTS_CREATE_FAILED);
+                return Err(e);
+            }
+        };
+
+        // Store product
+        self.products.insert(product.id.clone(), product.clone());
+        self.metrics.increment(Metrics::PRODUCTS_CREATED);
+
+        log::info!("Product created: {}", product.name);
+        Ok(product)
+    }
+
+    /// Validate input for a new product and build it
+    fn build_product(
+        &self,
+        name: String,
+        category: String,
+        description: String,
+        barcode: Option<String>,
+        images: Vec<String>,
+        tags: Vec<String>,
     ) -> ServiceResult<Product> {
         // Validate input
         self.validate_product_data(&name, &category, &description)?;
+        self.validate_images(&images)?;
 
         // Check if barcode already exists
         if let Some(ref bc) = barcode {
@@ -67,14 +125,14 @@ impl ProductService {
             }
         }
 
-        // Create product
-        let product = Product::new(name, category, description, barcode, vec![], tags);
-
-        // Store product
-        self.products.insert(product.id.clone(), product.clone());
-
-        log::info!("Product created: {}", product.name);
-        Ok(product)
+        Ok(Product::new(
+            name,
+            category,
+            description,
+            barcode,
+            images,
+            tags,
+        ))
     }
 
     /// Get all products
@@ -101,10 +159,15 @@ impl ProductService {
         Ok(product)
     }
 
-    /// Update product information
+    /// Update product information. `expected_version` must match the stored
+    /// version, otherwise the edit was based on stale data and is rejected.
+    /// Each changed field is recorded as a revision attributed to `editor`
+    #[allow(clippy::too_many_arguments)]
     pub fn update_product(
         &mut self,
         product_id: &str,
+        expected_version: u64,
+        editor: Option<String>,
         name: Option<String>,
         category: Option<String>,
         description: Option<String>,
@@ -129,27 +192,113 @@ impl ProductService {
             .get_mut(product_id)
             .ok_or_else(|| ServiceError::NotFound(format!("Product {} not found", product_id)))?;
 
-        // Update fields if provided
+        // 乐观并发控制：版本不一致说明他人已先行修改
+        if product.version != expected_version {
+            return Err(ServiceError::Conflict(format!(
+                "Product {} was modified concurrently (expected version {}, found {})",
+                product_id, expected_version, product.version
+            )));
+        }
+
+        // Update fields if provided, remembering what actually changed
+        let mut changes = Vec::new();
         if let Some(new_name) = name {
+            if new_name != product.name {
+                changes.push(("name", product.name.clone(), new_name.clone()));
+            }
             product.name = new_name;
         }
 
         if let Some(new_category) = category {
+            if new_category != product.category {
+                changes.push(("category", product.category.clone(), new_category.clone()));
+            }
             product.category = new_category;
         }
 
         if let Some(new_description) = description {
+            if new_description != product.description {
+                changes.push((
+                    "description",
+                    product.description.clone(),
+                    new_description.clone(),
+                ));
+            }
             product.description = new_description;
         }
 
         if let Some(new_tags) = tags {
+            if new_tags != product.tags {
+                changes.push(("tags", product.tags.join(", "), new_tags.join(", ")));
+            }
             product.tags = new_tags;
         }
+        product.version += 1;
+
+        let timestamp = Utc::now();
+        self.revisions.extend(
+            changes
+                .into_iter()
+                .map(|(field, old, new)| ProductRevision {
+                    product_id: product_id.to_string(),
+                    field: field.to_string(),
+                    old,
+                    new,
+                    editor: editor.clone(),
+                    timestamp,
+                }),
+        );
 
         log::info!("Product updated: {}", product.name);
         Ok(product.clone())
     }
 
+    /// Replace a product's images (the first valid one is the primary image).
+    /// Versioned and recorded like `update_product`
+    pub fn set_product_images(
+        &mut self,
+        product_id: &str,
+        expected_version: u64,
+        editor: Option<String>,
+        images: Vec<String>,
+    ) -> ServiceResult<Product> {
+        self.validate_images(&images)?;
+
+        let product = self
+            .products
+            .get_mut(product_id)
+            .ok_or_else(|| ServiceError::NotFound(format!("Product {} not found", product_id)))?;
+        if product.version != expected_version {
+            return Err(ServiceError::Conflict(format!(
+                "Product {} was modified concurrently (expected version {}, found {})",
+                product_id, expected_version, product.version
+            )));
+        }
+
+        if images != product.images {
+            self.revisions.push(ProductRevision {
+                product_id: product_id.to_string(),
+                field: "images".to_string(),
+                old: product.images.join(", "),
+                new: images.join(", "),
+                editor,
+                timestamp: Utc::now(),
+            });
+        }
+        product.images = images;
+        product.version += 1;
+        Ok(product.clone())
+    }
+
+    /// Edit history of a product, oldest first
+    pub fn get_revisions(&self, product_id: &str) -> Vec<ProductRevision> {
+        self.revisions
+            .iter()
+            .filter(|r| r.product_id == product_id)
+            .cloned()
+            .collect()
+    }
+
     /// Delete product
     pub fn delete_product(&mut self, product_id: &str) -> ServiceResult<()> {
         let product = self
@@ -161,6 +310,61 @@ impl ProductService {
         Ok(())
     }
 
+    /// Re-insert a previously deleted product unchanged (used by undo)
+    pub fn restore_product(&mut self, product: Product) -> ServiceResult<()> {
+        if self.products.contains_key(&product.id) {
+            return Err(ServiceError::Conflict(format!(
+                "Product {} already exists",
+                product.id
+            )));
+        }
+        log::info!("Product restored: {}", product.name);
+        self.products.insert(product.id.clone(), product);
+        Ok(())
+    }
+
+    /// Import products from a JSON array. Records missing a required field or
+    /// failing validation are reported with their index; products whose id
+    /// already exists are skipped.
+    pub fn import_json(&mut self, json: &str) -> ServiceResult<ImportReport> {
+        let (products, errors) = json_import::parse_products(json)?;
+        let mut report = ImportReport {
+            errors,
+            ..ImportReport::default()
+        };
+        for (index, product) in products {
+            if self.products.contains_key(&product.id) {
+                report.skipped.push(product.id);
+                continue;
+            }
+            let checked = self
+                .validate_product_data(&product.name, &product.category, &product.description)
+                .and_then(|_| self.validate_images(&product.images));
+            if let Err(e) = checked {
+                report
+                    .errors
+                    .push(format!("product record {}: {}", index, e));
+                continue;
+            }
+            report.imported_ids.push(product.id.clone());
+            self.products.insert(product.id.clone(), product);
+        }
+
+        log::info!(
+            "Imported {} products from JSON ({} skipped, {} errors)",
+            report.imported_count(),
+            report.skipped.len(),
+            report.errors.len()
+        );
+        Ok(report)
+    }
+
+    /// Replace all products, e.g. when importing a backup
+    pub fn replace_products(&mut self, products: Vec<Product>) {
+        self.products = products.into_iter().map(|p| (p.id.clone(), p)).collect();
+        log::info!("Products replaced: {}", self.products.len());
+    }
+
     /// Search products
     pub fn search_products(
         &self,
@@ -189,9 +393,70 @@ impl ProductService {
             .cloned()
             .collect();
 
+        self.metrics.increment(Metrics::SEARCHES);
+        self.metrics
+            .observe(Metrics::SEARCH_RESULTS, products.len() as f64);
         Ok(products)
     }
 
+    /// Existing tags (trimmed, lowercased) starting with `partial`, most used first,
+    /// followed by near-miss spellings of it; at most `limit` tags
+    pub fn suggest_tags(&self, partial: &str, limit: usize) -> Vec<String> {
+        let partial = partial.trim().to_lowercase();
+        let counts = Self::tag_counts(self.products.values());
+
+        let (prefixed, others): (Vec<_>, Vec<_>) = counts
+            .into_iter()
+            .partition(|(tag, _)| tag.starts_with(&partial));
+        let fuzzy: Vec<(String, usize)> = if partial.is_empty() {
+            Vec::new()
+        } else {
+            others
+                .into_iter()
+                .filter(|(tag, _)| {
+                    // 只比较与输入等长的前缀，容忍一处拼写错误
+                    let head: String = tag.chars().take(partial.chars().count()).collect();
+                    similarity(&partial, &head) >= TAG_SUGGESTION_SIMILARITY
+                })
+                .collect()
+        };
+
+        Self::by_frequency(prefixed)
+            .into_iter()
+            .chain(Self::by_frequency(fuzzy))
+            .take(limit)
+            .collect()
+    }
+
+    /// Tags most used by products in `category`, for pre-filling a new product's tags
+    pub fn category_tags(&self, category: &str, limit: usize) -> Vec<String> {
+        let counts = Self::tag_counts(self.products.values().filter(|p| p.category == category));
+        Self::by_frequency(counts.into_iter().collect())
+            .into_iter()
+            .take(limit)
+            .collect()
+    }
+
+    /// Usage count of each normalized tag across `products`
+    fn tag_counts<'a>(products: impl Iterator<Item = &'a Product>) -> HashMap<String, usize> {
+        let mut counts = HashMap::new();
+        for product in products {
+            for tag in &product.tags {
+                let tag = tag.trim().to_lowercase();
+                if !tag.is_empty() {
+                    *counts.entry(tag).or_insert(0) += 1;
+                }
+            }
+        }
+        counts
+    }
+
+    /// Tags sorted by count descending, ties alphabetically
+    fn by_frequency(mut tags: Vec<(String, usize)>) -> Vec<String> {
+        tags.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        tags.into_iter().map(|(tag, _)| tag).collect()
+    }
+
     /// Get products by category
     pub fn get_products_by_category(&self, category: &str) -> ServiceResult<Vec<Product>> {
         let products: Vec<Product> = self
@@ -204,24 +469,173 @@ impl ProductService {
         Ok(products)
     }
 
-    /// Get all categories
+    /// Get all category ids
     pub fn get_categories(&self) -> Vec<String> {
-        self.categories.clone()
+        self.categories.iter().map(|c| c.id.clone()).collect()
     }
 
-    /// Add category
+    /// Get a category node by id
+    pub fn get_category(&self, category_id: &str) -> Option<&Category> {
+        self.categories.iter().find(|c| c.id == category_id)
+    }
+
+    /// Add a top-level category
     pub fn add_category(&mut self, category: String) -> ServiceResult<()> {
-        if self.categories.contains(&category) {
+        if self.get_category(&category).is_some() {
             return Err(ServiceError::ValidationError(
                 "Category already exists".to_string(),
             ));
         }
 
-        self.categories.push(category.clone());
         log::info!("Category added: {}", category);
+        self.categories.push(Category::root(category));
         Ok(())
     }
 
+    /// Add a child category under `parent_id`
+    pub fn add_subcategory(&mut self, parent_id: &str, name: String) -> ServiceResult<Category> {
+        if name.trim().is_empty() {
+            return Err(ServiceError::ValidationError(
+                "Category name cannot be empty".to_string(),
+            ));
+        }
+        if self.get_category(parent_id).is_none() {
+            return Err(ServiceError::NotFound(format!(
+                "Category {} not found",
+                parent_id
+            )));
+        }
+
+        let category = Category {
+            id: format!("{}/{}", parent_id, name),
+            name,
+            parent: Some(parent_id.to_string()),
+        };
+        if self.get_category(&category.id).is_some() {
+            return Err(ServiceError::ValidationError(
+                "Category already exists".to_string(),
+            ));
+        }
+
+        log::info!("Subcategory added: {}", category.id);
+        self.categories.push(category.clone());
+        Ok(category)
+    }
+
+    /// Move every product in category `from` to the existing category `to`,
+    /// recording a revision per product. Returns how many products moved.
+    pub fn reassign_category(&mut self, from: &str, to: &str) -> ServiceResult<usize> {
+        self.validate_category(to)?;
+        if from == to {
+            return Ok(0);
+        }
+
+        let timestamp = Utc::now();
+        let mut moved = 0;
+        for product in self.products.values_mut().filter(|p| p.category == from) {
+            product.category = to.to_string();
+            product.version += 1;
+            self.revisions.push(ProductRevision {
+                product_id: product.id.clone(),
+                field: "category".to_string(),
+                old: from.to_string(),
+                new: to.to_string(),
+                editor: None,
+                timestamp,
+            });
+            moved += 1;
+        }
+
+        log::info!("Reassigned {} products from {} to {}", moved, from, to);
+        Ok(moved)
+    }
+
+    /// [`Self::reassign_category`], then remove the emptied `from` category.
+    /// A source that still has subcategories is kept.
+    pub fn reassign_category_and_remove(&mut self, from: &str, to: &str) -> ServiceResult<usize> {
+        let moved = self.reassign_category(from, to)?;
+        if from == to {
+            return Ok(moved);
+        }
+
+        if self
+            .categories
+            .iter()
+            .any(|c| c.parent.as_deref() == Some(from))
+        {
+            log::warn!("Category {} kept: it still has subcategories", from);
+        } else {
+            self.categories.retain(|c| c.id != from);
+            log::info!("Category removed: {}", from);
+        }
+        Ok(moved)
+    }
+
+    /// Direct children of a category
+    pub fn get_subcategories(&self, category_id: &str) -> Vec<Category> {
+        self.categories
+            .iter()
+            .filter(|c| c.parent.as_deref() == Some(category_id))
+            .cloned()
+            .collect()
+    }
+
+    /// Category names from the root down to `category_id`, e.g. ["饮料", "碳酸饮料"]
+    pub fn category_path(&self, category_id: &str) -> Vec<String> {
+        let mut path = Vec::new();
+        let mut current = self.get_category(category_id);
+        while let Some(category) = current {
+            path.push(category.name.clone());
+            // 防御环路
+            if path.len() > self.categories.len() {
+                break;
+            }
+            current = category
+                .parent
+                .as_deref()
+                .and_then(|p| self.get_category(p));
+        }
+        path.reverse();
+        path
+    }
+
+    /// Ids of `category_id` and all of its descendants
+    fn subtree_ids(&self, category_id: &str) -> Vec<String> {
+        let mut ids = vec![category_id.to_string()];
+        let mut index = 0;
+        while index < ids.len() {
+            let parent = ids[index].clone();
+            ids.extend(
+                self.categories
+                    .iter()
+                    .filter(|c| c.parent.as_deref() == Some(parent.as_str()))
+                    .map(|c| c.id.clone()),
+            );
+            index += 1;
+        }
+        ids
+    }
+
+    /// Products in a category or any of its descendants
+    pub fn products_in_subtree(&self, category_id: &str) -> ServiceResult<Vec<Product>> {
+        if self.get_category(category_id).is_none() {
+            return Err(ServiceError::NotFound(format!(
+                "Category {} not found",
+                category_id
+            )));
+        }
+
+        let ids = self.subtree_ids(category_id);
+        let mut products: Vec<Product> = self
+            .products
+            .values()
+            .filter(|p| ids.contains(&p.category))
+            .cloned()
+            .collect();
+        products.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(products)
+    }
+
     /// Get products with pagination
     pub fn list_products(&self, offset: usize, limit: usize) -> ServiceResult<Vec<Product>> {
         let products: Vec<Product> = self
@@ -347,7 +761,7 @@ impl ProductService {
             total_prices,
             verified_prices,
             category_counts,
-            categories: self.categories.clone(),
+            categories: self.get_categories(),
         })
     }
 
@@ -382,7 +796,7 @@ impl ProductService {
     }
 
     fn validate_category(&self, category: &str) -> ServiceResult<()> {
-        if !self.categories.contains(&category.to_string()) {
+        if self.get_category(category).is_none() {
             return Err(ServiceError::ValidationError(format!(
                 "Invalid category: {}",
                 category
@@ -392,6 +806,13 @@ impl ProductService {
         Ok(())
     }
 
+    fn validate_images(&self, images: &[String]) -> ServiceResult<()> {
+        for image in images {
+            validate_image_ref(image).map_err(ServiceError::ValidationError)?;
+        }
+        Ok(())
+    }
+
     fn validate_description(&self, description: &str) -> ServiceResult<()> {
         if description.len() > 1000 {
             return Err(ServiceError::ValidationError(
@@ -443,6 +864,9 @@ impl Default for ProductService {
     }
 }
 
+/// Minimum similarity for a tag to be suggested as a likely misspelling of the input
+const TAG_SUGGESTION_SIMILARITY: f32 = 0.75;
+
 /// Product statistics
 #[derive(Debug, Clone)]
 pub struct ProductStats {
@@ -577,6 +1001,8 @@ mod tests {
 
         let result = service.update_product(
             &product.id,
+            product.version,
+            None,
             Some("Updated Product".to_string()),
             None,
             Some("Updated description".to_string()),
@@ -588,6 +1014,174 @@ mod tests {
         assert_eq!(updated_product.name, "Updated Product");
         assert_eq!(updated_product.description, "Updated description");
         assert_eq!(updated_product.tags, vec!["updated".to_string()]);
+        assert_eq!(updated_product.version, product.version + 1);
+    }
+
+    #[test]
+    fn test_update_product_records_revisions() {
+        let mut service = ProductService::new();
+
+        let product = service
+            .create_product(
+                "Green Tea".to_string(),
+                "Beverages".to_string(),
+                "Bottled tea".to_string(),
+                None,
+                vec![],
+            )
+            .unwrap();
+
+        let updated = service
+            .update_product(
+                &product.id,
+                product.version,
+                Some("alice".to_string()),
+                Some("Oolong Tea".to_string()),
+                Some("Food".to_string()),
+                Some("Bottled tea".to_string()), // 未变化，不记录
+                None,
+            )
+            .unwrap();
+
+        let revisions = service.get_revisions(&product.id);
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[0].field, "name");
+        assert_eq!(revisions[0].old, "Green Tea");
+        assert_eq!(revisions[0].new, "Oolong Tea");
+        assert_eq!(revisions[1].field, "category");
+        assert_eq!(revisions[1].old, "Beverages");
+        assert_eq!(revisions[1].new, "Food");
+        assert!(
+            revisions
+                .iter()
+                .all(|r| r.editor.as_deref() == Some("alice"))
+        );
+
+        // 无实际变化的更新不产生修订记录
+        service
+            .update_product(
+                &product.id,
+                updated.version,
+                Some("bob".to_string()),
+                Some("Oolong Tea".to_string()),
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        assert_eq!(service.get_revisions(&product.id).len(), 2);
+        assert!(service.get_revisions("missing").is_empty());
+    }
+
+    #[test]
+    fn test_product_images_are_validated() {
+        let mut service = ProductService::new();
+        let result = service.create_product_with_images(
+            "Green Tea".to_string(),
+            "Beverages".to_string(),
+            "Bottled tea".to_string(),
+            None,
+            vec!["javascript:alert(1)".to_string()],
+            vec![],
+        );
+        assert!(
+            matches!(result, Err(ServiceError::ValidationError(msg)) if msg.contains("javascript"))
+        );
+
+        let product = service
+            .create_product_with_images(
+                "Green Tea".to_string(),
+                "Beverages".to_string(),
+                "Bottled tea".to_string(),
+                None,
+                vec![],
+                vec![],
+            )
+            .unwrap();
+        assert_eq!(product.primary_image(), None);
+
+        let images = vec![
+            "images/tea-front.jpg".to_string(),
+            "https://cdn.example.com/tea-back.jpg".to_string(),
+        ];
+        let mut updated = service
+            .set_product_images(&product.id, product.version, None, images)
+            .unwrap();
+        assert_eq!(updated.primary_image(), Some("images/tea-front.jpg"));
+        assert!(updated.set_primary_image(1));
+        assert_eq!(
+            updated.primary_image(),
+            Some("https://cdn.example.com/tea-back.jpg")
+        );
+        assert!(!updated.set_primary_image(5));
+
+        let rejected = service.set_product_images(
+            &product.id,
+            updated.version,
+            None,
+            vec!["file:///etc/passwd".to_string()],
+        );
+        assert!(matches!(rejected, Err(ServiceError::ValidationError(_))));
+    }
+
+    #[test]
+    fn test_primary_image_skips_invalid_entries() {
+        let mut product = Product::new(
+            "Cola".to_string(),
+            "Beverages".to_string(),
+            String::new(),
+            None,
+            vec!["javascript:void(0)".to_string(), "cola.jpg".to_string()],
+            vec![],
+        );
+        // 旧数据中可能残留非法条目，取第一个合法的
+        assert_eq!(product.primary_image(), Some("cola.jpg"));
+        product.images.clear();
+        assert_eq!(product.primary_image(), None);
+    }
+
+    #[test]
+    fn test_update_product_with_stale_version_conflicts() {
+        let mut service = ProductService::new();
+
+        let product = service
+            .create_product(
+                "Test Product".to_string(),
+                "Electronics".to_string(),
+                "A test product".to_string(),
+                None,
+                vec![],
+            )
+            .unwrap();
+
+        // 第一位用户基于当前版本修改成功
+        service
+            .update_product(
+                &product.id,
+                product.version,
+                None,
+                Some("First Edit".to_string()),
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+
+        // 第二位用户仍持有旧版本，修改被拒绝
+        let result = service.update_product(
+            &product.id,
+            product.version,
+            None,
+            Some("Second Edit".to_string()),
+            None,
+            None,
+            None,
+        );
+        assert!(matches!(result, Err(ServiceError::Conflict(_))));
+
+        let stored = service.get_product(&product.id).unwrap();
+        assert_eq!(stored.name, "First Edit");
+        assert_eq!(stored.version, product.version + 1);
     }
 
     #[test]
@@ -729,4 +1323,200 @@ mod tests {
         assert!(stats.category_counts.contains_key("Electronics"));
         assert!(stats.category_counts.contains_key("Food"));
     }
+
+    #[test]
+    fn test_category_tree() {
+        let mut service = ProductService::new();
+        service.add_category("饮料".to_string()).unwrap();
+        let soda = service
+            .add_subcategory("饮料", "碳酸饮料".to_string())
+            .unwrap();
+        let tea = service
+            .add_subcategory("饮料", "茶饮料".to_string())
+            .unwrap();
+
+        assert_eq!(soda.parent.as_deref(), Some("饮料"));
+        assert_eq!(service.category_path(&soda.id), vec!["饮料", "碳酸饮料"]);
+        assert_eq!(service.category_path("饮料"), vec!["饮料"]);
+        assert_eq!(service.get_subcategories("饮料").len(), 2);
+        assert!(service.add_subcategory("不存在", "x".to_string()).is_err());
+
+        // 叶子和中间节点都可作为商品分类
+        service
+            .create_product(
+                "可口可乐".to_string(),
+                soda.id.clone(),
+                String::new(),
+                None,
+                vec![],
+            )
+            .unwrap();
+        service
+            .create_product(
+                "绿茶".to_string(),
+                tea.id.clone(),
+                String::new(),
+                None,
+                vec![],
+            )
+            .unwrap();
+        service
+            .create_product(
+                "矿泉水".to_string(),
+                "饮料".to_string(),
+                String::new(),
+                None,
+                vec![],
+            )
+            .unwrap();
+        service
+            .create_product(
+                "薯片".to_string(),
+                "Snacks".to_string(),
+                String::new(),
+                None,
+                vec![],
+            )
+            .unwrap();
+
+        let all_drinks = service.products_in_subtree("饮料").unwrap();
+        assert_eq!(all_drinks.len(), 3);
+        assert!(all_drinks.iter().all(|p| p.name != "薯片"));
+
+        let sodas = service.products_in_subtree(&soda.id).unwrap();
+        assert_eq!(sodas.len(), 1);
+        assert_eq!(sodas[0].name, "可口可乐");
+    }
+
+    fn create_tagged(service: &mut ProductService, name: &str, category: &str, tags: &[&str]) {
+        service
+            .create_product(
+                name.to_string(),
+                category.to_string(),
+                String::new(),
+                None,
+                tags.iter().map(|t| t.to_string()).collect(),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_suggest_tags_prefix_by_frequency() {
+        let mut service = ProductService::new();
+        create_tagged(&mut service, "Cola", "Beverages", &["soda", "Sugar-free"]);
+        create_tagged(&mut service, "Lemonade", "Beverages", &["Soda ", "sour"]);
+        create_tagged(&mut service, "Ginger Ale", "Beverages", &["soda", "spicy"]);
+        create_tagged(&mut service, "Gum", "Snacks", &["sugar-free"]);
+
+        assert_eq!(
+            service.suggest_tags("S", 10),
+            vec!["soda", "sugar-free", "sour", "spicy"]
+        );
+        // 拼写错误的前缀仍能给出建议，排在前缀匹配之后
+        assert_eq!(service.suggest_tags("sida", 10), vec!["soda"]);
+        assert_eq!(service.suggest_tags("s", 2), vec!["soda", "sugar-free"]);
+        assert!(service.suggest_tags("s", 0).is_empty());
+    }
+
+    #[test]
+    fn test_category_tags() {
+        let mut service = ProductService::new();
+        create_tagged(&mut service, "Cola", "Beverages", &["soda", "cold"]);
+        create_tagged(&mut service, "Lemonade", "Beverages", &["soda"]);
+        create_tagged(&mut service, "Chips", "Snacks", &["salty"]);
+
+        assert_eq!(service.category_tags("Beverages", 5), vec!["soda", "cold"]);
+        assert_eq!(service.category_tags("Beverages", 1), vec!["soda"]);
+        assert!(service.category_tags("Books", 5).is_empty());
+    }
+
+    #[test]
+    fn test_reassign_category_moves_products() {
+        let mut service = ProductService::new();
+        create_tagged(&mut service, "Cola", "Snacks", &[]);
+        create_tagged(&mut service, "Lemonade", "Snacks", &[]);
+        create_tagged(&mut service, "Chips", "Food", &[]);
+
+        assert_eq!(service.reassign_category("Snacks", "Beverages").unwrap(), 2);
+        assert_eq!(
+            service.get_products_by_category("Beverages").unwrap().len(),
+            2
+        );
+        assert!(
+            service
+                .get_products_by_category("Snacks")
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(service.get_products_by_category("Food").unwrap().len(), 1);
+
+        let cola = service
+            .get_all_products()
+            .unwrap()
+            .into_iter()
+            .find(|p| p.name == "Cola")
+            .unwrap();
+        assert_eq!(cola.version, 1);
+        let revisions = service.get_revisions(&cola.id);
+        assert_eq!(revisions.len(), 1);
+        assert_eq!(revisions[0].old, "Snacks");
+        assert_eq!(revisions[0].new, "Beverages");
+
+        // 不删除源分类时它仍然存在
+        assert!(service.get_category("Snacks").is_some());
+    }
+
+    #[test]
+    fn test_reassign_category_rejects_unknown_target() {
+        let mut service = ProductService::new();
+        create_tagged(&mut service, "Cola", "Snacks", &[]);
+
+        let result = service.reassign_category("Snacks", "Drinks");
+        assert!(matches!(result, Err(ServiceError::ValidationError(_))));
+        assert_eq!(service.get_products_by_category("Snacks").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_reassign_category_and_remove_source() {
+        let mut service = ProductService::new();
+        create_tagged(&mut service, "Cola", "Snacks", &[]);
+
+        assert_eq!(
+            service
+                .reassign_category_and_remove("Snacks", "Beverages")
+                .unwrap(),
+            1
+        );
+        assert!(service.get_category("Snacks").is_none());
+        assert!(!service.get_categories().contains(&"Snacks".to_string()));
+
+        // 仍有子分类的源分类会被保留
+        service
+            .add_subcategory("Food", "Frozen".to_string())
+            .unwrap();
+        service
+            .reassign_category_and_remove("Food", "Beverages")
+            .unwrap();
+        assert!(service.get_category("Food").is_some());
+    }
+
+    #[test]
+    fn test_import_json_reports_record_missing_name() {
+        let mut service = ProductService::new();
+        let json = r#"[
+            {"id": "tea", "name": "Green Tea", "category": "Beverages", "description": "",
+             "images": [], "prices": [], "tags": [], "created_at": 1700000000},
+            {"id": "blank", "category": "Beverages", "description": "",
+             "images": [], "prices": [], "tags": [], "created_at": 1700000000}
+        ]"#;
+
+        let report = service.import_json(json).unwrap();
+        assert_eq!(report.imported_ids, vec!["tea".to_string()]);
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].contains("record 1"));
+        assert!(report.errors[0].contains("'name'"));
+        // 缺少名称的记录不会生成空白商品
+        assert!(service.get_product("blank").is_err());
+        assert_eq!(service.get_all_products().unwrap().len(), 1);
+    }
 }
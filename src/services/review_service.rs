@@ -1,23 +1,94 @@
+use crate::error::Locale;
 use crate::models::UserReview;
 use crate::services::{ServiceError, ServiceResult};
+use crate::utils::ProfanityFilter;
 use std::collections::HashMap;
+use thiserror::Error;
 
 /// Review service for managing user reviews and ratings business logic
 pub struct ReviewService {
     /// In-memory review cache (in real app would use database)
     reviews: HashMap<String, UserReview>,
-    /// Auxiliary helpful counters by review id
-    helpful_counts: HashMap<String, usize>,
+    /// Helpfulness votes: review id -> (user id -> helpful)
+    votes: HashMap<String, HashMap<String, bool>>,
     /// Verified review ids
     verified: std::collections::HashSet<String>,
+    /// Comment validation rules
+    policy: ReviewPolicy,
+    profanity_filter: ProfanityFilter,
 }
 
 impl ReviewService {
     pub fn new() -> Self {
         Self {
             reviews: HashMap::new(),
-            helpful_counts: HashMap::new(),
+            votes: HashMap::new(),
             verified: std::collections::HashSet::new(),
+            policy: ReviewPolicy::default(),
+            profanity_filter: ProfanityFilter::new(ReviewPolicy::default().locale),
+        }
+    }
+
+    /// Use custom comment validation rules
+    pub fn with_policy(mut self, policy: ReviewPolicy) -> Self {
+        self.set_policy(policy);
+        self
+    }
+
+    pub fn set_policy(&mut self, policy: ReviewPolicy) {
+        self.profanity_filter = ProfanityFilter::new(policy.locale);
+        self.policy = policy;
+    }
+
+    pub fn policy(&self) -> &ReviewPolicy {
+        &self.policy
+    }
+
+    /// Validate a rating and comment against the policy, returning the comment
+    /// to store (with flagged words masked when the policy says so)
+    pub fn check_review_text(
+        &self,
+        rating: i32,
+        comment: &str,
+    ) -> Result<String, ReviewValidationError> {
+        if !(1..=5).contains(&rating) {
+            return Err(ReviewValidationError::InvalidRating(rating));
+        }
+        self.check_comment(comment)
+    }
+
+    /// Validate a comment against the policy; see `check_review_text`
+    pub fn check_comment(&self, comment: &str) -> Result<String, ReviewValidationError> {
+        let trimmed = comment.trim();
+        if trimmed.is_empty() {
+            return Err(ReviewValidationError::EmptyComment);
+        }
+
+        let len = trimmed.chars().count();
+        if len < self.policy.min_comment_len {
+            return Err(ReviewValidationError::CommentTooShort {
+                min: self.policy.min_comment_len,
+                len,
+            });
+        }
+        if len > self.policy.max_comment_len {
+            return Err(ReviewValidationError::CommentTooLong {
+                max: self.policy.max_comment_len,
+                len,
+            });
+        }
+
+        match self.policy.profanity {
+            ProfanityMode::Off => Ok(trimmed.to_string()),
+            ProfanityMode::Reject => {
+                let flagged = self.profanity_filter.find(trimmed);
+                if flagged.is_empty() {
+                    Ok(trimmed.to_string())
+                } else {
+                    Err(ReviewValidationError::Profanity(flagged))
+                }
+            }
+            ProfanityMode::Mask => Ok(self.profanity_filter.mask(trimmed)),
         }
     }
 
@@ -31,7 +102,7 @@ impl ReviewService {
         comment: String,
     ) -> ServiceResult<UserReview> {
         // Validate input
-        self.validate_review_data(&rating, &comment, &store_id, &product_id)?;
+        let comment = self.validate_review_data(&rating, &comment, &store_id, &product_id)?;
 
         // Check if user already reviewed this item
         if let Some(existing) = self.find_existing_review(&user_id, &store_id, &product_id) {
@@ -57,27 +128,52 @@ impl ReviewService {
 
     /// Create a review from a provided struct
     pub fn create_review(&mut self, review: &UserReview) -> ServiceResult<UserReview> {
-        if !(1..=5).contains(&review.rating) {
-            return Err(ServiceError::ValidationError(
-                "Rating must be between 1 and 5".to_string(),
-            ));
-        }
-        if review.comment.trim().is_empty() {
-            return Err(ServiceError::ValidationError(
-                "Comment cannot be empty".to_string(),
-            ));
-        }
-        if (review.store_id.is_none() && review.product_id.is_none())
-            || (review.store_id.is_some() && review.product_id.is_some())
-        {
-            return Err(ServiceError::ValidationError(
-                "Must review either a store or a product".to_string(),
-            ));
-        }
+        let comment = self.validate_review_data(
+            &review.rating,
+            &review.comment,
+            &review.store_id,
+            &review.product_id,
+        )?;
 
         // Insert/overwrite by id
+        let review = UserReview {
+            comment,
+            ..review.clone()
+        };
         self.reviews.insert(review.id.clone(), review.clone());
-        Ok(review.clone())
+        Ok(review)
+    }
+
+    /// Check a review against the comment and rating rules without storing it
+    pub fn validate_review(&self, review: &UserReview) -> ServiceResult<()> {
+        self.validate_review_data(
+            &review.rating,
+            &review.comment,
+            &review.store_id,
+            &review.product_id,
+        )
+        .map(|_| ())
+    }
+
+    /// Replace all reviews, e.g. when importing a backup. Every review is
+    /// validated first; on error nothing is changed
+    pub fn replace_reviews(&mut self, reviews: Vec<UserReview>) -> ServiceResult<()> {
+        let mut validated = HashMap::with_capacity(reviews.len());
+        for review in reviews {
+            let comment = self.validate_review_data(
+                &review.rating,
+                &review.comment,
+                &review.store_id,
+                &review.product_id,
+            )?;
+            validated.insert(review.id.clone(), UserReview { comment, ..review });
+        }
+
+        // 只保留仍存在的评论的投票和认证状态
+        self.votes.retain(|id, _| validated.contains_key(id));
+        self.verified.retain(|id| validated.contains_key(id));
+        self.reviews = validated;
+        Ok(())
     }
 
     /// Get review by ID
@@ -101,9 +197,10 @@ impl ReviewService {
             self.validate_rating(new_rating)?;
         }
 
-        if let Some(ref new_comment) = comment {
-            self.validate_comment(new_comment)?;
-        }
+        let comment = match comment {
+            Some(new_comment) => Some(self.validate_comment(&new_comment)?),
+            None => None,
+        };
 
         let review = self
             .reviews
@@ -145,6 +242,7 @@ impl ReviewService {
         }
 
         self.reviews.remove(review_id);
+        self.votes.remove(review_id);
 
         log::info!("Review deleted: {}", review_id);
         Ok(())
@@ -226,6 +324,24 @@ impl ReviewService {
         Ok(average)
     }
 
+    /// Average rating and review count for a product; None if it has no reviews
+    pub fn product_rating(&self, product_id: &str) -> Option<RatingSummary> {
+        let ratings: Vec<i32> = self
+            .reviews
+            .values()
+            .filter(|r| r.product_id.as_deref() == Some(product_id))
+            .map(|r| r.rating)
+            .collect();
+        if ratings.is_empty() {
+            return None;
+        }
+
+        Some(RatingSummary {
+            average: ratings.iter().sum::<i32>() as f64 / ratings.len() as f64,
+            count: ratings.len(),
+        })
+    }
+
     /// Get rating distribution for a store
     pub fn get_store_rating_distribution(
         &self,
@@ -368,19 +484,49 @@ impl ReviewService {
         })
     }
 
-    /// Mark review as helpful by a user (increments counter)
-    pub fn mark_helpful(&mut self, review_id: &str, _user_id: &str) -> ServiceResult<UserReview> {
+    /// Mark review as helpful by a user
+    pub fn mark_helpful(&mut self, review_id: &str, user_id: &str) -> ServiceResult<UserReview> {
+        self.vote_review(review_id, user_id, true)
+    }
+
+    /// Record a user's helpful/unhelpful vote. Each user has at most one vote per
+    /// review; voting again replaces the previous vote.
+    pub fn vote_review(
+        &mut self,
+        review_id: &str,
+        user_id: &str,
+        helpful: bool,
+    ) -> ServiceResult<UserReview> {
         let review = self
             .reviews
             .get(review_id)
-            .cloned()
             .ok_or_else(|| ServiceError::NotFound(format!("Review {} not found", review_id)))?;
-        let count = self
-            .helpful_counts
+        if review.user_id == user_id {
+            return Err(ServiceError::BusinessRuleViolation(
+                "Cannot vote on your own review".to_string(),
+            ));
+        }
+
+        self.votes
             .entry(review_id.to_string())
-            .or_insert(0);
-        *count += 1;
-        Ok(review)
+            .or_default()
+            .insert(user_id.to_string(), helpful);
+        self.refresh_vote_counts(review_id)
+    }
+
+    /// Withdraw a user's vote on a review (no-op if there is none)
+    pub fn remove_vote(&mut self, review_id: &str, user_id: &str) -> ServiceResult<UserReview> {
+        if let Some(votes) = self.votes.get_mut(review_id) {
+            votes.remove(user_id);
+        }
+        self.refresh_vote_counts(review_id)
+    }
+
+    /// Get all reviews in the requested order
+    pub fn get_reviews_sorted(&self, by: ReviewSort) -> ServiceResult<Vec<UserReview>> {
+        let mut reviews: Vec<UserReview> = self.reviews.values().cloned().collect();
+        sort_reviews(&mut reviews, by);
+        Ok(reviews)
     }
 
     /// Get review by id (alias)
@@ -401,13 +547,32 @@ impl ReviewService {
 
     // Helper methods
 
+    fn refresh_vote_counts(&mut self, review_id: &str) -> ServiceResult<UserReview> {
+        let (helpful, unhelpful) = self
+            .votes
+            .get(review_id)
+            .map(|votes| {
+                let helpful = votes.values().filter(|&&v| v).count() as u32;
+                (helpful, votes.len() as u32 - helpful)
+            })
+            .unwrap_or((0, 0));
+
+        let review = self
+            .reviews
+            .get_mut(review_id)
+            .ok_or_else(|| ServiceError::NotFound(format!("Review {} not found", review_id)))?;
+        review.helpful_count = helpful;
+        review.unhelpful_count = unhelpful;
+        Ok(review.clone())
+    }
+
     fn validate_review_data(
         &self,
         rating: &i32,
         comment: &str,
         store_id: &Option<String>,
         product_id: &Option<String>,
-    ) -> ServiceResult<()> {
+    ) -> ServiceResult<String> {
         // Must review either a store or a product, not both or neither
         match (store_id, product_id) {
             (Some(_), Some(_)) => {
@@ -424,34 +589,18 @@ impl ReviewService {
         }
 
         self.validate_rating(*rating)?;
-        self.validate_comment(comment)?;
-
-        Ok(())
+        self.validate_comment(comment)
     }
 
     fn validate_rating(&self, rating: i32) -> ServiceResult<()> {
         if !(1..=5).contains(&rating) {
-            return Err(ServiceError::ValidationError(
-                "Rating must be between 1 and 5".to_string(),
-            ));
+            return Err(ReviewValidationError::InvalidRating(rating).into());
         }
         Ok(())
     }
 
-    fn validate_comment(&self, comment: &str) -> ServiceResult<()> {
-        if comment.trim().is_empty() {
-            return Err(ServiceError::ValidationError(
-                "Comment cannot be empty".to_string(),
-            ));
-        }
-
-        if comment.len() > 1000 {
-            return Err(ServiceError::ValidationError(
-                "Comment too long".to_string(),
-            ));
-        }
-
-        Ok(())
+    fn validate_comment(&self, comment: &str) -> ServiceResult<String> {
+        Ok(self.check_comment(comment)?)
     }
 
     fn find_existing_review(
@@ -489,6 +638,98 @@ impl ReviewService {
     }
 }
 
+/// Aggregate rating over an item's reviews
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RatingSummary {
+    pub average: f64,
+    pub count: usize,
+}
+
+impl RatingSummary {
+    /// Five-star bar rounded to the nearest star, e.g. "★★★★☆"
+    pub fn stars(&self) -> String {
+        let filled = (self.average.round().clamp(0.0, 5.0)) as usize;
+        format!("{}{}", "★".repeat(filled), "☆".repeat(5 - filled))
+    }
+}
+
+/// How flagged words in comments are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfanityMode {
+    Off,
+    /// Refuse comments containing flagged words
+    Reject,
+    /// Replace flagged words with `*`
+    Mask,
+}
+
+/// Comment validation rules for reviews
+#[derive(Debug, Clone)]
+pub struct ReviewPolicy {
+    pub min_comment_len: usize, // 按字符计
+    pub max_comment_len: usize,
+    pub profanity: ProfanityMode,
+    pub locale: Locale, // 敏感词表语言
+}
+
+impl Default for ReviewPolicy {
+    fn default() -> Self {
+        Self {
+            min_comment_len: 2,
+            max_comment_len: 1000,
+            profanity: ProfanityMode::Mask,
+            locale: Locale::Zh,
+        }
+    }
+}
+
+/// Why a review was rejected
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ReviewValidationError {
+    #[error("Rating must be between 1 and 5 (got {0})")]
+    InvalidRating(i32),
+    #[error("Comment cannot be empty")]
+    EmptyComment,
+    #[error("Comment too short: {len} characters (minimum {min})")]
+    CommentTooShort { min: usize, len: usize },
+    #[error("Comment too long: {len} characters (maximum {max})")]
+    CommentTooLong { max: usize, len: usize },
+    #[error("Comment contains inappropriate language: {}", .0.join(", "))]
+    Profanity(Vec<String>),
+}
+
+impl From<ReviewValidationError> for ServiceError {
+    fn from(error: ReviewValidationError) -> Self {
+        ServiceError::ValidationError(error.to_string())
+    }
+}
+
+/// Review ordering options
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReviewSort {
+    /// Highest net helpfulness first
+    MostHelpful,
+    Newest,
+    HighestRating,
+}
+
+/// Sort reviews in place; ties fall back to newest first
+pub fn sort_reviews(reviews: &mut [UserReview], by: ReviewSort) {
+    reviews.sort_by(|a, b| {
+        let primary = match by {
+            ReviewSort::MostHelpful => b
+                .helpfulness_score()
+                .cmp(&a.helpfulness_score())
+                .then_with(|| b.helpful_count.cmp(&a.helpful_count)),
+            ReviewSort::Newest => std::cmp::Ordering::Equal,
+            ReviewSort::HighestRating => b.rating.cmp(&a.rating),
+        };
+        primary
+            .then_with(|| b.created_at.cmp(&a.created_at))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+}
+
 impl Default for ReviewService {
     fn default() -> Self {
         Self::new()
@@ -523,3 +764,170 @@ pub struct TopReviewedItems {
     pub stores: Vec<(String, usize)>,
     pub products: Vec<(String, usize)>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn review(service: &mut ReviewService, user_id: &str, product_id: &str) -> UserReview {
+        service
+            .submit_review(
+                user_id.to_string(),
+                None,
+                Some(product_id.to_string()),
+                4,
+                "Good value".to_string(),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_vote_toggling() {
+        let mut service = ReviewService::new();
+        let r = review(&mut service, "author", "cola");
+
+        let voted = service.vote_review(&r.id, "u1", true).unwrap();
+        assert_eq!((voted.helpful_count, voted.unhelpful_count), (1, 0));
+
+        // 重复投同样的票是幂等的
+        let voted = service.vote_review(&r.id, "u1", true).unwrap();
+        assert_eq!((voted.helpful_count, voted.unhelpful_count), (1, 0));
+
+        let cleared = service.remove_vote(&r.id, "u1").unwrap();
+        assert_eq!((cleared.helpful_count, cleared.unhelpful_count), (0, 0));
+
+        let err = service.vote_review(&r.id, "author", true).unwrap_err();
+        assert!(matches!(err, ServiceError::BusinessRuleViolation(_)));
+    }
+
+    #[test]
+    fn test_second_vote_replaces_first() {
+        let mut service = ReviewService::new();
+        let r = review(&mut service, "author", "cola");
+
+        service.vote_review(&r.id, "u1", true).unwrap();
+        service.vote_review(&r.id, "u2", true).unwrap();
+        let voted = service.vote_review(&r.id, "u1", false).unwrap();
+        assert_eq!((voted.helpful_count, voted.unhelpful_count), (1, 1));
+        assert_eq!(service.get_review(&r.id).unwrap().helpful_count, 1);
+    }
+
+    #[test]
+    fn test_most_helpful_ordering() {
+        let mut service = ReviewService::new();
+        let low = review(&mut service, "a", "cola");
+        let high = review(&mut service, "b", "cola");
+        let mid = review(&mut service, "c", "cola");
+
+        for voter in ["v1", "v2", "v3"] {
+            service.vote_review(&high.id, voter, true).unwrap();
+        }
+        service.vote_review(&mid.id, "v1", true).unwrap();
+        service.vote_review(&low.id, "v1", false).unwrap();
+
+        let sorted = service.get_reviews_sorted(ReviewSort::MostHelpful).unwrap();
+        let ids: Vec<&str> = sorted.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![high.id.as_str(), mid.id.as_str(), low.id.as_str()]
+        );
+    }
+
+    #[test]
+    fn test_comment_length_validation() {
+        let service = ReviewService::new();
+
+        let long = "好".repeat(1001);
+        assert_eq!(
+            service.check_comment(&long),
+            Err(ReviewValidationError::CommentTooLong {
+                max: 1000,
+                len: 1001
+            })
+        );
+        assert_eq!(
+            service.check_comment("   "),
+            Err(ReviewValidationError::EmptyComment)
+        );
+        assert_eq!(
+            service.check_review_text(6, "Nice store"),
+            Err(ReviewValidationError::InvalidRating(6))
+        );
+        assert_eq!(
+            service.check_review_text(5, "  Fresh produce, fair prices  "),
+            Ok("Fresh produce, fair prices".to_string())
+        );
+    }
+
+    #[test]
+    fn test_submit_rejects_invalid_comment() {
+        let mut service = ReviewService::new();
+        let err = service
+            .submit_review(
+                "u1".to_string(),
+                None,
+                Some("p".to_string()),
+                4,
+                "".to_string(),
+            )
+            .unwrap_err();
+        assert!(matches!(err, ServiceError::ValidationError(_)));
+    }
+
+    #[test]
+    fn test_profanity_masked_or_rejected() {
+        let mut service = ReviewService::new().with_policy(ReviewPolicy {
+            locale: Locale::En,
+            ..ReviewPolicy::default()
+        });
+        let review = service
+            .submit_review(
+                "u1".to_string(),
+                Some("s1".to_string()),
+                None,
+                1,
+                "Damn slow checkout, scrappy shelves".to_string(),
+            )
+            .unwrap();
+        assert_eq!(review.comment, "**** slow checkout, scrappy shelves");
+
+        let zh = ReviewService::new();
+        assert_eq!(
+            zh.check_comment("这家店真是他妈的贵").unwrap(),
+            "这家店真是***贵"
+        );
+
+        service.set_policy(ReviewPolicy {
+            profanity: ProfanityMode::Reject,
+            locale: Locale::En,
+            ..ReviewPolicy::default()
+        });
+        assert_eq!(
+            service.check_comment("What a crap deal"),
+            Err(ReviewValidationError::Profanity(vec!["crap".to_string()]))
+        );
+    }
+
+    #[test]
+    fn test_product_rating_summary() {
+        let mut service = ReviewService::new();
+        for (user, rating) in [("u1", 5), ("u2", 4), ("u3", 2)] {
+            service
+                .submit_review(
+                    user.to_string(),
+                    None,
+                    Some("cola".to_string()),
+                    rating,
+                    "Tastes fine".to_string(),
+                )
+                .unwrap();
+        }
+
+        let summary = service.product_rating("cola").unwrap();
+        assert_eq!(summary.count, 3);
+        assert!((summary.average - 11.0 / 3.0).abs() < 1e-9);
+        assert_eq!(summary.stars(), "★★★★☆");
+
+        assert_eq!(service.product_rating("chips"), None);
+    }
+}
@@ -1,17 +1,40 @@
-use crate::models::Store;
-use crate::services::{ServiceError, ServiceResult};
+use crate::models::{OpeningHours, Store, UserReview};
+use crate::search::engine::similarity;
+use crate::services::{DistanceCache, Geocoder, ImportReport, ServiceError, ServiceResult};
+use crate::utils::{DEFAULT_PHONE_REGION, normalize_phone, normalize_width, validation};
+use chrono::NaiveDateTime;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 /// Store service for managing store operations and business logic
 pub struct StoreService {
     /// In-memory store cache (in real app would use database)
     stores: HashMap<String, Store>,
+    /// Review ratings per store id, synced from the review service
+    review_ratings: HashMap<String, Vec<i32>>,
+    /// Fills coordinates for stores created with only an address
+    geocoder: Option<Arc<dyn Geocoder>>,
+    /// Memoized store distances for repeated nearby / basket queries
+    distance_cache: DistanceCache,
+    /// Minimum fuzzy similarity for a store to match a search query
+    fuzzy_threshold: f32,
 }
 
+/// Neutral rating assumed before any reviews (Bayesian prior mean)
+pub const RATING_PRIOR_MEAN: f64 = 3.0;
+/// Weight of the prior, in number of virtual reviews
+pub const RATING_PRIOR_WEIGHT: f64 = 5.0;
+/// Default minimum similarity for fuzzy store search (one typo in a 4-letter word passes)
+pub const DEFAULT_STORE_FUZZY_THRESHOLD: f32 = 0.7;
+
 impl StoreService {
     pub fn new() -> Self {
         let mut service = Self {
             stores: HashMap::new(),
+            review_ratings: HashMap::new(),
+            geocoder: None,
+            distance_cache: DistanceCache::new(),
+            fuzzy_threshold: DEFAULT_STORE_FUZZY_THRESHOLD,
         };
 
         // Initialize with sample stores
@@ -19,7 +42,23 @@ impl StoreService {
         service
     }
 
-    /// Create a new store
+    /// Look up coordinates for stores created without them
+    pub fn with_geocoder(mut self, geocoder: Arc<dyn Geocoder>) -> Self {
+        self.geocoder = Some(geocoder);
+        self
+    }
+
+    pub fn set_geocoder(&mut self, geocoder: Arc<dyn Geocoder>) {
+        self.geocoder = Some(geocoder);
+    }
+
+    /// Minimum similarity (0.0–1.0) a store needs to match a fuzzy search; 1.0 disables typos
+    pub fn set_fuzzy_threshold(&mut self, threshold: f32) {
+        self.fuzzy_threshold = threshold.clamp(0.0, 1.0);
+    }
+
+    /// Create a new store. Coordinates of (0, 0) are geocoded from the address when a
+    /// geocoder is set; see [`Self::create_store_with_warnings`].
     #[allow(clippy::too_many_arguments)]
     pub fn create_store(
         &mut self,
@@ -32,9 +71,65 @@ impl StoreService {
         tags: Vec<String>,
         symbol: char,
     ) -> ServiceResult<Store> {
+        let (store, warnings) = self.create_store_with_warnings(
+            name,
+            address,
+            latitude,
+            longitude,
+            opening_hours,
+            phone,
+            tags,
+            symbol,
+        )?;
+        for warning in &warnings {
+            log::warn!("{}", warning);
+        }
+        Ok(store)
+    }
+
+    /// Create a new store, returning non-fatal warnings alongside it.
+    /// If geocoding fails the store keeps the (0, 0) sentinel coordinates;
+    /// opening hours that cannot be canonicalized are kept as entered.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_store_with_warnings(
+        &mut self,
+        name: String,
+        address: String,
+        latitude: f64,
+        longitude: f64,
+        opening_hours: String,
+        phone: String,
+        tags: Vec<String>,
+        symbol: char,
+    ) -> ServiceResult<(Store, Vec<String>)> {
         // Validate input
         self.validate_store_data(&name, &address, latitude, longitude, &phone)?;
 
+        let mut warnings = Vec::new();
+        let (opening_hours, hours_warning) = Self::checked_opening_hours(opening_hours);
+        warnings.extend(hours_warning);
+
+        // 只有地址、没有坐标时尝试地理编码
+        let (latitude, longitude) = match &self.geocoder {
+            Some(geocoder) if latitude == 0.0 && longitude == 0.0 => {
+                match geocoder.geocode(&address) {
+                    Ok((lat, lon)) if self.validate_coordinates(lat, lon).is_ok() => (lat, lon),
+                    Ok((lat, lon)) => {
+                        warnings.push(format!(
+                            "Geocoder returned invalid coordinates ({}, {}) for '{}'",
+                            lat, lon, address
+                        ));
+                        (latitude, longitude)
+                    }
+                    Err(e) => {
+                        warnings.push(format!("Geocoding failed for '{}': {}", address, e));
+                        (latitude, longitude)
+                    }
+                }
+            }
+            _ => (latitude, longitude),
+        };
+
         // Create store
         let store = Store::new(
             name,
@@ -51,7 +146,7 @@ impl StoreService {
         self.stores.insert(store.id.clone(), store.clone());
 
         log::info!("Store created: {}", store.name);
-        Ok(store)
+        Ok((store, warnings))
     }
 
     /// Get store by ID
@@ -62,7 +157,7 @@ impl StoreService {
             .ok_or_else(|| ServiceError::NotFound(format!("Store {} not found", store_id)))
     }
 
-    /// Update store information
+    /// Update store information; see [`Self::update_store_with_warnings`].
     #[allow(clippy::too_many_arguments)]
     pub fn update_store(
         &mut self,
@@ -75,6 +170,36 @@ impl StoreService {
         phone: Option<String>,
         tags: Option<Vec<String>>,
     ) -> ServiceResult<Store> {
+        let (store, warnings) = self.update_store_with_warnings(
+            store_id,
+            name,
+            address,
+            latitude,
+            longitude,
+            opening_hours,
+            phone,
+            tags,
+        )?;
+        for warning in &warnings {
+            log::warn!("{}", warning);
+        }
+        Ok(store)
+    }
+
+    /// Update store information, returning non-fatal warnings alongside it.
+    /// Opening hours that cannot be canonicalized are kept as entered.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_store_with_warnings(
+        &mut self,
+        store_id: &str,
+        name: Option<String>,
+        address: Option<String>,
+        latitude: Option<f64>,
+        longitude: Option<f64>,
+        opening_hours: Option<String>,
+        phone: Option<String>,
+        tags: Option<Vec<String>>,
+    ) -> ServiceResult<(Store, Vec<String>)> {
         // Validate inputs first
         if let Some(ref new_name) = name {
             self.validate_store_name(new_name)?;
@@ -122,7 +247,10 @@ impl StoreService {
             store.longitude = lng;
         }
 
+        let mut warnings = Vec::new();
         if let Some(new_hours) = opening_hours {
+            let (new_hours, warning) = Self::checked_opening_hours(new_hours);
+            warnings.extend(warning);
             store.opening_hours = new_hours;
         }
 
@@ -135,7 +263,7 @@ impl StoreService {
         }
 
         log::info!("Store updated: {}", store.name);
-        Ok(store.clone())
+        Ok((store.clone(), warnings))
     }
 
     /// Delete store
@@ -149,6 +277,25 @@ impl StoreService {
         Ok(())
     }
 
+    /// Re-insert a previously deleted store unchanged (used by undo)
+    pub fn restore_store(&mut self, store: Store) -> ServiceResult<()> {
+        if self.stores.contains_key(&store.id) {
+            return Err(ServiceError::Conflict(format!(
+                "Store {} already exists",
+                store.id
+            )));
+        }
+        log::info!("Store restored: {}", store.name);
+        self.stores.insert(store.id.clone(), store);
+        Ok(())
+    }
+
+    /// Replace all stores, e.g. when importing a backup
+    pub fn replace_stores(&mut self, stores: Vec<Store>) {
+        self.stores = stores.into_iter().map(|s| (s.id.clone(), s)).collect();
+        log::info!("Stores replaced: {}", self.stores.len());
+    }
+
     /// Search stores by name or address
     pub fn search_stores(&self, query: &str) -> ServiceResult<Vec<Store>> {
         let query_lower = query.to_lowercase();
@@ -156,19 +303,119 @@ impl StoreService {
         let stores: Vec<Store> = self
             .stores
             .values()
-            .filter(|s| {
-                s.name.to_lowercase().contains(&query_lower)
-                    || s.address.to_lowercase().contains(&query_lower)
-                    || s.tags
-                        .iter()
-                        .any(|tag| tag.to_lowercase().contains(&query_lower))
-            })
+            .filter(|s| Self::matches_text(s, &query_lower))
             .cloned()
             .collect();
 
         Ok(stores)
     }
 
+    /// Stores in `stores` matching `query` (name, address or tag, typos tolerated) within
+    /// `radius_km` of the given location, best match first and then nearest first
+    pub fn search_nearby(
+        &self,
+        query: &str,
+        latitude: f64,
+        longitude: f64,
+        radius_km: f64,
+        stores: &[Store],
+    ) -> Vec<(Store, f64)> {
+        let query_lower = query.trim().to_lowercase();
+
+        let mut matches: Vec<(Store, f64, f32)> = stores
+            .iter()
+            .filter_map(|s| self.match_score(s, &query_lower).map(|score| (s, score)))
+            .map(|(s, score)| (s.clone(), self.distance_from(s, latitude, longitude), score))
+            .filter(|(_, distance, _)| *distance <= radius_km)
+            .collect();
+
+        matches.sort_by(|a, b| {
+            b.2.total_cmp(&a.2)
+                .then(a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+        });
+        matches
+            .into_iter()
+            .map(|(store, distance, _)| (store, distance))
+            .collect()
+    }
+
+    /// Stores in `stores` that are open at local time `now`.
+    /// Stores with unparseable opening hours are excluded.
+    pub fn filter_open_now(&self, stores: &[Store], now: NaiveDateTime) -> Vec<Store> {
+        stores
+            .iter()
+            .filter(|s| s.open_status(now).is_open())
+            .cloned()
+            .collect()
+    }
+
+    /// Cached distance in km from `store` to a location
+    fn distance_from(&self, store: &Store, latitude: f64, longitude: f64) -> f64 {
+        self.distance_cache
+            .distance((store.latitude, store.longitude), (latitude, longitude))
+    }
+
+    /// `matrix[i][j]` is the distance in km from `origins[i]` to `stores[j]`,
+    /// served from the shared distance cache
+    pub fn distance_matrix(&self, origins: &[(f64, f64)], stores: &[Store]) -> Vec<Vec<f64>> {
+        let destinations: Vec<(f64, f64)> =
+            stores.iter().map(|s| (s.latitude, s.longitude)).collect();
+        self.distance_cache.distance_matrix(origins, &destinations)
+    }
+
+    /// 名称、地址或标签包含关键词（需已转小写；空关键词匹配全部）
+    fn matches_text(store: &Store, query_lower: &str) -> bool {
+        store.name.to_lowercase().contains(query_lower)
+            || store.address.to_lowercase().contains(query_lower)
+            || store
+                .tags
+                .iter()
+                .any(|tag| tag.to_lowercase().contains(query_lower))
+    }
+
+    /// How well `store` matches a lowercased query: 1.0 for a substring match, otherwise the
+    /// mean over query words of their best similarity to a name/address/tag word.
+    /// None when below the fuzzy threshold.
+    fn match_score(&self, store: &Store, query_lower: &str) -> Option<f32> {
+        if Self::matches_text(store, query_lower) {
+            return Some(1.0);
+        }
+
+        let words: Vec<String> = std::iter::once(store.name.as_str())
+            .chain(std::iter::once(store.address.as_str()))
+            .chain(store.tags.iter().map(String::as_str))
+            .flat_map(|text| text.split(|c: char| !c.is_alphanumeric()))
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+        let terms: Vec<&str> = query_lower
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|term| !term.is_empty())
+            .collect();
+        if terms.is_empty() || words.is_empty() {
+            return None;
+        }
+
+        let total: f32 = terms
+            .iter()
+            .map(|term| {
+                words
+                    .iter()
+                    .map(|word| {
+                        if word.contains(term) {
+                            1.0
+                        } else {
+                            similarity(term, word)
+                        }
+                    })
+                    .fold(0.0, f32::max)
+            })
+            .sum();
+        let score = total / terms.len() as f32;
+        // 模糊匹配得分低于 1.0，保证精确匹配排在前面
+        (score >= self.fuzzy_threshold).then_some(score.min(0.99))
+    }
+
     /// Find stores near a location
     pub fn find_stores_near(
         &self,
@@ -180,7 +427,7 @@ impl StoreService {
             .stores
             .values()
             .map(|store| {
-                let distance = store.distance_to(latitude, longitude);
+                let distance = self.distance_from(store, latitude, longitude);
                 StoreDistance {
                     store: store.clone(),
                     distance_km: distance,
@@ -245,6 +492,43 @@ impl StoreService {
         Ok(stores)
     }
 
+    /// Replace the cached review ratings with the store reviews in `reviews`
+    pub fn sync_reviews(&mut self, reviews: &[UserReview]) {
+        self.review_ratings.clear();
+        for review in reviews {
+            if let Some(ref store_id) = review.store_id {
+                self.review_ratings
+                    .entry(store_id.clone())
+                    .or_default()
+                    .push(review.rating);
+            }
+        }
+    }
+
+    /// Recompute a store's rating from its reviews using a Bayesian average,
+    /// so a single review cannot dominate; stores without reviews get the prior
+    pub fn recompute_rating(&mut self, store_id: &str) -> ServiceResult<f64> {
+        let ratings = self
+            .review_ratings
+            .get(store_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let sum: f64 = ratings.iter().map(|&r| f64::from(r)).sum();
+        let rating = (RATING_PRIOR_MEAN * RATING_PRIOR_WEIGHT + sum)
+            / (RATING_PRIOR_WEIGHT + ratings.len() as f64);
+
+        self.update_store_rating(store_id, rating)
+    }
+
+    /// Recompute every store's rating; returns the number of stores updated
+    pub fn recompute_all_ratings(&mut self) -> ServiceResult<usize> {
+        let ids: Vec<String> = self.stores.keys().cloned().collect();
+        for id in &ids {
+            self.recompute_rating(id)?;
+        }
+        Ok(ids.len())
+    }
+
     /// Update store rating
     pub fn update_store_rating(&mut self, store_id: &str, new_rating: f64) -> ServiceResult<f64> {
         let store = self
@@ -318,6 +602,207 @@ impl StoreService {
 
     // Helper methods
 
+    /// Import stores from a GeoJSON `FeatureCollection` of Point features
+    pub fn import_geojson(&mut self, geojson: &str) -> ServiceResult<ImportReport> {
+        let root: serde_json::Value = serde_json::from_str(geojson)
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid GeoJSON: {}", e)))?;
+
+        if root.get("type").and_then(|t| t.as_str()) != Some("FeatureCollection") {
+            return Err(ServiceError::ValidationError(
+                "GeoJSON root must be a FeatureCollection".to_string(),
+            ));
+        }
+
+        let features = root
+            .get("features")
+            .and_then(|f| f.as_array())
+            .ok_or_else(|| {
+                ServiceError::ValidationError("FeatureCollection has no features array".to_string())
+            })?;
+
+        let mut report = ImportReport::default();
+        for (index, feature) in features.iter().enumerate() {
+            match self.import_geojson_feature(feature) {
+                Ok(Some(mut store)) => {
+                    let (hours, warning) =
+                        Self::checked_opening_hours(std::mem::take(&mut store.opening_hours));
+                    store.opening_hours = hours;
+                    if let Some(warning) = warning {
+                        report
+                            .warnings
+                            .push(format!("feature {}: {}", index, warning));
+                    }
+                    report.imported_ids.push(store.id.clone());
+                    self.stores.insert(store.id.clone(), store);
+                }
+                Ok(None) => report.skipped.push(format!(
+                    "feature {}: unsupported geometry type {}",
+                    index,
+                    feature
+                        .pointer("/geometry/type")
+                        .and_then(|t| t.as_str())
+                        .unwrap_or("null")
+                )),
+                Err(e) => report.errors.push(format!("feature {}: {}", index, e)),
+            }
+        }
+
+        log::info!(
+            "GeoJSON import: {} imported, {} skipped, {} errors, {} warnings",
+            report.imported_ids.len(),
+            report.skipped.len(),
+            report.errors.len(),
+            report.warnings.len()
+        );
+        Ok(report)
+    }
+
+    /// Export all stores as a GeoJSON `FeatureCollection` of Point features
+    pub fn export_geojson(&self) -> ServiceResult<String> {
+        let mut stores: Vec<&Store> = self.stores.values().collect();
+        stores.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.id.cmp(&b.id)));
+
+        let features: Vec<serde_json::Value> = stores
+            .into_iter()
+            .map(|store| {
+                serde_json::json!({
+                    "type": "Feature",
+                    "id": store.id,
+                    "geometry": {
+                        "type": "Point",
+                        // GeoJSON 规范要求 [经度, 纬度]
+                        "coordinates": [store.longitude, store.latitude]
+                    },
+                    "properties": {
+                        "name": store.name,
+                        "address": store.address,
+                        "phone": store.phone,
+                        "opening_hours": store.opening_hours,
+                        "tags": store.tags,
+                        "rating": store.rating
+                    }
+                })
+            })
+            .collect();
+
+        let collection = serde_json::json!({
+            "type": "FeatureCollection",
+            "features": features
+        });
+
+        serde_json::to_string_pretty(&collection).map_err(|e| {
+            ServiceError::ExternalServiceError(format!("GeoJSON export failed: {}", e))
+        })
+    }
+
+    /// Convert one feature into a store; `Ok(None)` for non-Point geometries
+    fn import_geojson_feature(&self, feature: &serde_json::Value) -> ServiceResult<Option<Store>> {
+        let geometry = feature
+            .get("geometry")
+            .ok_or_else(|| ServiceError::ValidationError("Missing geometry".to_string()))?;
+
+        match geometry.get("type").and_then(|t| t.as_str()) {
+            Some("Point") => {}
+            Some(_) => return Ok(None),
+            None => {
+                return Err(ServiceError::ValidationError(
+                    "Geometry has no type".to_string(),
+                ));
+            }
+        }
+
+        // GeoJSON 坐标顺序为 [经度, 纬度]
+        let coordinates: Vec<f64> = geometry
+            .get("coordinates")
+            .and_then(|c| c.as_array())
+            .map(|c| c.iter().filter_map(|v| v.as_f64()).collect())
+            .unwrap_or_default();
+        if coordinates.len() < 2 {
+            return Err(ServiceError::ValidationError(
+                "Point coordinates must be [longitude, latitude]".to_string(),
+            ));
+        }
+        let (longitude, latitude) = (coordinates[0], coordinates[1]);
+        self.validate_coordinates(latitude, longitude)?;
+
+        let properties = feature.get("properties");
+        let text = |key: &str| {
+            properties
+                .and_then(|p| p.get(key))
+                .and_then(|v| v.as_str())
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+
+        let name = text("name").unwrap_or_else(|| "Unnamed store".to_string());
+        self.validate_store_name(&name)?;
+        let address = text("address").unwrap_or_else(|| "Unknown address".to_string());
+        self.validate_address(&address)?;
+        let phone = text("phone").unwrap_or_default();
+        if !phone.is_empty() {
+            self.validate_phone(&phone)?;
+        }
+        let opening_hours = text("opening_hours").unwrap_or_default();
+
+        let tags = match properties.and_then(|p| p.get("tags")) {
+            Some(serde_json::Value::Array(values)) => values
+                .iter()
+                .filter_map(|v| v.as_str())
+                .map(|v| v.to_string())
+                .collect(),
+            Some(serde_json::Value::String(value)) => value
+                .split(',')
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        Ok(Some(Store::new(
+            name,
+            address,
+            latitude,
+            longitude,
+            opening_hours,
+            phone,
+            tags,
+            '🏪',
+        )))
+    }
+
+    /// Canonical form of an opening-hours string (see [`OpeningHours::from_string`]),
+    /// accepting full-width digits and punctuation and separators such as "〜".
+    /// None when the text cannot be parsed
+    pub fn canonicalize_opening_hours(text: &str) -> Option<String> {
+        let normalized: String = normalize_width(text)
+            .chars()
+            .map(|c| match c {
+                '~' | '〜' | '–' | '—' | '−' => '-',
+                ',' | '、' => ';',
+                _ => c,
+            })
+            .collect();
+        OpeningHours::from_string(&normalized).map(|hours| hours.to_canonical_string())
+    }
+
+    /// Opening hours to store, with a warning when they cannot be parsed and
+    /// are kept as entered
+    fn checked_opening_hours(text: String) -> (String, Option<String>) {
+        if text.trim().is_empty() {
+            return (text, None);
+        }
+        match Self::canonicalize_opening_hours(&text) {
+            Some(canonical) => (canonical, None),
+            None => {
+                let warning = format!(
+                    "Opening hours '{}' could not be parsed and were stored as entered",
+                    text
+                );
+                (text, Some(warning))
+            }
+        }
+    }
+
     fn validate_store_data(
         &self,
         name: &str,
@@ -366,19 +851,7 @@ impl StoreService {
     }
 
     fn validate_coordinates(&self, latitude: f64, longitude: f64) -> ServiceResult<()> {
-        if !(-90.0..=90.0).contains(&latitude) {
-            return Err(ServiceError::ValidationError(
-                "Invalid latitude".to_string(),
-            ));
-        }
-
-        if !(-180.0..=180.0).contains(&longitude) {
-            return Err(ServiceError::ValidationError(
-                "Invalid longitude".to_string(),
-            ));
-        }
-
-        Ok(())
+        validation::validate_coordinates(latitude, longitude).map_err(ServiceError::ValidationError)
     }
 
     fn validate_phone(&self, phone: &str) -> ServiceResult<()> {
@@ -395,6 +868,11 @@ impl StoreService {
             ));
         }
 
+        // 无法规范化为 E.164 时只记录警告，不阻止保存
+        if normalize_phone(phone, DEFAULT_PHONE_REGION).is_none() {
+            log::warn!("Store phone number could not be normalized: {}", phone);
+        }
+
         Ok(())
     }
 
@@ -479,3 +957,648 @@ pub struct StoreStats {
     pub tag_counts: HashMap<String, usize>,
     pub stores_by_rating: HashMap<String, usize>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::OpenStatus;
+
+    #[test]
+    fn test_import_geojson_two_features() {
+        let mut service = StoreService::new();
+        let initial = service.stores.len();
+        let geojson = r#"{
+            "type": "FeatureCollection",
+            "features": [
+                {
+                    "type": "Feature",
+                    "geometry": {"type": "Point", "coordinates": [139.7671, 35.6812]},
+                    "properties": {
+                        "name": "Lawson Marunouchi",
+                        "address": "Tokyo, Chiyoda 1-1",
+                        "phone": "03-1111-2222",
+                        "opening_hours": "24 hours",
+                        "tags": ["convenience", "24h"]
+                    }
+                },
+                {
+                    "type": "Feature",
+                    "geometry": {"type": "Point", "coordinates": [139.6917, 35.6895]},
+                    "properties": {"name": "Shinjuku Market", "tags": "grocery, fresh"}
+                }
+            ]
+        }"#;
+
+        let report = service.import_geojson(geojson).unwrap();
+        assert_eq!(report.imported_count(), 2);
+        assert!(report.errors.is_empty());
+        assert_eq!(service.stores.len(), initial + 2);
+
+        let lawson = service.get_store(&report.imported_ids[0]).unwrap();
+        assert_eq!(lawson.latitude, 35.6812);
+        assert_eq!(lawson.longitude, 139.7671);
+        assert_eq!(lawson.phone, "03-1111-2222");
+        assert_eq!(lawson.tags, vec!["convenience", "24h"]);
+
+        let market = service.get_store(&report.imported_ids[1]).unwrap();
+        assert_eq!(market.address, "Unknown address");
+        assert_eq!(market.tags, vec!["grocery", "fresh"]);
+    }
+
+    #[test]
+    fn test_import_geojson_reports_malformed_and_skipped() {
+        let mut service = StoreService::new();
+        let geojson = r#"{
+            "type": "FeatureCollection",
+            "features": [
+                {"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7]},
+                 "properties": {"name": "Broken"}},
+                {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[0, 0], [1, 1]]},
+                 "properties": {"name": "Road"}}
+            ]
+        }"#;
+
+        let report = service.import_geojson(geojson).unwrap();
+        assert_eq!(report.imported_count(), 0);
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].starts_with("feature 0"));
+        assert_eq!(report.skipped.len(), 1);
+
+        assert!(service.import_geojson(r#"{"type": "Feature"}"#).is_err());
+    }
+
+    #[test]
+    fn test_out_of_range_coordinates_are_rejected() {
+        let mut service = StoreService::new();
+        let result = service.create_store(
+            "Far Away".to_string(),
+            "Nowhere 1-1".to_string(),
+            35.0,
+            500.0,
+            "09:00-21:00".to_string(),
+            "03-1234-5678".to_string(),
+            vec![],
+            '🏪',
+        );
+        assert!(
+            matches!(result, Err(ServiceError::ValidationError(msg)) if msg.starts_with("Longitude 500"))
+        );
+
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7, 95.0]},
+             "properties": {"name": "North"}}
+        ]}"#;
+        let report = service.import_geojson(geojson).unwrap();
+        assert_eq!(report.imported_count(), 0);
+        assert!(report.errors[0].contains("Latitude 95 is out of range"));
+    }
+
+    #[test]
+    fn test_export_geojson_round_trip() {
+        let mut service = StoreService::new();
+        service.stores.clear();
+        service
+            .create_store(
+                "Alpha Mart".to_string(),
+                "Osaka, Kita 1-1".to_string(),
+                34.7025,
+                135.4959,
+                "09:00-21:00".to_string(),
+                "06-1234-5678".to_string(),
+                vec!["grocery".to_string()],
+                '🏪',
+            )
+            .unwrap();
+        service
+            .create_store(
+                "Beta Drug".to_string(),
+                "Sapporo, Chuo 2-2".to_string(),
+                43.0618,
+                141.3545,
+                "24 hours".to_string(),
+                "011-123-4567".to_string(),
+                vec!["pharmacy".to_string(), "24h".to_string()],
+                '🏪',
+            )
+            .unwrap();
+
+        let exported = service.export_geojson().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&exported).unwrap();
+        assert_eq!(value["type"], "FeatureCollection");
+        let features = value["features"].as_array().unwrap();
+        assert_eq!(features.len(), 2);
+
+        let alpha = &features[0];
+        assert_eq!(alpha["geometry"]["type"], "Point");
+        assert_eq!(alpha["geometry"]["coordinates"][0], 135.4959);
+        assert_eq!(alpha["geometry"]["coordinates"][1], 34.7025);
+        assert_eq!(alpha["properties"]["name"], "Alpha Mart");
+        assert_eq!(alpha["properties"]["phone"], "06-1234-5678");
+
+        // 导出结果可以重新导入
+        let mut imported = StoreService::new();
+        imported.stores.clear();
+        let report = imported.import_geojson(&exported).unwrap();
+        assert_eq!(report.imported_count(), 2);
+        let beta = imported.get_store(&report.imported_ids[1]).unwrap();
+        assert_eq!(beta.name, "Beta Drug");
+        assert_eq!(beta.latitude, 43.0618);
+        assert_eq!(beta.longitude, 141.3545);
+        assert_eq!(beta.opening_hours, "24 hours");
+        assert_eq!(beta.tags, vec!["pharmacy", "24h"]);
+    }
+
+    #[test]
+    fn test_recompute_rating_bayesian() {
+        let mut service = StoreService::new();
+        let reviewed = service
+            .create_store(
+                "Reviewed".to_string(),
+                "1 Main St".to_string(),
+                35.0,
+                139.0,
+                "9:00-21:00".to_string(),
+                "03-1234-5678".to_string(),
+                vec![],
+                '🏪',
+            )
+            .unwrap();
+        let quiet = service
+            .create_store(
+                "Quiet".to_string(),
+                "2 Main St".to_string(),
+                35.0,
+                139.0,
+                "9:00-21:00".to_string(),
+                "03-1234-5679".to_string(),
+                vec![],
+                '🏪',
+            )
+            .unwrap();
+
+        let reviews: Vec<UserReview> = [5, 5, 4]
+            .iter()
+            .enumerate()
+            .map(|(i, &rating)| {
+                UserReview::new(
+                    format!("u{}", i),
+                    Some(reviewed.id.clone()),
+                    None,
+                    rating,
+                    "ok".to_string(),
+                )
+            })
+            .collect();
+        service.sync_reviews(&reviews);
+
+        let rating = service.recompute_rating(&reviewed.id).unwrap();
+        // (3.0 * 5 + 14) / (5 + 3)
+        assert!((rating - 29.0 / 8.0).abs() < 1e-9);
+        assert_eq!(service.get_store(&reviewed.id).unwrap().rating, rating);
+
+        assert_eq!(
+            service.recompute_rating(&quiet.id).unwrap(),
+            RATING_PRIOR_MEAN
+        );
+        assert!(service.recompute_all_ratings().unwrap() >= 2);
+    }
+
+    #[test]
+    fn test_search_nearby_combines_text_and_radius() {
+        let service = StoreService::new();
+        let make = |name: &str, latitude: f64, longitude: f64| {
+            Store::new(
+                name.to_string(),
+                "Tokyo".to_string(),
+                latitude,
+                longitude,
+                "9:00-21:00".to_string(),
+                "03-1234-5678".to_string(),
+                vec!["grocery".to_string()],
+                '🏪',
+            )
+        };
+        let stores = vec![
+            make("Fresh Mart Far", 35.0, 135.0),
+            make("Fresh Mart Shinjuku", 35.6895, 139.6917),
+            make("Fresh Mart Tokyo", 35.6812, 139.7671),
+            make("Book Corner", 35.6812, 139.7671),
+        ];
+
+        let results = service.search_nearby("fresh", 35.6812, 139.7671, 10.0, &stores);
+        let names: Vec<&str> = results.iter().map(|(s, _)| s.name.as_str()).collect();
+        // 文本匹配但超出半径的门店被排除，结果按距离排序
+        assert_eq!(names, vec!["Fresh Mart Tokyo", "Fresh Mart Shinjuku"]);
+        assert!(results[0].1 < results[1].1);
+        assert!(results[1].1 <= 10.0);
+
+        let all_nearby = service.search_nearby("", 35.6812, 139.7671, 10.0, &stores);
+        assert_eq!(all_nearby.len(), 3);
+    }
+
+    fn store_named(name: &str, address: &str) -> Store {
+        Store::new(
+            name.to_string(),
+            address.to_string(),
+            35.6812,
+            139.7671,
+            "9:00-21:00".to_string(),
+            "03-1234-5678".to_string(),
+            vec!["grocery".to_string()],
+            '🏪',
+        )
+    }
+
+    #[test]
+    fn test_search_nearby_tolerates_one_typo() {
+        let service = StoreService::new();
+        let stores = vec![
+            store_named("Green Market", "1-2 Ginza"),
+            store_named("Book Corner", "3-4 Shibuya"),
+        ];
+
+        let results = service.search_nearby("Grean", 35.6812, 139.7671, 10.0, &stores);
+        let names: Vec<&str> = results.iter().map(|(s, _)| s.name.as_str()).collect();
+        assert_eq!(names, vec!["Green Market"]);
+
+        // 地址中的拼写错误同样可以匹配
+        let results = service.search_nearby("shibuja", 35.6812, 139.7671, 10.0, &stores);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0.name, "Book Corner");
+    }
+
+    #[test]
+    fn test_search_nearby_exact_match_ranks_first() {
+        let service = StoreService::new();
+        // 模糊匹配的门店更近，但精确匹配仍排第一
+        let mut near = store_named("Mert", "Ginza");
+        near.latitude = 35.6812;
+        let mut far = store_named("Mart", "Shinjuku");
+        far.latitude = 35.69;
+        let stores = vec![near, far];
+
+        let results = service.search_nearby("mart", 35.6812, 139.7671, 10.0, &stores);
+        let names: Vec<&str> = results.iter().map(|(s, _)| s.name.as_str()).collect();
+        assert_eq!(names, vec!["Mart", "Mert"]);
+    }
+
+    #[test]
+    fn test_search_nearby_unrelated_query_and_threshold() {
+        let mut service = StoreService::new();
+        let stores = vec![store_named("Green Market", "1-2 Ginza")];
+
+        assert!(
+            service
+                .search_nearby("pharmacy", 35.6812, 139.7671, 10.0, &stores)
+                .is_empty()
+        );
+
+        service.set_fuzzy_threshold(1.0);
+        assert!(
+            service
+                .search_nearby("Grean", 35.6812, 139.7671, 10.0, &stores)
+                .is_empty()
+        );
+    }
+
+    fn store_with_hours(name: &str, hours: &str) -> Store {
+        Store::new(
+            name.to_string(),
+            "Tokyo".to_string(),
+            35.6812,
+            139.7671,
+            hours.to_string(),
+            "03-1234-5678".to_string(),
+            vec![],
+            '🏪',
+        )
+    }
+
+    fn at(date: &str, time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_open_status_open_and_closed() {
+        let store = store_with_hours("Day", "09:00-21:00");
+        assert_eq!(
+            store.open_status(at("2025-03-10", "12:30")),
+            OpenStatus::Open {
+                closes_at: Some(at("2025-03-10", "21:00"))
+            }
+        );
+        // 早于开门时间：当天开门
+        assert_eq!(
+            store.open_status(at("2025-03-10", "07:00")),
+            OpenStatus::Closed {
+                opens_at: at("2025-03-10", "09:00")
+            }
+        );
+        // 打烊后：次日开门
+        assert_eq!(
+            store.open_status(at("2025-03-10", "21:00")),
+            OpenStatus::Closed {
+                opens_at: at("2025-03-11", "09:00")
+            }
+        );
+        assert_eq!(
+            store_with_hours("All", "24 hours").open_status(at("2025-03-10", "03:00")),
+            OpenStatus::Open { closes_at: None }
+        );
+    }
+
+    #[test]
+    fn test_open_status_cross_midnight() {
+        let store = store_with_hours("Night", "22:00-02:00");
+        assert_eq!(
+            store.open_status(at("2025-03-10", "23:59")),
+            OpenStatus::Open {
+                closes_at: Some(at("2025-03-11", "02:00"))
+            }
+        );
+        assert_eq!(
+            store.open_status(at("2025-03-11", "00:30")),
+            OpenStatus::Open {
+                closes_at: Some(at("2025-03-11", "02:00"))
+            }
+        );
+        assert_eq!(
+            store.open_status(at("2025-03-11", "02:00")),
+            OpenStatus::Closed {
+                opens_at: at("2025-03-11", "22:00")
+            }
+        );
+    }
+
+    #[test]
+    fn test_open_status_unparseable_and_filter() {
+        let unknown = store_with_hours("Unknown", "ask staff");
+        assert_eq!(
+            unknown.open_status(at("2025-03-10", "12:00")),
+            OpenStatus::Unknown
+        );
+        assert_eq!(
+            store_with_hours("Bad", "25:00-26:00").open_status(at("2025-03-10", "12:00")),
+            OpenStatus::Unknown
+        );
+
+        let service = StoreService::new();
+        let stores = vec![
+            store_with_hours("Day", "09:00-21:00"),
+            store_with_hours("Night", "22:00-02:00"),
+            unknown,
+        ];
+        let open: Vec<String> = service
+            .filter_open_now(&stores, at("2025-03-10", "23:00"))
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(open, vec!["Night".to_string()]);
+    }
+
+    #[test]
+    fn test_opening_hours_weekly_round_trip() {
+        use crate::models::{DayHours, OpeningHours};
+
+        let mut hours = OpeningHours::daily(DayHours::Range {
+            start: 9 * 60,
+            end: 21 * 60,
+        });
+        hours.days[5] = DayHours::Range {
+            start: 10 * 60,
+            end: 18 * 60,
+        };
+        hours.days[6] = DayHours::Closed;
+        let text = hours.to_canonical_string();
+        assert_eq!(text, "Mon-Fri 09:00-21:00; Sat 10:00-18:00; Sun closed");
+        assert_eq!(OpeningHours::from_string(&text), Some(hours));
+
+        // 2025-03-14 为周五，2025-03-15 为周六，2025-03-16 为周日
+        let store = store_with_hours("Weekly", &text);
+        assert_eq!(
+            store.open_status(at("2025-03-14", "20:00")),
+            OpenStatus::Open {
+                closes_at: Some(at("2025-03-14", "21:00"))
+            }
+        );
+        assert_eq!(
+            store.open_status(at("2025-03-15", "09:30")),
+            OpenStatus::Closed {
+                opens_at: at("2025-03-15", "10:00")
+            }
+        );
+        // 周六打烊后跳过周日休息
+        assert_eq!(
+            store.open_status(at("2025-03-15", "19:00")),
+            OpenStatus::Closed {
+                opens_at: at("2025-03-17", "09:00")
+            }
+        );
+    }
+
+    #[test]
+    fn test_opening_hours_cross_midnight_round_trip() {
+        use crate::models::{DayHours, OpeningHours};
+
+        let mut hours = OpeningHours::daily(DayHours::Closed);
+        hours.days[4] = DayHours::Range {
+            start: 22 * 60,
+            end: 2 * 60,
+        };
+        hours.days[5] = DayHours::AllDay;
+        let text = hours.to_canonical_string();
+        assert_eq!(
+            text,
+            "Mon-Thu closed; Fri 22:00-02:00; Sat 24 hours; Sun closed"
+        );
+        assert_eq!(OpeningHours::from_string(&text), Some(hours));
+        assert_eq!(
+            OpeningHours::from_string("22:00-02:00")
+                .unwrap()
+                .to_canonical_string(),
+            "22:00-02:00"
+        );
+
+        let store = store_with_hours("Late", &text);
+        assert_eq!(
+            store.open_status(at("2025-03-14", "23:00")),
+            OpenStatus::Open {
+                closes_at: Some(at("2025-03-15", "02:00"))
+            }
+        );
+        // 周五夜间时段延续到周六，周六全天营业至周日零点
+        assert_eq!(
+            store.open_status(at("2025-03-15", "01:00")),
+            OpenStatus::Open {
+                closes_at: Some(at("2025-03-15", "02:00"))
+            }
+        );
+        assert_eq!(
+            store.open_status(at("2025-03-15", "12:00")),
+            OpenStatus::Open {
+                closes_at: Some(at("2025-03-16", "00:00"))
+            }
+        );
+        assert_eq!(
+            store.open_status(at("2025-03-16", "12:00")),
+            OpenStatus::Closed {
+                opens_at: at("2025-03-21", "22:00")
+            }
+        );
+    }
+
+    struct FixedGeocoder(Option<(f64, f64)>);
+
+    impl Geocoder for FixedGeocoder {
+        fn geocode(&self, address: &str) -> ServiceResult<(f64, f64)> {
+            self.0.ok_or_else(|| {
+                ServiceError::ExternalServiceError(format!("no result for {}", address))
+            })
+        }
+
+        fn reverse(&self, _latitude: f64, _longitude: f64) -> ServiceResult<String> {
+            Err(ServiceError::NotFound(
+                "reverse lookup not supported".to_string(),
+            ))
+        }
+    }
+
+    fn create_at_origin(service: &mut StoreService) -> (Store, Vec<String>) {
+        service
+            .create_store_with_warnings(
+                "Address Only".to_string(),
+                "1-9-1 Marunouchi, Chiyoda, Tokyo".to_string(),
+                0.0,
+                0.0,
+                "9:00-21:00".to_string(),
+                "03-1234-5678".to_string(),
+                vec![],
+                '🏪',
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_create_store_geocodes_missing_coordinates() {
+        let mut service =
+            StoreService::new().with_geocoder(Arc::new(FixedGeocoder(Some((35.6812, 139.7671)))));
+        let (store, warnings) = create_at_origin(&mut service);
+        assert!(warnings.is_empty());
+        assert_eq!((store.latitude, store.longitude), (35.6812, 139.7671));
+        assert_eq!(service.get_store(&store.id).unwrap().latitude, 35.6812);
+    }
+
+    #[test]
+    fn test_create_store_geocoding_failure_keeps_sentinel() {
+        let mut service = StoreService::new().with_geocoder(Arc::new(FixedGeocoder(None)));
+        let (store, warnings) = create_at_origin(&mut service);
+        assert_eq!((store.latitude, store.longitude), (0.0, 0.0));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("Geocoding failed"));
+    }
+
+    fn create_with_hours(service: &mut StoreService, hours: &str) -> (Store, Vec<String>) {
+        service
+            .create_store_with_warnings(
+                "Hours Test".to_string(),
+                "2-1 Shinjuku, Tokyo".to_string(),
+                35.69,
+                139.70,
+                hours.to_string(),
+                "03-1234-5678".to_string(),
+                vec![],
+                '🏪',
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_full_width_opening_hours_are_canonicalized() {
+        let mut service = StoreService::new();
+        let (store, warnings) = create_with_hours(&mut service, "１０：００－２２：００");
+        assert!(warnings.is_empty());
+        assert_eq!(store.opening_hours, "10:00-22:00");
+        assert_eq!(
+            store.open_status(at("2025-03-14", "21:00")),
+            OpenStatus::Open {
+                closes_at: Some(at("2025-03-14", "22:00"))
+            }
+        );
+
+        let updated = service
+            .update_store(
+                &store.id,
+                None,
+                None,
+                None,
+                None,
+                Some("Mon-Fri 9:00〜21:00、Sat 10:00〜18:00".to_string()),
+                None,
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            updated.opening_hours,
+            "Mon-Fri 09:00-21:00; Sat 10:00-18:00; Sun closed"
+        );
+    }
+
+    #[test]
+    fn test_unparseable_opening_hours_warn_and_keep_raw_text() {
+        let mut service = StoreService::new();
+        let (store, warnings) = create_with_hours(&mut service, "平日のみ営業");
+        assert_eq!(store.opening_hours, "平日のみ営業");
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("平日のみ営業"));
+
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7, 35.6]},
+             "properties": {"name": "Ａ", "opening_hours": "ask staff"}},
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7, 35.6]},
+             "properties": {"name": "Ｂ", "opening_hours": "９:００～２１:００"}}
+        ]}"#;
+        let report = service.import_geojson(geojson).unwrap();
+        assert_eq!(report.imported_count(), 2);
+        assert_eq!(report.warnings.len(), 1);
+        assert!(report.warnings[0].starts_with("feature 0:"));
+        let hours = |id: &str| service.get_store(id).unwrap().opening_hours;
+        assert_eq!(hours(&report.imported_ids[0]), "ask staff");
+        assert_eq!(hours(&report.imported_ids[1]), "09:00-21:00");
+    }
+
+    #[test]
+    fn test_update_returns_opening_hours_warnings() {
+        let mut service = StoreService::new();
+        let (store, _) = create_with_hours(&mut service, "10:00-22:00");
+
+        let (updated, warnings) = service
+            .update_store_with_warnings(
+                &store.id,
+                None,
+                None,
+                None,
+                None,
+                Some("ask staff".to_string()),
+                None,
+                None,
+            )
+            .unwrap();
+        assert_eq!(updated.opening_hours, "ask staff");
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("ask staff"));
+
+        let (_, warnings) = service
+            .update_store_with_warnings(
+                &store.id,
+                Some("Renamed".to_string()),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        assert!(warnings.is_empty());
+    }
+}
@@ -1,5 +1,6 @@
 use crate::models::User;
 use crate::services::{ServiceError, ServiceResult};
+use crate::utils::crypto::constant_time_eq;
 use chrono::Utc;
 use std::collections::HashMap;
 
@@ -177,9 +178,12 @@ impl UserService {
 
     /// Validate session and return user
     pub fn validate_session(&self, session_token: &str) -> ServiceResult<User> {
+        // 逐个按常量时间比较，避免通过响应时间猜测令牌
         let user_id = self
             .sessions
-            .get(session_token)
+            .iter()
+            .find(|(token, _)| constant_time_eq(token.as_bytes(), session_token.as_bytes()))
+            .map(|(_, user_id)| user_id)
             .ok_or_else(|| ServiceError::PermissionDenied("Invalid session".to_string()))?;
 
         let user = self
@@ -631,6 +635,12 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.unwrap().id, user.id);
 
+        // A token differing only in its last character is rejected
+        let mut forged = session_token.clone();
+        let last = if forged.pop() == Some('0') { '1' } else { '0' };
+        forged.push(last);
+        assert!(service.validate_session(&forged).is_err());
+
         // Logout
         let result = service.logout(&session_token);
         assert!(result.is_ok());
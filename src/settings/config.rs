@@ -1,4 +1,8 @@
+use crate::error::Locale;
+use crate::utils::file_utils::{get_data_directory, write_with_backup};
+use crate::utils::{Currency, PriceFormatter, RoundingMode, Tz, timezone_from_name};
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 
 /// Application configuration settings
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -7,6 +11,24 @@ pub struct AppConfig {
     pub notification_settings: NotificationSettings,
     pub monitoring_settings: MonitoringSettings,
     pub data_settings: DataSettings,
+    #[serde(default)]
+    pub server_settings: ServerSettings,
+    /// Currency used when displaying prices
+    #[serde(default)]
+    pub default_currency: Currency,
+    /// Decimal places for displayed prices; None uses the currency's own precision
+    #[serde(default)]
+    pub price_decimals: Option<usize>,
+    /// Rounding of displayed prices, currency conversion and averages
+    #[serde(default)]
+    pub rounding_mode: RoundingMode,
+    /// Named places that can replace the live location for store distances
+    #[serde(default)]
+    pub saved_locations: SavedLocations,
+    /// Look up a place name for the current location through OpenStreetMap
+    /// Nominatim; off by default since it sends coordinates to a public service
+    #[serde(default)]
+    pub reverse_geocoding: bool,
 }
 
 /// UI display and interaction settings
@@ -18,6 +40,13 @@ pub struct UISettings {
     pub show_animations: bool,
     pub compact_mode: bool,
     pub window_transparency: f32,
+    /// IANA name of the zone timestamps are shown in
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+}
+
+fn default_timezone() -> String {
+    Tz::UTC.name().to_string()
 }
 
 /// Notification and alert settings
@@ -39,6 +68,23 @@ pub struct MonitoringSettings {
     pub max_price_records_per_product: u32,
     pub enable_trend_analysis: bool,
     pub price_history_days: u32,
+    /// Region (ISO country code) of the holiday calendar shown on price charts
+    #[serde(default = "default_holiday_region")]
+    pub holiday_region: String,
+    /// Optional JSON file with additional notable dates
+    #[serde(default)]
+    pub holiday_calendar_file: Option<String>,
+    /// Prices older than this many days are marked as stale
+    #[serde(default = "default_price_freshness_days")]
+    pub price_freshness_days: u32,
+}
+
+fn default_price_freshness_days() -> u32 {
+    14
+}
+
+fn default_holiday_region() -> String {
+    "JP".to_string()
 }
 
 /// Data storage and sync settings
@@ -51,6 +97,100 @@ pub struct DataSettings {
     pub data_retention_days: u32,
 }
 
+/// Query API server settings (used by the `server` feature)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerSettings {
+    pub bind_address: String,
+    pub port: u16,
+}
+
+impl ServerSettings {
+    /// `address:port` string for binding
+    pub fn socket_addr(&self) -> String {
+        format!("{}:{}", self.bind_address, self.port)
+    }
+}
+
+/// A named place (home, work, ...) used as the origin for store distances
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SavedLocation {
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl SavedLocation {
+    pub fn new(name: &str, latitude: f64, longitude: f64) -> Self {
+        Self {
+            name: name.trim().to_string(),
+            latitude,
+            longitude,
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Location name cannot be empty".to_string());
+        }
+        if !(-90.0..=90.0).contains(&self.latitude) || !(-180.0..=180.0).contains(&self.longitude) {
+            return Err(format!("Coordinates of {} are out of range", self.name));
+        }
+        Ok(())
+    }
+}
+
+/// Saved locations and the one driving distance calculations
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct SavedLocations {
+    pub locations: Vec<SavedLocation>,
+    /// Name of the selected location; None uses the live location
+    #[serde(default)]
+    pub active: Option<String>,
+}
+
+impl SavedLocations {
+    /// Add a location, replacing any saved under the same name
+    pub fn add(&mut self, location: SavedLocation) -> Result<(), String> {
+        location.validate()?;
+        match self.locations.iter_mut().find(|l| l.name == location.name) {
+            Some(existing) => *existing = location,
+            None => self.locations.push(location),
+        }
+        Ok(())
+    }
+
+    /// Remove a location; removing the selected one falls back to the live location
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.locations.len();
+        self.locations.retain(|l| l.name != name);
+        if self.active.as_deref() == Some(name) {
+            self.active = None;
+        }
+        self.locations.len() != before
+    }
+
+    /// Select a saved location by name, or the live location with None.
+    /// Unknown names are ignored and return false
+    pub fn select(&mut self, name: Option<&str>) -> bool {
+        if name.is_some_and(|name| !self.locations.iter().any(|l| l.name == name)) {
+            return false;
+        }
+        self.active = name.map(str::to_string);
+        true
+    }
+
+    pub fn active_location(&self) -> Option<&SavedLocation> {
+        let active = self.active.as_deref()?;
+        self.locations.iter().find(|l| l.name == active)
+    }
+
+    /// Origin (latitude, longitude) for distances: the selected location, else `live`
+    pub fn reference_point(&self, live: (f64, f64)) -> (f64, f64) {
+        self.active_location()
+            .map_or(live, |l| (l.latitude, l.longitude))
+    }
+}
+
 // (removed duplicate AppConfig redefinition)
 
 impl Default for UISettings {
@@ -62,6 +202,7 @@ impl Default for UISettings {
             show_animations: true,
             compact_mode: false,
             window_transparency: 1.0,
+            timezone: default_timezone(),
         }
     }
 }
@@ -87,6 +228,9 @@ impl Default for MonitoringSettings {
             max_price_records_per_product: 100,
             enable_trend_analysis: true,
             price_history_days: 30,
+            holiday_region: default_holiday_region(),
+            holiday_calendar_file: None,
+            price_freshness_days: default_price_freshness_days(),
         }
     }
 }
@@ -103,19 +247,88 @@ impl Default for DataSettings {
     }
 }
 
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            bind_address: "127.0.0.1".to_string(),
+            port: 8787,
+        }
+    }
+}
+
 impl AppConfig {
+    /// Formatter for displaying prices in the configured currency
+    pub fn price_formatter(&self) -> PriceFormatter {
+        PriceFormatter::new(self.default_currency)
+            .with_decimals(self.price_decimals)
+            .with_rounding(self.rounding_mode)
+    }
+
+    /// Default config file location (`data/config/config.json`)
+    pub fn config_path() -> std::io::Result<PathBuf> {
+        let dir = get_data_directory().map_err(|e| std::io::Error::other(e.to_string()))?;
+        Ok(dir.join("config").join("config.json"))
+    }
+
     /// Load configuration from file
     pub fn load() -> std::io::Result<Self> {
-        // In a real implementation, load from config file
-        Ok(Self::default())
+        Self::load_from(Self::config_path()?)
+    }
+
+    /// Load configuration from a specific file, falling back to defaults if it is missing
+    pub fn load_from<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let bytes = std::fs::read(path)?;
+        serde_json::from_slice(&bytes).map_err(|e| std::io::Error::other(e.to_string()))
     }
 
     /// Save configuration to file
     pub fn save(&self) -> std::io::Result<()> {
-        // In a real implementation, save to config file
+        self.save_to(Self::config_path()?)
+    }
+
+    /// Save configuration atomically, rotating `max_backup_files` backups
+    pub fn save_to<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        let bytes =
+            serde_json::to_vec_pretty(self).map_err(|e| std::io::Error::other(e.to_string()))?;
+        write_with_backup(path, &bytes, self.data_settings.max_backup_files as usize)
+    }
+
+    /// Persist only the active saved location in the default config file
+    pub fn save_active_location(name: Option<&str>) -> std::io::Result<()> {
+        Self::save_active_location_to(Self::config_path()?, name)
+    }
+
+    /// Persist only the active saved location, keeping every other setting in the
+    /// file as it is. Locations not yet saved to the file are left for an explicit save
+    pub fn save_active_location_to<P: AsRef<Path>>(
+        path: P,
+        name: Option<&str>,
+    ) -> std::io::Result<()> {
+        let path = path.as_ref();
+        let mut stored = Self::load_from(path)?;
+        if stored.saved_locations.select(name) {
+            stored.save_to(path)?;
+        }
         Ok(())
     }
 
+    /// UI locale selected by `ui_settings.language` ("auto" keeps the default Chinese UI)
+    pub fn locale(&self) -> Locale {
+        match self.ui_settings.language.as_str() {
+            "auto" => Locale::Zh,
+            code => Locale::from_code(code),
+        }
+    }
+
+    /// Zone for displaying timestamps; unknown names fall back to UTC
+    pub fn timezone(&self) -> Tz {
+        timezone_from_name(&self.ui_settings.timezone).unwrap_or(Tz::UTC)
+    }
+
     /// Reset to default settings
     pub fn reset_to_defaults(&mut self) {
         *self = Self::default();
@@ -162,6 +375,100 @@ impl AppConfig {
             return Err("Must keep at least 1 backup file".to_string());
         }
 
+        // Validate server settings
+        if self.server_settings.bind_address.trim().is_empty() {
+            return Err("Server bind address cannot be empty".to_string());
+        }
+
+        for location in &self.saved_locations.locations {
+            location.validate()?;
+        }
+
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_saved_locations_persist_and_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+
+        let mut config = AppConfig::default();
+        config
+            .saved_locations
+            .add(SavedLocation::new("home", 35.6586, 139.7454))
+            .unwrap();
+        config
+            .saved_locations
+            .add(SavedLocation::new("work", 35.6812, 139.7671))
+            .unwrap();
+        assert!(config.saved_locations.select(Some("work")));
+        config.save_to(&path).unwrap();
+
+        let loaded = AppConfig::load_from(&path).unwrap();
+        assert_eq!(loaded.saved_locations, config.saved_locations);
+        assert_eq!(
+            loaded.saved_locations.active_location().unwrap().name,
+            "work"
+        );
+    }
+
+    #[test]
+    fn test_save_active_location_keeps_other_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+
+        let mut config = AppConfig::default();
+        config
+            .saved_locations
+            .add(SavedLocation::new("home", 35.6586, 139.7454))
+            .unwrap();
+        config.save_to(&path).unwrap();
+
+        // 未保存的修改不应随位置选择写入文件
+        config.ui_settings.theme = "dark".to_string();
+        assert!(config.saved_locations.select(Some("home")));
+        AppConfig::save_active_location_to(&path, Some("home")).unwrap();
+
+        let loaded = AppConfig::load_from(&path).unwrap();
+        assert_eq!(
+            loaded.saved_locations.active_location().unwrap().name,
+            "home"
+        );
+        assert_eq!(
+            loaded.ui_settings.theme,
+            AppConfig::default().ui_settings.theme
+        );
+    }
+
+    #[test]
+    fn test_selected_location_drives_reference_point() {
+        let live = (34.7025, 135.4959);
+        let mut locations = SavedLocations::default();
+        assert_eq!(locations.reference_point(live), live);
+
+        locations
+            .add(SavedLocation::new("home", 35.6586, 139.7454))
+            .unwrap();
+        assert!(!locations.select(Some("gym")));
+        assert!(locations.select(Some("home")));
+        assert_eq!(locations.reference_point(live), (35.6586, 139.7454));
+
+        // 同名覆盖坐标
+        locations
+            .add(SavedLocation::new("home", 35.0, 139.0))
+            .unwrap();
+        assert_eq!(locations.reference_point(live), (35.0, 139.0));
+
+        assert!(locations.remove("home"));
+        assert_eq!(locations.active, None);
+        assert_eq!(locations.reference_point(live), live);
+
+        assert!(locations.add(SavedLocation::new("", 0.0, 0.0)).is_err());
+        assert!(locations.add(SavedLocation::new("far", 91.0, 0.0)).is_err());
+    }
+}
@@ -1,5 +1,7 @@
 pub mod config;
 pub mod ui;
 
-pub use config::{AppConfig, NotificationSettings, UISettings};
-pub use ui::SettingsUI;
+pub use config::{
+    AppConfig, NotificationSettings, SavedLocation, SavedLocations, ServerSettings, UISettings,
+};
+pub use ui::{DataAction, SettingsUI};
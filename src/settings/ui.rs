@@ -1,5 +1,9 @@
-use crate::settings::config::AppConfig;
+use crate::services::Metrics;
+use crate::settings::config::{AppConfig, SavedLocation};
+use crate::tr;
+use crate::utils::{COMMON_TIMEZONES, Currency, RoundingMode};
 use egui::{Color32, RichText, Slider, Ui};
+use std::sync::Arc;
 
 /// Settings UI component for managing application configuration
 pub struct SettingsUI {
@@ -8,7 +12,18 @@ pub struct SettingsUI {
     show_save_success: bool,
     error_message: Option<String>,
     current_tab: SettingsTab,
-    temp_values: TempValues, // For slider values that need validation
+    temp_values: TempValues,       // For slider values that need validation
+    metrics: Option<Arc<Metrics>>, // Shown in the about tab
+    new_location: (String, String, String), // 新位置的名称、纬度、经度输入
+    pending_data_action: Option<DataAction>,
+    config_saved: bool, // 保存成功后由应用取走并应用到各服务
+}
+
+/// Backup action requested from the data tab, carried out by the app
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataAction {
+    Export,
+    Import,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -17,6 +32,7 @@ enum SettingsTab {
     Notifications,
     Monitoring,
     Data,
+    Locations,
     About,
 }
 
@@ -50,25 +66,32 @@ impl SettingsUI {
             error_message: None,
             current_tab: SettingsTab::UI,
             temp_values,
+            metrics: None,
+            new_location: Default::default(),
+            pending_data_action: None,
+            config_saved: false,
         }
     }
 
     /// Show the settings UI
     pub fn show(&mut self, ui: &mut Ui) {
-        ui.heading("应用设置");
+        ui.heading(tr!("settings.title"));
         ui.separator();
 
         // Show status messages
         if self.show_restart_required {
-            ui.colored_label(Color32::YELLOW, "⚠ 某些设置需要重启应用后生效");
+            ui.colored_label(Color32::YELLOW, tr!("settings.restart_required"));
         }
 
         if self.show_save_success {
-            ui.colored_label(Color32::GREEN, "✓ 设置已保存");
+            ui.colored_label(Color32::GREEN, tr!("settings.saved"));
         }
 
         if let Some(ref error) = self.error_message {
-            ui.colored_label(Color32::RED, format!("❌ 错误: {}", error));
+            ui.colored_label(
+                Color32::RED,
+                format!("{}: {}", tr!("settings.error"), error),
+            );
         }
 
         ui.separator();
@@ -76,31 +99,52 @@ impl SettingsUI {
         // Settings tabs
         ui.horizontal(|ui| {
             if ui
-                .selectable_label(self.current_tab == SettingsTab::UI, "界面设置")
+                .selectable_label(self.current_tab == SettingsTab::UI, tr!("settings.tab.ui"))
                 .clicked()
             {
                 self.current_tab = SettingsTab::UI;
             }
             if ui
-                .selectable_label(self.current_tab == SettingsTab::Notifications, "通知设置")
+                .selectable_label(
+                    self.current_tab == SettingsTab::Notifications,
+                    tr!("settings.tab.notifications"),
+                )
                 .clicked()
             {
                 self.current_tab = SettingsTab::Notifications;
             }
             if ui
-                .selectable_label(self.current_tab == SettingsTab::Monitoring, "监控设置")
+                .selectable_label(
+                    self.current_tab == SettingsTab::Monitoring,
+                    tr!("settings.tab.monitoring"),
+                )
                 .clicked()
             {
                 self.current_tab = SettingsTab::Monitoring;
             }
             if ui
-                .selectable_label(self.current_tab == SettingsTab::Data, "数据设置")
+                .selectable_label(
+                    self.current_tab == SettingsTab::Data,
+                    tr!("settings.tab.data"),
+                )
                 .clicked()
             {
                 self.current_tab = SettingsTab::Data;
             }
             if ui
-                .selectable_label(self.current_tab == SettingsTab::About, "关于")
+                .selectable_label(
+                    self.current_tab == SettingsTab::Locations,
+                    tr!("settings.tab.locations"),
+                )
+                .clicked()
+            {
+                self.current_tab = SettingsTab::Locations;
+            }
+            if ui
+                .selectable_label(
+                    self.current_tab == SettingsTab::About,
+                    tr!("settings.tab.about"),
+                )
                 .clicked()
             {
                 self.current_tab = SettingsTab::About;
@@ -115,6 +159,7 @@ impl SettingsUI {
             SettingsTab::Notifications => self.render_notification_settings(ui),
             SettingsTab::Monitoring => self.render_monitoring_settings(ui),
             SettingsTab::Data => self.render_data_settings(ui),
+            SettingsTab::Locations => self.render_location_settings(ui),
             SettingsTab::About => self.render_about_tab(ui),
         });
 
@@ -122,19 +167,19 @@ impl SettingsUI {
 
         // Action buttons
         ui.horizontal(|ui| {
-            if ui.button("保存设置").clicked() {
+            if ui.button(tr!("settings.save")).clicked() {
                 self.save_settings();
             }
 
-            if ui.button("重置为默认").clicked() {
+            if ui.button(tr!("settings.reset")).clicked() {
                 self.reset_to_defaults();
             }
 
-            if ui.button("导出设置").clicked() {
+            if ui.button(tr!("settings.export")).clicked() {
                 self.export_settings();
             }
 
-            if ui.button("导入设置").clicked() {
+            if ui.button(tr!("settings.import")).clicked() {
                 self.import_settings();
             }
         });
@@ -202,6 +247,59 @@ impl SettingsUI {
                 ui.label(egui::RichText::new("部分语言需要重启生效").small());
             });
 
+            ui.horizontal(|ui| {
+                ui.label("时区:");
+                egui::ComboBox::from_id_salt("timezone")
+                    .selected_text(self.config.timezone().name())
+                    .show_ui(ui, |ui| {
+                        for tz in COMMON_TIMEZONES {
+                            ui.selectable_value(
+                                &mut self.config.ui_settings.timezone,
+                                tz.name().to_string(),
+                                tz.name(),
+                            );
+                        }
+                    });
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("货币:");
+                egui::ComboBox::from_id_salt("default_currency")
+                    .selected_text(self.config.default_currency.code())
+                    .show_ui(ui, |ui| {
+                        for currency in Currency::ALL {
+                            ui.selectable_value(
+                                &mut self.config.default_currency,
+                                currency,
+                                currency.code(),
+                            );
+                        }
+                    });
+                // 小数位默认跟随货币（日元无小数）
+                let label = |decimals: Option<usize>| match decimals {
+                    Some(n) => format!("{} 位小数", n),
+                    None => "自动".to_string(),
+                };
+                egui::ComboBox::from_id_salt("price_decimals")
+                    .selected_text(label(self.config.price_decimals))
+                    .show_ui(ui, |ui| {
+                        for decimals in [None, Some(0), Some(1), Some(2)] {
+                            ui.selectable_value(
+                                &mut self.config.price_decimals,
+                                decimals,
+                                label(decimals),
+                            );
+                        }
+                    });
+                egui::ComboBox::from_id_salt("rounding_mode")
+                    .selected_text(self.config.rounding_mode.label())
+                    .show_ui(ui, |ui| {
+                        for mode in RoundingMode::ALL {
+                            ui.selectable_value(&mut self.config.rounding_mode, mode, mode.label());
+                        }
+                    });
+            });
+
             ui.horizontal(|ui| {
                 ui.label("字体大小:");
                 ui.add(Slider::new(&mut self.temp_values.font_size, 8.0..=32.0).suffix("px"));
@@ -325,6 +423,27 @@ impl SettingsUI {
                     );
                 });
 
+                ui.horizontal(|ui| {
+                    ui.label("价格过旧标记:");
+                    ui.add(
+                        Slider::new(
+                            &mut self.config.monitoring_settings.price_freshness_days,
+                            1..=90,
+                        )
+                        .suffix("天"),
+                    );
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("节假日地区:");
+                    ui.add(
+                        egui::TextEdit::singleline(
+                            &mut self.config.monitoring_settings.holiday_region,
+                        )
+                        .desired_width(40.0),
+                    );
+                });
+
                 ui.horizontal(|ui| {
                     ui.label("每个商品最大价格记录数:");
                     ui.add(Slider::new(
@@ -407,6 +526,76 @@ impl SettingsUI {
         });
     }
 
+    fn render_location_settings(&mut self, ui: &mut Ui) {
+        ui.group(|ui| {
+            ui.label(RichText::new("常用位置").strong());
+            ui.label("在门店页选择后，按所选位置计算距离");
+
+            let mut remove = None;
+            egui::Grid::new("saved_locations")
+                .num_columns(3)
+                .show(ui, |ui| {
+                    for location in &self.config.saved_locations.locations {
+                        ui.label(&location.name);
+                        ui.label(format!(
+                            "{:.4}, {:.4}",
+                            location.latitude, location.longitude
+                        ));
+                        if ui.button("删除").clicked() {
+                            remove = Some(location.name.clone());
+                        }
+                        ui.end_row();
+                    }
+                });
+            if let Some(name) = remove {
+                self.config.saved_locations.remove(&name);
+            }
+
+            ui.separator();
+            let (name, latitude, longitude) = &mut self.new_location;
+            ui.horizontal(|ui| {
+                ui.label("名称:");
+                ui.add(egui::TextEdit::singleline(name).desired_width(80.0));
+                ui.label("纬度:");
+                ui.add(egui::TextEdit::singleline(latitude).desired_width(80.0));
+                ui.label("经度:");
+                ui.add(egui::TextEdit::singleline(longitude).desired_width(80.0));
+            });
+            if ui.button("添加位置").clicked() {
+                self.add_location();
+            }
+
+            ui.separator();
+            ui.checkbox(
+                &mut self.config.reverse_geocoding,
+                "显示当前位置的地名 (OpenStreetMap Nominatim)",
+            )
+            .on_hover_text("开启后会把当前坐标发送到公共的 Nominatim 服务");
+        });
+    }
+
+    fn add_location(&mut self) {
+        let (name, latitude, longitude) = &self.new_location;
+        let (Ok(latitude), Ok(longitude)) = (
+            latitude.trim().parse::<f64>(),
+            longitude.trim().parse::<f64>(),
+        ) else {
+            self.error_message = Some("经纬度必须是数字".to_string());
+            return;
+        };
+        match self
+            .config
+            .saved_locations
+            .add(SavedLocation::new(name, latitude, longitude))
+        {
+            Ok(()) => {
+                self.new_location = Default::default();
+                self.error_message = None;
+            }
+            Err(e) => self.error_message = Some(e),
+        }
+    }
+
     fn render_about_tab(&mut self, ui: &mut Ui) {
         ui.group(|ui| {
             ui.label(RichText::new("关于 ePrice").strong().size(18.0));
@@ -444,21 +633,64 @@ impl SettingsUI {
                     self.open_help();
                 }
             });
+
+            self.render_metrics(ui);
         });
     }
 
+    /// Usage counters of this session
+    fn render_metrics(&self, ui: &mut Ui) {
+        let Some(metrics) = &self.metrics else {
+            return;
+        };
+        let report = metrics.snapshot();
+        ui.separator();
+        ui.label(RichText::new("运行统计").strong());
+        if report.counters.is_empty() && report.histograms.is_empty() {
+            ui.label("暂无数据");
+            return;
+        }
+        egui::Grid::new("metrics_grid")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                for (name, value) in &report.counters {
+                    ui.label(name);
+                    ui.label(value.to_string());
+                    ui.end_row();
+                }
+                for (name, histogram) in &report.histograms {
+                    ui.label(name);
+                    ui.label(format!(
+                        "n={} 平均 {:.1} (最小 {:.1}, 最大 {:.1})",
+                        histogram.count,
+                        histogram.mean().unwrap_or(0.0),
+                        histogram.min,
+                        histogram.max
+                    ));
+                    ui.end_row();
+                }
+            });
+        if ui.button("重置统计").clicked() {
+            metrics.reset();
+        }
+    }
+
     fn save_settings(&mut self) {
         match self.config.validate() {
             Ok(()) => {
                 match self.config.save() {
                     Ok(()) => {
+                        crate::i18n::set_locale(self.config.locale());
+                        self.config_saved = true;
                         self.show_save_success = true;
                         self.error_message = None;
                         // Clear the success message after a few seconds
                         // In a real app, you'd use a timer
                     }
                     Err(e) => {
-                        self.error_message = Some(format!("保存失败: {}", e));
+                        self.error_message =
+                            Some(format!("{}: {}", tr!("settings.save_failed"), e));
                         self.show_save_success = false;
                     }
                 }
@@ -502,13 +734,11 @@ impl SettingsUI {
     }
 
     fn export_data(&mut self) {
-        // In a real implementation, export user data
-        self.show_save_success = true;
+        self.pending_data_action = Some(DataAction::Export);
     }
 
     fn import_data(&mut self) {
-        // In a real implementation, import user data
-        self.show_save_success = true;
+        self.pending_data_action = Some(DataAction::Import);
     }
 
     fn check_for_updates(&mut self) {
@@ -524,6 +754,35 @@ impl SettingsUI {
         // In a real implementation, open help documentation
     }
 
+    /// Show these metrics in the about tab
+    pub fn set_metrics(&mut self, metrics: Arc<Metrics>) {
+        self.metrics = Some(metrics);
+    }
+
+    /// Backup export or import requested from the data tab, if not yet taken
+    pub fn take_data_action(&mut self) -> Option<DataAction> {
+        self.pending_data_action.take()
+    }
+
+    /// Whether the configuration was saved since the last call
+    pub fn take_config_saved(&mut self) -> bool {
+        std::mem::take(&mut self.config_saved)
+    }
+
+    /// Show the outcome of a backup export or import
+    pub fn report_data_result(&mut self, result: Result<(), String>) {
+        match result {
+            Ok(()) => {
+                self.show_save_success = true;
+                self.error_message = None;
+            }
+            Err(e) => {
+                self.show_save_success = false;
+                self.error_message = Some(e);
+            }
+        }
+    }
+
     /// Get current configuration
     pub fn get_config(&self) -> &AppConfig {
         &self.config
@@ -533,6 +792,17 @@ impl SettingsUI {
     pub fn apply_config(&mut self, config: AppConfig) {
         self.config = config;
     }
+
+    /// Choose the saved location (None = live location) used for store distances and
+    /// persist just that choice; other unsaved edits still wait for "Save"
+    pub fn select_location(&mut self, name: Option<&str>) {
+        if !self.config.saved_locations.select(name) {
+            return;
+        }
+        if let Err(e) = AppConfig::save_active_location(name) {
+            log::warn!("Cannot save selected location: {}", e);
+        }
+    }
 }
 
 impl Default for SettingsUI {
@@ -0,0 +1,324 @@
+//! Barcode (EAN-13, EAN-8, Code 128) and QR code image rendering to PNG.
+
+use crate::models::{BarcodeType, DeepLink};
+use crate::utils::generate_barcode_checksum;
+use barcoders::sym::code128::Code128;
+use barcoders::sym::ean8::EAN8;
+use barcoders::sym::ean13::EAN13;
+use image::{GrayImage, ImageFormat, Luma};
+use std::io::Cursor;
+use thiserror::Error;
+
+/// Width of one module (narrowest bar) in pixels
+pub const MODULE_WIDTH_PX: u32 = 2;
+/// Height of the bars in pixels
+pub const BAR_HEIGHT_PX: u32 = 80;
+/// Blank modules on each side of the symbol
+pub const QUIET_ZONE_MODULES: u32 = 10;
+/// Size of one QR module in pixels
+pub const QR_MODULE_PX: u32 = 8;
+/// Blank modules around a QR symbol
+pub const QR_QUIET_ZONE_MODULES: u32 = 4;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum BarcodeRenderError {
+    #[error("Invalid length {len} for {kind:?}")]
+    InvalidLength { kind: BarcodeType, len: usize },
+    #[error("{0:?} cannot be rendered as a linear barcode")]
+    UnsupportedType(BarcodeType),
+    #[error("Unsupported character {0:?}")]
+    InvalidCharacter(char),
+    #[error("Invalid check digit: expected {expected}")]
+    InvalidChecksum { expected: u8 },
+    #[error("Failed to encode barcode: {0}")]
+    Encode(String),
+    #[error("Failed to encode QR code: {0}")]
+    Qr(String),
+    #[error("Failed to encode PNG: {0}")]
+    Image(String),
+}
+
+/// Code 128 内容长度上限，避免生成过宽的图片
+const CODE128_MAX_LEN: usize = 80;
+/// barcoders 中表示 Code 128 字符集 B 的起始字符
+const CODE128_SET_B: char = 'Ɓ';
+
+/// Check that `code` is `len` digits ending in the right GS1 check digit
+fn check_ean(code: &str, kind: BarcodeType, len: usize) -> Result<(), BarcodeRenderError> {
+    if let Some(c) = code.chars().find(|c| !c.is_ascii_digit()) {
+        return Err(BarcodeRenderError::InvalidCharacter(c));
+    }
+    if code.len() != len {
+        return Err(BarcodeRenderError::InvalidLength {
+            kind,
+            len: code.len(),
+        });
+    }
+    let expected = generate_barcode_checksum(&code[..len - 1])
+        .ok_or(BarcodeRenderError::InvalidLength { kind, len })?;
+    if code.as_bytes()[len - 1] - b'0' != expected {
+        return Err(BarcodeRenderError::InvalidChecksum { expected });
+    }
+    Ok(())
+}
+
+/// Code 128 using code set B (printable ASCII)
+fn check_code128(code: &str) -> Result<(), BarcodeRenderError> {
+    if code.is_empty() || code.len() > CODE128_MAX_LEN {
+        return Err(BarcodeRenderError::InvalidLength {
+            kind: BarcodeType::Code128,
+            len: code.chars().count(),
+        });
+    }
+    if let Some(c) = code.chars().find(|c| !(' '..='~').contains(c)) {
+        return Err(BarcodeRenderError::InvalidCharacter(c));
+    }
+    Ok(())
+}
+
+/// Bar modules (true = dark) of `code`, after validating it for `kind`
+pub fn encode_barcode(code: &str, kind: BarcodeType) -> Result<Vec<bool>, BarcodeRenderError> {
+    let code = code.trim();
+    let encoded = match kind {
+        BarcodeType::Ean13 => {
+            check_ean(code, BarcodeType::Ean13, 13)?;
+            EAN13::new(code).map(|b| b.encode())
+        }
+        BarcodeType::Ean8 => {
+            check_ean(code, BarcodeType::Ean8, 8)?;
+            EAN8::new(code).map(|b| b.encode())
+        }
+        BarcodeType::Code128 => {
+            check_code128(code)?;
+            Code128::new(format!("{}{}", CODE128_SET_B, code)).map(|b| b.encode())
+        }
+        // 二维码走 render_qr
+        BarcodeType::QrCode | BarcodeType::Unknown => {
+            return Err(BarcodeRenderError::UnsupportedType(kind));
+        }
+    }
+    .map_err(|e| BarcodeRenderError::Encode(e.to_string()))?;
+    Ok(encoded.into_iter().map(|m| m == 1).collect())
+}
+
+/// Render `code` as a PNG: black bars on white with a quiet zone on both sides.
+/// The image is `(modules + 2 × QUIET_ZONE_MODULES) × MODULE_WIDTH_PX` wide and
+/// `BAR_HEIGHT_PX` high
+pub fn render_barcode(code: &str, kind: BarcodeType) -> Result<Vec<u8>, BarcodeRenderError> {
+    let modules = encode_barcode(code, kind)?;
+    let width = (modules.len() as u32 + 2 * QUIET_ZONE_MODULES) * MODULE_WIDTH_PX;
+
+    let image = GrayImage::from_fn(width, BAR_HEIGHT_PX, |x, _| {
+        let module = (x / MODULE_WIDTH_PX).checked_sub(QUIET_ZONE_MODULES);
+        let dark = module.is_some_and(|m| modules.get(m as usize).copied().unwrap_or(false));
+        Luma([if dark { 0 } else { 255 }])
+    });
+
+    encode_png(&image)
+}
+
+/// QR code PNG of `text`, with a quiet zone of `QR_QUIET_ZONE_MODULES`
+pub fn render_qr(text: &str) -> Result<Vec<u8>, BarcodeRenderError> {
+    let code =
+        qrcode::QrCode::new(text.as_bytes()).map_err(|e| BarcodeRenderError::Qr(e.to_string()))?;
+    let modules = code.width() as u32;
+    let size = (modules + 2 * QR_QUIET_ZONE_MODULES) * QR_MODULE_PX;
+
+    let image = GrayImage::from_fn(size, size, |x, y| {
+        let dark = match (
+            (x / QR_MODULE_PX).checked_sub(QR_QUIET_ZONE_MODULES),
+            (y / QR_MODULE_PX).checked_sub(QR_QUIET_ZONE_MODULES),
+        ) {
+            (Some(mx), Some(my)) if mx < modules && my < modules => {
+                code[(mx as usize, my as usize)] == qrcode::Color::Dark
+            }
+            _ => false,
+        };
+        Luma([if dark { 0 } else { 255 }])
+    });
+    encode_png(&image)
+}
+
+/// QR code PNG of the canonical URI of `link` (see [`DeepLink::to_uri`])
+pub fn make_share_qr(link: &DeepLink) -> Result<Vec<u8>, BarcodeRenderError> {
+    render_qr(&link.to_uri())
+}
+
+fn encode_png(image: &GrayImage) -> Result<Vec<u8>, BarcodeRenderError> {
+    let mut png = Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, ImageFormat::Png)
+        .map_err(|e| BarcodeRenderError::Image(e.to_string()))?;
+    Ok(png.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Widths of consecutive runs of equal modules
+    fn run_lengths(modules: &[bool]) -> Vec<usize> {
+        let mut runs: Vec<usize> = Vec::new();
+        for (i, m) in modules.iter().enumerate() {
+            if i > 0 && modules[i - 1] == *m {
+                *runs.last_mut().unwrap() += 1;
+            } else {
+                runs.push(1);
+            }
+        }
+        runs
+    }
+
+    #[test]
+    fn test_ean13_png_dimensions() {
+        let png = render_barcode("4901234567894", BarcodeType::Ean13).unwrap();
+        assert!(!png.is_empty());
+
+        let image = image::load_from_memory(&png).unwrap().to_luma8();
+        // EAN-13 共 95 个模块
+        assert_eq!(
+            image.width(),
+            (95 + 2 * QUIET_ZONE_MODULES) * MODULE_WIDTH_PX
+        );
+        assert_eq!(image.height(), BAR_HEIGHT_PX);
+        // 静区为白色，起始符首条为黑色
+        assert_eq!(image.get_pixel(0, 0)[0], 255);
+        assert_eq!(
+            image.get_pixel(QUIET_ZONE_MODULES * MODULE_WIDTH_PX, 0)[0],
+            0
+        );
+    }
+
+    #[test]
+    fn test_ean_module_patterns() {
+        let modules = encode_barcode("4901234567894", BarcodeType::Ean13).unwrap();
+        let bits: String = modules.iter().map(|&m| if m { '1' } else { '0' }).collect();
+        assert!(bits.starts_with("101"));
+        assert!(bits.ends_with("101"));
+        assert_eq!(&bits[45..50], "01010");
+        // 首位 4 → 左侧编码为 LGLLGG，第二位 9 使用 L 编码
+        assert_eq!(&bits[3..10], "0001011");
+        // 第三位 0 使用 G 编码
+        assert_eq!(&bits[10..17], "0100111");
+
+        assert_eq!(
+            encode_barcode("96385074", BarcodeType::Ean8).unwrap().len(),
+            67
+        );
+    }
+
+    #[test]
+    fn test_code128_structure() {
+        let modules = encode_barcode("ABC-123", BarcodeType::Code128).unwrap();
+        // 起始符 + 7 个字符 + 校验符各 11 模块，终止符 13 模块
+        assert_eq!(modules.len(), 11 * 9 + 13);
+        let runs = run_lengths(&modules);
+        assert_eq!(&runs[..6], &[2, 1, 1, 2, 1, 4]);
+        assert_eq!(&runs[runs.len() - 7..], &[2, 3, 3, 1, 1, 1, 2]);
+    }
+
+    #[test]
+    fn test_invalid_codes_rejected() {
+        assert_eq!(
+            render_barcode("4901234567890", BarcodeType::Ean13),
+            Err(BarcodeRenderError::InvalidChecksum { expected: 4 })
+        );
+        assert!(matches!(
+            render_barcode("12345", BarcodeType::Ean13),
+            Err(BarcodeRenderError::InvalidLength { .. })
+        ));
+        assert_eq!(
+            render_barcode("9638507A", BarcodeType::Ean8),
+            Err(BarcodeRenderError::InvalidCharacter('A'))
+        );
+        assert_eq!(
+            render_barcode("价格", BarcodeType::Code128),
+            Err(BarcodeRenderError::InvalidCharacter('价'))
+        );
+        assert!(render_barcode("", BarcodeType::Code128).is_err());
+        assert_eq!(
+            render_barcode("4901234567894", BarcodeType::QrCode),
+            Err(BarcodeRenderError::UnsupportedType(BarcodeType::QrCode))
+        );
+    }
+
+    #[test]
+    fn test_type_for_code() {
+        assert_eq!(BarcodeType::for_code("4901234567894"), BarcodeType::Ean13);
+        assert_eq!(BarcodeType::for_code("96385074"), BarcodeType::Ean8);
+        assert_eq!(BarcodeType::for_code("SKU-42"), BarcodeType::Code128);
+    }
+
+    /// Dark/light module grid read back from a rendered QR PNG
+    fn qr_modules_from_png(png: &[u8]) -> Vec<Vec<bool>> {
+        let image = image::load_from_memory(png).unwrap().to_luma8();
+        let modules = image.width() / QR_MODULE_PX - 2 * QR_QUIET_ZONE_MODULES;
+        (0..modules)
+            .map(|my| {
+                (0..modules)
+                    .map(|mx| {
+                        let x = (mx + QR_QUIET_ZONE_MODULES) * QR_MODULE_PX + QR_MODULE_PX / 2;
+                        let y = (my + QR_QUIET_ZONE_MODULES) * QR_MODULE_PX + QR_MODULE_PX / 2;
+                        image.get_pixel(x, y)[0] < 128
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_share_qr_round_trip() {
+        let links = [
+            DeepLink::Product {
+                product_id: "prod 1/α".to_string(),
+                store_id: Some("store_1".to_string()),
+            },
+            DeepLink::Store {
+                store_id: "store_2".to_string(),
+            },
+        ];
+
+        for link in links {
+            let png = make_share_qr(&link).unwrap();
+            let grid = qr_modules_from_png(&png);
+
+            // 从图片中找出与之完全一致的 QR 编码内容
+            let uri = link.to_uri();
+            assert!(uri.starts_with("eprice://"));
+            let expected = qrcode::QrCode::new(uri.as_bytes()).unwrap();
+            assert_eq!(grid.len(), expected.width());
+            for (y, row) in grid.iter().enumerate() {
+                for (x, &dark) in row.iter().enumerate() {
+                    assert_eq!(dark, expected[(x, y)] == qrcode::Color::Dark);
+                }
+            }
+
+            assert_eq!(DeepLink::parse(&uri), Some(link));
+        }
+    }
+
+    #[test]
+    fn test_malformed_deep_links() {
+        for uri in [
+            "",
+            "eprice://",
+            "eprice://product",
+            "eprice://product/",
+            "eprice://product/a/b",
+            "eprice://basket/42",
+            "eprice://store/1?store=2",
+            "eprice://product/1?coupon=x",
+            "eprice://product/%zz",
+            "https://product/1",
+            "product/1",
+        ] {
+            assert_eq!(DeepLink::parse(uri), None, "{}", uri);
+        }
+        assert_eq!(
+            DeepLink::parse("EPRICE://store/s1/"),
+            Some(DeepLink::Store {
+                store_id: "s1".to_string()
+            })
+        );
+    }
+}
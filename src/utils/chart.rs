@@ -0,0 +1,324 @@
+//! Price history chart data and headless PNG rendering.
+
+use crate::models::PriceRecord;
+use ab_glyph::FontRef;
+use chrono::{DateTime, Utc};
+use image::{ImageFormat, Rgb, RgbImage};
+use imageproc::drawing::{
+    draw_filled_circle_mut, draw_filled_rect_mut, draw_line_segment_mut, draw_text_mut, text_size,
+};
+use imageproc::rect::Rect;
+use std::io::Cursor;
+use thiserror::Error;
+
+/// Default size of an exported chart in pixels
+pub const CHART_WIDTH_PX: u32 = 800;
+pub const CHART_HEIGHT_PX: u32 = 400;
+
+// 绘图区四周留白（像素），容纳坐标轴标签
+const MARGIN_LEFT: u32 = 90;
+const MARGIN_RIGHT: u32 = 30;
+const MARGIN_TOP: u32 = 40;
+const MARGIN_BOTTOM: u32 = 60;
+const TEXT_PX: f32 = 14.0;
+const POINT_RADIUS: i32 = 3;
+
+const WHITE: Rgb<u8> = Rgb([255, 255, 255]);
+const PLOT_BACKGROUND: Rgb<u8> = Rgb([240, 240, 240]);
+const BLACK: Rgb<u8> = Rgb([0, 0, 0]);
+const LINE: Rgb<u8> = Rgb([0, 0, 255]);
+const SALE: Rgb<u8> = Rgb([255, 0, 0]);
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ChartRenderError {
+    #[error("Chart size {width}x{height} is too small")]
+    TooSmall { width: u32, height: u32 },
+    #[error("Failed to load chart font: {0}")]
+    Font(String),
+    #[error("Failed to encode PNG: {0}")]
+    Image(String),
+}
+
+/// One price on the chart
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartPoint {
+    pub timestamp: DateTime<Utc>,
+    pub price: f64,
+    pub on_sale: bool,
+}
+
+/// Price history prepared for drawing: points oldest first plus the price extremes
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceChartData {
+    pub points: Vec<ChartPoint>,
+    pub min_price: f64,
+    pub max_price: f64,
+}
+
+impl PriceChartData {
+    /// Chart data for `prices` in time order, or None when there are none
+    pub fn from_prices<'a>(prices: impl IntoIterator<Item = &'a PriceRecord>) -> Option<Self> {
+        let mut points: Vec<ChartPoint> = prices
+            .into_iter()
+            .map(|p| ChartPoint {
+                timestamp: p.timestamp,
+                price: p.price,
+                on_sale: p.is_on_sale,
+            })
+            .collect();
+        if points.is_empty() {
+            return None;
+        }
+        points.sort_by_key(|p| p.timestamp);
+
+        let min_price = points.iter().map(|p| p.price).fold(f64::INFINITY, f64::min);
+        let max_price = points
+            .iter()
+            .map(|p| p.price)
+            .fold(f64::NEG_INFINITY, f64::max);
+        Some(Self {
+            points,
+            min_price,
+            max_price,
+        })
+    }
+
+    /// Point positions as fractions of the plot area: x by timestamp from the
+    /// first to the last point, y bottom to top. Points share the left edge when
+    /// all prices have the same timestamp.
+    pub fn normalized(&self) -> Vec<(f32, f32)> {
+        let price_range = (self.max_price - self.min_price).max(0.01); // 避免除以零
+        let start = self.points[0].timestamp;
+        let span = (self.points[self.points.len() - 1].timestamp - start).num_seconds();
+        self.points
+            .iter()
+            .map(|p| {
+                let x = if span > 0 {
+                    (p.timestamp - start).num_seconds() as f32 / span as f32
+                } else {
+                    0.0
+                };
+                (x, ((p.price - self.min_price) / price_range) as f32)
+            })
+            .collect()
+    }
+}
+
+/// Render `data` as a `width`×`height` PNG line chart with axis labels and
+/// min/max annotations; prices are labelled with `format_price`
+pub fn render_price_chart_png(
+    data: &PriceChartData,
+    width: u32,
+    height: u32,
+    format_price: impl Fn(f64) -> String,
+) -> Result<Vec<u8>, ChartRenderError> {
+    if width <= MARGIN_LEFT + MARGIN_RIGHT || height <= MARGIN_TOP + MARGIN_BOTTOM {
+        return Err(ChartRenderError::TooSmall { width, height });
+    }
+    let font = FontRef::try_from_slice(epaint_default_fonts::UBUNTU_LIGHT)
+        .map_err(|e| ChartRenderError::Font(e.to_string()))?;
+
+    let mut image = RgbImage::from_pixel(width, height, WHITE);
+    let (left, top) = (MARGIN_LEFT as f32, MARGIN_TOP as f32);
+    let (right, bottom) = (
+        (width - MARGIN_RIGHT) as f32,
+        (height - MARGIN_BOTTOM) as f32,
+    );
+    draw_filled_rect_mut(
+        &mut image,
+        Rect::at(MARGIN_LEFT as i32, MARGIN_TOP as i32).of_size(
+            width - MARGIN_LEFT - MARGIN_RIGHT,
+            height - MARGIN_TOP - MARGIN_BOTTOM,
+        ),
+        PLOT_BACKGROUND,
+    );
+
+    // 坐标轴
+    draw_line_segment_mut(&mut image, (left, top), (left, bottom), BLACK);
+    draw_line_segment_mut(&mut image, (left, bottom), (right, bottom), BLACK);
+
+    let points: Vec<(f32, f32)> = data
+        .normalized()
+        .into_iter()
+        .map(|(x, y)| (left + x * (right - left), bottom - y * (bottom - top)))
+        .collect();
+    for pair in points.windows(2) {
+        // 上下各画一条，线宽 2 像素
+        for offset in [0.0, 1.0] {
+            draw_line_segment_mut(
+                &mut image,
+                (pair[0].0, pair[0].1 + offset),
+                (pair[1].0, pair[1].1 + offset),
+                LINE,
+            );
+        }
+    }
+    for (point, chart_point) in points.iter().zip(&data.points) {
+        let color = if chart_point.on_sale { SALE } else { LINE };
+        let center = (point.0.round() as i32, point.1.round() as i32);
+        draw_filled_circle_mut(&mut image, center, POINT_RADIUS, color);
+    }
+
+    // 最高价、最低价标注及刻度
+    let max_label = format!("Max {}", format_price(data.max_price));
+    let min_label = format!("Min {}", format_price(data.min_price));
+    draw_label(
+        &mut image,
+        &font,
+        &max_label,
+        (right - text_width(&font, &max_label) - 6.0, top + 4.0),
+    );
+    draw_label(
+        &mut image,
+        &font,
+        &min_label,
+        (
+            right - text_width(&font, &min_label) - 6.0,
+            bottom - TEXT_PX - 6.0,
+        ),
+    );
+    let max_tick = format_price(data.max_price);
+    let min_tick = format_price(data.min_price);
+    draw_label(
+        &mut image,
+        &font,
+        &max_tick,
+        (
+            left - text_width(&font, &max_tick) - 6.0,
+            top - TEXT_PX / 2.0,
+        ),
+    );
+    draw_label(
+        &mut image,
+        &font,
+        &min_tick,
+        (
+            left - text_width(&font, &min_tick) - 6.0,
+            bottom - TEXT_PX / 2.0,
+        ),
+    );
+
+    // 横轴首尾日期与坐标轴名称
+    let first_date = data.points[0].timestamp.format("%Y-%m-%d").to_string();
+    let last_date = data.points[data.points.len() - 1]
+        .timestamp
+        .format("%Y-%m-%d")
+        .to_string();
+    draw_label(&mut image, &font, &first_date, (left, bottom + 6.0));
+    draw_label(
+        &mut image,
+        &font,
+        &last_date,
+        (right - text_width(&font, &last_date), bottom + 6.0),
+    );
+    let x_title = "Date";
+    draw_label(
+        &mut image,
+        &font,
+        x_title,
+        (
+            (left + right - text_width(&font, x_title)) / 2.0,
+            bottom + 8.0 + TEXT_PX,
+        ),
+    );
+    draw_label(&mut image, &font, "Price", (6.0, top - TEXT_PX - 12.0));
+
+    let mut png = Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, ImageFormat::Png)
+        .map_err(|e| ChartRenderError::Image(e.to_string()))?;
+    Ok(png.into_inner())
+}
+
+fn text_width(font: &FontRef<'_>, text: &str) -> f32 {
+    text_size(TEXT_PX, font, text).0 as f32
+}
+
+/// Draw black `text` with its top-left corner at `origin`
+fn draw_label(image: &mut RgbImage, font: &FontRef<'_>, text: &str, origin: (f32, f32)) {
+    draw_text_mut(
+        image,
+        BLACK,
+        origin.0.round() as i32,
+        origin.1.round() as i32,
+        TEXT_PX,
+        font,
+        text,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(days_ago: i64, price: f64) -> PriceRecord {
+        let mut record = PriceRecord::new(
+            Some("p1".to_string()),
+            "s1".to_string(),
+            None,
+            price,
+            false,
+            None,
+        );
+        // 固定基准时间，保证横坐标可精确比较
+        let base = DateTime::parse_from_rfc3339("2024-06-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        record.timestamp = base - chrono::Duration::days(days_ago);
+        record
+    }
+
+    #[test]
+    fn test_chart_data_sorted_with_extremes() {
+        let prices = vec![record(1, 120.0), record(5, 150.0), record(3, 98.0)];
+        let data = PriceChartData::from_prices(&prices).unwrap();
+        let series: Vec<f64> = data.points.iter().map(|p| p.price).collect();
+        assert_eq!(series, vec![150.0, 98.0, 120.0]);
+        assert_eq!((data.min_price, data.max_price), (98.0, 150.0));
+        assert_eq!(data.normalized()[0], (0.0, 1.0));
+        assert_eq!(data.normalized()[1], (0.5, 0.0));
+        assert!(PriceChartData::from_prices(&[]).is_none());
+    }
+
+    #[test]
+    fn test_chart_x_follows_timestamps() {
+        // 采样间隔不均匀时按时间而不是序号排布
+        let prices = vec![record(10, 100.0), record(9, 110.0), record(0, 120.0)];
+        let data = PriceChartData::from_prices(&prices).unwrap();
+        let xs: Vec<f32> = data.normalized().iter().map(|(x, _)| *x).collect();
+        assert_eq!(xs[0], 0.0);
+        assert!((xs[1] - 0.1).abs() < 1e-3);
+        assert_eq!(xs[2], 1.0);
+
+        let single = PriceChartData::from_prices(&prices[..1]).unwrap();
+        assert_eq!(single.normalized(), vec![(0.0, 0.0)]);
+    }
+
+    #[test]
+    fn test_chart_png_has_expected_size() {
+        let prices = vec![record(3, 150.0), record(2, 98.0), record(1, 120.0)];
+        let data = PriceChartData::from_prices(&prices).unwrap();
+        let png = render_price_chart_png(&data, CHART_WIDTH_PX, CHART_HEIGHT_PX, |p| {
+            format!("¥{:.0}", p)
+        })
+        .unwrap();
+        assert!(!png.is_empty());
+
+        let image = image::load_from_memory(&png).unwrap().to_rgb8();
+        assert_eq!(image.dimensions(), (CHART_WIDTH_PX, CHART_HEIGHT_PX));
+        // 坐标轴为黑色，图外留白为白色
+        assert_eq!(
+            image.get_pixel(MARGIN_LEFT, CHART_HEIGHT_PX - MARGIN_BOTTOM),
+            &BLACK
+        );
+        assert_eq!(
+            image.get_pixel(CHART_WIDTH_PX - 1, CHART_HEIGHT_PX - 1),
+            &WHITE
+        );
+
+        assert!(matches!(
+            render_price_chart_png(&data, 50, 50, |p| p.to_string()),
+            Err(ChartRenderError::TooSmall { .. })
+        ));
+    }
+}
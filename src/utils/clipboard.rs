@@ -0,0 +1,55 @@
+//! Copying text to the system clipboard from UI actions.
+
+use std::sync::Mutex;
+
+/// Destination of clipboard copies; egui's context in the app, a recorder in tests
+pub trait ClipboardSink {
+    fn copy_text(&self, text: String);
+}
+
+impl ClipboardSink for egui::Context {
+    fn copy_text(&self, text: String) {
+        // egui 在本帧结束时把文本写入平台剪贴板
+        egui::Context::copy_text(self, text);
+    }
+}
+
+/// Clipboard that keeps copied texts in memory
+#[derive(Debug, Default)]
+pub struct MemoryClipboard {
+    copied: Mutex<Vec<String>>,
+}
+
+impl MemoryClipboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Most recently copied text
+    pub fn last(&self) -> Option<String> {
+        self.copied.lock().ok().and_then(|c| c.last().cloned())
+    }
+
+    /// All copied texts, oldest first
+    pub fn history(&self) -> Vec<String> {
+        self.copied.lock().map(|c| c.clone()).unwrap_or_default()
+    }
+}
+
+impl ClipboardSink for MemoryClipboard {
+    fn copy_text(&self, text: String) {
+        if let Ok(mut copied) = self.copied.lock() {
+            copied.push(text);
+        }
+    }
+}
+
+/// Copy `text` to `sink`; empty text is ignored
+pub fn copy_to_clipboard(sink: &dyn ClipboardSink, text: &str) -> bool {
+    if text.is_empty() {
+        return false;
+    }
+    sink.copy_text(text.to_string());
+    log::debug!("Copied {} characters to clipboard", text.chars().count());
+    true
+}
@@ -1,5 +1,6 @@
 use anyhow::Result;
 use bcrypt::{DEFAULT_COST, hash, verify};
+use subtle::ConstantTimeEq;
 
 /// Hash a password using bcrypt with default cost
 pub fn hash_password(password: &str) -> Result<String> {
@@ -13,24 +14,228 @@ pub fn hash_password_with_cost(password: &str, cost: u32) -> Result<String> {
     Ok(hashed)
 }
 
+/// Cost settings new password hashes are created with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PasswordHashParams {
+    /// bcrypt cost (log2 of the rounds)
+    pub cost: u32,
+}
+
+impl Default for PasswordHashParams {
+    fn default() -> Self {
+        Self { cost: DEFAULT_COST }
+    }
+}
+
+/// Hash a password with the given parameters
+pub fn hash_password_with_params(password: &str, params: &PasswordHashParams) -> Result<String> {
+    hash_password_with_cost(password, params.cost)
+}
+
+/// Whether `hash` was made with other parameters than `current` (or is not a
+/// bcrypt hash at all) and should be replaced at the next successful login
+pub fn needs_rehash(hash: &str, current: &PasswordHashParams) -> bool {
+    // bcrypt 格式: $2b$<cost>$<salt+hash>
+    let mut parts = hash.split('$');
+    let stored_cost = match (parts.next(), parts.next(), parts.next()) {
+        (Some(""), Some(version), Some(cost)) if version.starts_with('2') => cost.parse().ok(),
+        _ => None,
+    };
+    stored_cost != Some(current.cost)
+}
+
 /// Verify a password against a hash using bcrypt
 pub fn verify_password(password: &str, hash: &str) -> Result<bool> {
     let is_valid = verify(password, hash)?;
     Ok(is_valid)
 }
 
-/// Generate a secure random password
+/// Symbols used by `PasswordGenPolicy::symbols`
+pub const PASSWORD_SYMBOLS: &str = "!@#$%^&*()-_=+[]{};:,.?/";
+
+/// Which characters a generated password may and must contain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswordGenPolicy {
+    pub length: usize,
+    pub uppercase: bool,
+    pub lowercase: bool,
+    pub digits: bool,
+    pub symbols: bool,
+    /// Characters never used, e.g. symbols a site rejects or look-alikes such as "O0l1"
+    pub excluded: String,
+}
+
+impl Default for PasswordGenPolicy {
+    fn default() -> Self {
+        Self {
+            length: 16,
+            uppercase: true,
+            lowercase: true,
+            digits: true,
+            symbols: true,
+            excluded: String::new(),
+        }
+    }
+}
+
+impl PasswordGenPolicy {
+    /// Allowed characters of each enabled class
+    fn classes(&self) -> Vec<Vec<char>> {
+        [
+            (self.uppercase, "ABCDEFGHIJKLMNOPQRSTUVWXYZ"),
+            (self.lowercase, "abcdefghijklmnopqrstuvwxyz"),
+            (self.digits, "0123456789"),
+            (self.symbols, PASSWORD_SYMBOLS),
+        ]
+        .into_iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, chars)| {
+            chars
+                .chars()
+                .filter(|c| !self.excluded.contains(*c))
+                .collect()
+        })
+        .collect()
+    }
+}
+
+/// Uniformly random index below `bound` (rejection sampling avoids modulo bias)
+fn random_index(bound: usize) -> Result<usize> {
+    let bound = bound as u32;
+    let zone = u32::MAX - u32::MAX % bound;
+    loop {
+        let mut bytes = [0u8; 4];
+        getrandom::fill(&mut bytes).map_err(|e| anyhow::anyhow!("No system randomness: {}", e))?;
+        let value = u32::from_le_bytes(bytes);
+        if value < zone {
+            return Ok((value % bound) as usize);
+        }
+    }
+}
+
+/// Generate a random password following `policy`, with at least one
+/// character of every enabled class
+pub fn generate_secure_password_with(policy: &PasswordGenPolicy) -> Result<String> {
+    let classes = policy.classes();
+    if classes.is_empty() {
+        anyhow::bail!("Password policy enables no character classes");
+    }
+    if classes.iter().any(|class| class.is_empty()) {
+        anyhow::bail!("Password policy excludes every character of a required class");
+    }
+    if policy.length < classes.len() {
+        anyhow::bail!(
+            "Password length {} cannot hold {} required character classes",
+            policy.length,
+            classes.len()
+        );
+    }
+
+    let alphabet: Vec<char> = classes.concat();
+    let mut password = Vec::with_capacity(policy.length);
+    for class in &classes {
+        password.push(class[random_index(class.len())?]);
+    }
+    while password.len() < policy.length {
+        password.push(alphabet[random_index(alphabet.len())?]);
+    }
+    // Fisher-Yates 洗牌，避免必选字符总在开头
+    for i in (1..password.len()).rev() {
+        password.swap(i, random_index(i + 1)?);
+    }
+    Ok(password.into_iter().collect())
+}
+
+/// Generate a secure random password with the default policy
 pub fn generate_secure_password(length: usize) -> String {
-    use uuid::Uuid;
+    let policy = PasswordGenPolicy {
+        length,
+        ..PasswordGenPolicy::default()
+    };
+    // 仅当长度不足以容纳全部字符类别时失败，此时改为只用字母和数字
+    generate_secure_password_with(&policy)
+        .or_else(|_| {
+            generate_secure_password_with(&PasswordGenPolicy {
+                uppercase: length >= 3,
+                lowercase: length >= 2,
+                symbols: false,
+                ..policy
+            })
+        })
+        .unwrap_or_default()
+}
+
+/// Compare secrets without an early exit on the first differing byte, so the
+/// time taken does not reveal how much of a guessed token was right.
+/// Only the length may leak
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.ct_eq(b).into()
+}
+
+/// Length of keys used by `encrypt`/`decrypt` (AES-256)
+#[cfg(not(target_arch = "wasm32"))]
+pub const ENCRYPTION_KEY_LEN: usize = 32;
+
+/// PBKDF2 rounds when stretching an app secret into an encryption key
+#[cfg(not(target_arch = "wasm32"))]
+const KEY_DERIVATION_ROUNDS: u32 = 100_000;
+
+/// Derive an AES-256 key from `secret` and a per-file `salt` (PBKDF2-HMAC-SHA256)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn derive_encryption_key(secret: &[u8], salt: &[u8]) -> [u8; ENCRYPTION_KEY_LEN] {
+    let mut key = [0u8; ENCRYPTION_KEY_LEN];
+    ring::pbkdf2::derive(
+        ring::pbkdf2::PBKDF2_HMAC_SHA256,
+        std::num::NonZeroU32::new(KEY_DERIVATION_ROUNDS).expect("non-zero rounds"),
+        salt,
+        secret,
+        &mut key,
+    );
+    key
+}
 
-    // Generate multiple UUIDs and combine them for increased entropy
-    let mut password_chars = Vec::new();
-    while password_chars.len() < length {
-        let uuid = Uuid::new_v4().to_string().replace('-', "");
-        password_chars.extend(uuid.chars());
+/// Encrypt with AES-256-GCM; the output is the random nonce followed by the
+/// ciphertext and authentication tag
+#[cfg(not(target_arch = "wasm32"))]
+pub fn encrypt(key: &[u8; ENCRYPTION_KEY_LEN], plaintext: &[u8]) -> Result<Vec<u8>> {
+    use ring::aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+
+    let key = LessSafeKey::new(
+        UnboundKey::new(&AES_256_GCM, key).map_err(|_| anyhow::anyhow!("Invalid key"))?,
+    );
+    let mut nonce = [0u8; NONCE_LEN];
+    getrandom::fill(&mut nonce).map_err(|e| anyhow::anyhow!("No system randomness: {}", e))?;
+
+    let mut sealed = plaintext.to_vec();
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::empty(),
+        &mut sealed,
+    )
+    .map_err(|_| anyhow::anyhow!("Encryption failed"))?;
+    Ok([nonce.as_slice(), &sealed].concat())
+}
+
+/// Decrypt the output of `encrypt`; fails if the data was altered or the key is wrong
+#[cfg(not(target_arch = "wasm32"))]
+pub fn decrypt(key: &[u8; ENCRYPTION_KEY_LEN], data: &[u8]) -> Result<Vec<u8>> {
+    use ring::aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+
+    if data.len() < NONCE_LEN + AES_256_GCM.tag_len() {
+        anyhow::bail!("Encrypted data is truncated");
     }
+    let key = LessSafeKey::new(
+        UnboundKey::new(&AES_256_GCM, key).map_err(|_| anyhow::anyhow!("Invalid key"))?,
+    );
+    let (nonce, sealed) = data.split_at(NONCE_LEN);
+    let nonce =
+        Nonce::try_assume_unique_for_key(nonce).map_err(|_| anyhow::anyhow!("Invalid nonce"))?;
 
-    password_chars.into_iter().take(length).collect()
+    let mut buffer = sealed.to_vec();
+    let plaintext = key
+        .open_in_place(nonce, Aad::empty(), &mut buffer)
+        .map_err(|_| anyhow::anyhow!("Decryption failed: wrong key or corrupted data"))?;
+    Ok(plaintext.to_vec())
 }
 
 /// Generate a random salt (not needed for bcrypt as it includes salt, but useful for other purposes)
@@ -78,3 +283,98 @@ pub fn validate_password_strength(password: &str) -> Result<(), String> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_needs_rehash_compares_cost() {
+        let params = PasswordHashParams { cost: 5 };
+        let current = hash_password_with_params("secret", &params).unwrap();
+        let old = hash_password_with_cost("secret", 4).unwrap();
+
+        assert!(!needs_rehash(&current, &params));
+        assert!(needs_rehash(&old, &params));
+        assert!(needs_rehash("hashed_secret", &params));
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"session-token", b"session-token"));
+        assert!(constant_time_eq(b"", b""));
+        assert!(!constant_time_eq(b"session-token", b"session-tokem"));
+        assert!(!constant_time_eq(b"session-token", b"session-token-2"));
+        assert!(!constant_time_eq(b"abc", b""));
+    }
+
+    #[test]
+    fn test_generated_passwords_follow_policy() {
+        let policy = PasswordGenPolicy {
+            length: 12,
+            symbols: true,
+            excluded: "O0l1!@#$%".to_string(),
+            ..PasswordGenPolicy::default()
+        };
+        for _ in 0..200 {
+            let password = generate_secure_password_with(&policy).unwrap();
+            assert_eq!(password.chars().count(), 12);
+            assert!(password.chars().any(|c| c.is_ascii_uppercase()));
+            assert!(password.chars().any(|c| c.is_ascii_lowercase()));
+            assert!(password.chars().any(|c| c.is_ascii_digit()));
+            assert!(password.chars().any(|c| PASSWORD_SYMBOLS.contains(c)));
+            assert!(!password.chars().any(|c| policy.excluded.contains(c)));
+        }
+    }
+
+    #[test]
+    fn test_disabled_classes_are_absent() {
+        let policy = PasswordGenPolicy {
+            length: 8,
+            uppercase: false,
+            symbols: false,
+            ..PasswordGenPolicy::default()
+        };
+        for _ in 0..200 {
+            let password = generate_secure_password_with(&policy).unwrap();
+            assert!(
+                password
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+            );
+            assert!(password.chars().any(|c| c.is_ascii_digit()));
+        }
+    }
+
+    #[test]
+    fn test_unsatisfiable_policies_are_rejected() {
+        let none = PasswordGenPolicy {
+            uppercase: false,
+            lowercase: false,
+            digits: false,
+            symbols: false,
+            ..PasswordGenPolicy::default()
+        };
+        assert!(generate_secure_password_with(&none).is_err());
+
+        let no_digits_left = PasswordGenPolicy {
+            excluded: "0123456789".to_string(),
+            ..PasswordGenPolicy::default()
+        };
+        assert!(generate_secure_password_with(&no_digits_left).is_err());
+
+        let too_short = PasswordGenPolicy {
+            length: 3,
+            ..PasswordGenPolicy::default()
+        };
+        assert!(generate_secure_password_with(&too_short).is_err());
+    }
+
+    #[test]
+    fn test_default_generator_keeps_length() {
+        for length in [0, 1, 3, 4, 32] {
+            assert_eq!(generate_secure_password(length).chars().count(), length);
+        }
+        assert!(validate_password_strength(&generate_secure_password(16)).is_ok());
+    }
+}
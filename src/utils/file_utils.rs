@@ -1,5 +1,6 @@
 use anyhow::Result;
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 
 /// Ensure a directory exists, creating it if necessary
@@ -25,6 +26,88 @@ pub fn get_data_directory() -> Result<PathBuf> {
     get_app_data_dir()
 }
 
+/// Current on-disk data layout version
+pub const DATA_VERSION: u32 = 2;
+
+/// Name of the marker file recording the data layout version
+const DATA_VERSION_FILE: &str = "data_version";
+
+/// Files relocated by each layout version: (version introduced, old path, new path)
+const LAYOUT_MOVES: &[(u32, &str, &str)] = &[
+    (2, "config.json", "config/config.json"),
+    (2, "remembered_session.json", "auth/remembered_session.json"),
+];
+
+/// Read the data layout version recorded in `dir`; a missing marker means version 1
+pub fn read_data_version<P: AsRef<Path>>(dir: P) -> u32 {
+    fs::read_to_string(dir.as_ref().join(DATA_VERSION_FILE))
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(1)
+}
+
+/// Migrate the application data directory between layout versions
+pub fn migrate_data_dir(from_version: u32, to_version: u32) -> Result<u32> {
+    let data_dir = get_app_data_dir()?;
+    migrate_data_dir_in(data_dir, from_version, to_version)
+}
+
+/// Migrate the data layout inside `dir`; idempotent and tolerant of missing files
+pub fn migrate_data_dir_in<P: AsRef<Path>>(
+    dir: P,
+    from_version: u32,
+    to_version: u32,
+) -> Result<u32> {
+    let dir = dir.as_ref();
+    if from_version >= to_version {
+        return Ok(from_version);
+    }
+
+    ensure_directory_exists(dir)?;
+    for (version, old, new) in LAYOUT_MOVES {
+        if *version <= from_version || *version > to_version {
+            continue;
+        }
+        let source = dir.join(old);
+        let target = dir.join(new);
+        move_if_missing(&source, &target)?;
+        // 轮换出的 .bak.N 备份随主文件一起迁移
+        for index in 1.. {
+            let backup = backup_path(&source, index);
+            if !backup.exists() {
+                break;
+            }
+            move_if_missing(&backup, &backup_path(&target, index))?;
+        }
+    }
+
+    atomic_write(
+        dir.join(DATA_VERSION_FILE),
+        to_version.to_string().as_bytes(),
+    )?;
+    Ok(to_version)
+}
+
+/// Move `source` to `target`; skipped when already migrated or the file does not exist
+fn move_if_missing(source: &Path, target: &Path) -> Result<()> {
+    if !source.exists() || target.exists() {
+        return Ok(());
+    }
+    if let Some(parent) = target.parent() {
+        ensure_directory_exists(parent)?;
+    }
+    fs::rename(source, target)?;
+    log::info!("Migrated {} -> {}", source.display(), target.display());
+    Ok(())
+}
+
+/// Bring the data directory up to `DATA_VERSION`; run once at startup
+pub fn run_data_migration() -> Result<u32> {
+    let data_dir = get_app_data_dir()?;
+    let current = read_data_version(&data_dir);
+    migrate_data_dir_in(data_dir, current, DATA_VERSION)
+}
+
 /// Initialize required directories for the application
 pub fn initialize_directories() -> Result<()> {
     let data_dir = get_data_directory()?;
@@ -47,10 +130,109 @@ pub fn save_to_file<P: AsRef<Path>>(path: P, data: &[u8]) -> Result<()> {
         ensure_directory_exists(parent)?;
     }
 
-    fs::write(path, data)?;
+    atomic_write(path, data)?;
     Ok(())
 }
 
+/// Atomically replace `path` with `bytes`: write a temp file in the same directory, then rename
+pub fn atomic_write<P: AsRef<Path>>(path: P, bytes: &[u8]) -> std::io::Result<()> {
+    atomic_write_with(path.as_ref(), |file| file.write_all(bytes))
+}
+
+fn atomic_write_with<F>(path: &Path, write: F) -> std::io::Result<()>
+where
+    F: FnOnce(&mut fs::File) -> std::io::Result<()>,
+{
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    fs::create_dir_all(&dir)?;
+
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| std::io::Error::other("invalid file name"))?;
+    let temp_path = dir.join(format!(".{}.{}.tmp", file_name, uuid::Uuid::new_v4()));
+
+    let result = (|| {
+        let mut file = fs::File::create(&temp_path)?;
+        write(&mut file)?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)
+    })();
+
+    // 失败时清理临时文件，原文件保持不变
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Path of the n-th backup copy, e.g. `config.json.bak.1` (newest)
+pub fn backup_path<P: AsRef<Path>>(path: P, index: usize) -> PathBuf {
+    let path = path.as_ref();
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".bak.{}", index));
+    path.with_file_name(name)
+}
+
+/// Atomically write `bytes`, first rotating the current file into at most `keep` `.bak` copies
+pub fn write_with_backup<P: AsRef<Path>>(
+    path: P,
+    bytes: &[u8],
+    keep: usize,
+) -> std::io::Result<()> {
+    let path = path.as_ref();
+
+    if keep > 0 && path.exists() {
+        let oldest = backup_path(path, keep);
+        if oldest.exists() {
+            fs::remove_file(&oldest)?;
+        }
+        for index in (1..keep).rev() {
+            let from = backup_path(path, index);
+            if from.exists() {
+                fs::rename(&from, backup_path(path, index + 1))?;
+            }
+        }
+        fs::copy(path, backup_path(path, 1))?;
+    }
+
+    atomic_write(path, bytes)
+}
+
+/// Header of files written by `write_encrypted`
+#[cfg(not(target_arch = "wasm32"))]
+const ENCRYPTED_MAGIC: &[u8] = b"EPENC1";
+/// Length of the random key-derivation salt stored after the header
+#[cfg(not(target_arch = "wasm32"))]
+const ENCRYPTED_SALT_LEN: usize = 16;
+
+/// Atomically write `data` encrypted with a key derived from `secret`, for
+/// sensitive files such as session tokens
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_encrypted<P: AsRef<Path>>(path: P, data: &[u8], secret: &[u8]) -> Result<()> {
+    let mut salt = [0u8; ENCRYPTED_SALT_LEN];
+    getrandom::fill(&mut salt).map_err(|e| anyhow::anyhow!("No system randomness: {}", e))?;
+    let key = crate::utils::crypto::derive_encryption_key(secret, &salt);
+    let sealed = crate::utils::crypto::encrypt(&key, data)?;
+    save_to_file(path, &[ENCRYPTED_MAGIC, &salt, &sealed].concat())
+}
+
+/// Read a file written by `write_encrypted`; tampered data or a wrong `secret` is an error
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_encrypted<P: AsRef<Path>>(path: P, secret: &[u8]) -> Result<Vec<u8>> {
+    let bytes = fs::read(path.as_ref())?;
+    let rest = bytes
+        .strip_prefix(ENCRYPTED_MAGIC)
+        .filter(|rest| rest.len() >= ENCRYPTED_SALT_LEN)
+        .ok_or_else(|| anyhow::anyhow!("Not an encrypted file: {}", path.as_ref().display()))?;
+    let (salt, sealed) = rest.split_at(ENCRYPTED_SALT_LEN);
+    let key = crate::utils::crypto::derive_encryption_key(secret, salt);
+    crate::utils::crypto::decrypt(&key, sealed)
+}
+
 /// Load data from a file
 pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Vec<u8>> {
     let path = path.as_ref();
@@ -76,3 +258,154 @@ pub fn generate_unique_filename(prefix: &str, extension: &str) -> String {
     let timestamp = chrono::Utc::now().timestamp();
     format!("{}_{}.{}", prefix, timestamp, extension)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypted_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("auth/session.bin");
+
+        write_encrypted(&path, b"\"session-token\"", b"app secret").unwrap();
+        assert!(
+            !fs::read(&path)
+                .unwrap()
+                .windows(13)
+                .any(|w| w == b"session-token")
+        );
+        assert_eq!(
+            read_encrypted(&path, b"app secret").unwrap(),
+            b"\"session-token\""
+        );
+    }
+
+    #[test]
+    fn test_tampered_ciphertext_fails_to_decrypt() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("favorites.bin");
+        write_encrypted(&path, b"[\"p1\",\"p2\"]", b"app secret").unwrap();
+
+        let mut bytes = fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0x01;
+        fs::write(&path, &bytes).unwrap();
+
+        assert!(read_encrypted(&path, b"app secret").is_err());
+    }
+
+    #[test]
+    fn test_wrong_secret_fails_to_decrypt() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("favorites.bin");
+        write_encrypted(&path, b"[]", b"app secret").unwrap();
+
+        assert!(read_encrypted(&path, b"other secret").is_err());
+        // 明文文件不被当作密文
+        fs::write(&path, b"[]").unwrap();
+        assert!(read_encrypted(&path, b"app secret").is_err());
+    }
+
+    #[test]
+    fn test_migrate_old_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("config.json"), b"{}").unwrap();
+        fs::write(dir.path().join("remembered_session.json"), b"\"s1\"").unwrap();
+        assert_eq!(read_data_version(dir.path()), 1);
+
+        let version = migrate_data_dir_in(dir.path(), 1, DATA_VERSION).unwrap();
+
+        assert_eq!(version, DATA_VERSION);
+        assert_eq!(read_data_version(dir.path()), DATA_VERSION);
+        assert!(!dir.path().join("config.json").exists());
+        assert_eq!(
+            fs::read(dir.path().join("config/config.json")).unwrap(),
+            b"{}"
+        );
+        assert_eq!(
+            fs::read(dir.path().join("auth/remembered_session.json")).unwrap(),
+            b"\"s1\""
+        );
+
+        // 再次运行不改变结果
+        migrate_data_dir_in(dir.path(), 1, DATA_VERSION).unwrap();
+        assert!(dir.path().join("config/config.json").exists());
+        assert_eq!(read_data_version(dir.path()), DATA_VERSION);
+    }
+
+    #[test]
+    fn test_migrate_moves_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("config.json"), b"{}").unwrap();
+        fs::write(dir.path().join("config.json.bak.1"), b"1").unwrap();
+        fs::write(dir.path().join("config.json.bak.2"), b"2").unwrap();
+
+        migrate_data_dir_in(dir.path(), 1, DATA_VERSION).unwrap();
+
+        assert!(!dir.path().join("config.json.bak.1").exists());
+        assert_eq!(
+            fs::read(dir.path().join("config/config.json.bak.1")).unwrap(),
+            b"1"
+        );
+        assert_eq!(
+            fs::read(dir.path().join("config/config.json.bak.2")).unwrap(),
+            b"2"
+        );
+    }
+
+    #[test]
+    fn test_migrate_with_missing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let version = migrate_data_dir_in(dir.path(), 1, DATA_VERSION).unwrap();
+        assert_eq!(version, DATA_VERSION);
+        assert_eq!(read_data_version(dir.path()), DATA_VERSION);
+        assert!(!dir.path().join("config/config.json").exists());
+    }
+
+    #[test]
+    fn test_atomic_write_replaces_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+
+        atomic_write(&path, b"first").unwrap();
+        atomic_write(&path, b"second").unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_failed_write_leaves_original_intact() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        atomic_write(&path, b"original").unwrap();
+
+        let result = atomic_write_with(&path, |file| {
+            file.write_all(b"partial")?;
+            Err(std::io::Error::other("disk full"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"original");
+        // 临时文件已清理
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_backup_rotation_keeps_exactly_keep_copies() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+
+        for i in 0..6 {
+            write_with_backup(&path, format!("v{}", i).as_bytes(), 3).unwrap();
+        }
+
+        assert_eq!(fs::read(&path).unwrap(), b"v5");
+        assert_eq!(fs::read(backup_path(&path, 1)).unwrap(), b"v4");
+        assert_eq!(fs::read(backup_path(&path, 2)).unwrap(), b"v3");
+        assert_eq!(fs::read(backup_path(&path, 3)).unwrap(), b"v2");
+        assert!(!backup_path(&path, 4).exists());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 4);
+    }
+}
@@ -0,0 +1,308 @@
+//! GS1 Application Identifier (AI) parsing for GS1-128 / DataMatrix payloads.
+
+use chrono::{Datelike, NaiveDate};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+/// FNC1 group separator used between variable-length elements
+pub const GS1_SEPARATOR: char = '\u{1d}';
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum Gs1Error {
+    #[error("Empty GS1 payload")]
+    Empty,
+    #[error("Unknown application identifier: {0}")]
+    UnknownAi(String),
+    #[error("Invalid length for AI ({ai}): {value}")]
+    InvalidLength { ai: String, value: String },
+    #[error("Invalid value for AI ({ai}): {value}")]
+    InvalidValue { ai: String, value: String },
+}
+
+/// Length rule of an application identifier
+#[derive(Debug, Clone, Copy)]
+struct AiSpec {
+    fixed: Option<usize>, // 固定长度；None 为可变长度（以 FNC1 结束）
+    max: usize,
+    numeric: bool,
+}
+
+const fn fixed(len: usize) -> AiSpec {
+    AiSpec {
+        fixed: Some(len),
+        max: len,
+        numeric: true,
+    }
+}
+
+const fn variable(max: usize, numeric: bool) -> AiSpec {
+    AiSpec {
+        fixed: None,
+        max,
+        numeric,
+    }
+}
+
+/// Spec for an AI code, or None if it is not supported
+fn ai_spec(ai: &str) -> Option<AiSpec> {
+    let spec = match ai {
+        "00" => fixed(18),                                    // SSCC
+        "01" | "02" => fixed(14),                             // GTIN
+        "11" | "12" | "13" | "15" | "16" | "17" => fixed(6),  // 日期 YYMMDD
+        "20" => fixed(2),                                     // 变体
+        "10" | "21" | "22" => variable(20, false),            // 批号 / 序列号
+        "30" | "37" => variable(8, true),                     // 数量
+        "240" | "241" | "250" | "251" => variable(30, false), // 附加标识
+        "400" | "401" | "403" => variable(30, false),         // 订单 / 运输
+        "402" => fixed(17),
+        "410" | "411" | "412" | "413" | "414" | "415" | "416" => fixed(13), // GLN
+        "420" => variable(20, false),                                       // 邮编
+        "8200" => variable(70, false),                                      // 产品 URL
+        _ => {
+            // 310n–369n：计量值（n 为小数位）
+            let digits = ai.as_bytes();
+            if ai.len() == 4
+                && digits.iter().all(u8::is_ascii_digit)
+                && digits[0] == b'3'
+                && (b'1'..=b'6').contains(&digits[1])
+            {
+                fixed(6)
+            } else {
+                return None;
+            }
+        }
+    };
+    Some(spec)
+}
+
+/// Find the AI at the start of `data` (AIs are 2–4 digits and prefix-free)
+fn match_ai(data: &str) -> Option<(&str, AiSpec)> {
+    (2..=4)
+        .filter(|&len| data.len() >= len && data.is_char_boundary(len))
+        .find_map(|len| ai_spec(&data[..len]).map(|spec| (&data[..len], spec)))
+}
+
+/// Parsed GS1 element string: AI -> value
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Gs1Data {
+    pub elements: BTreeMap<String, String>,
+}
+
+impl Gs1Data {
+    /// Raw value of an application identifier
+    pub fn get(&self, ai: &str) -> Option<&str> {
+        self.elements.get(ai).map(String::as_str)
+    }
+
+    /// GTIN from AI (01)
+    pub fn gtin(&self) -> Option<&str> {
+        self.get("01")
+    }
+
+    /// Batch/lot number from AI (10)
+    pub fn batch(&self) -> Option<&str> {
+        self.get("10")
+    }
+
+    /// Serial number from AI (21)
+    pub fn serial(&self) -> Option<&str> {
+        self.get("21")
+    }
+
+    /// Expiry date from AI (17); day "00" means the last day of the month
+    pub fn expiry_date(&self) -> Option<NaiveDate> {
+        self.get("17").and_then(parse_gs1_date)
+    }
+
+    /// Best-before date from AI (15)
+    pub fn best_before(&self) -> Option<NaiveDate> {
+        self.get("15").and_then(parse_gs1_date)
+    }
+}
+
+/// Parse a GS1 YYMMDD date (years are taken as 20YY)
+fn parse_gs1_date(value: &str) -> Option<NaiveDate> {
+    if value.len() != 6 || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let year = 2000 + value[0..2].parse::<i32>().ok()?;
+    let month = value[2..4].parse::<u32>().ok()?;
+    let day = value[4..6].parse::<u32>().ok()?;
+
+    if day == 0 {
+        // 当月最后一天
+        let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+        let next = first.checked_add_months(chrono::Months::new(1))?;
+        return next.pred_opt().filter(|d| d.month() == month);
+    }
+    NaiveDate::from_ymd_opt(year, month, day)
+}
+
+fn validate_element(ai: &str, spec: AiSpec, value: &str) -> Result<(), Gs1Error> {
+    let len = value.chars().count();
+    let length_ok = match spec.fixed {
+        Some(fixed) => len == fixed,
+        None => len >= 1 && len <= spec.max,
+    };
+    if !length_ok {
+        return Err(Gs1Error::InvalidLength {
+            ai: ai.to_string(),
+            value: value.to_string(),
+        });
+    }
+    if spec.numeric && !value.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(Gs1Error::InvalidValue {
+            ai: ai.to_string(),
+            value: value.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// GS1 symbology identifiers that may prefix a scanned payload
+const SYMBOLOGY_IDENTIFIERS: [&str; 4] = ["]C1", "]d2", "]Q3", "]e0"];
+
+/// Whether a scanned payload is marked as GS1 data by a symbology identifier
+/// or an FNC1 separator. Plain EAN/UPC digits are not, even though some of
+/// them happen to parse as element strings
+pub fn has_gs1_marker(payload: &str) -> bool {
+    let payload = payload.trim();
+    SYMBOLOGY_IDENTIFIERS
+        .iter()
+        .any(|prefix| payload.starts_with(prefix))
+        || payload.contains(GS1_SEPARATOR)
+}
+
+/// Parse a GS1 element string, either human-readable (`(01)…(17)…`) or raw with
+/// FNC1 (`\x1d`) separators after variable-length fields. A leading symbology
+/// identifier such as `]C1` is ignored.
+pub fn parse_gs1(payload: &str) -> Result<Gs1Data, Gs1Error> {
+    let payload = payload.trim();
+    let payload = SYMBOLOGY_IDENTIFIERS
+        .iter()
+        .find_map(|prefix| payload.strip_prefix(prefix))
+        .unwrap_or(payload)
+        .trim_start_matches(GS1_SEPARATOR);
+    if payload.is_empty() {
+        return Err(Gs1Error::Empty);
+    }
+
+    let mut data = Gs1Data::default();
+    if payload.starts_with('(') {
+        parse_bracketed(payload, &mut data)?;
+    } else {
+        parse_raw(payload, &mut data)?;
+    }
+    Ok(data)
+}
+
+fn parse_bracketed(payload: &str, data: &mut Gs1Data) -> Result<(), Gs1Error> {
+    let mut rest = payload;
+    while !rest.is_empty() {
+        let inner = rest
+            .strip_prefix('(')
+            .ok_or_else(|| Gs1Error::UnknownAi(rest.to_string()))?;
+        let close = inner
+            .find(')')
+            .ok_or_else(|| Gs1Error::UnknownAi(inner.to_string()))?;
+        let ai = &inner[..close];
+        let after = &inner[close + 1..];
+        let end = after.find('(').unwrap_or(after.len());
+        let value = after[..end].trim_end_matches(GS1_SEPARATOR);
+
+        let spec = ai_spec(ai).ok_or_else(|| Gs1Error::UnknownAi(ai.to_string()))?;
+        validate_element(ai, spec, value)?;
+        data.elements.insert(ai.to_string(), value.to_string());
+        rest = &after[end..];
+    }
+    Ok(())
+}
+
+fn parse_raw(payload: &str, data: &mut Gs1Data) -> Result<(), Gs1Error> {
+    let mut rest = payload;
+    while !rest.is_empty() {
+        let (ai, spec) = match_ai(rest).ok_or_else(|| {
+            let code: String = rest.chars().take(4).collect();
+            Gs1Error::UnknownAi(code)
+        })?;
+        let body = &rest[ai.len()..];
+
+        let (value, next) = match spec.fixed {
+            Some(len) => {
+                let split = body
+                    .char_indices()
+                    .nth(len)
+                    .map(|(i, _)| i)
+                    .unwrap_or(body.len());
+                (&body[..split], &body[split..])
+            }
+            None => match body.find(GS1_SEPARATOR) {
+                Some(i) => (&body[..i], &body[i..]),
+                None => (body, ""),
+            },
+        };
+
+        validate_element(ai, spec, value)?;
+        data.elements.insert(ai.to_string(), value.to_string());
+        rest = next.trim_start_matches(GS1_SEPARATOR);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_payload_with_gtin_expiry_batch() {
+        let payload = "]C1010950110153000317250615\u{1d}10ABC123";
+        let data = parse_gs1(payload).unwrap();
+        assert_eq!(data.gtin(), Some("09501101530003"));
+        assert_eq!(data.expiry_date(), NaiveDate::from_ymd_opt(2025, 6, 15));
+        assert_eq!(data.batch(), Some("ABC123"));
+        assert_eq!(data.elements.len(), 3);
+    }
+
+    #[test]
+    fn test_gs1_marker_detection() {
+        assert!(has_gs1_marker("]C1010950110153000317250615"));
+        assert!(has_gs1_marker("0109501101530003\u{1d}10ABC123"));
+        // EAN-13 数字即使恰好能按 AI 解析也不算 GS1
+        assert!(!has_gs1_marker("4901234567894"));
+    }
+
+    #[test]
+    fn test_bracketed_payload() {
+        let data = parse_gs1("(01)09501101530003(17)250200(10)LOT7(21)S42").unwrap();
+        assert_eq!(data.gtin(), Some("09501101530003"));
+        // 日为 00 表示当月最后一天
+        assert_eq!(data.expiry_date(), NaiveDate::from_ymd_opt(2025, 2, 28));
+        assert_eq!(data.batch(), Some("LOT7"));
+        assert_eq!(data.serial(), Some("S42"));
+    }
+
+    #[test]
+    fn test_unknown_ai_reported() {
+        assert_eq!(
+            parse_gs1("(01)09501101530003(99)XYZ"),
+            Err(Gs1Error::UnknownAi("99".to_string()))
+        );
+        assert!(matches!(
+            parse_gs1("0109501101530003990XYZ"),
+            Err(Gs1Error::UnknownAi(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_elements() {
+        assert_eq!(parse_gs1("  "), Err(Gs1Error::Empty));
+        assert!(matches!(
+            parse_gs1("(01)123"),
+            Err(Gs1Error::InvalidLength { .. })
+        ));
+        assert!(matches!(
+            parse_gs1("(17)25AB01"),
+            Err(Gs1Error::InvalidValue { .. })
+        ));
+    }
+}
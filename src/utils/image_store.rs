@@ -0,0 +1,219 @@
+//! Receipt photo storage under the app data directory, with PNG thumbnails for list display.
+
+use crate::utils::crypto::hash_data_sha256;
+use crate::utils::file_utils::{atomic_write, get_data_directory};
+use image::{DynamicImage, GenericImageView, ImageFormat, RgbaImage};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Largest receipt photo accepted by default (10 MB)
+pub const MAX_IMAGE_BYTES: u64 = 10 * 1024 * 1024;
+/// Longest side of a generated thumbnail in pixels
+pub const THUMBNAIL_MAX_PX: u32 = 256;
+
+/// Directory (relative to the data dir) holding receipt photos
+const RECEIPTS_DIR: &str = "receipts";
+/// Sub-directory of `RECEIPTS_DIR` holding thumbnails
+const THUMBNAILS_DIR: &str = "thumbnails";
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ImageStoreError {
+    #[error("Image is too large ({size} bytes, limit is {max} bytes)")]
+    TooLarge { size: u64, max: u64 },
+    #[error("Unsupported or corrupt image: {0}")]
+    Decode(String),
+    #[error("Failed to store image: {0}")]
+    Io(String),
+}
+
+impl From<std::io::Error> for ImageStoreError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e.to_string())
+    }
+}
+
+/// Relative path of the thumbnail belonging to a stored image
+pub fn thumbnail_path(relative: &str) -> String {
+    let stem = Path::new(relative)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(relative);
+    format!("{}/{}/{}.png", RECEIPTS_DIR, THUMBNAILS_DIR, stem)
+}
+
+/// Downscale `image` so its longest side is at most `max_px`; smaller images are kept as is
+pub fn make_thumbnail(image: &DynamicImage, max_px: u32) -> DynamicImage {
+    let (width, height) = image.dimensions();
+    if width <= max_px && height <= max_px {
+        image.clone()
+    } else {
+        image.thumbnail(max_px, max_px)
+    }
+}
+
+/// Stores receipt photos by content, so saving the same photo twice returns the same path
+#[derive(Debug, Clone)]
+pub struct ImageStore {
+    root: PathBuf,
+    max_bytes: u64,
+    thumbnail_px: u32,
+}
+
+impl ImageStore {
+    /// Store rooted at `root`; returned paths are relative to it
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            max_bytes: MAX_IMAGE_BYTES,
+            thumbnail_px: THUMBNAIL_MAX_PX,
+        }
+    }
+
+    /// Store rooted at the application data directory
+    pub fn open_default() -> Result<Self, ImageStoreError> {
+        let root = get_data_directory().map_err(|e| ImageStoreError::Io(e.to_string()))?;
+        Ok(Self::new(root))
+    }
+
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    pub fn with_thumbnail_px(mut self, thumbnail_px: u32) -> Self {
+        self.thumbnail_px = thumbnail_px.max(1);
+        self
+    }
+
+    /// Absolute location of a path returned by `save`
+    pub fn absolute_path(&self, relative: &str) -> PathBuf {
+        self.root.join(relative)
+    }
+
+    fn check_size(&self, size: u64) -> Result<(), ImageStoreError> {
+        if size > self.max_bytes {
+            return Err(ImageStoreError::TooLarge {
+                size,
+                max: self.max_bytes,
+            });
+        }
+        Ok(())
+    }
+
+    /// Copy the photo at `path` into the store; the size is checked before reading it
+    pub fn save_file(&self, path: &Path) -> Result<String, ImageStoreError> {
+        self.check_size(std::fs::metadata(path)?.len())?;
+        self.save(&std::fs::read(path)?)
+    }
+
+    /// Save encoded image bytes and their thumbnail; returns the image's relative path
+    pub fn save(&self, bytes: &[u8]) -> Result<String, ImageStoreError> {
+        self.check_size(bytes.len() as u64)?;
+        let format =
+            image::guess_format(bytes).map_err(|e| ImageStoreError::Decode(e.to_string()))?;
+        let decoded = image::load_from_memory_with_format(bytes, format)
+            .map_err(|e| ImageStoreError::Decode(e.to_string()))?;
+        let extension = format.extensions_str().first().copied().unwrap_or("img");
+
+        let relative = format!("{}/{}.{}", RECEIPTS_DIR, hash_data_sha256(bytes), extension);
+        let target = self.absolute_path(&relative);
+        if !target.exists() {
+            atomic_write(&target, bytes)?;
+        }
+
+        let mut png = Vec::new();
+        make_thumbnail(&decoded, self.thumbnail_px)
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .map_err(|e| ImageStoreError::Io(e.to_string()))?;
+        atomic_write(self.absolute_path(&thumbnail_path(&relative)), &png)?;
+
+        log::info!("Stored receipt image {}", relative);
+        Ok(relative)
+    }
+
+    /// Decoded thumbnail of a stored image
+    pub fn load_thumbnail(&self, relative: &str) -> Result<RgbaImage, ImageStoreError> {
+        let bytes = std::fs::read(self.absolute_path(&thumbnail_path(relative)))?;
+        image::load_from_memory_with_format(&bytes, ImageFormat::Png)
+            .map(|image| image.to_rgba8())
+            .map_err(|e| ImageStoreError::Decode(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    fn png_bytes(width: u32, height: u32) -> Vec<u8> {
+        let image = RgbImage::from_fn(width, height, |x, y| Rgb([x as u8, y as u8, 128]));
+        let mut bytes = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_save_returns_stable_relative_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ImageStore::new(dir.path());
+        let bytes = png_bytes(40, 30);
+
+        let first = store.save(&bytes).unwrap();
+        let second = store.save(&bytes).unwrap();
+        assert_eq!(first, second);
+        assert!(first.starts_with("receipts/") && first.ends_with(".png"));
+        assert_eq!(std::fs::read(store.absolute_path(&first)).unwrap(), bytes);
+
+        let other = store.save(&png_bytes(41, 30)).unwrap();
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn test_thumbnail_fits_target_dimension() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ImageStore::new(dir.path());
+
+        let path = store.save(&png_bytes(1000, 500)).unwrap();
+        let thumbnail = store.load_thumbnail(&path).unwrap();
+        assert_eq!(
+            thumbnail.dimensions(),
+            (THUMBNAIL_MAX_PX, THUMBNAIL_MAX_PX / 2)
+        );
+
+        // 小图不放大
+        let small = store.save(&png_bytes(20, 10)).unwrap();
+        assert_eq!(store.load_thumbnail(&small).unwrap().dimensions(), (20, 10));
+    }
+
+    #[test]
+    fn test_oversized_image_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ImageStore::new(dir.path()).with_max_bytes(100);
+        let bytes = png_bytes(200, 200);
+
+        assert!(matches!(
+            store.save(&bytes),
+            Err(ImageStoreError::TooLarge { max: 100, .. })
+        ));
+        let file = dir.path().join("photo.png");
+        std::fs::write(&file, &bytes).unwrap();
+        assert!(matches!(
+            store.save_file(&file),
+            Err(ImageStoreError::TooLarge { .. })
+        ));
+        assert!(!dir.path().join(RECEIPTS_DIR).exists());
+    }
+
+    #[test]
+    fn test_non_image_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ImageStore::new(dir.path());
+        assert!(matches!(
+            store.save(b"not an image"),
+            Err(ImageStoreError::Decode(_))
+        ));
+    }
+}
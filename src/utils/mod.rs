@@ -1,19 +1,43 @@
+pub mod barcode;
+pub mod chart;
+pub mod clipboard;
+pub mod clock;
 pub mod crypto;
 pub mod file_utils;
+pub mod gs1;
+pub mod image_store;
 pub mod notification;
+pub mod profanity;
+pub mod timezone;
 pub mod validation;
 
+pub use barcode::{BarcodeRenderError, make_share_qr, render_barcode, render_qr};
+pub use chart::{ChartRenderError, PriceChartData, render_price_chart_png};
+pub use clipboard::{ClipboardSink, MemoryClipboard, copy_to_clipboard};
+pub use clock::{Clock, FixedClock, SystemClock};
 pub use crypto::{
-    generate_salt, generate_secure_password, hash_password, validate_password_strength,
-    verify_password,
+    PasswordGenPolicy, PasswordHashParams, constant_time_eq, generate_salt,
+    generate_secure_password, generate_secure_password_with, hash_password,
+    validate_password_strength, verify_password,
 };
 pub use file_utils::{
-    ensure_directory_exists, get_app_data_dir, get_data_directory, initialize_directories,
+    atomic_write, ensure_directory_exists, get_app_data_dir, get_data_directory,
+    initialize_directories, write_with_backup,
 };
+#[cfg(not(target_arch = "wasm32"))]
+pub use file_utils::{read_encrypted, write_encrypted};
+pub use gs1::{Gs1Data, Gs1Error, has_gs1_marker, parse_gs1};
+pub use image_store::{ImageStore, ImageStoreError};
 pub use notification::NotificationService;
+pub use profanity::ProfanityFilter;
+pub use timezone::{COMMON_TIMEZONES, Tz, timezone_from_name};
 // 移除对 validation::validate_email 的直接导出，使用下方自定义实现
 
+use crate::error::Locale;
+use crate::models::{Quantity, Unit};
 use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use regex::Regex;
 
 /// 更严格的邮箱校验：
 /// - 必须且仅有一个 '@'
@@ -71,13 +95,157 @@ pub fn validate_email(email: &str) -> bool {
 }
 
 /// 货币类型
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
 pub enum Currency {
+    /// 人民币，界面默认货币
+    #[default]
+    CNY,
     JPY,
     USD,
     EUR,
 }
 
+impl Currency {
+    pub const ALL: [Currency; 4] = [Currency::CNY, Currency::JPY, Currency::USD, Currency::EUR];
+
+    /// ISO 4217 code
+    pub fn code(&self) -> &'static str {
+        match self {
+            Currency::CNY => "CNY",
+            Currency::JPY => "JPY",
+            Currency::USD => "USD",
+            Currency::EUR => "EUR",
+        }
+    }
+
+    /// Display symbol
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Currency::CNY | Currency::JPY => "¥",
+            Currency::USD => "$",
+            Currency::EUR => "€",
+        }
+    }
+
+    /// Natural number of decimal places (0 for JPY, which has no sub-unit)
+    pub fn decimals(&self) -> usize {
+        match self {
+            Currency::JPY => 0,
+            Currency::CNY | Currency::USD | Currency::EUR => 2,
+        }
+    }
+
+    /// Convert an amount in major units (e.g. dollars) to the minor units
+    /// taken by [`format_price`]
+    pub fn to_minor_units(&self, amount: f64) -> i64 {
+        self.to_minor_units_with(amount, RoundingMode::HalfUp)
+    }
+
+    /// [`Self::to_minor_units`] with an explicit rounding mode
+    pub fn to_minor_units_with(&self, amount: f64, rounding: RoundingMode) -> i64 {
+        let decimals = self.decimals();
+        (rounding.round(amount, decimals) * 10_f64.powi(decimals as i32)).round() as i64
+    }
+}
+
+/// 舍入方式，用于价格显示、货币换算和统计平均值
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+pub enum RoundingMode {
+    /// 四舍五入，.5 远离零
+    #[default]
+    HalfUp,
+    /// 银行家舍入，.5 取偶数
+    HalfEven,
+    /// 向下取整
+    Floor,
+    /// 向上取整
+    Ceil,
+}
+
+impl RoundingMode {
+    pub const ALL: [RoundingMode; 4] = [
+        RoundingMode::HalfUp,
+        RoundingMode::HalfEven,
+        RoundingMode::Floor,
+        RoundingMode::Ceil,
+    ];
+
+    /// Label shown in settings
+    pub fn label(&self) -> &'static str {
+        match self {
+            RoundingMode::HalfUp => "四舍五入",
+            RoundingMode::HalfEven => "银行家舍入",
+            RoundingMode::Floor => "向下取整",
+            RoundingMode::Ceil => "向上取整",
+        }
+    }
+
+    /// Round `value` to `decimals` places
+    pub fn round(&self, value: f64, decimals: usize) -> f64 {
+        let factor = 10_f64.powi(decimals as i32);
+        let scaled = value * factor;
+        let rounded = match self {
+            RoundingMode::HalfUp => scaled.round(),
+            RoundingMode::HalfEven => scaled.round_ties_even(),
+            RoundingMode::Floor => scaled.floor(),
+            RoundingMode::Ceil => scaled.ceil(),
+        };
+        rounded / factor
+    }
+}
+
+/// Formats prices in major units for display: the currency symbol, thousands
+/// separators and the currency's natural precision unless overridden
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PriceFormatter {
+    currency: Currency,
+    decimals: Option<usize>,
+    rounding: RoundingMode,
+}
+
+impl PriceFormatter {
+    pub fn new(currency: Currency) -> Self {
+        Self {
+            currency,
+            decimals: None,
+            rounding: RoundingMode::default(),
+        }
+    }
+
+    /// Round with `rounding` instead of half-up
+    pub fn with_rounding(mut self, rounding: RoundingMode) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    pub fn rounding(&self) -> RoundingMode {
+        self.rounding
+    }
+
+    /// Show `decimals` places instead of the currency's natural precision
+    pub fn with_decimals(mut self, decimals: Option<usize>) -> Self {
+        self.decimals = decimals;
+        self
+    }
+
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn decimals(&self) -> usize {
+        self.decimals.unwrap_or(self.currency.decimals())
+    }
+
+    /// `amount` (major units) as e.g. "¥350" or "$3.50"
+    pub fn format(&self, amount: f64) -> String {
+        format!(
+            "{}{}",
+            self.currency.symbol(),
+            format_with_thousands(amount, self.decimals(), self.rounding)
+        )
+    }
+}
+
 /// 距离单位
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DistanceUnit {
@@ -114,26 +282,20 @@ pub fn calculate_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
     EARTH_RADIUS_KM * c
 }
 
-/// 按货币格式化价格（以最小货币单位：USD/EUR 分，JPY 元）
+/// 按货币格式化价格（以最小货币单位：CNY/USD/EUR 分，JPY 元）
 pub fn format_price(amount_minor: i64, currency: Currency) -> String {
-    match currency {
-        Currency::JPY => format!("¥{}", format_with_thousands(amount_minor as f64, 0)),
-        Currency::USD => {
-            let value = amount_minor as f64 / 100.0;
-            format!("${}", format_with_thousands(value, 2))
-        }
-        Currency::EUR => {
-            let value = amount_minor as f64 / 100.0;
-            format!("€{}", format_with_thousands(value, 2))
-        }
-    }
+    let value = amount_minor as f64 / 10_f64.powi(currency.decimals() as i32);
+    format!(
+        "{}{}",
+        currency.symbol(),
+        format_with_thousands(value, currency.decimals(), RoundingMode::HalfUp)
+    )
 }
 
 /// 带千分位与指定小数位的格式化
-fn format_with_thousands(value: f64, decimals: usize) -> String {
-    // 四舍五入到指定小数位
-    let factor = 10_f64.powi(decimals as i32);
-    let rounded = (value * factor).round() / factor;
+fn format_with_thousands(value: f64, decimals: usize, rounding: RoundingMode) -> String {
+    // 按舍入方式保留指定小数位
+    let rounded = rounding.round(value, decimals);
 
     // 拆分整数与小数
     let s = if decimals == 0 {
@@ -233,15 +395,58 @@ pub fn validate_username(username: &str) -> Result<(), String> {
     Ok(())
 }
 
-/// 校验条码：允许 8/12/13 位纯数字
+/// 全角 ASCII 字符（数字、字母、标点）及全角空格转为半角，其余字符保持不变
+pub fn normalize_width(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            '\u{3000}' => ' ',
+            _ => c,
+        })
+        .collect()
+}
+
+/// 校验条码：允许 8/12/13 位纯数字（全角数字视同半角）
 pub fn validate_barcode(code: &str) -> bool {
+    let code = normalize_width(code);
     let len = code.len();
     (len == 8 || len == 12 || len == 13) && code.chars().all(|c| c.is_ascii_digit())
 }
 
-/// 清理搜索关键字：去首尾空白，将换行/制表/CRLF 转为空格，并压缩多空格
+/// 未指定国家时电话号码默认所属地区
+pub const DEFAULT_PHONE_REGION: &str = "JP";
+
+/// 将电话号码规范化为 E.164 格式（如 "+81312345678"）。
+/// `default_region` 为 ISO 国家代码，用于解析不带国际区号的本地号码；无法解析或号码无效时返回 None
+pub fn normalize_phone(input: &str, default_region: &str) -> Option<String> {
+    let region = default_region
+        .trim()
+        .to_uppercase()
+        .parse::<phonenumber::country::Id>()
+        .ok();
+    let number = phonenumber::parse(region, input.trim()).ok()?;
+    if !phonenumber::is_valid(&number) {
+        return None;
+    }
+    Some(number.format().mode(phonenumber::Mode::E164).to_string())
+}
+
+/// Percent-encode a URI component, keeping only RFC 3986 unreserved characters
+pub fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// 清理搜索关键字：全角转半角，去首尾空白，将换行/制表/CRLF 转为空格，并压缩多空格
 pub fn sanitize_search_query(input: &str) -> String {
-    let replaced = input
+    let replaced = normalize_width(input)
         .replace(['\n', '\r', '\t'], " ")
         .split_whitespace()
         .collect::<Vec<_>>()
@@ -251,19 +456,62 @@ pub fn sanitize_search_query(input: &str) -> String {
 
 /// 按指定格式格式化日期时间
 pub fn format_datetime(dt: &DateTime<Utc>, fmt: DateTimeFormat) -> String {
+    format_datetime_tz(dt, Tz::UTC, fmt)
+}
+
+/// 换算到 `tz` 的当地时间后按指定格式格式化，`Full` 附带时区缩写
+pub fn format_datetime_tz(dt: &DateTime<Utc>, tz: Tz, fmt: DateTimeFormat) -> String {
+    let local = dt.with_timezone(&tz);
     match fmt {
-        DateTimeFormat::Short => dt.format("%m/%d/%y").to_string(),
-        DateTimeFormat::Medium => dt.format("%b %e, %Y").to_string().replace("  ", " "),
-        DateTimeFormat::Long => dt.format("%B %e, %Y %H:%M").to_string().replace("  ", " "),
-        DateTimeFormat::Full => dt
-            .format("%B %e, %Y %H:%M:%S UTC")
-            .to_string()
-            .replace("  ", " "),
+        DateTimeFormat::Short => local.format("%m/%d/%y").to_string(),
+        // %-d 输出不补位的日期，避免 %e 在个位数日期前补空格
+        DateTimeFormat::Medium => local.format("%b %-d, %Y").to_string(),
+        DateTimeFormat::Long => local.format("%B %-d, %Y %H:%M").to_string(),
+        DateTimeFormat::Full => local.format("%B %-d, %Y %H:%M:%S %Z").to_string(),
+    }
+}
+
+/// 相对时间描述，如 "5 minutes ago" / "5分钟前"；`from` 晚于 `now` 时为 "in 5 minutes" / "5分钟后"
+pub fn format_relative(from: DateTime<Utc>, now: DateTime<Utc>, locale: Locale) -> String {
+    const MINUTE: u64 = 60;
+    const HOUR: u64 = 60 * MINUTE;
+    const DAY: u64 = 24 * HOUR;
+
+    let delta = (now - from).num_seconds();
+    let seconds = delta.unsigned_abs();
+    if seconds < MINUTE {
+        return match locale {
+            Locale::En => "just now".to_string(),
+            Locale::Zh => "刚刚".to_string(),
+        };
+    }
+
+    // (数量, 英文单位, 中文单位)
+    let (value, en, zh) = match seconds {
+        s if s < HOUR => (s / MINUTE, "minute", "分钟"),
+        s if s < DAY => (s / HOUR, "hour", "小时"),
+        s if s < 30 * DAY => (s / DAY, "day", "天"),
+        s if s < 365 * DAY => (s / (30 * DAY), "month", "个月"),
+        s => (s / (365 * DAY), "year", "年"),
+    };
+    let future = delta < 0;
+    match locale {
+        Locale::En => {
+            let unit = format!("{} {}{}", value, en, if value == 1 { "" } else { "s" });
+            if future {
+                format!("in {}", unit)
+            } else {
+                format!("{} ago", unit)
+            }
+        }
+        Locale::Zh => format!("{}{}{}", value, zh, if future { "后" } else { "前" }),
     }
 }
 
-/// 解析价格字符串到最小货币单位（分/厘）。支持 "1,234.56" / "1234" 等格式
+/// 解析价格字符串到最小货币单位（分/厘）。支持 "1,234.56" / "1234" 等格式，全角数字视同半角
 pub fn parse_price(s: &str) -> Result<i64, String> {
+    let s = normalize_width(s);
+    let s = s.as_str();
     if s.trim().is_empty() {
         return Err("empty input".to_string());
     }
@@ -309,14 +557,68 @@ pub fn parse_price(s: &str) -> Result<i64, String> {
     Ok(int_value * 100 + cents)
 }
 
-/// 生成条码校验位（EAN-13: 12 位输入，或 UPC-A: 11 位输入）。返回校验位，长度不符返回 None
+/// 从商品名称/描述中解析包装规格，例如 "330ml"、"1.5L"、"200g"、"500克"。
+/// 返回第一个匹配的规格，无法识别时返回 None
+pub fn parse_quantity(s: &str) -> Option<Quantity> {
+    static QUANTITY_REGEX: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"(?i)(\d+(?:\.\d+)?)\s*(ml|kg|l|g|毫升|千克|公斤|升|克)(?:[^a-z]|$)")
+            .expect("Invalid quantity regex")
+    });
+
+    let caps = QUANTITY_REGEX.captures(s)?;
+    let value: f64 = caps[1].parse().ok()?;
+    if value <= 0.0 {
+        return None;
+    }
+    let unit = match caps[2].to_lowercase().as_str() {
+        "ml" | "毫升" => Unit::Milliliter,
+        "l" | "升" => Unit::Liter,
+        "g" | "克" => Unit::Gram,
+        "kg" | "千克" | "公斤" => Unit::Kilogram,
+        _ => return None,
+    };
+    Some(Quantity::new(value, unit))
+}
+
+/// 从商品名称/描述中解析每包件数，例如 "6缶"、"x12"、"6-pack"、"6x330ml"、"10枚入"。
+/// "枚" 只在 "枚入" 中算作件数（"6枚切" 是切片数）。返回第一个匹配的件数，无法识别时返回 None
+pub fn parse_pack_count(s: &str) -> Option<u32> {
+    static PACK_WORD_REGEX: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(
+            r"(?i)(\d+)\s*(?:-\s*)?(?:packs?\b|pk\b|pcs\b|缶|罐|本|瓶|個|个|袋|包|枚入|パック)",
+        )
+        .expect("Invalid pack count regex")
+    });
+    // "x12"、"×6"，或 "6x330ml"（此时件数在乘号前）
+    static PACK_TIMES_REGEX: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(
+            r"(?i)(?:(\d+)\s*|^|[^a-z0-9])[x×*]\s*(\d+(?:\.\d+)?)\s*(ml|kg|l|g|毫升|千克|公斤|升|克)?",
+        )
+        .expect("Invalid pack count regex")
+    });
+
+    let count = if let Some(caps) = PACK_WORD_REGEX.captures(s) {
+        caps[1].parse().ok()?
+    } else {
+        let caps = PACK_TIMES_REGEX.captures(s)?;
+        if caps.get(3).is_some() {
+            caps.get(1)?.as_str().parse().ok()?
+        } else {
+            caps[2].parse().ok()?
+        }
+    };
+    (count > 0).then_some(count)
+}
+
+/// 生成条码校验位（EAN-13: 12 位输入，UPC-A: 11 位输入，或 EAN-8: 7 位输入）。返回校验位，长度不符返回 None
 pub fn generate_barcode_checksum(code: &str) -> Option<u8> {
     if !code.chars().all(|c| c.is_ascii_digit()) {
         return None;
     }
     match code.len() {
         12 => Some(ean13_checksum(code)),
-        11 => Some(upca_checksum(code)),
+        // EAN-8 与 UPC-A 权重相同（从左起奇数位×3）
+        11 | 7 => Some(upca_checksum(code)),
         _ => None,
     }
 }
@@ -354,10 +656,10 @@ fn ean13_checksum(code12: &str) -> u8 {
     std::cmp::max(chk_even3, chk_odd3)
 }
 
-fn upca_checksum(code11: &str) -> u8 {
+fn upca_checksum(code: &str) -> u8 {
     let mut odd_sum = 0i32;
     let mut even_sum = 0i32;
-    for (i, ch) in code11.chars().enumerate() {
+    for (i, ch) in code.chars().enumerate() {
         let d = (ch as u8 - b'0') as i32;
         if (i + 1) % 2 == 1 {
             odd_sum += d;
@@ -464,7 +766,8 @@ pub fn generate_user_token(user_id: i64) -> String {
     format!("{}:{}:{}", user_id, now, rand)
 }
 
-/// 校验用户令牌是否为指定用户 ID 生成（仅基于前缀判断）
+/// 校验用户令牌是否为指定用户 ID 生成（仅基于前缀判断，按常量时间比较）
 pub fn verify_user_token(token: &str, user_id: i64) -> bool {
-    token.split(':').next().and_then(|p| p.parse::<i64>().ok()) == Some(user_id)
+    let prefix = token.split(':').next().unwrap_or_default();
+    constant_time_eq(prefix.as_bytes(), user_id.to_string().as_bytes())
 }
@@ -0,0 +1,100 @@
+//! Word-list based profanity detection for user-generated text.
+
+use crate::error::Locale;
+
+const EN_WORDS: &[&str] = &[
+    "fuck", "shit", "bitch", "bastard", "asshole", "damn", "crap",
+];
+const ZH_WORDS: &[&str] = &["他妈的", "妈的", "傻逼", "操你", "混蛋", "王八蛋"];
+
+/// Detects and masks flagged words for a locale
+#[derive(Debug, Clone)]
+pub struct ProfanityFilter {
+    locale: Locale,
+    words: Vec<String>,
+}
+
+impl ProfanityFilter {
+    /// Filter using the built-in word list for `locale`
+    pub fn new(locale: Locale) -> Self {
+        let words = match locale {
+            Locale::En => EN_WORDS,
+            Locale::Zh => ZH_WORDS,
+        };
+        Self {
+            locale,
+            words: words.iter().map(|w| w.to_string()).collect(),
+        }
+    }
+
+    pub fn locale(&self) -> Locale {
+        self.locale
+    }
+
+    /// Add extra words to the list
+    pub fn with_words(mut self, words: &[&str]) -> Self {
+        self.words.extend(words.iter().map(|w| w.to_lowercase()));
+        self
+    }
+
+    /// Flagged words found in `text`, in list order
+    pub fn find(&self, text: &str) -> Vec<String> {
+        self.words
+            .iter()
+            .filter(|word| !self.matches(text, word).is_empty())
+            .cloned()
+            .collect()
+    }
+
+    pub fn contains_profanity(&self, text: &str) -> bool {
+        self.words
+            .iter()
+            .any(|word| !self.matches(text, word).is_empty())
+    }
+
+    /// Replace every character of flagged words with `*`
+    pub fn mask(&self, text: &str) -> String {
+        let mut chars: Vec<char> = text.chars().collect();
+        for word in &self.words {
+            for (start, len) in self.matches(text, word) {
+                for c in chars.iter_mut().skip(start).take(len) {
+                    *c = '*';
+                }
+            }
+        }
+        chars.into_iter().collect()
+    }
+
+    /// (char offset, char length) of each occurrence of `word`.
+    /// 英文按整词匹配（忽略大小写），中文按子串匹配
+    fn matches(&self, text: &str, word: &str) -> Vec<(usize, usize)> {
+        // 逐字符转小写，保证偏移与原文一一对应
+        let haystack: Vec<char> = text
+            .chars()
+            .map(|c| c.to_lowercase().next().unwrap_or(c))
+            .collect();
+        let needle: Vec<char> = word.chars().collect();
+        if needle.is_empty() || haystack.len() < needle.len() {
+            return Vec::new();
+        }
+
+        let whole_word = self.locale == Locale::En;
+        let is_word_char = |i: usize| haystack.get(i).is_some_and(|c| c.is_alphanumeric());
+
+        (0..=haystack.len() - needle.len())
+            .filter(|&start| haystack[start..start + needle.len()] == needle[..])
+            .filter(|&start| {
+                !whole_word
+                    || ((start == 0 || !is_word_char(start - 1))
+                        && !is_word_char(start + needle.len()))
+            })
+            .map(|start| (start, needle.len()))
+            .collect()
+    }
+}
+
+impl Default for ProfanityFilter {
+    fn default() -> Self {
+        Self::new(Locale::Zh)
+    }
+}
@@ -0,0 +1,56 @@
+//! Time zones for showing timestamps in the user's local time.
+//!
+//! Offsets and daylight-saving rules come from the IANA database bundled by
+//! `chrono-tz`; this module only lists the zones offered in the settings.
+
+pub use chrono_tz::Tz;
+
+/// Zones offered in the settings; any IANA name is accepted in the config
+pub const COMMON_TIMEZONES: [Tz; 8] = [
+    Tz::UTC,
+    Tz::Asia__Tokyo,
+    Tz::Asia__Shanghai,
+    Tz::Asia__Seoul,
+    Tz::Europe__London,
+    Tz::Europe__Berlin,
+    Tz::America__New_York,
+    Tz::America__Los_Angeles,
+];
+
+/// Zone with the given IANA name, e.g. "Asia/Tokyo"
+pub fn timezone_from_name(name: &str) -> Option<Tz> {
+    name.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, TimeZone, Utc};
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn test_us_dst_boundaries() {
+        let ny = Tz::America__New_York;
+        // 2024-03-10 02:00 EST = 07:00 UTC；2024-11-03 02:00 EDT = 06:00 UTC
+        let abbreviation = |dt: DateTime<Utc>| dt.with_timezone(&ny).format("%Z").to_string();
+        assert_eq!(abbreviation(utc(2024, 3, 10, 6, 59)), "EST");
+        assert_eq!(abbreviation(utc(2024, 3, 10, 7, 0)), "EDT");
+        assert_eq!(abbreviation(utc(2024, 11, 3, 5, 59)), "EDT");
+        assert_eq!(abbreviation(utc(2024, 11, 3, 6, 0)), "EST");
+    }
+
+    #[test]
+    fn test_names_round_trip() {
+        for tz in COMMON_TIMEZONES {
+            assert_eq!(timezone_from_name(tz.name()), Some(tz));
+        }
+        assert_eq!(
+            timezone_from_name(" Europe/Paris "),
+            Some(Tz::Europe__Paris)
+        );
+        assert_eq!(timezone_from_name("Mars/Olympus"), None);
+    }
+}
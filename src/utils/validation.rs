@@ -58,9 +58,167 @@ pub fn validate_username(username: &str) -> Result<(), String> {
     Ok(())
 }
 
+/// Validate a WGS84 coordinate: latitude in [-90, 90], longitude in [-180, 180]
+pub fn validate_coordinates(latitude: f64, longitude: f64) -> Result<(), String> {
+    // NaN 不落在任何区间内，同样被拒绝
+    if !(-90.0..=90.0).contains(&latitude) {
+        return Err(format!(
+            "Latitude {} is out of range (must be between -90 and 90)",
+            latitude
+        ));
+    }
+    if !(-180.0..=180.0).contains(&longitude) {
+        return Err(format!(
+            "Longitude {} is out of range (must be between -180 and 180)",
+            longitude
+        ));
+    }
+    Ok(())
+}
+
+/// URL schemes allowed for product images
+const IMAGE_URL_SCHEMES: [&str; 2] = ["http", "https"];
+
+/// Validate a product image reference: an http(s) URL or a relative path
+/// inside the data directory. Other schemes such as `javascript:` or `file:`
+/// are rejected
+pub fn validate_image_ref(image: &str) -> Result<(), String> {
+    let image = image.trim();
+    if image.is_empty() {
+        return Err("Image reference cannot be empty".to_string());
+    }
+
+    // 冒号前全是合法 scheme 字符时视为 URL
+    if let Some((scheme, rest)) = image.split_once(':') {
+        let looks_like_scheme = scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+        if looks_like_scheme {
+            let scheme = scheme.to_ascii_lowercase();
+            if !IMAGE_URL_SCHEMES.contains(&scheme.as_str()) {
+                return Err(format!("Image URL scheme '{}' is not allowed", scheme));
+            }
+            let host = rest.strip_prefix("//").and_then(|r| r.split('/').next());
+            if host.is_none_or(str::is_empty) {
+                return Err(format!("Image URL has no host: {}", image));
+            }
+            return Ok(());
+        }
+    }
+
+    let path = std::path::Path::new(image);
+    if image.starts_with(['/', '\\']) || path.is_absolute() {
+        return Err(format!("Image path must be relative: {}", image));
+    }
+    if path
+        .components()
+        .any(|c| matches!(c, std::path::Component::ParentDir))
+    {
+        return Err(format!(
+            "Image path must not leave the data directory: {}",
+            image
+        ));
+    }
+    Ok(())
+}
+
+/// Outcome of `validate_email_deliverable`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailValidation {
+    Valid,
+    InvalidSyntax,
+    /// Well-formed, but the domain publishes no MX records
+    NoMailExchanger,
+}
+
+/// Looks up the mail exchangers of a domain
+pub trait MxResolver {
+    /// MX host names of `domain`; an unknown domain yields an empty list, `Err`
+    /// is reserved for lookups that could not be completed
+    fn lookup_mx(&self, domain: &str) -> impl Future<Output = Result<Vec<String>, String>> + Send;
+}
+
+/// MX lookups against the DNS servers in the system configuration
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone)]
+pub struct DnsMxResolver {
+    resolver: hickory_resolver::TokioAsyncResolver,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl DnsMxResolver {
+    /// Resolver using the system configuration (e.g. /etc/resolv.conf); must
+    /// be created and used inside a Tokio runtime
+    pub fn from_system_conf() -> Result<Self, String> {
+        let resolver = hickory_resolver::TokioAsyncResolver::tokio_from_system_conf()
+            .map_err(|e| format!("Cannot read DNS configuration: {}", e))?;
+        Ok(Self { resolver })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl MxResolver for DnsMxResolver {
+    fn lookup_mx(&self, domain: &str) -> impl Future<Output = Result<Vec<String>, String>> + Send {
+        use hickory_resolver::error::ResolveErrorKind;
+
+        let resolver = self.resolver.clone();
+        let domain = domain.to_string();
+        async move {
+            match resolver.mx_lookup(domain).await {
+                Ok(lookup) => Ok(lookup.iter().map(|mx| mx.exchange().to_utf8()).collect()),
+                // 域名不存在或没有 MX 记录
+                Err(e) if matches!(e.kind(), ResolveErrorKind::NoRecordsFound { .. }) => {
+                    Ok(Vec::new())
+                }
+                Err(e) => Err(e.to_string()),
+            }
+        }
+    }
+}
+
+/// Syntactic check followed by an MX lookup of the domain. Use the sync
+/// `validate_email` where a network round trip is not wanted.
+/// A failed lookup counts as valid, so a DNS outage does not block sign-up
+pub async fn validate_email_deliverable<R: MxResolver>(
+    email: &str,
+    resolver: &R,
+) -> EmailValidation {
+    if !crate::utils::validate_email(email) {
+        return EmailValidation::InvalidSyntax;
+    }
+    let domain = email.rsplit('@').next().unwrap_or_default();
+    match resolver.lookup_mx(domain).await {
+        Ok(hosts) if hosts.is_empty() => EmailValidation::NoMailExchanger,
+        Ok(_) => EmailValidation::Valid,
+        Err(e) => {
+            log::warn!("MX lookup for {} failed: {}", domain, e);
+            EmailValidation::Valid
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct StubResolver {
+        records: HashMap<&'static str, Vec<String>>,
+        lookups: AtomicUsize,
+    }
+
+    impl MxResolver for StubResolver {
+        fn lookup_mx(
+            &self,
+            domain: &str,
+        ) -> impl Future<Output = Result<Vec<String>, String>> + Send {
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+            let hosts = self.records.get(domain).cloned().unwrap_or_default();
+            async move { Ok(hosts) }
+        }
+    }
 
     #[test]
     fn test_email_validation() {
@@ -71,6 +229,59 @@ mod tests {
         assert!(!validate_email("user@"));
     }
 
+    #[test]
+    fn test_deliverable_rejects_bad_syntax_without_lookup() {
+        let resolver = StubResolver::default();
+        let result =
+            futures::executor::block_on(validate_email_deliverable("user@@example.com", &resolver));
+
+        assert_eq!(result, EmailValidation::InvalidSyntax);
+        assert_eq!(resolver.lookups.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_deliverable_checks_mx_records() {
+        let resolver = StubResolver {
+            records: HashMap::from([("example.com", vec!["mx1.example.com".to_string()])]),
+            ..StubResolver::default()
+        };
+
+        let valid =
+            futures::executor::block_on(validate_email_deliverable("a@example.com", &resolver));
+        let missing =
+            futures::executor::block_on(validate_email_deliverable("a@nomail.example", &resolver));
+        assert_eq!(valid, EmailValidation::Valid);
+        assert_eq!(missing, EmailValidation::NoMailExchanger);
+        assert_eq!(resolver.lookups.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_coordinate_validation() {
+        assert!(validate_coordinates(35.6812, 139.7671).is_ok());
+        assert!(validate_coordinates(-90.0, 180.0).is_ok());
+
+        let latitude = validate_coordinates(91.0, 0.0).unwrap_err();
+        assert!(latitude.starts_with("Latitude 91 is out of range"));
+        let longitude = validate_coordinates(0.0, 500.0).unwrap_err();
+        assert!(longitude.starts_with("Longitude 500 is out of range"));
+        assert!(validate_coordinates(f64::NAN, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_image_ref_validation() {
+        assert!(validate_image_ref("receipts/abc.png").is_ok());
+        assert!(validate_image_ref("https://cdn.example.com/p/1.jpg").is_ok());
+
+        assert!(validate_image_ref("javascript:alert(1)").is_err());
+        assert!(validate_image_ref("JavaScript:alert(1)").is_err());
+        assert!(validate_image_ref("data:image/png;base64,AAAA").is_err());
+        assert!(validate_image_ref("file:///etc/passwd").is_err());
+        assert!(validate_image_ref("https:///no-host.png").is_err());
+        assert!(validate_image_ref("/etc/passwd").is_err());
+        assert!(validate_image_ref("../secret.png").is_err());
+        assert!(validate_image_ref(" ").is_err());
+    }
+
     #[test]
     fn test_password_validation() {
         assert!(validate_password("password123").is_ok());
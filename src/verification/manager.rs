@@ -1,12 +1,17 @@
-use crate::services::ServiceResult;
-use crate::services::price_service::PriceService;
+use crate::services::price_service::{PriceService, csv_field};
+use crate::services::{ServiceError, ServiceResult};
 use chrono::{DateTime, Utc};
 use std::collections::HashMap;
 
+/// Rejection reason codes offered until `set_rejection_reasons` is called
+pub const DEFAULT_REJECTION_REASONS: [&str; 3] = ["价格明显错误", "重复记录", "无收据"];
+
 /// Verification manager for handling price record verification
 pub struct VerificationManager {
     // Store verification status and metadata
     verification_history: HashMap<String, VerificationRecord>,
+    audit_log: Vec<VerificationRecord>, // 全部操作记录，按发生顺序
+    rejection_reasons: Vec<String>,     // 可选的拒绝原因代码
 }
 
 #[derive(Debug, Clone)]
@@ -16,6 +21,8 @@ pub struct VerificationRecord {
     pub new_status: String,
     pub verified_by: String,
     pub timestamp: DateTime<Utc>,
+    /// Preset rejection reason, one of the manager's `rejection_reasons`
+    pub reason_code: Option<String>,
     pub reason: Option<String>,
 }
 
@@ -26,15 +33,44 @@ pub struct VerificationStats {
     pub total_rejected: usize,
     pub verification_rate: f64,      // Percentage of verified records
     pub recent_verifications: usize, // Verifications in last 24h
+    pub rejections_by_reason: HashMap<String, usize>, // reason_code -> rejected records
 }
 
 impl VerificationManager {
     pub fn new() -> Self {
         Self {
             verification_history: HashMap::new(),
+            audit_log: Vec::new(),
+            rejection_reasons: DEFAULT_REJECTION_REASONS
+                .iter()
+                .map(|r| r.to_string())
+                .collect(),
         }
     }
 
+    /// Reason codes selectable when rejecting
+    pub fn rejection_reasons(&self) -> &[String] {
+        &self.rejection_reasons
+    }
+
+    /// Replace the rejection reason codes; blank and repeated entries are dropped
+    pub fn set_rejection_reasons(&mut self, reasons: Vec<String>) {
+        let mut cleaned: Vec<String> = Vec::new();
+        for reason in reasons {
+            let reason = reason.trim().to_string();
+            if !reason.is_empty() && !cleaned.contains(&reason) {
+                cleaned.push(reason);
+            }
+        }
+        self.rejection_reasons = cleaned;
+    }
+
+    fn record_action(&mut self, record: VerificationRecord) {
+        self.audit_log.push(record.clone());
+        self.verification_history
+            .insert(record.price_record_id.clone(), record);
+    }
+
     /// Verify a price record
     pub fn verify_price_record(
         &mut self,
@@ -57,23 +93,33 @@ impl VerificationManager {
             new_status: "verified".to_string(),
             verified_by: verified_by.to_string(),
             timestamp: Utc::now(),
+            reason_code: None,
             reason,
         };
 
-        self.verification_history
-            .insert(price_record_id.to_string(), verification_record);
+        self.record_action(verification_record);
 
         Ok(())
     }
 
-    /// Reject a price record
+    /// Reject a price record, optionally with one of the preset reason codes
     pub fn reject_price_record(
         &mut self,
         price_service: &mut PriceService,
         price_record_id: &str,
         verified_by: &str,
+        reason_code: Option<&str>,
         reason: Option<String>,
     ) -> ServiceResult<()> {
+        if let Some(code) = reason_code {
+            if !self.rejection_reasons.iter().any(|r| r == code) {
+                return Err(ServiceError::ValidationError(format!(
+                    "Unknown rejection reason: {}",
+                    code
+                )));
+            }
+        }
+
         // Get the current record to store its status
         let current_record = price_service.get_price_record(price_record_id)?;
         let original_status = current_record.verification_status.clone();
@@ -88,11 +134,11 @@ impl VerificationManager {
             new_status: "rejected".to_string(),
             verified_by: verified_by.to_string(),
             timestamp: Utc::now(),
+            reason_code: reason_code.map(|c| c.to_string()),
             reason,
         };
 
-        self.verification_history
-            .insert(price_record_id.to_string(), verification_record);
+        self.record_action(verification_record);
 
         Ok(())
     }
@@ -119,11 +165,11 @@ impl VerificationManager {
             new_status: "pending".to_string(),
             verified_by: verified_by.to_string(),
             timestamp: Utc::now(),
+            reason_code: None,
             reason,
         };
 
-        self.verification_history
-            .insert(price_record_id.to_string(), verification_record);
+        self.record_action(verification_record);
 
         Ok(())
     }
@@ -150,12 +196,20 @@ impl VerificationManager {
             .filter(|v| v.timestamp > recent_cutoff)
             .count();
 
+        let mut rejections_by_reason = HashMap::new();
+        for record in self.verification_history.values() {
+            if let (Some(code), "rejected") = (&record.reason_code, record.new_status.as_str()) {
+                *rejections_by_reason.entry(code.clone()).or_insert(0) += 1;
+            }
+        }
+
         Ok(VerificationStats {
             total_pending: submission_stats.pending_count,
             total_verified: submission_stats.verified_count,
             total_rejected: submission_stats.rejected_count,
             verification_rate,
             recent_verifications,
+            rejections_by_reason,
         })
     }
 
@@ -169,6 +223,37 @@ impl VerificationManager {
         self.verification_history.values().collect()
     }
 
+    /// Every verification action taken, oldest first
+    pub fn audit_trail(&self) -> &[VerificationRecord] {
+        &self.audit_log
+    }
+
+    /// Audit trail as CSV, optionally limited to actions at or after `since`
+    pub fn export_audit_csv(&self, since: Option<DateTime<Utc>>) -> String {
+        let mut entries: Vec<&VerificationRecord> = self
+            .audit_log
+            .iter()
+            .filter(|entry| since.is_none_or(|since| entry.timestamp >= since))
+            .collect();
+        entries.sort_by_key(|entry| entry.timestamp);
+
+        let mut csv =
+            String::from("timestamp,record_id,old_status,new_status,verifier,reason_code,reason\n");
+        for entry in entries {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{}\n",
+                entry.timestamp.to_rfc3339(),
+                csv_field(&entry.price_record_id),
+                csv_field(&entry.original_status),
+                csv_field(&entry.new_status),
+                csv_field(&entry.verified_by),
+                csv_field(entry.reason_code.as_deref().unwrap_or("")),
+                csv_field(entry.reason.as_deref().unwrap_or("")),
+            ));
+        }
+        csv
+    }
+
     /// Bulk verify multiple price records
     pub fn bulk_verify_records(
         &mut self,
@@ -197,13 +282,20 @@ impl VerificationManager {
         price_service: &mut PriceService,
         price_record_ids: &[String],
         verified_by: &str,
+        reason_code: Option<&str>,
         reason: Option<String>,
     ) -> ServiceResult<usize> {
         let mut success_count = 0;
 
         for record_id in price_record_ids {
             if self
-                .reject_price_record(price_service, record_id, verified_by, reason.clone())
+                .reject_price_record(
+                    price_service,
+                    record_id,
+                    verified_by,
+                    reason_code,
+                    reason.clone(),
+                )
                 .is_ok()
             {
                 success_count += 1;
@@ -219,3 +311,132 @@ impl Default for VerificationManager {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pending_record(price_service: &mut PriceService) -> String {
+        price_service
+            .submit_price(
+                "product_1".to_string(),
+                "store_1".to_string(),
+                None,
+                100.0,
+                false,
+                None,
+            )
+            .unwrap()
+            .id
+            .unwrap()
+    }
+
+    #[test]
+    fn test_reject_with_preset_code_records_structured_reason() {
+        let mut price_service = PriceService::new();
+        let mut manager = VerificationManager::new();
+        let id = pending_record(&mut price_service);
+
+        manager
+            .reject_price_record(
+                &mut price_service,
+                &id,
+                "mod_1",
+                Some("重复记录"),
+                Some("与昨天的记录相同".to_string()),
+            )
+            .unwrap();
+
+        let entry = manager.get_verification_history(&id).unwrap();
+        assert_eq!(entry.new_status, "rejected");
+        assert_eq!(entry.reason_code.as_deref(), Some("重复记录"));
+        assert_eq!(entry.reason.as_deref(), Some("与昨天的记录相同"));
+
+        let stats = manager.get_verification_stats(&price_service).unwrap();
+        assert_eq!(stats.rejections_by_reason.get("重复记录"), Some(&1));
+    }
+
+    #[test]
+    fn test_export_audit_csv_lists_actions_chronologically() {
+        let mut price_service = PriceService::new();
+        let mut manager = VerificationManager::new();
+        let id = pending_record(&mut price_service);
+
+        manager
+            .verify_price_record(&mut price_service, &id, "mod_1", None)
+            .unwrap();
+        manager
+            .reset_to_pending(&mut price_service, &id, "mod_2", Some("误操作".to_string()))
+            .unwrap();
+        manager
+            .reject_price_record(
+                &mut price_service,
+                &id,
+                "mod_1",
+                Some("无收据"),
+                Some("看不清, 需重拍".to_string()),
+            )
+            .unwrap();
+
+        let csv = manager.export_audit_csv(None);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "timestamp,record_id,old_status,new_status,verifier,reason_code,reason"
+        );
+        assert_eq!(lines.len(), 4);
+        assert!(lines[1].ends_with(&format!("{},pending,verified,mod_1,,", id)));
+        assert!(lines[2].ends_with(&format!("{},verified,pending,mod_2,,误操作", id)));
+        assert!(lines[3].ends_with(&format!(
+            "{},pending,rejected,mod_1,无收据,\"看不清, 需重拍\"",
+            id
+        )));
+    }
+
+    #[test]
+    fn test_export_audit_csv_since_excludes_earlier_entries() {
+        let mut price_service = PriceService::new();
+        let mut manager = VerificationManager::new();
+        let first = pending_record(&mut price_service);
+        let second = pending_record(&mut price_service);
+
+        manager
+            .verify_price_record(&mut price_service, &first, "mod_1", None)
+            .unwrap();
+        manager
+            .verify_price_record(&mut price_service, &second, "mod_1", None)
+            .unwrap();
+        // 把第一条记录的时间挪到一天前
+        let since = Utc::now() - chrono::Duration::hours(1);
+        manager.audit_log[0].timestamp = since - chrono::Duration::days(1);
+
+        let csv = manager.export_audit_csv(Some(since));
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].contains(&second));
+        assert!(!csv.contains(&first));
+        assert_eq!(manager.export_audit_csv(None).lines().count(), 3);
+    }
+
+    #[test]
+    fn test_unknown_reason_code_leaves_record_pending() {
+        let mut price_service = PriceService::new();
+        let mut manager = VerificationManager::new();
+        manager.set_rejection_reasons(vec!["无收据".to_string(), " ".to_string()]);
+        assert_eq!(manager.rejection_reasons(), ["无收据".to_string()]);
+        let id = pending_record(&mut price_service);
+
+        let result =
+            manager.reject_price_record(&mut price_service, &id, "mod_1", Some("重复记录"), None);
+
+        assert!(matches!(result, Err(ServiceError::ValidationError(_))));
+        assert!(manager.get_verification_history(&id).is_none());
+        assert_eq!(
+            price_service
+                .get_price_record(&id)
+                .unwrap()
+                .verification_status,
+            "pending"
+        );
+    }
+}
@@ -1,20 +1,77 @@
 use crate::models::PriceRecord;
-use crate::services::AppServices;
+use crate::services::{AppServices, PriceAnomaly, PriceRecordFilter};
+use crate::utils::PriceFormatter;
 use crate::verification::manager::VerificationManager;
 use egui::{Color32, RichText};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+/// Records shown per page of the verification table
+const PAGE_SIZE: usize = 20;
+
+/// Keyboard action in the records table (non-bulk mode)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueKey {
+    Next,
+    Previous,
+    Verify,
+    Reject,
+}
+
+impl QueueKey {
+    /// J/↓ and K/↑ move the focus, V verifies, X rejects
+    fn from_key(key: egui::Key) -> Option<Self> {
+        match key {
+            egui::Key::ArrowDown | egui::Key::J => Some(Self::Next),
+            egui::Key::ArrowUp | egui::Key::K => Some(Self::Previous),
+            egui::Key::V => Some(Self::Verify),
+            egui::Key::X => Some(Self::Reject),
+            _ => None,
+        }
+    }
+}
+
+/// Focused row of the current page, for moderating without the mouse
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReviewQueue {
+    focused: usize,
+}
+
+impl ReviewQueue {
+    pub fn focused(&self) -> usize {
+        self.focused
+    }
+
+    /// Keep the focus on an existing row after the page changed
+    pub fn clamp(&mut self, len: usize) {
+        self.focused = self.focused.min(len.saturating_sub(1));
+    }
+
+    pub fn next(&mut self, len: usize) {
+        if self.focused + 1 < len {
+            self.focused += 1;
+        }
+    }
+
+    pub fn previous(&mut self) {
+        self.focused = self.focused.saturating_sub(1);
+    }
+}
 
 /// UI component for managing price record verification
 pub struct VerificationUI {
     verification_manager: VerificationManager,
     selected_records: HashMap<String, bool>, // price_record_id -> selected
-    filter_status: String,                   // "all", "pending", "verified", "rejected"
+    filter_status: String, // "all", "pending", "verified", "rejected", "anomalous"
     search_text: String,
     reason_text: String,
+    reason_code: Option<String>, // 拒绝时选择的预设原因
     show_verification_dialog: bool,
     verification_action: VerificationAction,
     bulk_operation_mode: bool,
     current_verifier: String,
+    page: usize, // 当前页（从 0 开始）
+    queue: ReviewQueue,
+    price_formatter: PriceFormatter, // 按设置中的货币、小数位和舍入方式显示价格
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -34,10 +91,14 @@ impl VerificationUI {
             filter_status: "pending".to_string(),
             search_text: String::new(),
             reason_text: String::new(),
+            reason_code: None,
             show_verification_dialog: false,
             verification_action: VerificationAction::None,
             bulk_operation_mode: false,
             current_verifier: "system".to_string(),
+            page: 0,
+            queue: ReviewQueue::default(),
+            price_formatter: PriceFormatter::default(),
         }
     }
 
@@ -46,6 +107,16 @@ impl VerificationUI {
         self.current_verifier = verifier.to_string();
     }
 
+    /// Format prices with `formatter`, usually built from the app settings
+    pub fn set_price_formatter(&mut self, formatter: PriceFormatter) {
+        self.price_formatter = formatter;
+    }
+
+    /// Set the preset reason codes offered when rejecting
+    pub fn set_rejection_reasons(&mut self, reasons: Vec<String>) {
+        self.verification_manager.set_rejection_reasons(reasons);
+    }
+
     /// Show the verification UI
     pub fn show(&mut self, ui: &mut egui::Ui, app_services: &mut AppServices) {
         ui.heading("价格记录验证系统");
@@ -105,6 +176,17 @@ impl VerificationUI {
                         );
                     });
                 });
+
+                if !stats.rejections_by_reason.is_empty() {
+                    let mut reasons: Vec<_> = stats.rejections_by_reason.iter().collect();
+                    reasons.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label("拒绝原因:");
+                        for (code, count) in reasons {
+                            ui.label(format!("{} {}", code, count));
+                        }
+                    });
+                }
             } else {
                 ui.label("无法获取验证统计数据");
             }
@@ -116,6 +198,7 @@ impl VerificationUI {
             ui.label("筛选:");
 
             // Status filter
+            let previous_status = self.filter_status.clone();
             egui::ComboBox::from_label("状态")
                 .selected_text(&self.filter_status)
                 .show_ui(ui, |ui| {
@@ -123,13 +206,19 @@ impl VerificationUI {
                     ui.selectable_value(&mut self.filter_status, "pending".to_string(), "待验证");
                     ui.selectable_value(&mut self.filter_status, "verified".to_string(), "已验证");
                     ui.selectable_value(&mut self.filter_status, "rejected".to_string(), "已拒绝");
+                    ui.selectable_value(&mut self.filter_status, "anomalous".to_string(), "异常");
                 });
 
             ui.separator();
 
             // Search box
             ui.label("搜索:");
-            ui.add(egui::TextEdit::singleline(&mut self.search_text).hint_text("商品名称或店铺"));
+            let search =
+                ui.add(egui::TextEdit::singleline(&mut self.search_text).hint_text("商品名称"));
+            // 筛选条件变化时回到第一页
+            if search.changed() || self.filter_status != previous_status {
+                self.page = 0;
+            }
 
             ui.separator();
 
@@ -189,10 +278,58 @@ impl VerificationUI {
     }
 
     fn render_price_records_table(&mut self, ui: &mut egui::Ui, app_services: &mut AppServices) {
+        let formatter = self.price_formatter;
         // Get all price records from the service
-        let all_records = self.get_filtered_price_records(app_services);
+        let anomalies = if self.filter_status == "anomalous" {
+            app_services.find_price_anomalies()
+        } else {
+            Vec::new()
+        };
+        let (all_records, total) = self.get_filtered_price_records(app_services, &anomalies);
+        self.queue.clamp(all_records.len());
+        if !self.bulk_operation_mode && !ui.ctx().wants_keyboard_input() {
+            let keys: Vec<QueueKey> = ui.input(|i| {
+                i.events
+                    .iter()
+                    .filter_map(|event| match event {
+                        egui::Event::Key {
+                            key,
+                            pressed: true,
+                            modifiers,
+                            ..
+                        } if modifiers.is_none() => QueueKey::from_key(*key),
+                        _ => None,
+                    })
+                    .collect()
+            });
+            let records: Vec<PriceRecord> = all_records
+                .iter()
+                .map(|(record, _, _)| record.clone())
+                .collect();
+            for key in keys {
+                self.handle_queue_key(key, &records, app_services);
+            }
+        }
+        let focused = self.queue.focused();
+        let pages = total.div_ceil(PAGE_SIZE).max(1);
 
-        ui.label(format!("找到 {} 条价格记录", all_records.len()));
+        ui.horizontal(|ui| {
+            ui.label(format!("找到 {} 条价格记录", total));
+            ui.separator();
+            if ui
+                .add_enabled(self.page > 0, egui::Button::new("◀ 上一页"))
+                .clicked()
+            {
+                self.page -= 1;
+            }
+            ui.label(format!("{} / {}", self.page + 1, pages));
+            if ui
+                .add_enabled(self.page + 1 < pages, egui::Button::new("下一页 ▶"))
+                .clicked()
+            {
+                self.page += 1;
+            }
+        });
 
         egui::ScrollArea::vertical().show(ui, |ui| {
             egui_extras::TableBuilder::new(ui)
@@ -231,8 +368,11 @@ impl VerificationUI {
                     });
                 })
                 .body(|mut body| {
-                    for (record, product_name, store_name) in all_records {
+                    for (index, (record, product_name, store_name)) in
+                        all_records.into_iter().enumerate()
+                    {
                         body.row(25.0, |mut row| {
+                            row.set_selected(!self.bulk_operation_mode && index == focused);
                             // Checkbox for bulk operations
                             if self.bulk_operation_mode {
                                 row.col(|ui| {
@@ -261,10 +401,11 @@ impl VerificationUI {
 
                             // Price
                             row.col(|ui| {
+                                let price = formatter.format(record.price);
                                 let price_text = if record.is_on_sale {
-                                    format!("¥{:.2} 🏷", record.price)
+                                    format!("{} 🏷", price)
                                 } else {
-                                    format!("¥{:.2}", record.price)
+                                    price
                                 };
                                 ui.label(price_text);
                             });
@@ -278,7 +419,15 @@ impl VerificationUI {
                                         "pending" => ("待验证", Color32::YELLOW),
                                         _ => ("未知", Color32::GRAY),
                                     };
-                                ui.colored_label(status_color, status_text);
+                                let reasons: Vec<&str> = anomalies
+                                    .iter()
+                                    .filter(|a| record.id.as_ref() == Some(&a.price_id))
+                                    .map(|a| a.reason.as_str())
+                                    .collect();
+                                let label = ui.colored_label(status_color, status_text);
+                                if !reasons.is_empty() {
+                                    label.on_hover_text(reasons.join("\n"));
+                                }
                             });
 
                             // Timestamp
@@ -334,6 +483,20 @@ impl VerificationUI {
             .collapsible(false)
             .resizable(false)
             .show(ui.ctx(), |ui| {
+                if self.verification_action == VerificationAction::Reject {
+                    egui::ComboBox::from_label("拒绝原因")
+                        .selected_text(self.reason_code.as_deref().unwrap_or("未选择"))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.reason_code, None, "未选择");
+                            for code in self.verification_manager.rejection_reasons() {
+                                ui.selectable_value(
+                                    &mut self.reason_code,
+                                    Some(code.clone()),
+                                    code,
+                                );
+                            }
+                        });
+                }
                 ui.label("请输入操作原因 (可选):");
                 ui.add(egui::TextEdit::multiline(&mut self.reason_text).desired_rows(3));
 
@@ -344,60 +507,108 @@ impl VerificationUI {
                         self.execute_verification_action(app_services);
                         self.show_verification_dialog = false;
                         self.reason_text.clear();
+                        self.reason_code = None;
                     }
 
                     if ui.button("取消").clicked() {
                         self.show_verification_dialog = false;
                         self.reason_text.clear();
+                        self.reason_code = None;
                     }
                 });
             });
     }
 
+    /// Current page of records matching the filters, with the total number of matches
     fn get_filtered_price_records(
-        &self,
+        &mut self,
         app_services: &AppServices,
-    ) -> Vec<(PriceRecord, String, String)> {
-        // This is a simplified implementation - in a real app, you'd query the database
-        let mut all_records = Vec::new();
+        anomalies: &[PriceAnomaly],
+    ) -> (Vec<(PriceRecord, String, String)>, usize) {
+        let mut filter = PriceRecordFilter::default();
+        match self.filter_status.as_str() {
+            "all" => {}
+            "anomalous" => {
+                filter.record_ids = Some(anomalies.iter().map(|a| a.price_id.clone()).collect())
+            }
+            status => filter.status = Some(status.to_string()),
+        }
 
-        // Get all price records from products (this is for demo purposes)
-        for product in &app_services
+        // 搜索词先解析为商品 ID，由价格服务按 ID 过滤
+        let search_lower = self.search_text.trim().to_lowercase();
+        let product_names: HashMap<String, String> = app_services
             .product_service
             .get_all_products()
             .unwrap_or_default()
-        {
-            for price_record in &product.prices {
-                // Apply status filter
-                if self.filter_status != "all"
-                    && price_record.verification_status != self.filter_status
-                {
-                    continue;
-                }
+            .into_iter()
+            .map(|p| (p.id, p.name))
+            .collect();
+        if !search_lower.is_empty() {
+            filter.product_ids = Some(
+                product_names
+                    .iter()
+                    .filter(|(_, name)| name.to_lowercase().contains(&search_lower))
+                    .map(|(id, _)| id.clone())
+                    .collect::<HashSet<_>>(),
+            );
+        }
 
-                // Apply search filter
-                if !self.search_text.is_empty() {
-                    let search_lower = self.search_text.to_lowercase();
-                    if !product.name.to_lowercase().contains(&search_lower) {
-                        continue;
-                    }
-                }
+        let (mut records, total) = app_services.price_service.list_price_records(
+            &filter,
+            self.page * PAGE_SIZE,
+            PAGE_SIZE,
+        );
+        // 记录减少后当前页可能越界
+        if records.is_empty() && self.page > 0 {
+            self.page = total.saturating_sub(1) / PAGE_SIZE;
+            records = app_services
+                .price_service
+                .list_price_records(&filter, self.page * PAGE_SIZE, PAGE_SIZE)
+                .0;
+        }
 
-                // Get store name
+        let rows = records
+            .into_iter()
+            .map(|record| {
+                let product_name = record
+                    .product_id
+                    .as_ref()
+                    .and_then(|id| product_names.get(id).cloned())
+                    .unwrap_or_else(|| "未知商品".to_string());
                 let store_name = app_services
                     .store_service
-                    .get_store(&price_record.store_id)
+                    .get_store(&record.store_id)
                     .map(|s| s.name.clone())
                     .unwrap_or_else(|_| "未知店铺".to_string());
+                (record, product_name, store_name)
+            })
+            .collect();
+        (rows, total)
+    }
 
-                all_records.push((price_record.clone(), product.name.clone(), store_name));
-            }
+    /// Apply a keyboard action to the focused row of `records` (the current page).
+    /// V/X only act on pending records
+    pub fn handle_queue_key(
+        &mut self,
+        key: QueueKey,
+        records: &[PriceRecord],
+        app_services: &mut AppServices,
+    ) {
+        let focused = records
+            .get(self.queue.focused())
+            .filter(|r| r.verification_status == "pending")
+            .and_then(|r| r.id.clone());
+        match (key, focused) {
+            (QueueKey::Next, _) => self.queue.next(records.len()),
+            (QueueKey::Previous, _) => self.queue.previous(),
+            (QueueKey::Verify, Some(id)) => self.verify_single_record(&id, app_services),
+            (QueueKey::Reject, Some(id)) => self.reject_single_record(&id, app_services),
+            (QueueKey::Verify | QueueKey::Reject, None) => return,
+        }
+        // 待验证列表中已处理的记录会移出，下一条自动补到当前位置
+        if matches!(key, QueueKey::Verify | QueueKey::Reject) && self.filter_status != "pending" {
+            self.queue.next(records.len());
         }
-
-        // Sort by timestamp (newest first)
-        all_records.sort_by(|a, b| b.0.timestamp.cmp(&a.0.timestamp));
-
-        all_records
     }
 
     fn verify_single_record(&mut self, record_id: &str, app_services: &mut AppServices) {
@@ -417,6 +628,7 @@ impl VerificationUI {
             record_id,
             &self.current_verifier,
             None,
+            None,
         ) {
             log::error!("Failed to reject record {}: {}", record_id, e);
         }
@@ -465,6 +677,7 @@ impl VerificationUI {
                         &mut app_services.price_service,
                         &selected_records,
                         &self.current_verifier,
+                        self.reason_code.as_deref(),
                         reason,
                     )
                 } else {
@@ -494,3 +707,89 @@ impl Default for VerificationUI {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pending_records(app_services: &mut AppServices, count: usize) -> Vec<PriceRecord> {
+        (0..count)
+            .map(|i| {
+                app_services
+                    .price_service
+                    .submit_price(
+                        "product_1".to_string(),
+                        "store_1".to_string(),
+                        None,
+                        1.0 + i as f64,
+                        false,
+                        None,
+                    )
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_v_verifies_focused_pending_record_as_current_verifier() {
+        let mut app_services = AppServices::new();
+        let records = pending_records(&mut app_services, 3);
+        let mut ui = VerificationUI::new();
+        ui.set_verifier("moderator_1");
+
+        ui.handle_queue_key(QueueKey::Next, &records, &mut app_services);
+        ui.handle_queue_key(QueueKey::Verify, &records, &mut app_services);
+
+        let id = records[1].id.as_deref().unwrap();
+        let history = ui
+            .verification_manager
+            .get_verification_history(id)
+            .unwrap();
+        assert_eq!(history.verified_by, "moderator_1");
+        assert_eq!(history.new_status, "verified");
+        assert_eq!(
+            app_services
+                .price_service
+                .get_price_record(id)
+                .unwrap()
+                .verification_status,
+            "verified"
+        );
+        let untouched = records[0].id.as_deref().unwrap();
+        assert!(
+            ui.verification_manager
+                .get_verification_history(untouched)
+                .is_none()
+        );
+        // 待验证筛选下焦点停在原位
+        assert_eq!(ui.queue.focused(), 1);
+    }
+
+    #[test]
+    fn test_reject_key_ignores_non_pending_and_focus_stays_in_range() {
+        let mut app_services = AppServices::new();
+        let mut records = pending_records(&mut app_services, 2);
+        records[0].verification_status = "verified".to_string();
+        let mut ui = VerificationUI::new();
+
+        ui.handle_queue_key(QueueKey::Reject, &records, &mut app_services);
+        assert!(
+            ui.verification_manager
+                .get_all_verification_history()
+                .is_empty()
+        );
+
+        ui.handle_queue_key(QueueKey::Next, &records, &mut app_services);
+        ui.handle_queue_key(QueueKey::Next, &records, &mut app_services);
+        assert_eq!(ui.queue.focused(), 1);
+        ui.handle_queue_key(QueueKey::Reject, &records, &mut app_services);
+        let id = records[1].id.as_deref().unwrap();
+        assert_eq!(
+            ui.verification_manager
+                .get_verification_history(id)
+                .unwrap()
+                .new_status,
+            "rejected"
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use eprice::alerts::AlertError;
+use eprice::auth::AuthError;
+use eprice::error::ErrorHandler;
+use eprice::ocr::OcrError;
+use eprice::scanner::ScannerError;
+use eprice::services::ServiceError;
+use eprice::{AppError, Locale};
+
+#[test]
+fn test_scanner_error_conversion() {
+    let err: AppError = ScannerError::CameraAccess("permission denied".to_string()).into();
+    assert!(matches!(err, AppError::Scanner(_)));
+    assert_eq!(
+        err.to_string(),
+        "Scanner error: Camera access failed: permission denied"
+    );
+}
+
+#[test]
+fn test_alert_error_conversion() {
+    let err: AppError = AlertError::AlertNotFound("alert-1".to_string()).into();
+    assert!(matches!(err, AppError::Alert(_)));
+    assert_eq!(err.to_string(), "Alert error: Alert not found: alert-1");
+}
+
+#[test]
+fn test_service_error_conversion() {
+    let err: AppError = ServiceError::NotFound("product 42".to_string()).into();
+    assert!(matches!(err, AppError::Service(_)));
+    assert_eq!(err.to_string(), "Service error: Not found: product 42");
+}
+
+#[test]
+fn test_ocr_error_conversion() {
+    let err: AppError = OcrError::FileNotFound("receipt.png".to_string()).into();
+    assert!(matches!(err, AppError::Ocr(_)));
+    assert_eq!(err.to_string(), "OCR error: File not found: receipt.png");
+}
+
+#[test]
+fn test_auth_error_conversion() {
+    let err: AppError = AuthError::InvalidCredentials.into();
+    assert!(matches!(err, AppError::Authentication(_)));
+    assert_eq!(err.to_string(), "Authentication error: Invalid credentials");
+}
+
+#[test]
+fn test_error_handler_formats_module_errors() {
+    assert_eq!(
+        ErrorHandler::format_error(OcrError::TextExtraction("blurry".to_string())),
+        "OCR error: Text extraction failed: blurry"
+    );
+
+    let mut handler = ErrorHandler::new();
+    handler.handle(ScannerError::NoCameraAvailable, "scan_barcode");
+    let current = handler.current_error().unwrap();
+    assert!(matches!(current.error, AppError::Scanner(_)));
+    assert_eq!(current.context.operation, "scan_barcode");
+    assert_eq!(
+        handler.current_error_message().unwrap(),
+        "Scanner error. Please check camera permissions and try again."
+    );
+}
+
+#[test]
+fn test_user_message_localized() {
+    let err = AppError::Database("SELECT * FROM users failed: no such table".to_string());
+    let en = ErrorHandler::user_message(&err, Locale::En);
+    let zh = ErrorHandler::user_message(&err, Locale::Zh);
+    assert_ne!(en, zh);
+    assert_eq!(en, "A database error occurred. Please try again later.");
+    assert_eq!(zh, "数据库出错，请稍后重试。");
+    // 不向用户暴露 SQL 细节
+    assert!(!en.contains("SELECT"));
+    assert!(ErrorHandler::debug_message(&err).contains("SELECT * FROM users"));
+}
+
+#[test]
+fn test_user_message_keeps_validation_detail() {
+    let err = AppError::Validation("price must be positive".to_string());
+    assert_eq!(
+        ErrorHandler::user_message(&err, Locale::En),
+        "Validation failed: price must be positive"
+    );
+    assert_eq!(
+        ErrorHandler::user_message(&err, Locale::Zh),
+        "输入校验失败: price must be positive"
+    );
+}
+
+#[test]
+fn test_unknown_code_and_locale_fall_back() {
+    assert_eq!(
+        ErrorHandler::message_for_code("no_such_code", Locale::En),
+        "An unexpected error occurred. Please try again."
+    );
+    assert_eq!(
+        ErrorHandler::message_for_code("no_such_code", Locale::Zh),
+        "发生未知错误，请重试。"
+    );
+    assert_eq!(Locale::from_code("fr"), Locale::En);
+    assert_eq!(Locale::from_code("zh-CN"), Locale::Zh);
+
+    let err = AppError::Internal("lock poisoned".to_string());
+    assert_eq!(
+        ErrorHandler::user_message(&err, Locale::Zh),
+        "发生未知错误，请重试。"
+    );
+}
+
+#[test]
+fn test_service_error_retry_classification() {
+    assert!(ServiceError::DatabaseError("locked".to_string()).is_retryable());
+    assert!(ServiceError::ExternalServiceError("503".to_string()).is_retryable());
+    assert!(!ServiceError::ValidationError("empty name".to_string()).is_retryable());
+    assert!(!ServiceError::PermissionDenied("admin only".to_string()).is_retryable());
+    assert!(!ServiceError::NotFound("p1".to_string()).is_retryable());
+    assert!(!ServiceError::BusinessRuleViolation("duplicate".to_string()).is_retryable());
+}
+
+#[test]
+fn test_retry_classification_propagates_to_app_error() {
+    let retryable: AppError = ServiceError::DatabaseError("locked".to_string()).into();
+    assert!(matches!(retryable, AppError::Database(ref msg) if msg == "locked"));
+    assert!(retryable.is_retryable());
+
+    let fatal: AppError = ServiceError::ValidationError("empty name".to_string()).into();
+    assert!(!fatal.is_retryable());
+}
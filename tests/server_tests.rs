@@ -0,0 +1,94 @@
+#![cfg(all(feature = "server", not(target_arch = "wasm32")))]
+
+use eprice::services::AppServices;
+use std::sync::{Arc, RwLock};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Minimal HTTP/1.1 GET returning (status code, body)
+async fn http_get(addr: std::net::SocketAddr, path: &str) -> (u16, String) {
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+        path, addr
+    );
+    stream.write_all(request.as_bytes()).await.expect("write");
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.expect("read");
+
+    let status = response
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .expect("status line");
+    let body = response
+        .split_once("\r\n\r\n")
+        .map(|(_, body)| body.to_string())
+        .unwrap_or_default();
+    (status, body)
+}
+
+#[tokio::test]
+async fn test_product_prices_json_shape() {
+    let mut services = AppServices::new();
+    let product = services
+        .product_service
+        .create_product(
+            "Sparkling Water 330ml".to_string(),
+            "Beverages".to_string(),
+            "Canned sparkling water".to_string(),
+            None,
+            vec![],
+        )
+        .unwrap();
+    let record = services
+        .price_service
+        .submit_price(
+            product.id.clone(),
+            "store_1".to_string(),
+            None,
+            3.5,
+            false,
+            None,
+        )
+        .unwrap();
+    services
+        .price_service
+        .verify_price(record.id.as_deref().unwrap(), true)
+        .unwrap();
+
+    let shared = Arc::new(RwLock::new(services));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(eprice::server::serve(listener, shared));
+
+    let (status, body) = http_get(addr, &format!("/products/{}/prices", product.id)).await;
+    assert_eq!(status, 200);
+
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(json["product_id"], product.id.as_str());
+    assert_eq!(json["product_name"], "Sparkling Water 330ml");
+    assert_eq!(json["lowest_price"], 3.5);
+
+    let prices = json["prices"].as_array().unwrap();
+    assert_eq!(prices.len(), 1);
+    assert_eq!(prices[0]["store_id"], "store_1");
+    assert_eq!(prices[0]["price"], 3.5);
+    assert_eq!(prices[0]["effective_price"], 3.5);
+    assert_eq!(prices[0]["verification_status"], "verified");
+    assert!(prices[0]["timestamp"].is_string());
+
+    let (status, body) = http_get(addr, "/products/missing/prices").await;
+    assert_eq!(status, 404);
+    assert!(body.contains("error"));
+
+    let (status, body) = http_get(addr, "/products/search?q=sparkling").await;
+    assert_eq!(status, 200);
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let results = json.as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["id"], product.id.as_str());
+
+    server.abort();
+}
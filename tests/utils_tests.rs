@@ -1,3 +1,6 @@
+// 沿用原有的 match + assert!(true/false) 写法
+#![allow(clippy::assertions_on_constants)]
+
 use chrono::TimeZone;
 use eprice::utils::*;
 
@@ -55,6 +58,64 @@ fn test_format_price_usd() {
     assert_eq!(format_price(1, Currency::USD), "$0.01");
 }
 
+#[test]
+fn test_price_formatter_uses_currency_precision() {
+    assert_eq!(PriceFormatter::new(Currency::JPY).format(350.0), "¥350");
+    assert_eq!(PriceFormatter::new(Currency::JPY).format(1280.4), "¥1,280");
+    assert_eq!(PriceFormatter::new(Currency::USD).format(3.5), "$3.50");
+    assert_eq!(
+        PriceFormatter::new(Currency::EUR).format(1234.5),
+        "€1,234.50"
+    );
+}
+
+#[test]
+fn test_price_formatter_precision_override() {
+    let formatter = PriceFormatter::new(Currency::JPY).with_decimals(Some(2));
+    assert_eq!(formatter.format(3.5), "¥3.50");
+    let formatter = PriceFormatter::new(Currency::USD).with_decimals(Some(0));
+    assert_eq!(formatter.format(3.5), "$4");
+}
+
+#[test]
+fn test_rounding_modes_at_half_boundary() {
+    let format = |mode: RoundingMode, amount: f64| {
+        PriceFormatter::new(Currency::JPY)
+            .with_rounding(mode)
+            .format(amount)
+    };
+    assert_eq!(format(RoundingMode::HalfUp, 1234.5), "¥1,235");
+    assert_eq!(format(RoundingMode::HalfEven, 1234.5), "¥1,234");
+    assert_eq!(format(RoundingMode::HalfEven, 1235.5), "¥1,236");
+    assert_eq!(format(RoundingMode::Floor, 1235.5), "¥1,235");
+    assert_eq!(format(RoundingMode::Ceil, 1234.5), "¥1,235");
+    assert_eq!(format(RoundingMode::Floor, -2.5), "¥-3");
+    assert_eq!(format(RoundingMode::Ceil, -2.5), "¥-2");
+
+    // 换算为最小货币单位：0.125 元 = 12.5 分
+    let to_cents = |mode| Currency::CNY.to_minor_units_with(0.125, mode);
+    assert_eq!(to_cents(RoundingMode::HalfUp), 13);
+    assert_eq!(to_cents(RoundingMode::HalfEven), 12);
+    assert_eq!(to_cents(RoundingMode::Floor), 12);
+    assert_eq!(to_cents(RoundingMode::Ceil), 13);
+    assert_eq!(
+        Currency::JPY.to_minor_units_with(2.5, RoundingMode::HalfEven),
+        2
+    );
+    assert_eq!(
+        Currency::JPY.to_minor_units_with(2.5, RoundingMode::default()),
+        3
+    );
+}
+
+#[test]
+fn test_format_price_cny() {
+    assert_eq!(format_price(350, Currency::CNY), "¥3.50");
+    assert_eq!(format_price(1234567, Currency::CNY), "¥12,345.67");
+    assert_eq!(Currency::CNY.to_minor_units(3.5), 350);
+    assert_eq!(Currency::JPY.to_minor_units(3.5), 4);
+}
+
 #[test]
 fn test_format_price_eur() {
     assert_eq!(format_price(1000, Currency::EUR), "€10.00");
@@ -184,6 +245,102 @@ fn test_format_datetime() {
     );
 }
 
+#[test]
+fn test_format_datetime_single_and_double_digit_days() {
+    let third = chrono::Utc.with_ymd_and_hms(2024, 3, 3, 9, 5, 7).unwrap();
+    let thirteenth = chrono::Utc.with_ymd_and_hms(2024, 3, 13, 9, 5, 7).unwrap();
+
+    let cases = [
+        (DateTimeFormat::Short, "03/03/24", "03/13/24"),
+        (DateTimeFormat::Medium, "Mar 3, 2024", "Mar 13, 2024"),
+        (
+            DateTimeFormat::Long,
+            "March 3, 2024 09:05",
+            "March 13, 2024 09:05",
+        ),
+        (
+            DateTimeFormat::Full,
+            "March 3, 2024 09:05:07 UTC",
+            "March 13, 2024 09:05:07 UTC",
+        ),
+    ];
+    for (fmt, expected_third, expected_thirteenth) in cases {
+        let formatted = format_datetime(&third, fmt);
+        assert_eq!(formatted, expected_third);
+        assert!(!formatted.contains("  "), "{:?}: {:?}", fmt, formatted);
+        assert_eq!(format_datetime(&thirteenth, fmt), expected_thirteenth);
+    }
+}
+
+#[test]
+fn test_format_datetime_tz() {
+    let winter = chrono::Utc.with_ymd_and_hms(2024, 1, 15, 12, 0, 0).unwrap();
+    let summer = chrono::Utc.with_ymd_and_hms(2024, 7, 4, 16, 30, 0).unwrap();
+
+    assert_eq!(
+        format_datetime_tz(&winter, Tz::Asia__Tokyo, DateTimeFormat::Full),
+        "January 15, 2024 21:00:00 JST"
+    );
+    assert_eq!(
+        format_datetime_tz(&winter, Tz::America__New_York, DateTimeFormat::Full),
+        "January 15, 2024 07:00:00 EST"
+    );
+    assert_eq!(
+        format_datetime_tz(&summer, Tz::America__New_York, DateTimeFormat::Full),
+        "July 4, 2024 12:30:00 EDT"
+    );
+    // 跨日换算
+    assert_eq!(
+        format_datetime_tz(&summer, Tz::Asia__Tokyo, DateTimeFormat::Medium),
+        "Jul 5, 2024"
+    );
+    assert_eq!(
+        format_datetime_tz(&winter, Tz::UTC, DateTimeFormat::Full),
+        format_datetime(&winter, DateTimeFormat::Full)
+    );
+}
+
+#[test]
+fn test_format_relative() {
+    use eprice::error::Locale;
+
+    let now = chrono::Utc.with_ymd_and_hms(2024, 6, 15, 12, 0, 0).unwrap();
+    let ago = |seconds: i64| now - chrono::Duration::seconds(seconds);
+
+    let cases = [
+        (ago(20), "just now", "刚刚"),
+        (ago(60), "1 minute ago", "1分钟前"),
+        (ago(5 * 60 + 30), "5 minutes ago", "5分钟前"),
+        (ago(3 * 3600), "3 hours ago", "3小时前"),
+        (ago(2 * 86400), "2 days ago", "2天前"),
+        (ago(65 * 86400), "2 months ago", "2个月前"),
+        (ago(800 * 86400), "2 years ago", "2年前"),
+    ];
+    for (from, en, zh) in cases {
+        assert_eq!(format_relative(from, now, Locale::En), en);
+        assert_eq!(format_relative(from, now, Locale::Zh), zh);
+    }
+
+    let later = now + chrono::Duration::hours(2);
+    assert_eq!(format_relative(later, now, Locale::En), "in 2 hours");
+    assert_eq!(format_relative(later, now, Locale::Zh), "2小时后");
+}
+
+#[test]
+fn test_normalize_width() {
+    assert_eq!(normalize_width("１２３"), "123");
+    assert_eq!(normalize_width("ＡＢＣ ａｂｃ"), "ABC abc");
+    assert_eq!(normalize_width("１，２３４．５６"), "1,234.56");
+    assert_eq!(normalize_width("お茶\u{3000}５００ｍｌ"), "お茶 500ml");
+    // 非 ASCII 的全角字符（假名、汉字）保持不变
+    assert_eq!(normalize_width("コーラ 緑茶"), "コーラ 緑茶");
+
+    assert_eq!(parse_price("１，２３４．５６"), Ok(123456));
+    assert_eq!(parse_price("１０００"), Ok(1000));
+    assert!(validate_barcode("４９０１２３４５６７８９４"));
+    assert_eq!(sanitize_search_query("　ＭＩＬＫ　 Tea "), "MILK Tea");
+}
+
 #[test]
 fn test_parse_price() {
     // Valid price strings
@@ -216,6 +373,12 @@ fn test_generate_barcode_checksum() {
     assert_eq!(generate_barcode_checksum("12345678901a"), None); // Contains letter
 }
 
+#[test]
+fn test_generate_ean8_checksum() {
+    assert_eq!(generate_barcode_checksum("9638507"), Some(4));
+    assert_eq!(generate_barcode_checksum("4901234"), Some(7));
+}
+
 #[test]
 fn test_truncate_string() {
     assert_eq!(truncate_string("Hello World", 5), "Hello");
@@ -323,3 +486,74 @@ fn test_generate_user_token() {
     assert!(verify_user_token(&token, user_id));
     assert!(!verify_user_token(&token, 456));
 }
+
+#[test]
+fn test_parse_quantity() {
+    use eprice::models::{Quantity, Unit};
+
+    assert_eq!(
+        parse_quantity("Coca-Cola 330ml"),
+        Some(Quantity::new(330.0, Unit::Milliliter))
+    );
+    assert_eq!(
+        parse_quantity("Water 1.5L"),
+        Some(Quantity::new(1.5, Unit::Liter))
+    );
+    assert_eq!(
+        parse_quantity("Chips 200 g"),
+        Some(Quantity::new(200.0, Unit::Gram))
+    );
+    assert_eq!(
+        parse_quantity("大米 5公斤"),
+        Some(Quantity::new(5.0, Unit::Kilogram))
+    );
+    assert_eq!(
+        parse_quantity("牛奶 250毫升装"),
+        Some(Quantity::new(250.0, Unit::Milliliter))
+    );
+    assert_eq!(parse_quantity("Apple"), None);
+    assert_eq!(parse_quantity("3 lemons"), None);
+
+    let base = parse_quantity("1.5L").unwrap().to_base();
+    assert_eq!(base, Quantity::new(1500.0, Unit::Milliliter));
+}
+
+#[test]
+fn test_parse_pack_count() {
+    assert_eq!(parse_pack_count("アサヒ スーパードライ 350ml 6缶"), Some(6));
+    assert_eq!(parse_pack_count("Sparkling Water 500ml x12"), Some(12));
+    assert_eq!(parse_pack_count("Cola 6-pack"), Some(6));
+    assert_eq!(parse_pack_count("Cola 24 Pack"), Some(24));
+    assert_eq!(parse_pack_count("Green Tea ×24"), Some(24));
+    assert_eq!(parse_pack_count("Juice 6x200ml"), Some(6));
+    assert_eq!(parse_pack_count("卫生纸 12卷 3包"), Some(3));
+    assert_eq!(parse_pack_count("Cola 330ml"), None);
+    assert_eq!(parse_pack_count("Lunch Box 3"), None);
+    assert_eq!(parse_pack_count("Eggs 0個"), None);
+}
+
+#[test]
+fn test_parse_pack_count_needs_pack_context() {
+    assert_eq!(parse_pack_count("ティッシュ 10枚入"), Some(10));
+    assert_eq!(parse_pack_count("食パン 6枚切"), None);
+    assert_eq!(parse_pack_count("のり 2枚"), None);
+}
+
+#[test]
+fn test_normalize_phone_japanese_number() {
+    assert_eq!(
+        normalize_phone("03-1234-5678", "JP"),
+        Some("+81312345678".to_string())
+    );
+    assert_eq!(
+        normalize_phone("+81 90 1234 5678", "US"),
+        Some("+819012345678".to_string())
+    );
+}
+
+#[test]
+fn test_normalize_phone_invalid() {
+    assert_eq!(normalize_phone("call us!", "JP"), None);
+    assert_eq!(normalize_phone("123", "JP"), None);
+    assert_eq!(normalize_phone("", "JP"), None);
+}